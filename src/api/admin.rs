@@ -0,0 +1,550 @@
+use crate::api::auth::{require_admin, AdminAuth, AdminSettings};
+use crate::api::{
+    ApiJson, ApiResponse, ConnectionRegistry, FaultSettings, FaultStatus, Pagination,
+    ReadOnlyStatus, ReadOnlyToggle, ResourceRoutes, FAULTS, MAINTENANCE,
+};
+use crate::app::StartupReport;
+use crate::config::{ConfigReloader, ReloadReport};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::admin::{AdminDatabase, NamespaceInfo, Scope};
+use crate::surreal::backup;
+use crate::surreal::breaker::{BreakerMetrics, BreakerSettings, BREAKER};
+use crate::surreal::connection::{ConnectionMetrics, CONNECTION};
+use crate::surreal::count::COUNTS;
+use crate::surreal::duplicates::{find_duplicates, DuplicateCluster, DuplicatesQuery};
+use crate::surreal::explain::QueryPlan;
+use crate::surreal::flags::{FeatureFlags, Flag, FlagCacheMetrics};
+use crate::surreal::journal::PendingBatch;
+use crate::surreal::query_manager::{
+    discard_batch, pending_batches, resume_batch, TransactionMetrics, TransactionReport,
+    TransactionSettings, TRANSACTIONS,
+};
+use crate::surreal::request_log::{
+    query_requests, RecentErrors, RequestLogMetrics, RequestRecord, RequestsQuery, REQUEST_LOG,
+};
+use crate::surreal::request_session::RequestSessions;
+use crate::surreal::restore::{self, RestorePlan, RESTORES};
+use crate::surreal::schema::live::{self, LiveSchema};
+use crate::surreal::schema::tables::SchemaGuard;
+use crate::surreal::session::{SessionMetrics, SessionSettings, SESSION};
+use crate::surreal::slow_log::{SlowQuery, SLOW_QUERIES};
+use crate::surreal::snapshot::RestoreReport;
+use crate::surreal::ttl::{RuleProgress, TtlSettings, TTL};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode, Uri};
+use axum::response::IntoResponse;
+use axum::{middleware, Router};
+use axum_macros::debug_handler;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Every route here needs the admin token, checked by [`require_admin`].
+pub fn admin_routes(settings: &AdminSettings) -> Router<AppState> {
+    ResourceRoutes::new()
+        .get("/admin/status", status)
+        .get("/admin/namespaces", namespaces)
+        .get("/admin/scope", scope)
+        .put("/admin/scope", use_scope)
+        .get("/admin/slow-queries", slow_queries)
+        .delete("/admin/slow-queries", clear_slow_queries)
+        .post("/admin/explain", explain)
+        .get("/admin/transactions", transactions)
+        .get("/admin/batches", batches)
+        .delete("/admin/batches/:id", discard)
+        .post("/admin/batches/:id/resume", resume)
+        .get("/admin/session", session)
+        .get("/admin/breaker", breaker)
+        .get("/admin/requests", requests)
+        .post("/admin/reload", reload)
+        .get("/admin/flags", flags)
+        .put("/admin/flags/:name", set_flag)
+        .get("/admin/snapshot", export_snapshot)
+        .post("/admin/snapshot", restore_snapshot)
+        .post("/admin/restore", restore)
+        .post("/admin/restore/confirm", confirm_restore)
+        .get("/admin/routes", startup_report)
+        .get("/admin/schema", schema)
+        .get("/admin/duplicates", duplicates)
+        .get("/admin/read-only", read_only_status)
+        .put("/admin/read-only", set_read_only)
+        .get("/admin/faults", faults)
+        .put("/admin/faults", set_faults)
+        .delete("/admin/faults", clear_faults)
+        .get("/admin/ttl", ttl)
+        .post("/admin/ttl/run", run_ttl)
+        .into_router()
+        .route_layer(middleware::from_fn_with_state(
+            AdminAuth::new(settings),
+            require_admin,
+        ))
+}
+
+#[derive(Serialize, Debug)]
+pub struct LiveStatus {
+    connections: usize,
+    subscriptions: usize,
+    /// Tables with an open feed, each received once for all connections.
+    feeds: usize,
+}
+
+/// Everything the other admin endpoints report on, in one document, so a
+/// dashboard needs a single request per refresh.
+#[derive(Serialize, Debug)]
+pub struct ServiceStatus {
+    /// The one WebSocket connection to SurrealDB every request shares.
+    pool: ConnectionMetrics,
+    breaker: BreakerMetrics,
+    session: SessionMetrics,
+    transactions: TransactionMetrics,
+    flag_cache: FlagCacheMetrics,
+    /// `/ws` connections and their subscriptions.
+    live: LiveStatus,
+    /// Request log records waiting to be written.
+    request_log: RequestLogMetrics,
+    errors: RecentErrors,
+    slow_queries: usize,
+    read_only: ReadOnlyStatus,
+    faults: FaultStatus,
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Status", skip(flags, connections))]
+pub async fn status(
+    State(flags): State<FeatureFlags>,
+    State(connections): State<ConnectionRegistry>,
+) -> ApiResponse<ServiceStatus> {
+    ApiResponse::ok(ServiceStatus {
+        pool: CONNECTION.metrics(),
+        breaker: BREAKER.metrics(),
+        session: SESSION.metrics().await,
+        transactions: TRANSACTIONS.metrics(),
+        flag_cache: flags.metrics(),
+        live: LiveStatus {
+            connections: connections.connections(),
+            subscriptions: connections.subscriptions(),
+            feeds: connections.fanout().feeds(),
+        },
+        request_log: REQUEST_LOG.metrics(),
+        errors: REQUEST_LOG.recent_errors(Instant::now()),
+        slow_queries: SLOW_QUERIES.entries().len(),
+        read_only: MAINTENANCE.status(),
+        faults: FAULTS.status(),
+    })
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Namespaces", skip(admin))]
+pub async fn namespaces(
+    State(admin): State<AdminDatabase>,
+) -> Result<ApiResponse<Vec<NamespaceInfo>>, Error> {
+    let namespaces = admin.namespaces().await?;
+    Ok(ApiResponse::ok(namespaces))
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Scope", skip(admin))]
+pub async fn scope(State(admin): State<AdminDatabase>) -> ApiResponse<Scope> {
+    ApiResponse::ok(admin.scope().await)
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Use Scope", skip(admin))]
+pub async fn use_scope(
+    State(admin): State<AdminDatabase>,
+    ApiJson(scope): ApiJson<Scope>,
+) -> Result<ApiResponse<Scope>, Error> {
+    let scope = admin.use_scope(scope).await?;
+    Ok(ApiResponse::ok(scope))
+}
+
+#[derive(Serialize, Debug)]
+pub struct SlowQueryReport {
+    threshold_ms: u128,
+    queries: Vec<SlowQuery>,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Slow Queries")]
+pub async fn slow_queries() -> ApiResponse<SlowQueryReport> {
+    ApiResponse::ok(SlowQueryReport {
+        threshold_ms: SLOW_QUERIES.threshold().as_millis(),
+        queries: SLOW_QUERIES.entries(),
+    })
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Clear Slow Queries")]
+pub async fn clear_slow_queries() -> StatusCode {
+    SLOW_QUERIES.clear();
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExplainRequest {
+    query: String,
+    /// `EXPLAIN FULL`, which also counts the records each step fetches.
+    #[serde(default)]
+    full: bool,
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Explain", skip(admin, request))]
+pub async fn explain(
+    State(admin): State<AdminDatabase>,
+    ApiJson(request): ApiJson<ExplainRequest>,
+) -> Result<ApiResponse<QueryPlan>, Error> {
+    let plan = admin.explain(&request.query, request.full).await?;
+    Ok(ApiResponse::ok(plan))
+}
+
+#[derive(Serialize, Debug)]
+pub struct TransactionSizeReport {
+    settings: TransactionSettings,
+    metrics: TransactionMetrics,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Transactions")]
+pub async fn transactions() -> ApiResponse<TransactionSizeReport> {
+    ApiResponse::ok(TransactionSizeReport {
+        settings: TRANSACTIONS.settings(),
+        metrics: TRANSACTIONS.metrics(),
+    })
+}
+
+/// Batches left unfinished in `transactions.journal_dir`, oldest first.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Batches")]
+pub async fn batches() -> Result<ApiResponse<Vec<PendingBatch>>, Error> {
+    Ok(ApiResponse::ok(pending_batches()?))
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Resume Batch", skip(db))]
+pub async fn resume(
+    State(db): State<Surreal<Client>>,
+    Path(id): Path<String>,
+) -> Result<ApiResponse<TransactionReport>, Error> {
+    let report = resume_batch(&db, &id).await?;
+    Ok(ApiResponse::ok(report))
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Discard Batch")]
+pub async fn discard(Path(id): Path<String>) -> Result<ApiResponse<PendingBatch>, Error> {
+    Ok(ApiResponse::ok(discard_batch(&id)?))
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionReport {
+    settings: SessionSettings,
+    metrics: SessionMetrics,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Session")]
+pub async fn session() -> ApiResponse<SessionReport> {
+    ApiResponse::ok(SessionReport {
+        settings: SESSION.settings(),
+        metrics: SESSION.metrics().await,
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct BreakerReport {
+    settings: BreakerSettings,
+    metrics: BreakerMetrics,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Breaker")]
+pub async fn breaker() -> ApiResponse<BreakerReport> {
+    ApiResponse::ok(BreakerReport {
+        settings: BREAKER.settings(),
+        metrics: BREAKER.metrics(),
+    })
+}
+
+/// The newest kept requests matching the filters, e.g.
+/// `?status=500&since=2023-05-01T00:00:00Z`.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Requests", skip(db))]
+pub async fn requests(
+    State(db): State<Surreal<Client>>,
+    Query(query): Query<RequestsQuery>,
+) -> Result<ApiResponse<Vec<RequestRecord>>, Error> {
+    let records = query_requests(&db, &query).await?;
+    Ok(ApiResponse::ok(records))
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Reload", skip(config))]
+pub async fn reload(
+    State(config): State<ConfigReloader>,
+) -> Result<ApiResponse<ReloadReport>, Error> {
+    let report = config.reload()?;
+    Ok(ApiResponse::ok(report))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FlagToggle {
+    enabled: bool,
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Flags", skip(flags))]
+pub async fn flags(State(flags): State<FeatureFlags>) -> ApiResponse<Vec<Flag>> {
+    ApiResponse::ok(flags.all())
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Set Flag", skip(flags))]
+pub async fn set_flag(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    ApiJson(toggle): ApiJson<FlagToggle>,
+) -> Result<ApiResponse<Flag>, Error> {
+    let flag = flags.set(&name, toggle.enabled).await?;
+    Ok(ApiResponse::ok(flag))
+}
+
+/// Query string for `GET /admin/snapshot`. Without it the admin connection's
+/// current scope is exported.
+#[derive(Deserialize, Debug)]
+pub struct SnapshotQuery {
+    namespace: Option<String>,
+    database: Option<String>,
+}
+
+impl SnapshotQuery {
+    fn scope(self) -> Result<Option<Scope>, Error> {
+        match (self.namespace, self.database) {
+            (Some(namespace), Some(database)) => Ok(Some(Scope {
+                namespace,
+                database,
+            })),
+            (None, None) => Ok(None),
+            _ => Err(Error::InvalidQuery(
+                "give both `namespace` and `database`, or neither".into(),
+            )),
+        }
+    }
+}
+
+/// Downloads the database as a `.surql` file.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Export Snapshot", skip(admin))]
+pub async fn export_snapshot(
+    State(admin): State<AdminDatabase>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<impl IntoResponse, Error> {
+    let snapshot = admin.export(query.scope()?).await?;
+    tracing::info!(
+        tables = snapshot.tables,
+        records = snapshot.records,
+        "Exported snapshot"
+    );
+    let filename = format!(
+        "attachment; filename=\"{}-{}.surql\"",
+        snapshot.scope.namespace, snapshot.scope.database
+    );
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        snapshot.surql,
+    ))
+}
+
+/// Restores a `.surql` snapshot from the body into `namespace`/`database`.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Restore Snapshot", skip(admin, surql))]
+pub async fn restore_snapshot(
+    State(admin): State<AdminDatabase>,
+    Query(into): Query<Scope>,
+    surql: String,
+) -> Result<ApiResponse<RestoreReport>, Error> {
+    let report = admin.restore(into, &surql).await?;
+    Ok(ApiResponse::ok(report))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestoreRequest {
+    /// A backup's name in the `backup.target` store.
+    snapshot: String,
+}
+
+/// Restores a backup into a staging database and checks it. The live
+/// database is only switched by [`confirm_restore`] with the returned token.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Restore", skip(admin, config))]
+pub async fn restore(
+    State(admin): State<AdminDatabase>,
+    State(config): State<ConfigReloader>,
+    ApiJson(request): ApiJson<RestoreRequest>,
+) -> Result<ApiResponse<RestorePlan>, Error> {
+    let settings = config.settings();
+    let store = backup::store::open(&settings.backup.target)?;
+    let live = Scope {
+        namespace: settings.database.namespace,
+        database: settings.database.database,
+    };
+    let plan = restore::stage(&admin, store.as_ref(), &request.snapshot, &live).await?;
+    tracing::info!(
+        staging = plan.staging.database,
+        passed = plan.passed,
+        "Staged restore"
+    );
+    Ok(ApiResponse::ok(RESTORES.offer(plan, Utc::now())))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RestoreConfirmation {
+    token: String,
+}
+
+/// Switches the app's connection, and so every request after this one, to
+/// the staging database of the restore `token` was given for. The settings
+/// in effect and the request session pool follow, so a later restore stages
+/// beside the restored database and callers' requests use it too.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(
+    name = "Admin: Confirm Restore",
+    skip(db, config, sessions, confirmation)
+)]
+pub async fn confirm_restore(
+    State(db): State<Surreal<Client>>,
+    State(config): State<ConfigReloader>,
+    State(sessions): State<RequestSessions>,
+    ApiJson(confirmation): ApiJson<RestoreConfirmation>,
+) -> Result<ApiResponse<Scope>, Error> {
+    let staging = RESTORES.confirm(&confirmation.token, Utc::now())?;
+    db.use_ns(&staging.namespace)
+        .use_db(&staging.database)
+        .await?;
+    SESSION.use_scope(&staging.namespace, &staging.database);
+    config.use_database(&staging.namespace, &staging.database);
+    sessions.use_database(&staging.namespace, &staging.database);
+    COUNTS.clear();
+    tracing::warn!(
+        namespace = staging.namespace,
+        database = staging.database,
+        "Switched to the restored database"
+    );
+    Ok(ApiResponse::ok(staging))
+}
+
+/// The report logged at startup: configuration with secrets redacted, the
+/// database, what the schema sync did and every route served.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Startup Report", skip(startup))]
+pub async fn startup_report(
+    State(startup): State<Arc<StartupReport>>,
+) -> ApiResponse<StartupReport> {
+    ApiResponse::ok(startup.as_ref().clone())
+}
+
+/// The tables, fields, indexes and events the database defines, and how
+/// they differ from the schema in code.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Admin: Schema", skip(db, schema))]
+pub async fn schema(
+    State(db): State<Surreal<Client>>,
+    State(schema): State<SchemaGuard>,
+) -> Result<ApiResponse<LiveSchema>, Error> {
+    Ok(ApiResponse::ok(live::describe(&db, schema.tables()).await?))
+}
+
+/// Records sharing a value of `key`, grouped into clusters of possible
+/// duplicates for a merge, most shared first and paged by `start`/`limit`.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Duplicates", skip(db, uri))]
+pub async fn duplicates(
+    State(db): State<Surreal<Client>>,
+    uri: Uri,
+    Query(query): Query<DuplicatesQuery>,
+) -> Result<ApiResponse<Vec<DuplicateCluster>>, Error> {
+    let (clusters, total) = find_duplicates(&db, &query).await?;
+    let pagination = Pagination {
+        start: query.start(),
+        limit: query.limit(),
+        count: clusters.len(),
+        total: Some(total as usize),
+    };
+    Ok(ApiResponse::ok(clusters).with_pagination(pagination, &uri))
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Read-Only")]
+pub async fn read_only_status() -> ApiResponse<ReadOnlyStatus> {
+    ApiResponse::ok(MAINTENANCE.status())
+}
+
+/// Switches read-only mode on or off until the next restart, or a reload
+/// that changes `maintenance`.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Set Read-Only")]
+pub async fn set_read_only(
+    ApiJson(toggle): ApiJson<ReadOnlyToggle>,
+) -> Result<ApiResponse<ReadOnlyStatus>, Error> {
+    Ok(ApiResponse::ok(MAINTENANCE.set(toggle)?))
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Faults")]
+pub async fn faults() -> ApiResponse<FaultStatus> {
+    ApiResponse::ok(FAULTS.status())
+}
+
+/// Replaces the faults being injected until the next restart, or a reload
+/// that changes `faults`. Refused in production.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Set Faults")]
+pub async fn set_faults(
+    ApiJson(settings): ApiJson<FaultSettings>,
+) -> Result<ApiResponse<FaultStatus>, Error> {
+    Ok(ApiResponse::ok(FAULTS.set(settings)?))
+}
+
+/// Stops injecting faults and drops every rule.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Clear Faults")]
+pub async fn clear_faults() -> ApiResponse<FaultStatus> {
+    FAULTS.configure(&FaultSettings::default());
+    ApiResponse::ok(FAULTS.status())
+}
+
+#[derive(Serialize, Debug)]
+pub struct TtlReport {
+    settings: TtlSettings,
+    rules: Vec<RuleProgress>,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Admin: TTL")]
+pub async fn ttl() -> ApiResponse<TtlReport> {
+    ApiResponse::ok(TtlReport {
+        settings: TTL.settings(),
+        rules: TTL.progress(),
+    })
+}
+
+/// Runs every TTL rule now rather than at the next interval, and answers
+/// once they are done.
+#[debug_handler]
+#[tracing::instrument(name = "Admin: Run TTL", skip(db))]
+pub async fn run_ttl(State(db): State<Surreal<Client>>) -> ApiResponse<TtlReport> {
+    let rules = TTL.run(&db).await;
+    ApiResponse::ok(TtlReport {
+        settings: TTL.settings(),
+        rules,
+    })
+}
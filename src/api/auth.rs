@@ -0,0 +1,117 @@
+use crate::error::Error;
+use crate::secret::Secret;
+use crate::telemetry;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::Span;
+
+// region: -- AdminSettings
+/// Admin features stay disabled until a token is configured, e.g. through
+/// `APP_ADMIN__TOKEN`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct AdminSettings {
+    pub token: Option<Secret<String>>,
+}
+// endregion: -- AdminSettings
+
+// region: -- Principal
+/// Who made a request, known only after their token was validated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Principal {
+    pub user_id: String,
+    pub tenant: Option<String>,
+}
+
+impl Principal {
+    pub fn admin() -> Self {
+        Self {
+            user_id: "admin".into(),
+            tenant: None,
+        }
+    }
+}
+// endregion: -- Principal
+
+// region: -- AdminAuth
+/// Checks `Authorization: Bearer <token>` against the configured admin token.
+#[derive(Clone, Debug, Default)]
+pub struct AdminAuth {
+    token: Option<Arc<Secret<String>>>,
+}
+
+impl AdminAuth {
+    pub fn new(settings: &AdminSettings) -> Self {
+        Self {
+            token: settings.token.clone().map(Arc::new),
+        }
+    }
+
+    pub fn authorize(&self, headers: &HeaderMap) -> Result<(), Error> {
+        let expected = self.token.as_deref().ok_or(Error::Unauthorized)?.expose();
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(Error::Unauthorized)?;
+
+        // Compared in constant time, so how long a wrong guess takes says
+        // nothing about how much of it was right.
+        if bool::from(provided.as_bytes().ct_eq(expected.as_bytes())) {
+            Ok(())
+        } else {
+            Err(Error::Unauthorized)
+        }
+    }
+
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, Error> {
+        self.authorize(headers)?;
+        Ok(Principal::admin())
+    }
+}
+// endregion: -- AdminAuth
+
+// region: -- identify
+/// Records who is calling into the request span and makes the [`Principal`]
+/// available as a request extension, and as a response extension for the
+/// middleware outside this one. Requests without a valid token pass through
+/// unchanged; routes that need one still check for themselves.
+pub async fn identify<B>(
+    State(auth): State<AdminAuth>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let mut principal = None;
+    if request.headers().contains_key(header::AUTHORIZATION) {
+        if let Ok(caller) = auth.authenticate(request.headers()) {
+            telemetry::record_principal(&Span::current(), &caller);
+            request.extensions_mut().insert(caller.clone());
+            principal = Some(caller);
+        }
+    }
+    let mut response = next.run(request).await;
+    if let Some(principal) = principal {
+        response.extensions_mut().insert(principal);
+    }
+    response
+}
+// endregion: -- identify
+
+// region: -- require_admin
+/// Refuses requests without the admin token with `401`. Added as a
+/// `route_layer`, so paths the router doesn't serve still answer `404`.
+pub async fn require_admin<B>(
+    State(auth): State<AdminAuth>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match auth.authorize(request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(error) => error.into_response(),
+    }
+}
+// endregion: -- require_admin
@@ -0,0 +1,180 @@
+use axum::body::{boxed, Full};
+use axum::http::{header, HeaderName, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+
+/// `camel` or `snake`: the case of the keys in the JSON response, overriding
+/// `response.case`.
+pub const X_CASE: HeaderName = HeaderName::from_static("x-case");
+
+pub static CASING: Lazy<Casing> = Lazy::new(|| Casing::new(&ResponseSettings::default()));
+
+tokio::task_local! {
+    static CURRENT_CASE: Case;
+}
+
+// region: -- ResponseSettings
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    /// Keys as the DTOs and records have them.
+    #[default]
+    Snake,
+    Camel,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResponseSettings {
+    /// Key case for clients that don't send `X-Case`.
+    pub case: Case,
+}
+// endregion: -- ResponseSettings
+
+// region: -- Case
+impl Case {
+    /// The case `X-Case` asks for; unknown values are ignored.
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?.trim();
+        if value.eq_ignore_ascii_case("camel") {
+            Some(Self::Camel)
+        } else if value.eq_ignore_ascii_case("snake") {
+            Some(Self::Snake)
+        } else {
+            None
+        }
+    }
+
+    /// Renames every object key in `value`, however deeply nested.
+    pub fn apply(self, value: &mut Value) {
+        if self == Self::Snake {
+            return;
+        }
+        match value {
+            Value::Object(object) => {
+                let renamed = std::mem::take(object)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        self.apply(&mut value);
+                        (camel_case(&key), value)
+                    })
+                    .collect::<Map<_, _>>();
+                *object = renamed;
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.apply(value)),
+            _ => {}
+        }
+    }
+}
+
+/// `date_of_birth` to `dateOfBirth`. Leading underscores are kept, so keys
+/// like `_id` aren't mangled.
+pub fn camel_case(key: &str) -> String {
+    let trimmed = key.trim_start_matches('_');
+    let mut camel = String::with_capacity(key.len());
+    camel.push_str(&key[..key.len() - trimmed.len()]);
+    let mut upper = false;
+    for c in trimmed.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                camel.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => camel.push(c),
+        }
+    }
+    camel
+}
+
+/// Case of the request being handled: its `X-Case`, or `response.case`.
+pub fn current_case() -> Case {
+    CURRENT_CASE
+        .try_with(|case| *case)
+        .unwrap_or_else(|_| CASING.settings().case)
+}
+// endregion: -- Case
+
+// region: -- Casing
+/// The configured `response.case`, swapped on reload.
+#[derive(Debug)]
+pub struct Casing {
+    settings: RwLock<ResponseSettings>,
+}
+
+impl Casing {
+    pub fn new(settings: &ResponseSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+        }
+    }
+
+    pub fn configure(&self, settings: &ResponseSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    pub fn settings(&self) -> ResponseSettings {
+        self.settings.read().unwrap().clone()
+    }
+}
+
+/// Marks a response whose keys are already in [`current_case`], like
+/// [`crate::api::Streamed`] lists that rename each row as it goes out.
+#[derive(Clone, Copy, Debug)]
+pub struct Cased;
+// endregion: -- Casing
+
+// region: -- Middleware
+/// Renames the keys of JSON responses to the case the client asked for, so
+/// every DTO and record gets the same treatment without serde attributes of
+/// its own. Snake case responses go through untouched.
+pub async fn casing<B>(request: Request<B>, next: Next<B>) -> Response {
+    let case = request
+        .headers()
+        .get(X_CASE)
+        .and_then(Case::from_header)
+        .unwrap_or_else(|| CASING.settings().case);
+
+    let mut response = CURRENT_CASE.scope(case, next.run(request)).await;
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("x-case"));
+    if case == Case::Snake || response.extensions().get::<Cased>().is_some() || !is_json(&response)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            tracing::error!(%error, "Failed to read the response body to rename its keys");
+            return crate::error::Error::Db.into_response();
+        }
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut value) => {
+            case.apply(&mut value);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            serde_json::to_vec(&value).map_or(bytes, Into::into)
+        }
+        Err(_) => bytes,
+    };
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim();
+            mime == "application/json" || mime.ends_with("+json")
+        })
+}
+// endregion: -- Middleware
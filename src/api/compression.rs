@@ -0,0 +1,69 @@
+use crate::error::Error;
+use axum::error_handling::HandleErrorLayer;
+use axum::BoxError;
+use serde::{Deserialize, Serialize};
+use std::future::{ready, Ready};
+use tower::layer::util::{Identity, Stack};
+use tower::ServiceBuilder;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+
+// region: -- CompressionSettings
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Br,
+    Gzip,
+    Deflate,
+    Zstd,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CompressionSettings {
+    /// Bodies smaller than this are sent as-is.
+    pub min_size_bytes: u16,
+    /// Encodings offered to clients. Among the ones a client accepts, its
+    /// `Accept-Encoding` q-values decide.
+    pub algorithms: Vec<Algorithm>,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            min_size_bytes: 1024,
+            algorithms: vec![Algorithm::Br, Algorithm::Gzip],
+        }
+    }
+}
+// endregion: -- CompressionSettings
+
+// region: -- layers
+pub fn compression(settings: &CompressionSettings) -> CompressionLayer<impl Predicate> {
+    let enabled = |algorithm| settings.algorithms.contains(&algorithm);
+    CompressionLayer::new()
+        .br(enabled(Algorithm::Br))
+        .gzip(enabled(Algorithm::Gzip))
+        .deflate(enabled(Algorithm::Deflate))
+        .zstd(enabled(Algorithm::Zstd))
+        .compress_when(
+            SizeAbove::new(settings.min_size_bytes)
+                .and(NotForContentType::GRPC)
+                .and(NotForContentType::IMAGES)
+                .and(NotForContentType::const_new("text/event-stream")),
+        )
+}
+
+type Decompression<F> =
+    ServiceBuilder<Stack<RequestDecompressionLayer, Stack<HandleErrorLayer<F, ()>, Identity>>>;
+
+/// Accepts `Content-Encoding: gzip`/`br`/`deflate`/`zstd` request bodies.
+/// Meant for routes that take large uploads, like batch imports.
+pub fn request_decompression() -> Decompression<impl Fn(BoxError) -> Ready<Error> + Clone> {
+    ServiceBuilder::new()
+        .layer(HandleErrorLayer::new(|error: BoxError| {
+            ready(Error::InvalidBody(error.to_string()))
+        }))
+        .layer(RequestDecompressionLayer::new())
+}
+// endregion: -- layers
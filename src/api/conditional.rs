@@ -0,0 +1,79 @@
+use crate::api::ApiResponse;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+// region: -- Stamped
+/// A record read together with the `updated_at` the database maintains on
+/// it. Records written before the field was defined have none.
+#[derive(Deserialize, Debug)]
+pub struct Stamped<T> {
+    #[serde(flatten)]
+    pub record: T,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+// endregion: -- Stamped
+
+// region: -- Conditional
+/// A single resource answered with `Last-Modified`, or with an empty
+/// `304 Not Modified` when the request's `If-Modified-Since` is no older
+/// than the resource.
+#[derive(Debug)]
+pub enum Conditional<T> {
+    NotModified(DateTime<Utc>),
+    Modified(ApiResponse<T>, Option<DateTime<Utc>>),
+}
+
+impl<T> Conditional<T> {
+    pub fn new(headers: &HeaderMap, last_modified: Option<DateTime<Utc>>, data: T) -> Self {
+        match last_modified {
+            Some(at) if !modified_since(headers, at) => Self::NotModified(at),
+            _ => Self::Modified(ApiResponse::ok(data), last_modified),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Conditional<T> {
+    fn into_response(self) -> Response {
+        let (mut response, last_modified) = match self {
+            Self::NotModified(at) => (StatusCode::NOT_MODIFIED.into_response(), Some(at)),
+            Self::Modified(body, at) => (body.into_response(), at),
+        };
+        if let Some(value) = last_modified.and_then(|at| HeaderValue::from_str(&http_date(at)).ok())
+        {
+            response.headers_mut().insert(header::LAST_MODIFIED, value);
+        }
+        response
+    }
+}
+
+/// Whether the resource changed after the request's `If-Modified-Since`.
+/// Without the header, or with one that doesn't parse, it has. HTTP dates
+/// only have whole seconds, so `at` is truncated before comparing.
+pub fn modified_since(headers: &HeaderMap, at: DateTime<Utc>) -> bool {
+    let Some(since) = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+    else {
+        return true;
+    };
+    let at = at
+        .duration_trunc(chrono::Duration::seconds(1))
+        .unwrap_or(at);
+    at > since
+}
+
+/// IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+pub fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+// endregion: -- Conditional
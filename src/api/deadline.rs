@@ -0,0 +1,133 @@
+use crate::error::Error;
+use axum::http::{HeaderMap, HeaderName, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Time left for the request, in milliseconds, or the RFC 3339 instant it
+/// must be answered by.
+pub const REQUEST_DEADLINE: HeaderName = HeaderName::from_static("x-request-deadline");
+/// gRPC's form: an integer and a unit, e.g. `250m` for 250 milliseconds.
+pub const GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
+
+tokio::task_local! {
+    static CURRENT_DEADLINE: Deadline;
+}
+
+// region: -- Deadline
+/// When the caller stops waiting for the response.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    started: Instant,
+    budget: Duration,
+}
+
+impl Deadline {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            budget,
+        }
+    }
+
+    pub fn at(&self) -> Instant {
+        self.started + self.budget
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at().saturating_duration_since(Instant::now())
+    }
+
+    pub fn expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    pub fn exceeded(&self) -> Error {
+        Error::DeadlineExceeded {
+            budget_ms: self.budget.as_millis(),
+            elapsed_ms: self.started.elapsed().as_millis(),
+        }
+    }
+
+    /// The tighter of the two headers, if the request sent either.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let deadline = headers
+            .get(REQUEST_DEADLINE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_request_deadline(value, Utc::now()));
+        let grpc = headers
+            .get(GRPC_TIMEOUT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_grpc_timeout);
+        deadline.into_iter().chain(grpc).min().map(Self::new)
+    }
+}
+
+/// Deadline of the request being handled, if it has one and this is called
+/// from inside [`deadline`].
+pub fn current_deadline() -> Option<Deadline> {
+    CURRENT_DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Milliseconds left, or an RFC 3339 instant; a past instant leaves no time.
+pub fn parse_request_deadline(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(ms) = value.parse::<u64>() {
+        return Some(Duration::from_millis(ms));
+    }
+    let at = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// `grpc-timeout`: at most 8 digits followed by `H`, `M`, `S`, `m`, `u` or
+/// `n`.
+pub fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit_at = value
+        .len()
+        .checked_sub(1)
+        .filter(|&i| value.is_char_boundary(i))?;
+    let (amount, unit) = value.split_at(unit_at);
+    if amount.is_empty() || amount.len() > 8 || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+// endregion: -- Deadline
+
+// region: -- Middleware
+/// Gives requests that send a deadline header that much time, as a
+/// [`Deadline`] extension and for the queries they run, and answers `504` once
+/// it is up. Dropping the handler abandons any query still in flight.
+pub async fn deadline<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let Some(deadline) = Deadline::from_headers(request.headers()) else {
+        return next.run(request).await;
+    };
+    request.extensions_mut().insert(deadline);
+
+    let handled = tokio::time::timeout_at(deadline.at(), next.run(request));
+    match CURRENT_DEADLINE.scope(deadline, handled).await {
+        Ok(response) => response,
+        Err(_) => {
+            let error = deadline.exceeded();
+            tracing::warn!(%error, "Request deadline exceeded");
+            error.into_response()
+        }
+    }
+}
+// endregion: -- Middleware
@@ -0,0 +1,42 @@
+use crate::api::auth::AdminAuth;
+use crate::surreal::stats::{self, QueryStats};
+use axum::extract::State;
+use axum::http::{HeaderName, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+pub const DEBUG_DB: HeaderName = HeaderName::from_static("x-debug-db");
+pub const DB_QUERY_COUNT: HeaderName = HeaderName::from_static("x-db-query-count");
+pub const DB_TIME_MS: HeaderName = HeaderName::from_static("x-db-time-ms");
+
+/// When an admin sends `X-Debug-DB: true`, reports how many queries the
+/// request ran and how long they took in total as response headers.
+pub async fn debug_db<B>(
+    State(auth): State<AdminAuth>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let requested = request
+        .headers()
+        .get(DEBUG_DB)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"true"));
+    if !requested {
+        return next.run(request).await;
+    }
+    if let Err(error) = auth.authorize(request.headers()) {
+        return error.into_response();
+    }
+
+    let query_stats = Arc::new(QueryStats::default());
+    let mut response = stats::collect(query_stats.clone(), next.run(request)).await;
+
+    let headers = response.headers_mut();
+    headers.insert(DB_QUERY_COUNT, HeaderValue::from(query_stats.count()));
+    let millis = format!("{:.3}", query_stats.elapsed().as_secs_f64() * 1000.0);
+    if let Ok(value) = HeaderValue::from_str(&millis) {
+        headers.insert(DB_TIME_MS, value);
+    }
+
+    response
+}
@@ -0,0 +1,120 @@
+use crate::error::Error;
+use crate::surreal::db::{RequestTransaction, Transaction};
+use crate::surreal::model::SurrealModel;
+use crate::surreal::request_session::SessionClient;
+use crate::surreal::schema::tables::SchemaGuard;
+use axum::async_trait;
+use axum::body::HttpBody;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::Request;
+use axum::BoxError;
+use axum_macros::FromRequest;
+use serde::de::DeserializeOwned;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use surrealdb::{engine::remote::ws::Client, Response, Surreal};
+
+/// `axum::Json` whose rejections are reported through [`Error`], so bad
+/// bodies get the same envelope as every other failure.
+#[derive(FromRequest, Debug)]
+#[from_request(via(axum::Json), rejection(Error))]
+pub struct ApiJson<T>(pub T);
+
+/// [`ApiJson`] for a body written to `N`'s table: fields the table doesn't
+/// have are refused by the [`SchemaGuard`] when it is schemafull. `N` is only
+/// needed when `T` isn't the record itself, e.g. `SchemaJson<Vec<Person>, Person>`.
+#[derive(Debug)]
+pub struct SchemaJson<T, N = T>(pub T, pub PhantomData<N>);
+
+#[async_trait]
+impl<T, N, S, B> axum::extract::FromRequest<S, B> for SchemaJson<T, N>
+where
+    T: DeserializeOwned,
+    N: SurrealModel,
+    SchemaGuard: FromRef<S>,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Error;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let ApiJson(body) =
+            <ApiJson<serde_json::Value> as axum::extract::FromRequest<S, B>>::from_request(
+                request, state,
+            )
+            .await?;
+        SchemaGuard::from_ref(state).check(N::TABLE, &body)?;
+        let body = serde_json::from_value(body).map_err(|e| Error::InvalidBody(e.to_string()))?;
+        Ok(Self(body, PhantomData))
+    }
+}
+
+/// The connection a handler queries on: the caller's own when the request
+/// runs in a request session, the application's otherwise.
+#[derive(Debug)]
+pub struct Db(pub Surreal<Client>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Db
+where
+    Surreal<Client>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let client = match parts.extensions.get::<SessionClient>() {
+            Some(SessionClient(client)) => client.clone(),
+            None => Surreal::<Client>::from_ref(state),
+        };
+        Ok(Self(client))
+    }
+}
+
+/// The request's transaction, on the connection [`Db`] gives. Handlers
+/// queue statements on it rather than querying a connection, so whatever
+/// they and the repository code they call queue is sent together as one
+/// [`Transaction`]: by [`Tx::commit`] when the handler needs the results,
+/// or by the [`crate::api::transaction`] middleware once the handler answers
+/// with a success. An error response, or a request dropped before it
+/// answers, sends nothing.
+#[derive(Debug)]
+pub struct Tx(pub RequestTransaction);
+
+impl Tx {
+    pub fn queue<R>(&self, queue: impl FnOnce(&mut Transaction) -> R) -> Result<R, Error> {
+        self.0.queue(queue)
+    }
+
+    pub async fn commit(&self) -> Result<Response, Error> {
+        self.0.commit().await
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancel()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Tx
+where
+    Surreal<Client>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Db(client) = Db::from_request_parts(parts, state)
+            .await
+            .unwrap_or_else(|never| match never {});
+        let Some(transaction) = RequestTransaction::current() else {
+            tracing::error!("`Tx` extracted outside the transaction middleware");
+            return Err(Error::Db);
+        };
+        transaction.begin(&client);
+        Ok(Self(transaction))
+    }
+}
@@ -0,0 +1,219 @@
+use crate::api::Action;
+use crate::error::Error;
+use crate::surreal::cache::{redis_error, RedisSettings};
+use futures_util::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Events a table's feed holds for listeners that fall behind.
+const FEED_SIZE: usize = 256;
+
+/// How long a feed waits before subscribing to Redis again.
+const RESUBSCRIBE_AFTER: Duration = Duration::from_secs(1);
+
+// region: -- LiveSettings
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LiveSettings {
+    pub bus: EventBus,
+}
+
+/// How write events reach the feeds. `local` only carries writes made
+/// through this instance; `redis` carries writes made through any instance
+/// publishing to the same server, each table on `{channel}:{table}`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventBus {
+    #[default]
+    Local,
+    Redis(RedisSettings),
+}
+
+impl LiveSettings {
+    pub fn problems(&self) -> Vec<String> {
+        match &self.bus {
+            EventBus::Local => Vec::new(),
+            EventBus::Redis(redis) => redis.problems("live.bus"),
+        }
+    }
+}
+// endregion: -- LiveSettings
+
+// region: -- Fanout
+/// A write to a record, as the feed of its table carries it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableEvent {
+    pub action: Action,
+    pub table: String,
+    pub record: String,
+    pub data: Value,
+}
+
+struct Feed {
+    events: broadcast::Sender<TableEvent>,
+    listeners: usize,
+    /// The one subscription to the bus for this table, if the bus isn't
+    /// local.
+    upstream: Option<JoinHandle<()>>,
+}
+
+struct RedisBus {
+    client: redis::Client,
+    connection: MultiplexedConnection,
+    channel: String,
+}
+
+/// One feed per table for the whole instance: the first connection to listen
+/// to a table opens it, with its single subscription to the bus, and the
+/// last to leave closes it. However many clients watch a table, the instance
+/// receives each of its events once and hands it to all of them.
+#[derive(Clone)]
+pub struct Fanout {
+    feeds: Arc<Mutex<HashMap<String, Feed>>>,
+    bus: Option<Arc<RedisBus>>,
+}
+
+impl Default for Fanout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fanout {
+    /// A fanout on the local bus.
+    pub fn new() -> Self {
+        Self {
+            feeds: Arc::default(),
+            bus: None,
+        }
+    }
+
+    /// A fanout on the configured bus. Redis is reached before this returns.
+    pub async fn connect(settings: &LiveSettings) -> Result<Self, Error> {
+        let EventBus::Redis(redis) = &settings.bus else {
+            return Ok(Self::new());
+        };
+        let client = redis.client()?;
+        let connection = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .map_err(redis_error)?;
+        Ok(Self {
+            feeds: Arc::default(),
+            bus: Some(Arc::new(RedisBus {
+                client,
+                connection,
+                channel: redis.channel.clone(),
+            })),
+        })
+    }
+
+    /// Starts listening to `table`, opening its feed if nobody was.
+    pub fn join(&self, table: &str) -> broadcast::Receiver<TableEvent> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let feed = feeds.entry(table.to_string()).or_insert_with(|| {
+            let (events, _) = broadcast::channel(FEED_SIZE);
+            let upstream = self
+                .bus
+                .as_ref()
+                .map(|bus| spawn_upstream(bus.clone(), table.to_string(), events.clone()));
+            Feed {
+                events,
+                listeners: 0,
+                upstream,
+            }
+        });
+        feed.listeners += 1;
+        feed.events.subscribe()
+    }
+
+    /// Stops one listener of `table`, closing its feed if it was the last.
+    pub fn leave(&self, table: &str) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let Some(feed) = feeds.get_mut(table) else {
+            return;
+        };
+        feed.listeners = feed.listeners.saturating_sub(1);
+        if feed.listeners == 0 {
+            if let Some(upstream) = feed.upstream.take() {
+                upstream.abort();
+            }
+            feeds.remove(table);
+        }
+    }
+
+    /// Open feeds, each with one subscription to the bus.
+    pub fn feeds(&self) -> usize {
+        self.feeds.lock().unwrap().len()
+    }
+
+    /// Sends `event` to the listeners of its table on every instance on the
+    /// bus, this one included.
+    pub fn publish(&self, event: TableEvent) {
+        let Some(bus) = &self.bus else {
+            self.deliver(event);
+            return;
+        };
+        let bus = bus.clone();
+        tokio::spawn(async move {
+            let channel = format!("{}:{}", bus.channel, event.table);
+            let Ok(payload) = serde_json::to_string(&event) else {
+                return;
+            };
+            let mut connection = bus.connection.clone();
+            let published: redis::RedisResult<()> = connection.publish(&channel, payload).await;
+            if let Err(error) = published {
+                tracing::warn!(%error, channel, "Failed to publish a write event");
+            }
+        });
+    }
+
+    fn deliver(&self, event: TableEvent) {
+        let feeds = self.feeds.lock().unwrap();
+        if let Some(feed) = feeds.get(&event.table) {
+            // No receivers just means everyone left since the lookup.
+            let _ = feed.events.send(event);
+        }
+    }
+}
+
+/// Feeds `table`'s channel into `events` until aborted, subscribing again
+/// whenever the subscription drops.
+fn spawn_upstream(
+    bus: Arc<RedisBus>,
+    table: String,
+    events: broadcast::Sender<TableEvent>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let channel = format!("{}:{table}", bus.channel);
+        loop {
+            let subscribed: redis::RedisResult<()> = async {
+                let mut pubsub = bus.client.get_async_connection().await?.into_pubsub();
+                pubsub.subscribe(&channel).await?;
+                let mut messages = pubsub.on_message();
+                while let Some(message) = messages.next().await {
+                    let payload: String = message.get_payload()?;
+                    match serde_json::from_str(&payload) {
+                        Ok(event) => {
+                            let _ = events.send(event);
+                        }
+                        Err(error) => tracing::warn!(%error, channel, "Ignored a malformed event"),
+                    }
+                }
+                Ok(())
+            }
+            .await;
+            if let Err(error) = subscribed {
+                tracing::warn!(%error, channel, "Event subscription lost; resubscribing");
+            }
+            tokio::time::sleep(RESUBSCRIBE_AFTER).await;
+        }
+    })
+}
+// endregion: -- Fanout
@@ -0,0 +1,203 @@
+use crate::config::Environment;
+use crate::error::Error;
+use axum::body::{Bytes, StreamBody};
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::stream;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+use surrealdb::error::Api;
+
+// What the queries of a request given the `query` fault fail with. A lost
+// connection is retried and counts towards the breaker like a real one.
+const QUERY_FAULT: &str = "fault injected: connection lost";
+
+/// Routes faults are never injected into: admin, so they can be switched
+/// back off, and health, so the probes keep telling the truth.
+const EXEMPT: &[&str] = &["/admin/", "/health"];
+
+pub static FAULTS: Lazy<Faults> = Lazy::new(|| Faults::new(&FaultSettings::default()));
+
+tokio::task_local! {
+    static FAILING: ();
+}
+
+// region: -- FaultSettings
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FaultSettings {
+    /// Injects the faults in `rules`. Never allowed in production.
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<FaultRule>,
+}
+
+/// A fault injected into `percent` of the requests under `route`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FaultRule {
+    /// The path prefix the rule applies to, `/` for every route.
+    pub route: String,
+    pub fault: Fault,
+    /// Share of the matching requests that get the fault, from 0 to 100.
+    pub percent: u8,
+    /// How long `delay` holds a request before running it.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// Waits `delay_ms`, then runs the request.
+    Delay,
+    /// Sends the response head and cuts the connection before the body.
+    Drop,
+    /// Answers `500` without running the request.
+    Error,
+    /// Runs the request with every query it makes failing as if the
+    /// database connection was lost, so they are retried and trip the
+    /// breaker.
+    Query,
+}
+
+impl FaultSettings {
+    pub fn problems(&self, environment: &Environment) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.enabled && matches!(environment, Environment::Production) {
+            problems.push("`faults.enabled` must not be set in production".into());
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.route.starts_with('/') {
+                problems.push(format!("`faults.rules[{i}].route` must start with `/`"));
+            }
+            if rule.percent > 100 {
+                problems.push(format!("`faults.rules[{i}].percent` must be at most 100"));
+            }
+            if rule.fault == Fault::Delay && rule.delay_ms == 0 {
+                problems.push(format!(
+                    "`faults.rules[{i}].delay_ms` must be at least 1 for a `delay`"
+                ));
+            }
+        }
+        problems
+    }
+}
+// endregion: -- FaultSettings
+
+// region: -- Faults
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FaultStatus {
+    #[serde(flatten)]
+    pub settings: FaultSettings,
+    /// Faults injected since startup.
+    pub injected: u64,
+}
+
+/// The faults being injected, set from `faults` at startup and on reload,
+/// and by `PUT /admin/faults` in between. A reload only overrides the
+/// endpoint when `faults` itself changed.
+#[derive(Debug)]
+pub struct Faults {
+    settings: RwLock<FaultSettings>,
+    injected: AtomicU64,
+}
+
+impl Faults {
+    pub fn new(settings: &FaultSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+            injected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn configure(&self, settings: &FaultSettings) {
+        let mut current = self.settings.write().unwrap();
+        if settings.enabled {
+            tracing::warn!(rules = settings.rules.len(), "Fault injection on");
+        } else if current.enabled {
+            tracing::info!("Fault injection off");
+        }
+        *current = settings.clone();
+    }
+
+    /// Replaces the settings with `settings` when they are valid for the
+    /// environment the service runs in.
+    pub fn set(&self, settings: FaultSettings) -> Result<FaultStatus, Error> {
+        let environment = Environment::current().unwrap_or(Environment::Local);
+        if settings.enabled && matches!(environment, Environment::Production) {
+            return Err(Error::FaultsUnavailable);
+        }
+        let problems = settings.problems(&environment);
+        if !problems.is_empty() {
+            return Err(Error::InvalidBody(problems.join("; ")));
+        }
+        self.configure(&settings);
+        Ok(self.status())
+    }
+
+    pub fn status(&self) -> FaultStatus {
+        FaultStatus {
+            settings: self.settings.read().unwrap().clone(),
+            injected: self.injected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The fault a request to `path` gets, if any: the first rule under
+    /// whose route it falls that hits its `percent`.
+    pub fn pick(&self, path: &str) -> Option<FaultRule> {
+        let settings = self.settings.read().unwrap();
+        if !settings.enabled || EXEMPT.iter().any(|prefix| path.starts_with(prefix)) {
+            return None;
+        }
+        let mut rng = rand::thread_rng();
+        let rule = settings
+            .rules
+            .iter()
+            .filter(|rule| path.starts_with(&rule.route))
+            .find(|rule| rng.gen_range(0..100) < rule.percent)?;
+        self.injected.fetch_add(1, Ordering::Relaxed);
+        Some(rule.clone())
+    }
+
+    /// What [`crate::surreal::instrument::traced`] returns instead of
+    /// running a query for a request given the `query` fault.
+    pub fn fail_query(&self) -> Option<surrealdb::Error> {
+        FAILING
+            .try_with(|_| surrealdb::Error::Api(Api::Ws(QUERY_FAULT.into())))
+            .ok()
+    }
+}
+// endregion: -- Faults
+
+// region: -- Middleware
+/// Injects the fault [`FAULTS`] picks for the request, if any.
+pub async fn inject_faults<B>(request: Request<B>, next: Next<B>) -> Response {
+    let Some(rule) = FAULTS.pick(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    tracing::warn!(fault = ?rule.fault, route = %rule.route, "Injecting fault");
+    match rule.fault {
+        Fault::Delay => {
+            tokio::time::sleep(Duration::from_millis(rule.delay_ms)).await;
+            next.run(request).await
+        }
+        Fault::Drop => {
+            let cut = stream::once(async {
+                Err::<Bytes, _>(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "fault injected",
+                ))
+            });
+            StreamBody::new(cut).into_response()
+        }
+        Fault::Error => Error::FaultInjected(rule.route).into_response(),
+        Fault::Query => FAILING.scope((), next.run(request)).await,
+    }
+}
+// endregion: -- Middleware
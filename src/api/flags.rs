@@ -0,0 +1,35 @@
+use crate::surreal::flags::FeatureFlags;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+/// Snapshot of the feature flags taken when the request arrived, so a handler
+/// sees the same answer for a flag however often it asks.
+///
+/// ```ignore
+/// async fn list(flags: Flags, ...) {
+///     if flags.enabled("new_pagination") { ... }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Flags(BTreeMap<String, bool>);
+
+impl Flags {
+    pub fn enabled(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(false)
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Flags
+where
+    FeatureFlags: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Flags(FeatureFlags::from_ref(state).snapshot()))
+    }
+}
@@ -0,0 +1,50 @@
+use crate::api::{ApiResponse, Db, ResourceRoutes};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::edge::{parse_record, EdgeAllowList};
+use crate::surreal::graph::{export, Graph, MAX_GRAPH_DEPTH};
+use crate::surreal::schema::tables::TABLES;
+use axum::extract::{Query, State};
+use axum::Router;
+use axum_macros::debug_handler;
+use serde::Deserialize;
+
+pub fn graph_routes() -> Router<AppState> {
+    ResourceRoutes::new()
+        .get("/graph/export", export_graph)
+        .into_router()
+}
+
+/// `?root=person:john&depth=2`. `depth` defaults to 2.
+#[derive(Deserialize, Debug)]
+pub struct GraphQuery {
+    pub root: String,
+    pub depth: Option<usize>,
+}
+
+/// The records within `depth` hops of `root` along the allowed edges, for
+/// graph views and debugging.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Graph: Export", skip(db, edges))]
+pub async fn export_graph(
+    Db(db): Db,
+    State(edges): State<EdgeAllowList>,
+    Query(query): Query<GraphQuery>,
+) -> Result<ApiResponse<Graph>, Error> {
+    let root = parse_record(&query.root)?;
+    if !TABLES.iter().any(|definition| definition.table == root.tb) {
+        return Err(Error::InvalidQuery(format!(
+            "`root` must be a record of a table with a declared schema, not `{}`",
+            root.tb
+        )));
+    }
+    let depth = query.depth.unwrap_or(2);
+    if !(1..=MAX_GRAPH_DEPTH).contains(&depth) {
+        return Err(Error::InvalidQuery(format!(
+            "`depth` must be between 1 and {MAX_GRAPH_DEPTH}"
+        )));
+    }
+
+    let graph = export(&db, &edges, &root, depth).await?;
+    Ok(ApiResponse::ok(graph))
+}
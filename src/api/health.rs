@@ -0,0 +1,39 @@
+use crate::api::{ApiResponse, ResourceRoutes};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::backup::{BackupStatus, BACKUPS};
+use crate::surreal::version::{is_supported, SUPPORTED_VERSIONS};
+use axum::extract::State;
+use axum::Router;
+use axum_macros::debug_handler;
+use serde::Serialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+pub fn health_routes() -> Router<AppState> {
+    ResourceRoutes::new()
+        .get("/health/ready", ready)
+        .into_router()
+}
+
+#[derive(Serialize, Debug)]
+pub struct Readiness {
+    surrealdb_version: String,
+    supported: bool,
+    supported_versions: &'static str,
+    backup: BackupStatus,
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Health: Ready", skip(db))]
+pub async fn ready(State(db): State<Surreal<Client>>) -> Result<ApiResponse<Readiness>, Error> {
+    let version = db
+        .version()
+        .await
+        .map_err(|e| Error::NotReady(e.to_string()))?;
+    Ok(ApiResponse::ok(Readiness {
+        surrealdb_version: version.to_string(),
+        supported: is_supported(&version),
+        supported_versions: SUPPORTED_VERSIONS,
+        backup: BACKUPS.status(),
+    }))
+}
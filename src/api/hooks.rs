@@ -0,0 +1,246 @@
+use crate::error::Error;
+use crate::surreal::count::COUNTS;
+use futures_core::future::BoxFuture;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+// region: -- Mutation
+/// The record a mutation touches.
+#[derive(Debug, Clone, Copy)]
+pub struct Mutation<'a> {
+    pub table: &'a str,
+    pub id: &'a str,
+}
+
+impl fmt::Display for Mutation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.table, self.id)
+    }
+}
+// endregion: -- Mutation
+
+// region: -- MutationHook
+/// Reacts to writes made by the handlers. Audit logging, cache invalidation,
+/// webhooks, ... each get their own implementation instead of living in the
+/// handlers.
+///
+/// A `before_*` error aborts the mutation and is returned to the client.
+/// `after_*` hooks run once the write is done, so their errors are only
+/// logged. Every method defaults to doing nothing.
+pub trait MutationHook: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn before_create<'a>(
+        &'a self,
+        _mutation: Mutation<'a>,
+        _data: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn after_create<'a>(
+        &'a self,
+        _mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn before_update<'a>(
+        &'a self,
+        _mutation: Mutation<'a>,
+        _data: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn after_update<'a>(
+        &'a self,
+        _mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn before_delete<'a>(&'a self, _mutation: Mutation<'a>) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// `record` is what was deleted, or `Null` if there was nothing to delete.
+    fn after_delete<'a>(
+        &'a self,
+        _mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+// endregion: -- MutationHook
+
+// region: -- MutationHooks
+/// The hooks registered for the application, run in registration order.
+/// Handlers take it as `State<MutationHooks>`.
+#[derive(Clone, Default)]
+pub struct MutationHooks {
+    hooks: Vec<Arc<dyn MutationHook>>,
+}
+
+impl MutationHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, hook: impl MutationHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    pub async fn before_create(&self, mutation: Mutation<'_>, data: &Value) -> Result<(), Error> {
+        for hook in self.hooks.iter() {
+            hook.before_create(mutation, data).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_create(&self, mutation: Mutation<'_>, record: &Value) {
+        for hook in self.hooks.iter() {
+            if let Err(error) = hook.after_create(mutation, record).await {
+                log_failure(hook.name(), "after_create", mutation, &error);
+            }
+        }
+    }
+
+    pub async fn before_update(&self, mutation: Mutation<'_>, data: &Value) -> Result<(), Error> {
+        for hook in self.hooks.iter() {
+            hook.before_update(mutation, data).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_update(&self, mutation: Mutation<'_>, record: &Value) {
+        for hook in self.hooks.iter() {
+            if let Err(error) = hook.after_update(mutation, record).await {
+                log_failure(hook.name(), "after_update", mutation, &error);
+            }
+        }
+    }
+
+    pub async fn before_delete(&self, mutation: Mutation<'_>) -> Result<(), Error> {
+        for hook in self.hooks.iter() {
+            hook.before_delete(mutation).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_delete(&self, mutation: Mutation<'_>, record: &Value) {
+        for hook in self.hooks.iter() {
+            if let Err(error) = hook.after_delete(mutation, record).await {
+                log_failure(hook.name(), "after_delete", mutation, &error);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MutationHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+fn log_failure(hook: &str, stage: &str, mutation: Mutation<'_>, error: &Error) {
+    tracing::warn!(hook, stage, record = %mutation, %error, "Mutation hook failed");
+}
+// endregion: -- MutationHooks
+
+// region: -- AuditLog
+/// Logs every completed mutation under the `audit` target.
+pub struct AuditLog;
+
+impl MutationHook for AuditLog {
+    fn name(&self) -> &'static str {
+        "audit_log"
+    }
+
+    fn after_create<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            tracing::info!(target: "audit", action = "create", record = %mutation, data = %record);
+            Ok(())
+        })
+    }
+
+    fn after_update<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            tracing::info!(target: "audit", action = "update", record = %mutation, data = %record);
+            Ok(())
+        })
+    }
+
+    fn after_delete<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            tracing::info!(target: "audit", action = "delete", record = %mutation);
+            Ok(())
+        })
+    }
+}
+// endregion: -- AuditLog
+
+// region: -- CountInvalidation
+/// Drops the cached counts of every table written to, so list totals and
+/// `/people/count` reflect writes made through the API straight away.
+pub struct CountInvalidation;
+
+impl CountInvalidation {
+    fn invalidate(mutation: Mutation<'_>) -> BoxFuture<'static, Result<(), Error>> {
+        COUNTS.invalidate(mutation.table);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl MutationHook for CountInvalidation {
+    fn name(&self) -> &'static str {
+        "count_invalidation"
+    }
+
+    fn after_create<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Self::invalidate(mutation)
+    }
+
+    /// Filters may match the record differently after an update.
+    fn after_update<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Self::invalidate(mutation)
+    }
+
+    fn after_delete<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Self::invalidate(mutation)
+    }
+}
+// endregion: -- CountInvalidation
@@ -0,0 +1,256 @@
+use crate::api::compression::request_decompression;
+use crate::api::hooks::{Mutation, MutationHooks};
+use crate::api::person::Person;
+use crate::api::shed::batch_shed;
+use crate::api::{ApiResponse, Db, ResourceRoutes};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::ids::IdGenerator;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::model::SurrealModel;
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::Router;
+use axum_macros::debug_handler;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Person fields a CSV column can be mapped to.
+const PERSON_FIELDS: [&str; 2] = ["id", "name"];
+/// Rows inserted per transaction. A failing row rejects its whole chunk.
+const IMPORT_CHUNK_ROWS: usize = 100;
+
+pub fn import_routes() -> Router<AppState> {
+    // Imports share the batch limits with `batch_up` and may be compressed.
+    ResourceRoutes::new()
+        .post("/people/import/csv", import_csv)
+        .into_router()
+        .layer(request_decompression())
+        .layer(batch_shed())
+}
+
+// region: -- ColumnMapping
+/// Query string for `POST /people/import/csv`.
+#[derive(Deserialize, Debug, Default)]
+pub struct ImportQuery {
+    /// JSON object from CSV header to person field, e.g.
+    /// `{"Full Name":"name"}`. Headers it doesn't mention map to the field
+    /// of the same name, if there is one, and are ignored otherwise.
+    pub mapping: Option<String>,
+}
+
+/// Which CSV column feeds which person field.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub id: Option<usize>,
+    pub name: usize,
+}
+
+impl ColumnMapping {
+    pub fn resolve(headers: &[&str], mapping: &BTreeMap<String, String>) -> Result<Self, Error> {
+        for (header, field) in mapping {
+            if !PERSON_FIELDS.contains(&field.as_str()) {
+                return Err(Error::InvalidQuery(format!(
+                    "`{header}` is mapped to unknown field `{field}` (fields: {})",
+                    PERSON_FIELDS.join(", ")
+                )));
+            }
+            if !headers.contains(&header.as_str()) {
+                return Err(Error::InvalidQuery(format!(
+                    "mapped column `{header}` is not in the CSV header"
+                )));
+            }
+        }
+
+        let mut columns: BTreeMap<&str, usize> = BTreeMap::new();
+        for (index, header) in headers.iter().enumerate() {
+            let field = match mapping.get(*header) {
+                Some(field) => field.as_str(),
+                None if PERSON_FIELDS.contains(header) => header,
+                None => continue,
+            };
+            if columns.insert(field, index).is_some() {
+                return Err(Error::InvalidQuery(format!(
+                    "more than one column maps to `{field}`"
+                )));
+            }
+        }
+
+        let name = columns.get("name").copied().ok_or_else(|| {
+            Error::InvalidQuery("no column maps to `name`; add one to `mapping`".into())
+        })?;
+        Ok(Self {
+            id: columns.get("id").copied(),
+            name,
+        })
+    }
+
+    /// The row's id, if it has one, and the content to create it with.
+    pub fn person(&self, row: &csv::StringRecord) -> Result<(Option<String>, Value), String> {
+        let id = self
+            .id
+            .and_then(|i| row.get(i))
+            .filter(|id| !id.is_empty())
+            .map(String::from);
+        let name = row.get(self.name).unwrap_or_default();
+        if name.is_empty() {
+            return Err("`name` must not be empty".into());
+        }
+        Ok((id, json!({ "name": name })))
+    }
+}
+// endregion: -- ColumnMapping
+
+// region: -- ImportReport
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RejectedRow {
+    /// 1-based line in the upload, counting the header.
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+struct PendingRow {
+    line: u64,
+    id: String,
+    content: Value,
+}
+// endregion: -- ImportReport
+
+// region: -- Import
+/// Creates a person per CSV row. Rows that fail validation, the id strategy
+/// or a hook are rejected on their own; the rest go in chunks of
+/// [`IMPORT_CHUNK_ROWS`], each in one transaction, so a row the database
+/// refuses (e.g. a taken id) rejects the rows of its chunk with it.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Import CSV", skip(db, hooks, ids, query, body))]
+pub async fn import_csv(
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    State(ids): State<IdGenerator>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<ApiResponse<ImportReport>, Error> {
+    let mapping: BTreeMap<String, String> = match &query.mapping {
+        Some(mapping) => serde_json::from_str(mapping)
+            .map_err(|e| Error::InvalidQuery(format!("`mapping` is not a JSON object: {e}")))?,
+        None => BTreeMap::new(),
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(&body[..]);
+    let headers = reader
+        .headers()
+        .map_err(|e| Error::InvalidBody(format!("unreadable CSV header: {e}")))?
+        .clone();
+    let columns = ColumnMapping::resolve(&headers.iter().collect::<Vec<_>>(), &mapping)?;
+
+    let mut report = ImportReport::default();
+    let mut pending = Vec::with_capacity(IMPORT_CHUNK_ROWS);
+    for row in reader.records() {
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                report.rejected.push(RejectedRow {
+                    line,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = row.position().map_or(0, |p| p.line());
+
+        match prepare_row(&hooks, &ids, &columns, &row).await {
+            Ok((id, content)) => pending.push(PendingRow { line, id, content }),
+            Err(error) => report.rejected.push(RejectedRow { line, error }),
+        }
+        if pending.len() == IMPORT_CHUNK_ROWS {
+            insert_chunk(&db, &hooks, std::mem::take(&mut pending), &mut report).await;
+        }
+    }
+    if !pending.is_empty() {
+        insert_chunk(&db, &hooks, pending, &mut report).await;
+    }
+
+    tracing::info!(
+        imported = report.imported,
+        rejected = report.rejected.len(),
+        "CSV import finished"
+    );
+    Ok(ApiResponse::ok(report))
+}
+
+async fn prepare_row(
+    hooks: &MutationHooks,
+    ids: &IdGenerator,
+    columns: &ColumnMapping,
+    row: &csv::StringRecord,
+) -> Result<(String, Value), String> {
+    let (id, content) = columns.person(row)?;
+    let id = ids
+        .resolve_record(Person::TABLE, id.as_deref(), &content)
+        .map_err(|e| e.to_string())?;
+    hooks
+        .before_create(
+            Mutation {
+                table: Person::TABLE,
+                id: &id,
+            },
+            &content,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((id, content))
+}
+
+async fn insert_chunk(
+    db: &Surreal<Client>,
+    hooks: &MutationHooks,
+    rows: Vec<PendingRow>,
+    report: &mut ImportReport,
+) {
+    let mut sql = String::from("BEGIN TRANSACTION;\n");
+    for i in 0..rows.len() {
+        sql.push_str(&format!("CREATE $record_{i} CONTENT $content_{i};\n"));
+    }
+    sql.push_str("COMMIT TRANSACTION;");
+
+    let mut query = db.query(&sql).bind(correlation());
+    for (i, row) in rows.iter().enumerate() {
+        query = query
+            .bind((format!("record_{i}"), Person::record(&row.id)))
+            .bind((format!("content_{i}"), &row.content));
+    }
+
+    match traced(&sql, async { query.await?.check() }).await {
+        Ok(_) => {
+            report.imported += rows.len();
+            for row in &rows {
+                let mutation = Mutation {
+                    table: Person::TABLE,
+                    id: &row.id,
+                };
+                hooks.after_create(mutation, &row.content).await;
+            }
+        }
+        Err(e) => {
+            let error = Error::from(e).to_string();
+            report
+                .rejected
+                .extend(rows.into_iter().map(|row| RejectedRow {
+                    line: row.line,
+                    error: error.clone(),
+                }));
+        }
+    }
+}
+// endregion: -- Import
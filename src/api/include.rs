@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::surreal::edge::EdgeAllowList;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Most segments an `?include=` path may have, e.g. `licenses.registry` is
+/// two. Every segment is another subquery per record it reaches.
+pub const MAX_INCLUDE_DEPTH: usize = 4;
+
+// region: -- Includes
+/// `?include=` on read endpoints: comma-separated paths of related records
+/// to expand inline, e.g. `?include=licenses.registry`.
+#[derive(Deserialize, Debug, Default)]
+pub struct IncludeQuery {
+    pub include: Option<String>,
+}
+
+/// Related records to expand, as a tree of paths that alternate an edge
+/// table and the node table at its far end. Only edges under
+/// `edges.allowed` can be followed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Includes {
+    edges: BTreeMap<String, EdgeInclude>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EdgeInclude {
+    /// The side of the edge record pointing at the record it is expanded on.
+    near: &'static str,
+    far: &'static str,
+    far_table: String,
+    /// Set when the path goes on past the edge to its far end.
+    node: Option<Includes>,
+}
+
+impl Includes {
+    /// `None` when nothing was asked for. Paths are checked against `edges`,
+    /// walking from records of `table`.
+    pub fn parse(
+        edges: &EdgeAllowList,
+        table: &str,
+        include: Option<&str>,
+    ) -> Result<Option<Self>, Error> {
+        let Some(include) = include else {
+            return Ok(None);
+        };
+        let mut includes = Self::default();
+        for path in include.split(',').map(str::trim) {
+            let segments: Vec<&str> = path.split('.').collect();
+            if segments.len() > MAX_INCLUDE_DEPTH {
+                return Err(Error::InvalidQuery(format!(
+                    "`include` path `{path}` is deeper than {MAX_INCLUDE_DEPTH} levels"
+                )));
+            }
+            includes.add(edges, table, path, &segments)?;
+        }
+        Ok(Some(includes))
+    }
+
+    fn add(
+        &mut self,
+        edges: &EdgeAllowList,
+        table: &str,
+        path: &str,
+        segments: &[&str],
+    ) -> Result<(), Error> {
+        let Some((edge, rest)) = segments.split_first() else {
+            return Ok(());
+        };
+        let endpoints = edges
+            .endpoints(edge)
+            .filter(|endpoints| endpoints.from == table || endpoints.to == table)
+            .ok_or_else(|| {
+                Error::InvalidQuery(format!(
+                    "`include` path `{path}`: `{edge}` is not an edge of {table}"
+                ))
+            })?;
+        // An edge between records of the same table is followed outwards.
+        let (near, far, far_table) = if endpoints.from == table {
+            ("in", "out", &endpoints.to)
+        } else {
+            ("out", "in", &endpoints.from)
+        };
+        let include = self
+            .edges
+            .entry(edge.to_string())
+            .or_insert_with(|| EdgeInclude {
+                near,
+                far,
+                far_table: far_table.clone(),
+                node: None,
+            });
+
+        let Some((node, rest)) = rest.split_first() else {
+            return Ok(());
+        };
+        if *node != far_table.as_str() {
+            return Err(Error::InvalidQuery(format!(
+                "`include` path `{path}`: `{edge}` leads to {far_table}, not {node}"
+            )));
+        }
+        include
+            .node
+            .get_or_insert_with(Self::default)
+            .add(edges, far_table, path, rest)
+    }
+
+    /// Subqueries to add to the `SELECT` list, one per edge, each named after
+    /// its edge. Nodes come back as one-record arrays; see [`Self::take`].
+    pub fn select(&self) -> String {
+        self.edges
+            .iter()
+            .map(|(edge, include)| {
+                let mut fields = String::from("*");
+                if let Some(node) = &include.node {
+                    let table = &include.far_table;
+                    fields.push_str(&format!(
+                        ", (SELECT {} FROM {table} WHERE id = $parent.{}) AS {table}",
+                        node.fields(),
+                        include.far
+                    ));
+                }
+                format!(
+                    "(SELECT {fields} FROM {edge} WHERE {} = $parent.id) AS {edge}",
+                    include.near
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn fields(&self) -> String {
+        if self.edges.is_empty() {
+            "*".into()
+        } else {
+            format!("*, {}", self.select())
+        }
+    }
+
+    /// Removes the expanded edges from `row` and returns them, with each
+    /// node unwrapped from its one-record array (`null` if it is gone).
+    pub fn take(&self, row: &mut Value) -> Map<String, Value> {
+        let mut taken = Map::new();
+        let Value::Object(row) = row else {
+            return taken;
+        };
+        for (edge, include) in &self.edges {
+            let Some(mut records) = row.remove(edge) else {
+                continue;
+            };
+            if let (Some(node), Value::Array(records)) = (&include.node, &mut records) {
+                for record in records.iter_mut().filter_map(Value::as_object_mut) {
+                    let table = &include.far_table;
+                    let mut found = match record.remove(table) {
+                        Some(Value::Array(found)) => found.into_iter().next(),
+                        found => found,
+                    }
+                    .unwrap_or(Value::Null);
+                    let nested = node.take(&mut found);
+                    if let Value::Object(found) = &mut found {
+                        found.extend(nested);
+                    }
+                    record.insert(table.clone(), found);
+                }
+            }
+            taken.insert(edge.clone(), records);
+        }
+        taken
+    }
+}
+// endregion: -- Includes
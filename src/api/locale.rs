@@ -0,0 +1,170 @@
+use crate::error::Error;
+use axum::extract::State;
+use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::fmt;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static CURRENT_CATALOG: Arc<dyn MessageCatalog>;
+}
+
+// region: -- MessageCatalog
+/// Error messages in one language. Anything a catalog returns `None` for is
+/// reported in English, so a catalog can translate as much or as little as
+/// it likes.
+pub trait MessageCatalog: Send + Sync {
+    /// Language tag matched against `Accept-Language`, e.g. `de` or `pt-BR`.
+    fn language(&self) -> &'static str;
+
+    /// The problem's `title` for `status`.
+    fn title(&self, _status: StatusCode) -> Option<String> {
+        None
+    }
+
+    /// The problem's `detail` for `error`.
+    fn detail(&self, _error: &Error) -> Option<String> {
+        None
+    }
+}
+
+/// The messages as the errors define them.
+pub struct English;
+
+impl MessageCatalog for English {
+    fn language(&self) -> &'static str {
+        "en"
+    }
+
+    fn title(&self, status: StatusCode) -> Option<String> {
+        Some(status.canonical_reason().unwrap_or("Error").into())
+    }
+
+    fn detail(&self, error: &Error) -> Option<String> {
+        Some(error.to_string())
+    }
+}
+
+/// Catalog of the request being handled, or [`English`] outside of
+/// [`locale`].
+pub fn current_catalog() -> Arc<dyn MessageCatalog> {
+    CURRENT_CATALOG
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| Arc::new(English))
+}
+
+/// `title` and `detail` for `error` in the current request's language.
+pub fn localize(error: &Error) -> (String, String) {
+    let catalog = current_catalog();
+    let status = error.status();
+    let title = catalog
+        .title(status)
+        .or_else(|| English.title(status))
+        .unwrap_or_default();
+    let detail = catalog.detail(error).unwrap_or_else(|| error.to_string());
+    (title, detail)
+}
+// endregion: -- MessageCatalog
+
+// region: -- Catalogs
+/// The catalogs registered for the application. [`English`] is always
+/// there and is used when nothing else matches.
+#[derive(Clone)]
+pub struct Catalogs {
+    catalogs: Vec<Arc<dyn MessageCatalog>>,
+}
+
+impl Default for Catalogs {
+    fn default() -> Self {
+        Self {
+            catalogs: vec![Arc::new(English)],
+        }
+    }
+}
+
+impl Catalogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `catalog`, replacing any registered for the same language.
+    pub fn with(mut self, catalog: impl MessageCatalog + 'static) -> Self {
+        self.catalogs
+            .retain(|c| !c.language().eq_ignore_ascii_case(catalog.language()));
+        self.catalogs.push(Arc::new(catalog));
+        self
+    }
+
+    pub fn languages(&self) -> Vec<&'static str> {
+        self.catalogs.iter().map(|c| c.language()).collect()
+    }
+
+    /// The catalog for the most preferred language in `accept_language` that
+    /// has one. `fr-CA` falls back to `fr`; `*` and no match give English.
+    pub fn negotiate(&self, accept_language: Option<&str>) -> Arc<dyn MessageCatalog> {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let q = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (!tag.is_empty() && q > 0.0).then_some((tag, q))
+            })
+            .collect();
+        // Stable, so equally preferred languages keep the client's order.
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| self.find(tag))
+            .or_else(|| self.find(English.language()))
+            .unwrap_or_else(|| Arc::new(English))
+    }
+
+    fn find(&self, tag: &str) -> Option<Arc<dyn MessageCatalog>> {
+        let primary = tag.split('-').next().unwrap_or(tag);
+        [tag, primary].into_iter().find_map(|tag| {
+            self.catalogs
+                .iter()
+                .find(|c| c.language().eq_ignore_ascii_case(tag))
+                .cloned()
+        })
+    }
+}
+
+impl fmt::Debug for Catalogs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.languages()).finish()
+    }
+}
+// endregion: -- Catalogs
+
+// region: -- Middleware
+/// Renders the request's errors in the language its `Accept-Language`
+/// prefers, and says which one with `Content-Language`.
+pub async fn locale<B>(
+    State(catalogs): State<Catalogs>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let accept_language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    let catalog = catalogs.negotiate(accept_language);
+    let language = catalog.language();
+
+    let mut response = CURRENT_CATALOG.scope(catalog, next.run(request)).await;
+    let failed = response.status().is_client_error() || response.status().is_server_error();
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static("accept-language"));
+    if let Some(language) = HeaderValue::from_str(language).ok().filter(|_| failed) {
+        headers.insert(header::CONTENT_LANGUAGE, language);
+    }
+    response
+}
+// endregion: -- Middleware
@@ -0,0 +1,213 @@
+use crate::error::Error;
+use axum::http::{Method, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use surrealdb::error::Api;
+
+// Sent back instead of running a write while read-only, so the rejection
+// travels through `surrealdb::Result` like the breaker's.
+const READ_ONLY: &str = "writes are disabled while the service is read-only";
+
+/// Routes that take a `POST` but only read, so they stay open while
+/// read-only.
+pub const READS_BY_POST: &[&str] = &["/people/lookup", "/admin/explain"];
+
+/// Routes read-only mode never applies to: the switch itself, so it can be
+/// turned back off, and health, whose self test writes a probe record. Other
+/// admin routes may still read, but their writes are refused like any other.
+const EXEMPT: &[&str] = &["/admin/read-only", "/health"];
+
+/// Statements that change data or the schema.
+const WRITES: &[&str] = &[
+    "CREATE", "UPDATE", "DELETE", "RELATE", "INSERT", "DEFINE", "REMOVE",
+];
+
+pub static MAINTENANCE: Lazy<Maintenance> =
+    Lazy::new(|| Maintenance::new(&MaintenanceSettings::default()));
+
+tokio::task_local! {
+    static GUARDED: ();
+}
+
+// region: -- MaintenanceSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceSettings {
+    /// Turns writes away with `503` while reads go on.
+    pub read_only: bool,
+    /// The problem `detail` writes get while read-only.
+    pub message: String,
+    /// `Retry-After` on those `503`s.
+    pub retry_after_secs: u64,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            message: "the service is read-only for maintenance; try again later".into(),
+            retry_after_secs: 60,
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.message.trim().is_empty() {
+            problems.push("`maintenance.message` must not be empty".into());
+        }
+        if self.retry_after_secs == 0 {
+            problems.push("`maintenance.retry_after_secs` must be at least 1".into());
+        }
+        problems
+    }
+}
+// endregion: -- MaintenanceSettings
+
+// region: -- Maintenance
+/// Body of `PUT /admin/read-only`. Without a `message` the current one is
+/// kept.
+#[derive(Deserialize, Debug)]
+pub struct ReadOnlyToggle {
+    pub read_only: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReadOnlyStatus {
+    #[serde(flatten)]
+    pub settings: MaintenanceSettings,
+    /// When read-only mode was last switched on, while it is.
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// Whether the service is read-only, set from `maintenance` at startup and
+/// on reload, and by `PUT /admin/read-only` in between. A reload only
+/// overrides the endpoint when `maintenance` itself changed.
+#[derive(Debug)]
+pub struct Maintenance {
+    status: RwLock<ReadOnlyStatus>,
+}
+
+impl Maintenance {
+    pub fn new(settings: &MaintenanceSettings) -> Self {
+        Self {
+            status: RwLock::new(ReadOnlyStatus {
+                settings: settings.clone(),
+                since: settings.read_only.then(Utc::now),
+            }),
+        }
+    }
+
+    pub fn configure(&self, settings: &MaintenanceSettings) {
+        let mut status = self.status.write().unwrap();
+        if settings.read_only != status.settings.read_only {
+            status.since = settings.read_only.then(Utc::now);
+            log_switch(settings);
+        }
+        status.settings = settings.clone();
+    }
+
+    pub fn set(&self, toggle: ReadOnlyToggle) -> Result<ReadOnlyStatus, Error> {
+        let mut settings = self.status().settings;
+        settings.read_only = toggle.read_only;
+        if let Some(message) = toggle.message {
+            if message.trim().is_empty() {
+                return Err(Error::InvalidBody("`message` must not be empty".into()));
+            }
+            settings.message = message;
+        }
+        self.configure(&settings);
+        Ok(self.status())
+    }
+
+    pub fn status(&self) -> ReadOnlyStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.status.read().unwrap().settings.read_only
+    }
+
+    /// The `503` a write gets while read-only.
+    pub fn rejection(&self) -> Error {
+        let settings = self.status().settings;
+        Error::ReadOnly {
+            message: settings.message,
+            retry_after_secs: settings.retry_after_secs,
+        }
+    }
+
+    /// What [`crate::surreal::instrument::traced`] returns instead of running
+    /// `sql` when it writes, the service is read-only and the query comes
+    /// from a request [`read_only`] guards. The read-only switch, health
+    /// checks and background tasks aren't guarded, so the request log, schema
+    /// sync and write-behind queue keep going.
+    pub fn reject_write(&self, sql: &str) -> Option<surrealdb::Error> {
+        let guarded = GUARDED.try_with(|_| ()).is_ok();
+        (guarded && self.is_read_only() && writes(sql))
+            .then(|| surrealdb::Error::Api(Api::InternalError(READ_ONLY.into())))
+    }
+}
+
+fn log_switch(settings: &MaintenanceSettings) {
+    if settings.read_only {
+        tracing::warn!(message = %settings.message, "Read-only mode on; writes are turned away");
+    } else {
+        tracing::info!("Read-only mode off; writes are accepted again");
+    }
+}
+
+/// Whether `error` is a write [`Maintenance::reject_write`] turned away.
+pub fn is_read_only_rejection(error: &surrealdb::Error) -> bool {
+    matches!(error, surrealdb::Error::Api(Api::InternalError(message)) if message == READ_ONLY)
+}
+
+/// Whether `sql` has a statement that writes, e.g. the `UPDATE` in
+/// `BEGIN; UPDATE person:1 SET age = 3; COMMIT`. Keywords inside strings
+/// don't count.
+pub fn writes(sql: &str) -> bool {
+    let mut in_string = None;
+    sql.split(|c: char| {
+        if in_string == Some(c) {
+            in_string = None;
+        } else if in_string.is_none() && matches!(c, '\'' | '"') {
+            in_string = Some(c);
+        }
+        in_string.is_some() || !(c.is_alphanumeric() || c == '_')
+    })
+    .any(|word| {
+        WRITES
+            .iter()
+            .any(|keyword| word.eq_ignore_ascii_case(keyword))
+    })
+}
+
+/// Whether a request to `path` with `method` is a write read-only mode
+/// turns away.
+pub fn is_write(method: &Method, path: &str) -> bool {
+    let writes = [Method::POST, Method::PUT, Method::PATCH, Method::DELETE].contains(method);
+    writes && !READS_BY_POST.contains(&path) && !EXEMPT.iter().any(|p| path.starts_with(p))
+}
+// endregion: -- Maintenance
+
+// region: -- Middleware
+/// While read-only, answers writes with `503` and the maintenance message
+/// before they reach a handler. Everything else not exempt runs guarded, so
+/// a write a read route makes, or one still in flight when the mode is
+/// switched on, is turned away by the query layer too.
+pub async fn read_only<B>(request: Request<B>, next: Next<B>) -> Response {
+    let path = request.uri().path();
+    if EXEMPT.iter().any(|prefix| path.starts_with(prefix)) {
+        return next.run(request).await;
+    }
+    if MAINTENANCE.is_read_only() && is_write(request.method(), path) {
+        return MAINTENANCE.rejection().into_response();
+    }
+    GUARDED.scope((), next.run(request)).await
+}
+// endregion: -- Middleware
@@ -0,0 +1,87 @@
+use crate::api::ResourceRoutes;
+use crate::state::AppState;
+use crate::surreal::connection::{ConnectionMetrics, ErrorKind, CONNECTION};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Router;
+use axum_macros::debug_handler;
+use std::fmt::Write;
+
+/// Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn metrics_routes() -> Router<AppState> {
+    ResourceRoutes::new().get("/metrics", metrics).into_router()
+}
+
+#[debug_handler]
+#[tracing::instrument(name = "Metrics")]
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        render_connection_metrics(&CONNECTION.metrics()),
+    )
+}
+
+// region: -- Exposition
+pub fn render_connection_metrics(metrics: &ConnectionMetrics) -> String {
+    let mut out = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{name}{labels} {value}");
+        }
+    };
+
+    family(
+        "surrealdb_connected",
+        "gauge",
+        "Whether the WebSocket connection to SurrealDB is up.",
+        &[("", u64::from(metrics.connected))],
+    );
+    family(
+        "surrealdb_disconnects_total",
+        "counter",
+        "Times the connection was found down.",
+        &[("", metrics.disconnects)],
+    );
+    family(
+        "surrealdb_reconnects_total",
+        "counter",
+        "Times the connection came back after being down.",
+        &[("", metrics.reconnects)],
+    );
+    family(
+        "surrealdb_reconnect_attempts_total",
+        "counter",
+        "Queries sent while the connection was down.",
+        &[("", metrics.reconnect_attempts)],
+    );
+    family(
+        "surrealdb_queries_in_flight",
+        "gauge",
+        "Queries sent and not yet answered.",
+        &[("", metrics.in_flight)],
+    );
+    family(
+        "surrealdb_queries_total",
+        "counter",
+        "Queries sent.",
+        &[("", metrics.queries)],
+    );
+    let errors: Vec<(String, u64)> = ErrorKind::ALL
+        .iter()
+        .zip(metrics.errors)
+        .map(|(kind, count)| (format!("{{kind=\"{}\"}}", kind.as_str()), count))
+        .collect();
+    let errors: Vec<(&str, u64)> = errors.iter().map(|(l, c)| (l.as_str(), *c)).collect();
+    family(
+        "surrealdb_query_errors_total",
+        "counter",
+        "Failed queries, by kind of error.",
+        &errors,
+    );
+    out
+}
+// endregion: -- Exposition
@@ -1,5 +1,67 @@
+mod admin;
+pub mod auth;
+mod casing;
+pub mod compression;
+mod conditional;
+mod deadline;
+mod debug_db;
+mod extract;
+mod fanout;
+mod faults;
+mod flags;
+mod graph;
+mod health;
+pub mod hooks;
+mod import;
+mod include;
+mod locale;
+mod maintenance;
+mod metrics;
 mod person;
 mod person_qry;
+mod projection;
+mod registry;
+mod relate;
+mod request_id;
+mod request_log;
+mod request_session;
+mod response;
+mod returning;
+mod routing;
+pub mod shed;
+mod sort;
+mod transaction;
+mod with_id;
+mod ws;
 
+pub use admin::*;
+pub use casing::*;
+pub use conditional::*;
+pub use deadline::*;
+pub use debug_db::*;
+pub use extract::*;
+pub use fanout::*;
+pub use faults::*;
+pub use flags::*;
+pub use graph::*;
+pub use health::*;
+pub use import::*;
+pub use include::*;
+pub use locale::*;
+pub use maintenance::*;
+pub use metrics::*;
 pub use person::*;
 pub use person_qry::*;
+pub use projection::*;
+pub use registry::*;
+pub use relate::*;
+pub use request_id::*;
+pub use request_log::*;
+pub use request_session::*;
+pub use response::*;
+pub use returning::*;
+pub use routing::*;
+pub use sort::*;
+pub use transaction::*;
+pub use with_id::*;
+pub use ws::*;
@@ -1,71 +1,952 @@
+use crate::api::auth::Principal;
+use crate::api::hooks::{Mutation, MutationHooks};
+use crate::api::registry::License;
+use crate::api::{
+    plain_ids, ApiJson, ApiResponse, Conditional, Created, Db, FieldsQuery, IncludeQuery, Includes,
+    Pagination, Projection, ResourceRoutes, ReturnQuery, SchemaJson, Sort, Stamped, Streamed, Tx,
+    WithId,
+};
 use crate::error::Error;
-use axum::extract::{Path, State};
-use axum::{Json, Router};
+use crate::from_response;
+use crate::state::AppState;
+use crate::surreal::count::count;
+use crate::surreal::edge::{
+    delete_node, parse_record, queue_edge_policy, still_related, EdgeAllowList,
+};
+use crate::surreal::from_response::FromResponse;
+use crate::surreal::history::{self, update_with_history, Version};
+use crate::surreal::ids::{create_or_match, Creation, IdStrategy};
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::model::SurrealModel;
+use crate::surreal::paging::{stream_projection, stream_table, STREAM_PAGE_SIZE};
+use crate::surreal::request_session::in_request_session;
+use crate::surreal::retry::{retry, READ_RETRY};
+use crate::surreal::slow_log::Binding;
+use crate::surreal::write_behind::{WriteBehind, WriteKind};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use axum::Router;
 use axum_macros::debug_handler;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use surrealdb::sql::{Id, Thing};
 use surrealdb::{engine::remote::ws::Client, Surreal};
 
-const PERSON: &str = "person";
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+const MAX_TAG_LEN: usize = 64;
 
-pub fn person_routes() -> Router<Surreal<Client>> {
-    Router::new()
-        .route("/person/:id", axum::routing::post(create))
-        .route("/person/:id", axum::routing::get(read))
-        .route("/person/:id", axum::routing::put(update))
-        .route("/person/:id", axum::routing::delete(delete))
-        .route("/people", axum::routing::get(list))
+pub fn person_routes() -> Router<AppState> {
+    ResourceRoutes::new()
+        .post("/person", create_generated)
+        .post("/person/:id", create)
+        .get("/person/:id", read)
+        .put("/person/:id", update)
+        .delete("/person/:id", delete)
+        .get("/people", list)
+        .delete("/people", delete_people)
+        .get("/people/stats", stats)
+        .get("/people/count", count_people)
+        .post("/people/lookup", lookup_people)
+        .get("/person/:id/licenses", licenses)
+        .get("/person/:id/history", person_history)
+        .get("/person/:id/versions/:version", person_version)
+        .post("/person/:id/revert/:version", revert)
+        .post("/person/:id/tags", add_tags)
+        .delete("/person/:id/tags/:tag", remove_tag)
+        .into_router()
 }
 
+/// A person as written. Computed fields are left out: the database sets
+/// them.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Person {
     name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_of_birth: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Create", skip(db, id, person))]
+/// A person as read, with the fields the database computes.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PersonView {
+    #[serde(flatten)]
+    person: Person,
+    /// Whole years since `date_of_birth`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    age: Option<u32>,
+}
+
+impl SurrealModel for Person {
+    const TABLE: &'static str = "person";
+}
+
+impl Person {
+    /// Tags cleaned by [`clean_tags`], and a `date_of_birth` from year 1 up
+    /// to today: `age` casts it with `<datetime>`, which can't read dates
+    /// outside four-digit years back.
+    pub fn validated(mut self) -> Result<Self, Error> {
+        self.tags = clean_tags(self.tags)?;
+        if let Some(date) = self.date_of_birth {
+            if date.year() < 1 || date > Utc::now().date_naive() {
+                return Err(Error::InvalidBody(format!(
+                    "`date_of_birth` {date} must be between 0001-01-01 and today"
+                )));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Tags trimmed and without repeats, in the order given. Commas are refused
+/// because `?tag=` separates tags with them.
+pub fn clean_tags(tags: Vec<String>) -> Result<Vec<String>, Error> {
+    let mut cleaned: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() || tag.len() > MAX_TAG_LEN || tag.contains(',') {
+            return Err(Error::InvalidBody(format!(
+                "tag `{tag}` must be 1 to {MAX_TAG_LEN} bytes without commas"
+            )));
+        }
+        if !cleaned.iter().any(|kept| kept == tag) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    Ok(cleaned)
+}
+
+/// Query string for `GET /people`. Without any of these set the whole table
+/// is returned as before.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PeopleQuery {
+    pub name_starts_with: Option<String>,
+    /// Only people with (`true`) or without (`false`) any license.
+    pub has_license: Option<bool>,
+    /// Only people holding the license with this registration number.
+    pub license_number: Option<u64>,
+    /// Only people with every one of these comma-separated tags.
+    pub tag: Option<String>,
+    /// See [`Sort`]; `name:asc` when left out.
+    pub sort: Option<String>,
+    pub start: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl PeopleQuery {
+    pub fn is_empty(&self) -> bool {
+        self.name_starts_with.is_none()
+            && self.has_license.is_none()
+            && self.license_number.is_none()
+            && self.tag.is_none()
+            && self.sort.is_none()
+            && self.start.is_none()
+            && self.limit.is_none()
+    }
+
+    /// The `WHERE` clause for the filters that are set, with the values to
+    /// bind. Licenses point from a registry to a person, so relation filters
+    /// walk `<-licenses<-registry` back from each person.
+    pub fn filter(&self) -> (String, BTreeMap<String, serde_json::Value>) {
+        let mut conditions = Vec::new();
+        let mut bindings = BTreeMap::new();
+
+        if let Some(prefix) = &self.name_starts_with {
+            // The range bound on `name` lets the `name` index narrow the
+            // scan; the `startsWith` check keeps the match exact.
+            conditions.push("name >= $prefix AND string::startsWith(name, $prefix)");
+            bindings.insert("prefix".into(), prefix.clone().into());
+        }
+        match self.has_license {
+            Some(true) => conditions.push("count(<-licenses) > 0"),
+            Some(false) => conditions.push("count(<-licenses) = 0"),
+            None => {}
+        }
+        if let Some(number) = self.license_number {
+            conditions.push("<-licenses<-registry.registration CONTAINS $license_number");
+            bindings.insert("license_number".into(), number.into());
+        }
+        if let Some(tags) = &self.tag {
+            conditions.push("tags CONTAINSALL $tags");
+            let tags: Vec<&str> = tags.split(',').map(str::trim).collect();
+            bindings.insert("tags".into(), tags.into());
+        }
+
+        let clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        (clause, bindings)
+    }
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Create", skip(state, db, id, person))]
 pub async fn create(
-    State(db): State<Surreal<Client>>,
-    id: Path<String>,
-    Json(person): Json<Person>,
-) -> Result<Json<Option<Person>>, Error> {
-    let person = db.create((PERSON, &*id)).content(person).await?;
-    Ok(Json(person))
+    State(state): State<AppState>,
+    Db(db): Db,
+    Path(id): Path<String>,
+    SchemaJson(person, _): SchemaJson<Person>,
+) -> Result<Created<Option<WithId<PersonView>>>, Error> {
+    let id = state
+        .ids
+        .resolve_record(Person::TABLE, Some(&id), &json!(person))?;
+    create_person(&state, &db, &id, person).await
+}
+
+/// Like [`create`], with the id picked by the `person` id strategy.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Create Generated", skip(state, db, person))]
+pub async fn create_generated(
+    State(state): State<AppState>,
+    Db(db): Db,
+    SchemaJson(person, _): SchemaJson<Person>,
+) -> Result<Created<Option<WithId<PersonView>>>, Error> {
+    let id = state
+        .ids
+        .resolve_record(Person::TABLE, None, &json!(person))?;
+    create_person(&state, &db, &id, person).await
+}
+
+/// With natural key ids, creating a person that is already there returns
+/// them instead of failing. Otherwise the create goes through the
+/// write-behind buffer when it is on, and the repository when it isn't or
+/// the request runs with its caller's token.
+async fn create_person(
+    state: &AppState,
+    db: &Surreal<Client>,
+    id: &str,
+    person: Person,
+) -> Result<Created<Option<WithId<PersonView>>>, Error> {
+    let AppState {
+        hooks,
+        ids,
+        repository,
+        write_behind,
+        ..
+    } = state;
+    let person = person.validated()?;
+    let mutation = Mutation {
+        table: Person::TABLE,
+        id,
+    };
+    let location = format!("/person/{id}");
+    let data = json!(person);
+    hooks.before_create(mutation, &data).await?;
+    if ids.strategy(Person::TABLE) == IdStrategy::Natural {
+        return match create_or_match(db, Person::TABLE, id, &data).await? {
+            Creation::Created(person) => {
+                hooks.after_create(mutation, &json!(person)).await;
+                Ok(Created::new(location, person))
+            }
+            Creation::Existing(person) => Ok(Created::existing(location, person)),
+        };
+    }
+    let record = Person::record(id);
+    let person: WithId<PersonView> = if write_behind.is_enabled() && !in_request_session() {
+        write_behind
+            .write(record.clone(), WriteKind::Create, data)
+            .await?;
+        WithId::new(record, PersonView { person, age: None })
+    } else {
+        repository.create(db, &record, &data).await?
+    };
+    hooks.after_create(mutation, &json!(person)).await;
+    Ok(Created::new(location, Some(person)))
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Read", skip(db, id))]
+/// Sends `Last-Modified` and honours `If-Modified-Since`, so pollers get an
+/// empty `304` while the person is unchanged. `?fields=` picks the fields
+/// sent back, and `?include=` expands related records inline, in the same
+/// query. The whole person sees a write still in the write-behind buffer,
+/// if `read_your_writes`, unless the request runs with its caller's token.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Read", skip(state, db, id, headers))]
 pub async fn read(
-    State(db): State<Surreal<Client>>,
+    State(state): State<AppState>,
+    Db(db): Db,
     id: Path<String>,
-) -> Result<Json<Option<Person>>, Error> {
-    let person = db.select((PERSON, &*id)).await?;
-    Ok(Json(person))
+    Query(fields): Query<FieldsQuery>,
+    Query(include): Query<IncludeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let AppState {
+        edges,
+        repository,
+        write_behind,
+        ..
+    } = &state;
+    let projection = Projection::parse(Person::TABLE, fields.fields.as_deref())?;
+    let includes = Includes::parse(edges, Person::TABLE, include.include.as_deref())?;
+    if projection.is_none() && includes.is_none() {
+        let record = Person::record(&id);
+        let pending = (!in_request_session())
+            .then(|| write_behind.pending(&record))
+            .flatten();
+        if let Some(pending) = pending {
+            let person: PersonView = serde_json::from_value(pending).map_err(|_| Error::Db)?;
+            return Ok(ApiResponse::ok(WithId::new(record, person)).into_response());
+        }
+        let person: Option<Stamped<WithId<PersonView>>> = repository.select(&db, &record).await?;
+        let person = person.ok_or_else(|| Person::not_found(&id))?;
+        return Ok(Conditional::new(&headers, person.updated_at, person.record).into_response());
+    }
+
+    // `updated_at` is selected either way for `Last-Modified`.
+    let mut select = projection.as_ref().map_or_else(
+        || "*".to_string(),
+        |projection| projection.select(&["updated_at"]),
+    );
+    if let Some(includes) = &includes {
+        select = format!("{select}, {}", includes.select());
+    }
+    let sql = format!("SELECT {select} FROM type::thing($table, $id)");
+    let bindings = vec![
+        Binding::new("table", &Person::TABLE),
+        Binding::new("id", &*id),
+    ];
+    let rows: Vec<serde_json::Value> = retry(&READ_RETRY, || {
+        traced_with_bindings(&sql, bindings.clone(), async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(("table", Person::TABLE))
+                .bind(("id", &*id))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+    let mut person = rows
+        .into_iter()
+        .next()
+        .ok_or_else(|| Person::not_found(&id))?;
+    plain_ids(&mut person);
+    let updated_at = serde_json::from_value(person["updated_at"].clone()).ok();
+    let included = includes
+        .map(|includes| includes.take(&mut person))
+        .unwrap_or_default();
+    if let Some(projection) = &projection {
+        projection.trim(&mut person);
+    }
+    if let serde_json::Value::Object(person) = &mut person {
+        person.extend(included);
+    }
+    Ok(Conditional::new(&headers, updated_at, person).into_response())
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Update", skip(db, id, person))]
+/// The person as it was is kept in `person_history` first, along with who
+/// replaced it. The update goes through the write-behind buffer when it is on
+/// and the request doesn't run with its caller's token.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Update", skip(db, hooks, write_behind, principal, id, person))]
 pub async fn update(
-    State(db): State<Surreal<Client>>,
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    State(write_behind): State<WriteBehind>,
+    principal: Option<Extension<Principal>>,
     id: Path<String>,
-    Json(person): Json<Person>,
-) -> Result<Json<Option<Person>>, Error> {
-    let person = db.update((PERSON, &*id)).content(person).await?;
-    Ok(Json(person))
+    Query(returning): Query<ReturnQuery>,
+    SchemaJson(person, _): SchemaJson<Person>,
+) -> Result<ApiResponse<Option<serde_json::Value>>, Error> {
+    let person = person.validated()?;
+    let mutation = Mutation {
+        table: Person::TABLE,
+        id: &id,
+    };
+    let data = json!(person);
+    hooks.before_update(mutation, &data).await?;
+    let actor = principal.map(|Extension(principal)| principal.user_id);
+    let record = Person::record(&id);
+    let mut result = if write_behind.is_enabled() && !in_request_session() {
+        let kind = WriteKind::Update {
+            returning: returning.mode.clause(),
+            actor,
+        };
+        write_behind.write(record, kind, data.clone()).await?
+    } else {
+        update_with_history(
+            &db,
+            &record,
+            &data,
+            returning.mode.clause(),
+            actor.as_deref(),
+        )
+        .await?
+    };
+    if let Some(result) = &mut result {
+        plain_ids(result);
+    }
+    // `CONTENT` replaces the record, so `data` is what it now holds whatever
+    // the client asked to get back.
+    hooks.after_update(mutation, &data).await;
+    Ok(ApiResponse::ok(result))
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Delete", skip(db, id))]
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Delete", skip(db, hooks, edges, id))]
 pub async fn delete(
-    State(db): State<Surreal<Client>>,
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    State(edges): State<EdgeAllowList>,
+    id: Path<String>,
+) -> Result<ApiResponse<WithId<PersonView>>, Error> {
+    let mutation = Mutation {
+        table: Person::TABLE,
+        id: &id,
+    };
+    hooks.before_delete(mutation).await?;
+    let person: WithId<PersonView> = delete_node(&db, &edges, &Person::record(&id)).await?;
+    hooks.after_delete(mutation, &json!(person)).await;
+    Ok(ApiResponse::ok(person))
+}
+
+/// Without any filters the whole table is streamed, unpaginated, with its
+/// size as `X-Total-Count`; otherwise one page of matches is returned. `?fields=` picks the fields sent back.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "List", skip(db, uri))]
+pub async fn list(
+    Db(db): Db,
+    uri: Uri,
+    Query(query): Query<PeopleQuery>,
+    Query(fields): Query<FieldsQuery>,
+) -> Result<Response, Error> {
+    let projection = Projection::parse(Person::TABLE, fields.fields.as_deref())?;
+    if query.is_empty() {
+        let total = retry(&READ_RETRY, || {
+            count(&db, Person::TABLE, "", &BTreeMap::new())
+        })
+        .await? as usize;
+        let Some(projection) = projection else {
+            let pages = stream_table::<WithId<PersonView>>(db, Person::TABLE, STREAM_PAGE_SIZE);
+            return Ok(Streamed::new(pages).with_total(total).into_response());
+        };
+        // Pages are keyed on `id`, so it is selected either way.
+        let select = projection.select(&["id"]);
+        let pages =
+            stream_projection::<serde_json::Value>(db, Person::TABLE, select, STREAM_PAGE_SIZE)
+                .map_ok(move |mut rows| {
+                    rows.iter_mut().for_each(|row| {
+                        plain_ids(row);
+                        projection.trim(row);
+                    });
+                    rows
+                });
+        return Ok(Streamed::new(pages).with_total(total).into_response());
+    }
+
+    let start = query.start.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let (filter, bindings) = query.filter();
+    let sort = Sort::parse(Person::TABLE, query.sort.as_deref(), "name:asc")?;
+    // Results are ordered on the selected fields, so the sort keys and `id`
+    // are selected either way.
+    let mut ordered = sort.fields();
+    ordered.push("id");
+    let select = std::iter::once(
+        projection
+            .as_ref()
+            .map_or_else(|| "*".to_string(), |projection| projection.select(&ordered)),
+    )
+    .chain(sort.computed())
+    .collect::<Vec<_>>()
+    .join(", ");
+
+    let sql = format!(
+        "SELECT {select} FROM person{filter}{} LIMIT {limit} START {start}",
+        sort.order_by()
+    );
+    let mut response = retry(&READ_RETRY, || {
+        let metadata = bindings
+            .iter()
+            .map(|(name, value)| Binding::new(name, value))
+            .collect();
+        traced_with_bindings(
+            &sql,
+            metadata,
+            db.query(&sql).bind(correlation()).bind(&bindings),
+        )
+    })
+    .await?;
+    let people: Vec<serde_json::Value> = match &projection {
+        Some(projection) => {
+            let mut rows: Vec<serde_json::Value> = response.take(0)?;
+            rows.iter_mut().for_each(|row| {
+                plain_ids(row);
+                projection.trim(row);
+            });
+            rows
+        }
+        None => {
+            let people: Vec<WithId<PersonView>> = response.take(0)?;
+            people.into_iter().map(|person| json!(person)).collect()
+        }
+    };
+    let total = retry(&READ_RETRY, || {
+        count(&db, Person::TABLE, &filter, &bindings)
+    })
+    .await?;
+    let pagination = Pagination {
+        start,
+        limit,
+        count: people.len(),
+        total: Some(total as usize),
+    };
+    Ok(ApiResponse::ok(people)
+        .with_pagination(pagination, &uri)
+        .into_response())
+}
+
+/// Query string for `DELETE /people`. Takes the same filters as `GET /people`;
+/// deleting without any needs `confirm=all`.
+#[derive(Deserialize, Debug, Default)]
+pub struct DeletePeopleQuery {
+    pub name_starts_with: Option<String>,
+    pub has_license: Option<bool>,
+    pub license_number: Option<u64>,
+    pub tag: Option<String>,
+    /// Delete at most this many, in name order.
+    pub limit: Option<u32>,
+    pub confirm: Option<String>,
+}
+
+impl DeletePeopleQuery {
+    pub fn filters(&self) -> PeopleQuery {
+        PeopleQuery {
+            name_starts_with: self.name_starts_with.clone(),
+            has_license: self.has_license,
+            license_number: self.license_number,
+            tag: self.tag.clone(),
+            ..PeopleQuery::default()
+        }
+    }
+
+    /// The `SELECT` of the ids to delete and its bindings, or why the
+    /// request is refused.
+    pub fn selection(&self) -> Result<(String, BTreeMap<String, serde_json::Value>), Error> {
+        let filters = self.filters();
+        match self.confirm.as_deref() {
+            None if filters.is_empty() => {
+                return Err(Error::InvalidQuery(
+                    "deleting people needs a filter, or `confirm=all` to delete everyone".into(),
+                ))
+            }
+            Some(confirm) if confirm != "all" => {
+                return Err(Error::InvalidQuery(format!(
+                    "`confirm` must be `all`, not `{confirm}`"
+                )))
+            }
+            _ => {}
+        }
+
+        let (filter, bindings) = filters.filter();
+        let sql = match self.limit {
+            Some(limit) => {
+                format!("SELECT VALUE id FROM person{filter} ORDER BY name LIMIT {limit}")
+            }
+            None => format!("SELECT VALUE id FROM person{filter}"),
+        };
+        Ok((sql, bindings))
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct DeleteReport {
+    pub deleted: usize,
+}
+
+/// Edges touching the people deleted are handled as `edges.on_delete` says,
+/// in the same transaction as [`delete`] does for one. The `after_delete`
+/// hooks run for each person deleted, once the delete has committed. There
+/// is no `before_delete`, since who will be deleted is only known once they
+/// are.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Delete People", skip(tx, hooks, edges))]
+pub async fn delete_people(
+    tx: Tx,
+    State(hooks): State<MutationHooks>,
+    State(edges): State<EdgeAllowList>,
+    Query(query): Query<DeletePeopleQuery>,
+) -> Result<ApiResponse<DeleteReport>, Error> {
+    let (selection, bindings) = query.selection()?;
+    let index = tx.queue(|transaction| {
+        for (name, value) in bindings {
+            transaction.bind(name, value);
+        }
+        transaction.query(format!("LET $deleting = ({selection})"));
+        queue_edge_policy(transaction, &edges, Person::TABLE, "$deleting");
+        transaction.query("DELETE person WHERE id INSIDE $deleting RETURN id")
+    })?;
+    // Committed here rather than by the middleware: the report needs the
+    // deleted ids, and the hooks must only run once the deletes have landed.
+    // Run before, their count invalidation could let a concurrent count cache
+    // the old total again, and subscribers would hear of deletes that might
+    // still be cancelled.
+    let deleted: Vec<Thing> = tx
+        .commit()
+        .await
+        .map_err(still_related)?
+        .take((index, "id"))?;
+    for record in &deleted {
+        let id = match &record.id {
+            Id::String(id) => id.clone(),
+            id => id.to_string(),
+        };
+        let mutation = Mutation {
+            table: Person::TABLE,
+            id: &id,
+        };
+        hooks
+            .after_delete(mutation, &json!({ "id": record.to_string() }))
+            .await;
+    }
+
+    Ok(ApiResponse::ok(DeleteReport {
+        deleted: deleted.len(),
+    }))
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct LicensesQuery {
+    /// `true` keeps licenses that are active and not past `expires_at`,
+    /// `false` keeps the rest.
+    pub active: Option<bool>,
+}
+
+impl LicensesQuery {
+    pub fn filter(&self) -> &'static str {
+        match self.active {
+            Some(true) => {
+                " AND status = 'active' AND (expires_at = NONE OR expires_at > time::now())"
+            }
+            Some(false) => {
+                " AND (status != 'active' OR (expires_at != NONE AND expires_at <= time::now()))"
+            }
+            None => "",
+        }
+    }
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Licenses", skip(db, id))]
+pub async fn licenses(
+    Db(db): Db,
+    id: Path<String>,
+    Query(query): Query<LicensesQuery>,
+) -> Result<ApiResponse<Vec<License>>, Error> {
+    let person = Person::record(&id);
+    // The `<-licenses` edges of the person, filtered on their own properties.
+    let sql = format!(
+        "SELECT in.registration AS registration, issued_at, expires_at, status \
+         FROM licenses WHERE out = $person{} ORDER BY issued_at",
+        query.filter()
+    );
+    let licenses: Vec<License> = retry(&READ_RETRY, || {
+        traced_with_bindings(&sql, vec![Binding::new("person", &person)], async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(("person", &person))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+    Ok(ApiResponse::ok(licenses))
+}
+
+/// Earlier versions of the person, oldest first. Kept after the person is
+/// deleted.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "History", skip(db, id))]
+pub async fn person_history(
+    Db(db): Db,
     id: Path<String>,
-) -> Result<Json<Option<Person>>, Error> {
-    let person = db.delete((PERSON, &*id)).await?;
-    Ok(Json(person))
+) -> Result<ApiResponse<Vec<Version>>, Error> {
+    let record = Person::record(&id);
+    let mut versions = retry(&READ_RETRY, || history::history(&db, &record)).await?;
+    versions
+        .iter_mut()
+        .for_each(|version| plain_ids(&mut version.data));
+    Ok(ApiResponse::ok(versions))
+}
+
+/// The person as it was at `version`. The current version is read with
+/// `GET /person/:id`.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Version", skip(db, path))]
+pub async fn person_version(
+    Db(db): Db,
+    path: Path<(String, u64)>,
+) -> Result<ApiResponse<Version>, Error> {
+    let (id, n) = &*path;
+    let record = Person::record(id);
+    let mut found = retry(&READ_RETRY, || history::version(&db, &record, *n))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("version {n} of {record}")))?;
+    plain_ids(&mut found.data);
+    Ok(ApiResponse::ok(found))
+}
+
+/// Writes version `:version` back as a new update, so the version it
+/// replaces joins the history and nothing is lost. A person deleted since is
+/// `410 Gone`.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Revert", skip(db, hooks, principal, path))]
+pub async fn revert(
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    principal: Option<Extension<Principal>>,
+    path: Path<(String, u64)>,
+    Query(returning): Query<ReturnQuery>,
+) -> Result<ApiResponse<Option<serde_json::Value>>, Error> {
+    let (id, n) = &*path;
+    let record = Person::record(id);
+    let target = retry(&READ_RETRY, || history::version(&db, &record, *n))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("version {n} of {record}")))?;
+    // Only the written fields: computed ones are the database's to set.
+    let person: Person = serde_json::from_value(target.data).map_err(|_| Error::Db)?;
+    let data = json!(person);
+
+    let mutation = Mutation {
+        table: Person::TABLE,
+        id,
+    };
+    hooks.before_update(mutation, &data).await?;
+    let actor = principal.map(|Extension(principal)| principal.user_id);
+    let mut result = history::revert(
+        &db,
+        &record,
+        &data,
+        returning.mode.clause(),
+        actor.as_deref(),
+    )
+    .await?;
+    if let Some(result) = &mut result {
+        plain_ids(result);
+    }
+    hooks.after_update(mutation, &data).await;
+    Ok(ApiResponse::ok(result))
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "List", skip(db))]
-pub async fn list(State(db): State<Surreal<Client>>) -> Result<Json<Vec<Person>>, Error> {
-    let people = db.select(PERSON).await?;
-    Ok(Json(people))
+#[derive(Deserialize, Debug)]
+pub struct TagsBody {
+    tags: Vec<String>,
+}
+
+/// Adds the tags the person doesn't have yet. Like any update, the person as
+/// they were is kept in `person_history`.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Add Tags", skip(db, hooks, principal, id, body))]
+pub async fn add_tags(
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    principal: Option<Extension<Principal>>,
+    id: Path<String>,
+    ApiJson(body): ApiJson<TagsBody>,
+) -> Result<ApiResponse<Option<serde_json::Value>>, Error> {
+    let tags = clean_tags(body.tags)?;
+    let actor = principal.map(|Extension(principal)| principal.user_id);
+    let set = "tags = array::union(tags OR [], $data)";
+    change_tags(&db, &hooks, actor.as_deref(), &id, set, json!(tags)).await
+}
+
+/// Removing a tag the person doesn't have changes nothing but still answers
+/// with the person.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Remove Tag", skip(db, hooks, principal, path))]
+pub async fn remove_tag(
+    Db(db): Db,
+    State(hooks): State<MutationHooks>,
+    principal: Option<Extension<Principal>>,
+    path: Path<(String, String)>,
+) -> Result<ApiResponse<Option<serde_json::Value>>, Error> {
+    let (id, tag) = &*path;
+    let actor = principal.map(|Extension(principal)| principal.user_id);
+    let set = "tags = array::complement(tags OR [], [$data])";
+    change_tags(&db, &hooks, actor.as_deref(), id, set, json!(tag)).await
+}
+
+/// Hooks see the tags being changed before, and the whole person after.
+async fn change_tags(
+    db: &Surreal<Client>,
+    hooks: &MutationHooks,
+    actor: Option<&str>,
+    id: &str,
+    set: &str,
+    tags: serde_json::Value,
+) -> Result<ApiResponse<Option<serde_json::Value>>, Error> {
+    let mutation = Mutation {
+        table: Person::TABLE,
+        id,
+    };
+    hooks.before_update(mutation, &tags).await?;
+    let mut person = history::set_with_history(db, &Person::record(id), set, &tags, actor).await?;
+    if let Some(person) = &mut person {
+        plain_ids(person);
+    }
+    hooks.after_update(mutation, &json!(person)).await;
+    Ok(ApiResponse::ok(person))
+}
+
+#[derive(Serialize, Debug)]
+pub struct PeopleCount {
+    pub count: u64,
+}
+
+/// How many people match the `GET /people` filters, without fetching any.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Count People", skip(db))]
+pub async fn count_people(
+    Db(db): Db,
+    Query(query): Query<PeopleQuery>,
+) -> Result<ApiResponse<PeopleCount>, Error> {
+    let (filter, bindings) = query.filter();
+    let count = retry(&READ_RETRY, || {
+        count(&db, Person::TABLE, &filter, &bindings)
+    })
+    .await?;
+    Ok(ApiResponse::ok(PeopleCount { count }))
+}
+
+/// Most ids one `POST /people/lookup` may ask for.
+pub const MAX_LOOKUP_IDS: usize = 100;
+
+/// The answer to `POST /people/lookup`: the people found, in the order
+/// asked for, and the ids, as sent, that matched nobody.
+#[derive(Serialize, Debug)]
+pub struct PeopleLookup {
+    pub found: Vec<WithId<PersonView>>,
+    pub missing: Vec<String>,
+}
+
+impl PeopleLookup {
+    /// Each of `ids` as the record it names, repeats dropped. An id is
+    /// either bare, `abc`, or a whole `person:abc`.
+    pub fn records(ids: &[String]) -> Result<Vec<(String, Thing)>, Error> {
+        if ids.len() > MAX_LOOKUP_IDS {
+            return Err(Error::InvalidBody(format!(
+                "at most {MAX_LOOKUP_IDS} ids can be looked up at once, not {}",
+                ids.len()
+            )));
+        }
+        let mut records: Vec<(String, Thing)> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let record = if id.contains(':') {
+                parse_record(id)?
+            } else if id.is_empty() {
+                return Err(Error::InvalidId("an id can't be empty".into()));
+            } else {
+                Person::record(id)
+            };
+            if record.tb != Person::TABLE {
+                return Err(Error::InvalidId(format!("`{id}` isn't a person")));
+            }
+            if !records.iter().any(|(_, seen)| *seen == record) {
+                records.push((id.clone(), record));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Sorts `rows` into the order of `records`, noting those with none.
+    pub fn new(records: Vec<(String, Thing)>, mut rows: Vec<WithId<PersonView>>) -> Self {
+        let mut lookup = Self {
+            found: Vec::with_capacity(rows.len()),
+            missing: Vec::new(),
+        };
+        for (id, record) in records {
+            match rows.iter().position(|row| row.id == record) {
+                Some(i) => lookup.found.push(rows.swap_remove(i)),
+                None => lookup.missing.push(id),
+            }
+        }
+        lookup
+    }
+}
+
+/// Reads a list of people in one query, e.g. `["john", "person:jane"]`.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Lookup People", skip(db, ids))]
+pub async fn lookup_people(
+    Db(db): Db,
+    ApiJson(ids): ApiJson<Vec<String>>,
+) -> Result<ApiResponse<PeopleLookup>, Error> {
+    let records = PeopleLookup::records(&ids)?;
+    if records.is_empty() {
+        return Ok(ApiResponse::ok(PeopleLookup::new(records, Vec::new())));
+    }
+    let things: Vec<&Thing> = records.iter().map(|(_, record)| record).collect();
+    let sql = "SELECT * FROM $ids";
+    let rows: Vec<WithId<PersonView>> = retry(&READ_RETRY, || {
+        traced_with_bindings(sql, vec![Binding::new("ids", &things)], async {
+            db.query(sql)
+                .bind(correlation())
+                .bind(("ids", &things))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+    Ok(ApiResponse::ok(PeopleLookup::new(records, rows)))
+}
+
+#[derive(Serialize, Debug)]
+pub struct PeopleStats {
+    pub total: u64,
+    /// People per uppercased first letter of their name.
+    pub by_initial: BTreeMap<String, u64>,
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct InitialCount {
+    initial: String,
+    count: u64,
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Stats", skip(db))]
+pub async fn stats(Db(db): Db) -> Result<ApiResponse<PeopleStats>, Error> {
+    let stats = retry(&READ_RETRY, || people_stats(&db)).await?;
+    Ok(ApiResponse::ok(stats))
+}
+
+from_response! {
+    /// What the statements of [`people_stats`] return.
+    struct StatsResponse {
+        total: Option<u64> = (0, "total"),
+        initials: Vec<InitialCount> = 1,
+        last_updated: Option<DateTime<Utc>> = (2, "updated_at"),
+    }
+}
+
+/// Counts are aggregated by SurrealDB; no person rows leave the database.
+async fn people_stats(db: &Surreal<Client>) -> surrealdb::Result<PeopleStats> {
+    let sql = "\
+        SELECT count() AS total FROM person GROUP ALL;\
+        SELECT string::uppercase(string::slice(name, 0, 1)) AS initial, count() AS count \
+            FROM person GROUP BY initial;\
+        SELECT updated_at FROM person ORDER BY updated_at DESC LIMIT 1;";
+    let response = traced(sql, db.query(sql).bind(correlation())).await?;
+    let StatsResponse {
+        total,
+        initials,
+        last_updated,
+    } = StatsResponse::from_response(response)?;
+
+    Ok(PeopleStats {
+        total: total.unwrap_or(0),
+        by_initial: initials
+            .into_iter()
+            .map(|row| (row.initial, row.count))
+            .collect(),
+        last_updated,
+    })
 }
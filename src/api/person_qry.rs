@@ -1,25 +1,41 @@
+use crate::api::compression::request_decompression;
+use crate::api::hooks::{Mutation, MutationHooks};
+use crate::api::person::{create, delete, list, read, update};
+use crate::api::shed::batch_shed;
+use crate::api::{ApiResponse, Db, ResourceRoutes, SchemaJson, WithId};
 use crate::error::Error;
-use crate::surreal::db::Transaction;
-// use crate::surreal::db::QueryManager;
-use axum::extract::{Path, State};
-use axum::{Json, Router};
+use crate::state::AppState;
+use crate::surreal::ids::IdGenerator;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::model::SurrealModel;
+use crate::surreal::query_manager::{QueryManager, StatementPolicy};
+use axum::extract::State;
+use axum::Router;
 use axum_macros::debug_handler;
-use color_eyre::eyre::eyre;
 use serde::{Deserialize, Serialize};
-use surrealdb::sql::Thing;
+use serde_json::json;
 use surrealdb::{engine::remote::ws::Client, Surreal};
 
-const PERSON: &str = "person";
-
-pub fn person_query_routes() -> Router<Surreal<Client>> {
-    Router::new()
-        .route("/person/qry/:id", axum::routing::post(create))
-        .route("/person/qry/:id", axum::routing::get(read))
-        .route("/person/qry/:id", axum::routing::put(update))
-        .route("/person/qry/:id", axum::routing::delete(delete))
-        .route("/person/qry/people", axum::routing::get(list))
-        .route("/person/qry/batch_up", axum::routing::post(batch_up))
-        .route("/person/qry/batch_down", axum::routing::delete(batch_down))
+pub fn person_query_routes() -> Router<AppState> {
+    // Batches get their own small limits so a flood of imports can't take
+    // every slot from plain reads, and may be sent compressed.
+    let batch = ResourceRoutes::new()
+        .post("/person/qry/batch_up", batch_up)
+        .into_router()
+        .layer(request_decompression())
+        .layer(batch_shed());
+
+    // The same handlers as `/person/:id`, kept for clients that still use
+    // these paths; `repository.strategy` decides how they reach the
+    // database.
+    ResourceRoutes::new()
+        .post("/person/qry/:id", create)
+        .get("/person/qry/:id", read)
+        .put("/person/qry/:id", update)
+        .delete("/person/qry/:id", delete)
+        .get("/person/qry/people", list)
+        .into_router()
+        .merge(batch)
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,158 +43,68 @@ pub struct Person {
     name: String,
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Batch Delete", skip(db))]
-pub async fn batch_down(
-    State(db): State<Surreal<Client>>,
-) -> Result<Json<Option<Vec<Person>>>, Error> {
-    let sql = format!("DELETE {}", PERSON);
-    tracing::info!(sql);
-    let people: Option<Vec<Person>> = db.query(sql).await.unwrap().take(0)?;
-    Ok(Json(people))
+impl SurrealModel for Person {
+    const TABLE: &'static str = "person";
 }
 
-#[debug_handler]
-#[tracing::instrument(name = "Batch Create", skip(db, people))]
+/// Runs the `person` create hooks for each person, like `POST /person/:id`:
+/// any `before_create` failing refuses the whole batch, and `after_create`
+/// runs for each once the batch has committed.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Batch Create", skip(db, ids, hooks, people))]
 pub async fn batch_up(
-    State(db): State<Surreal<Client>>,
-    Json(people): Json<Vec<Person>>,
-) -> Result<Json<Option<Vec<Person>>>, Error> {
-    let people = batch_up_fn(&db, people).await?;
-    Ok(Json(Some(people)))
+    Db(db): Db,
+    State(ids): State<IdGenerator>,
+    State(hooks): State<MutationHooks>,
+    SchemaJson(people, _): SchemaJson<Vec<Person>, Person>,
+) -> Result<ApiResponse<Option<Vec<WithId<Person>>>>, Error> {
+    let people = batch_up_fn(&db, &ids, &hooks, people).await?;
+    Ok(ApiResponse::ok(Some(people)))
 }
 
-async fn batch_up_fn(db: &Surreal<Client>, people: Vec<Person>) -> Result<Vec<Person>, Error> {
-    let transaction = Transaction::begin(db).await?;
-    let conn = transaction.conn;
+async fn batch_up_fn(
+    db: &Surreal<Client>,
+    ids: &IdGenerator,
+    hooks: &MutationHooks,
+    people: Vec<Person>,
+) -> Result<Vec<WithId<Person>>, Error> {
+    let mut manager = QueryManager::new().with_policy(StatementPolicy::request_path());
+    let mut created = Vec::with_capacity(people.len());
     for person in people {
-        let sql = format!("CREATE person:uuid() CONTENT {{ name: '{}' }}", person.name);
-        conn.query(&sql).await?;
-    }
-    transaction.commit().await;
-    let sql = format!("SELECT * FROM {}", PERSON);
-    tracing::info!(sql);
-    let people: Vec<Person> = db.query(sql).await.unwrap().take(0)?;
-    Ok(people)
-}
-
-// region: CREATE
-#[debug_handler]
-// #[tracing::instrument(name = "Create", skip(db, id, person))]
-pub async fn create(
-    State(db): State<Surreal<Client>>,
-    id: Path<String>,
-    Json(person): Json<Person>,
-) -> Result<Json<Person>, Error> {
-    let person = create_person(&db, &id, person).await.map_err(|e| {
-        tracing::error!("{:?}", e);
-        e
-    });
-
-    match person {
-        Ok(person) => Ok(Json(person)),
-        Err(_) => Err(Error::Db),
+        let content = json!(person);
+        let id = ids.resolve_record(Person::TABLE, None, &content)?;
+        let mutation = Mutation {
+            table: Person::TABLE,
+            id: &id,
+        };
+        hooks.before_create(mutation, &content).await?;
+        // Batches are plain text, so the content goes in as a JSON object,
+        // which SurrealQL reads as is, escapes and all.
+        manager.add_query(format!(
+            "CREATE {} CONTENT {}",
+            Person::record(&id),
+            content
+        ));
+        created.push((id, content));
     }
-}
-
-// #[tracing::instrument(name = "Query: Create Person", skip(db, id, person))]
-async fn create_person(
-    db: &Surreal<Client>,
-    id: &str,
-    person: Person,
-) -> color_eyre::Result<Person> {
-    let sql = format!(
-        "CREATE {} CONTENT {{ name: '{}' }}",
-        Thing::from((PERSON, id)),
-        person.name
+    let report = manager.execute(db).await?;
+    tracing::info!(
+        statements = report.size.statements,
+        transactions = report.chunks.len(),
+        "Batch committed"
     );
-    tracing::info!(sql);
-    let person: Option<Person> = db.query(sql).await?.take(0)?;
-    match person {
-        Some(person) => Ok(person),
-        None => Err(eyre!("Person not created")),
+    for (id, content) in &created {
+        let mutation = Mutation {
+            table: Person::TABLE,
+            id,
+        };
+        hooks.after_create(mutation, content).await;
     }
-}
-// endregion
-
-#[debug_handler]
-#[tracing::instrument(name = "Read", skip(db, id))]
-pub async fn read(
-    State(db): State<Surreal<Client>>,
-    id: Path<String>,
-) -> Result<Json<Person>, Error> {
-    let person = read_person(&db, &id).await?;
-    Ok(Json(person.unwrap()))
-}
-
-#[debug_handler]
-#[tracing::instrument(name = "Update", skip(db, id, person))]
-pub async fn update(
-    State(db): State<Surreal<Client>>,
-    id: Path<String>,
-    Json(person): Json<Person>,
-) -> Result<Json<Person>, Error> {
-    let person = update_person(&db, &id, person).await?;
-    Ok(Json(person.unwrap()))
-}
-
-#[debug_handler]
-#[tracing::instrument(name = "Delete", skip(db, id))]
-pub async fn delete(
-    State(db): State<Surreal<Client>>,
-    id: Path<String>,
-) -> Result<Json<Option<Person>>, Error> {
-    let person = delete_person(&db, &id).await?;
-    Ok(Json(person))
-}
-
-#[debug_handler]
-#[tracing::instrument(name = "List", skip(db))]
-pub async fn list(State(db): State<Surreal<Client>>) -> Result<Json<Vec<Person>>, Error> {
-    let people = list_people(&db).await?;
-    Ok(Json(people))
-}
-
-#[tracing::instrument(name = "Query: Read Person", skip(db, id))]
-async fn read_person(db: &Surreal<Client>, id: &str) -> Result<Option<Person>, Error> {
-    let sql = format!(
-        "SELECT * FROM {} WHERE id = '{}'",
-        PERSON,
-        Thing::from((PERSON, id)),
-    );
-    tracing::info!(sql);
-    let person: Option<Person> = db.query(sql).await.unwrap().take(0).unwrap();
-    Ok(person)
-}
-
-#[tracing::instrument(name = "Query: Update Person", skip(db, id, person))]
-async fn update_person(
-    db: &Surreal<Client>,
-    id: &str,
-    person: Person,
-) -> Result<Option<Person>, Error> {
-    let sql = format!(
-        "UPDATE {} CONTENT {{ name: '{}' }}",
-        Thing::from((PERSON, id)),
-        person.name
-    );
-    tracing::info!(sql);
-    let person: Option<Person> = db.query(sql).await.unwrap().take(0).unwrap();
-    Ok(person)
-}
-
-#[tracing::instrument(name = "Query: Delete Person", skip(db, id))]
-async fn delete_person(db: &Surreal<Client>, id: &str) -> Result<Option<Person>, Error> {
-    let sql = format!("DELETE {}", Thing::from((PERSON, id)));
-    tracing::info!(sql);
-    let person: Option<Person> = db.query(sql).await.unwrap().take(0).unwrap();
-    Ok(person)
-}
-
-#[tracing::instrument(name = "Query: List People", skip(db))]
-async fn list_people(db: &Surreal<Client>) -> Result<Vec<Person>, Error> {
-    let sql = format!("SELECT * FROM {}", PERSON);
+    let sql = format!("SELECT * FROM {}", Person::TABLE);
     tracing::info!(sql);
-    let people: Vec<Person> = db.query(sql).await.unwrap().take(0).unwrap();
+    let people: Vec<WithId<Person>> = traced(&sql, async {
+        db.query(&sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
     Ok(people)
 }
@@ -0,0 +1,60 @@
+use crate::error::Error;
+use crate::surreal::schema::tables::known_fields;
+use serde::Deserialize;
+use serde_json::Value;
+
+// region: -- Projection
+/// `?fields=` on read endpoints, e.g. `?fields=id,name`.
+#[derive(Deserialize, Debug, Default)]
+pub struct FieldsQuery {
+    pub fields: Option<String>,
+}
+
+/// The fields a read asked for, checked against what `table` records can
+/// have, so only those are selected and sent back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projection {
+    fields: Vec<&'static str>,
+}
+
+impl Projection {
+    /// `None` when every field was asked for, by leaving `fields` out.
+    pub fn parse(table: &str, fields: Option<&str>) -> Result<Option<Self>, Error> {
+        let Some(fields) = fields else {
+            return Ok(None);
+        };
+        let known = known_fields(table);
+        let mut projection = Vec::new();
+        for field in fields.split(',').map(str::trim) {
+            let Some(field) = known.iter().find(|known| **known == field) else {
+                return Err(Error::InvalidQuery(format!(
+                    "`fields` has `{field}`, but {table} only has {}",
+                    known.join(", ")
+                )));
+            };
+            if !projection.contains(field) {
+                projection.push(*field);
+            }
+        }
+        Ok(Some(Self { fields: projection }))
+    }
+
+    pub fn fields(&self) -> &[&'static str] {
+        &self.fields
+    }
+
+    /// The `SELECT` list, plus `extra` fields the endpoint needs itself.
+    pub fn select(&self, extra: &[&'static str]) -> String {
+        let mut fields = self.fields.clone();
+        fields.extend(extra.iter().filter(|field| !self.fields.contains(field)));
+        fields.join(", ")
+    }
+
+    /// Drops whatever `row` has beyond the fields asked for.
+    pub fn trim(&self, row: &mut Value) {
+        if let Value::Object(row) = row {
+            row.retain(|field, _| self.fields.contains(&field.as_str()));
+        }
+    }
+}
+// endregion: -- Projection
@@ -0,0 +1,141 @@
+use crate::api::person::Person;
+use crate::api::{ApiResponse, Created, Db, ResourceRoutes, SchemaJson, WithId};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::edge::Edge;
+use crate::surreal::ids::{create_or_match, Creation, IdGenerator, IdStrategy};
+use crate::surreal::instrument::traced;
+use crate::surreal::model::SurrealModel;
+use crate::surreal::queries::RunQuery;
+use crate::surreal::retry::{retry, READ_RETRY};
+use axum::extract::{Path, State};
+use axum::Router;
+use axum_macros::debug_handler;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// SurrealDB keeps integers as `i64`; anything larger would come back as a
+/// rounded float.
+pub const MAX_REGISTRATION: u64 = i64::MAX as u64;
+
+pub fn registry_routes() -> Router<AppState> {
+    ResourceRoutes::new()
+        .post("/registry/:id", create_registry)
+        .get("/registry/:id", read_registry)
+        .get("/registry/:id/people", licensed_people)
+        .into_router()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Registry {
+    registration: u64,
+}
+
+impl SurrealModel for Registry {
+    const TABLE: &'static str = "registry";
+}
+
+impl Registry {
+    pub fn validated(self) -> Result<Self, Error> {
+        if self.registration > MAX_REGISTRATION {
+            return Err(Error::InvalidBody(format!(
+                "`registration` must be at most {MAX_REGISTRATION}"
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// `registry->licenses->person`: the registry issued the person a license.
+pub struct Licenses;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseStatus {
+    #[default]
+    Active,
+    Expired,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct LicenseProps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub status: LicenseStatus,
+}
+
+/// A license as listed for a person: the issuing registry's number plus the
+/// edge properties. Edges made before properties existed have none of them.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct License {
+    pub registration: Option<u64>,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub status: Option<LicenseStatus>,
+}
+
+impl Edge for Licenses {
+    const TABLE: &'static str = "licenses";
+    type From = Registry;
+    type To = Person;
+    type Props = LicenseProps;
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Create Registry", skip(db, ids, id, registry))]
+pub async fn create_registry(
+    Db(db): Db,
+    State(ids): State<IdGenerator>,
+    Path(id): Path<String>,
+    SchemaJson(registry, _): SchemaJson<Registry>,
+) -> Result<Created<Option<WithId<Registry>>>, Error> {
+    let registry = registry.validated()?;
+    let data = json!(registry);
+    let id = ids.resolve_record(Registry::TABLE, Some(&id), &data)?;
+    let location = format!("/registry/{id}");
+    if ids.strategy(Registry::TABLE) == IdStrategy::Natural {
+        return match create_or_match(&db, Registry::TABLE, &id, &data).await? {
+            Creation::Created(registry) => Ok(Created::new(location, registry)),
+            Creation::Existing(registry) => Ok(Created::existing(location, registry)),
+        };
+    }
+    let registry = traced("CREATE registry:? CONTENT $data", async {
+        db.create((Registry::TABLE, &*id)).content(registry).await
+    })
+    .await?;
+    Ok(Created::new(location, registry))
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Read Registry", skip(db, id))]
+pub async fn read_registry(
+    Db(db): Db,
+    id: Path<String>,
+) -> Result<ApiResponse<WithId<Registry>>, Error> {
+    let registry: Option<WithId<Registry>> = retry(&READ_RETRY, || {
+        traced(
+            "SELECT * FROM registry:?",
+            db.select((Registry::TABLE, &*id)),
+        )
+    })
+    .await?;
+    let registry = registry.ok_or_else(|| Registry::not_found(&id))?;
+    Ok(ApiResponse::ok(registry))
+}
+
+/// The people the registry has licensed, by name.
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Licensed People", skip(db, id))]
+pub async fn licensed_people(
+    Db(db): Db,
+    id: Path<String>,
+) -> Result<ApiResponse<Vec<WithId<Person>>>, Error> {
+    let bindings = BTreeMap::from([("registry".to_string(), json!(*id))]);
+    let people = db.run("person_by_license", bindings).await?.take(0)?;
+    Ok(ApiResponse::ok(people))
+}
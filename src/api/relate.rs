@@ -0,0 +1,55 @@
+use crate::api::{ApiJson, ApiResponse, Db, ResourceRoutes};
+use crate::error::Error;
+use crate::state::AppState;
+use crate::surreal::edge::{parse_record, relate_records, EdgeAllowList};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Router;
+use axum_macros::debug_handler;
+use serde::{Deserialize, Serialize};
+
+pub fn relate_routes() -> Router<AppState> {
+    ResourceRoutes::new().post("/relate", relate).into_router()
+}
+
+/// `{ "from": "registry:xyz", "edge": "licenses", "to": "person:abc" }`, with
+/// optional `data` stored on the edge.
+#[derive(Deserialize, Debug)]
+pub struct RelateRequest {
+    pub from: String,
+    pub edge: String,
+    pub to: String,
+    #[serde(default)]
+    pub data: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Relation {
+    pub id: String,
+    pub from: String,
+    pub edge: String,
+    pub to: String,
+}
+
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "Relate", skip(db, edges, request))]
+pub async fn relate(
+    Db(db): Db,
+    State(edges): State<EdgeAllowList>,
+    ApiJson(request): ApiJson<RelateRequest>,
+) -> Result<ApiResponse<Relation>, Error> {
+    let from = parse_record(&request.from)?;
+    let to = parse_record(&request.to)?;
+    edges.check(&from, &request.edge, &to)?;
+
+    let data = serde_json::Value::Object(request.data.unwrap_or_default());
+    let id = relate_records(&db, &from, &request.edge, &to, &data).await?;
+
+    Ok(ApiResponse::ok(Relation {
+        id: id.to_string(),
+        from: from.to_string(),
+        edge: request.edge,
+        to: to.to_string(),
+    })
+    .with_status(StatusCode::CREATED))
+}
@@ -0,0 +1,33 @@
+use axum::http::{HeaderName, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use uuid::Uuid;
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Id of the request being handled, if called from inside [`request_id`].
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Reuses the caller's `X-Request-Id` or assigns a fresh one, makes it
+/// available to the handler and echoes it back on the response.
+pub async fn request_id<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request ids are valid header values");
+    request.headers_mut().insert(REQUEST_ID, value.clone());
+
+    let mut response = CURRENT_REQUEST_ID.scope(id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID, value);
+    response
+}
@@ -0,0 +1,47 @@
+use crate::api::auth::Principal;
+use crate::api::current_request_id;
+use crate::surreal::request_log::{RequestRecord, REQUEST_LOG};
+use axum::extract::{MatchedPath, State};
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Keeps a summary of the request in the `requests` table when
+/// [`REQUEST_LOG`] samples it. Runs outside the rest of the middleware so
+/// shed, timed-out and unauthorized requests are kept too.
+pub async fn request_log<B>(
+    State(db): State<Surreal<Client>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    REQUEST_LOG.record_status(Instant::now(), status);
+    if REQUEST_LOG.keeps(&route, status) {
+        let record = RequestRecord {
+            at: None,
+            method,
+            route,
+            status,
+            latency_ms: start.elapsed().as_millis() as u64,
+            user: response
+                .extensions()
+                .get::<Principal>()
+                .map(|principal| principal.user_id.clone()),
+            request_id: current_request_id(),
+        };
+        REQUEST_LOG.spawn_write(db, record);
+    }
+    response
+}
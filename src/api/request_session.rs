@@ -0,0 +1,48 @@
+use crate::error::Error;
+use crate::surreal::request_session::{scoped, RequestSessions, SessionClient};
+use axum::body::{boxed, HttpBody, StreamBody};
+use axum::extract::State;
+use axum::http::{header, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::stream;
+
+// region: -- Middleware
+/// Runs the requests of the route groups in `request_sessions.routes` on a
+/// connection signed in with their `Authorization: Bearer` token, handed to
+/// the handlers as a [`SessionClient`] for [`crate::api::Db`]. Requests
+/// without a token get a `401`. The connection is kept until the response
+/// body is sent, since streamed pages query as they go.
+pub async fn request_session<B>(
+    State(sessions): State<RequestSessions>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !sessions.applies_to(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let session = match token {
+        Some(token) => sessions.open(token).await,
+        None => Err(Error::InvalidToken),
+    };
+    let session = match session {
+        Ok(session) => session,
+        Err(error) => return error.into_response(),
+    };
+    request
+        .extensions_mut()
+        .insert(SessionClient(session.client().clone()));
+
+    let (parts, body) = scoped(next.run(request)).await.into_parts();
+    let body = stream::unfold((body, session), |(mut body, session)| async move {
+        let chunk = body.data().await?;
+        Some((chunk, (body, session)))
+    });
+    Response::from_parts(parts, boxed(StreamBody::new(body)))
+}
+// endregion: -- Middleware
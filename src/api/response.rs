@@ -0,0 +1,279 @@
+use crate::api::{current_case, current_request_id, Cased};
+use crate::error::{Error, Problem};
+use axum::body::{Bytes, StreamBody};
+use axum::http::{header, HeaderName, HeaderValue, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures_core::Stream;
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+// region: -- ApiResponse
+/// Every response body has the same shape: `data` on success, `errors` on
+/// failure and `meta` always.
+#[derive(Serialize, Debug)]
+pub struct ApiResponse<T> {
+    pub data: Option<T>,
+    pub meta: Meta,
+    pub errors: Vec<Problem>,
+    #[serde(skip)]
+    status: StatusCode,
+    #[serde(skip)]
+    links: Option<String>,
+}
+
+/// Total number of items across every page of a list.
+pub const TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+#[derive(Serialize, Debug, Default)]
+pub struct Meta {
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    pub start: u32,
+    pub limit: u32,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+}
+
+impl Pagination {
+    /// RFC 8288 `Link` header value with `first`, `prev`, `next` and `last`
+    /// pages of `uri`, as far as they exist. Other query parameters are kept.
+    pub fn links(&self, uri: &Uri) -> Option<String> {
+        let limit = self.limit.max(1);
+        let start = self.start;
+        let mut links = Vec::new();
+        let mut link = |start: u32, rel: &str| {
+            links.push(format!("<{}>; rel=\"{rel}\"", page_uri(uri, start, limit)));
+        };
+
+        if start > 0 {
+            link(0, "first");
+            link(start.saturating_sub(limit), "prev");
+        }
+        let has_next = match self.total {
+            Some(total) => (start as usize + self.count) < total,
+            None => self.count >= limit as usize,
+        };
+        if has_next {
+            link(start + limit, "next");
+        }
+        if let Some(total) = self.total.filter(|&total| total > 0) {
+            let last = (total as u32 - 1) / limit * limit;
+            if last != start {
+                link(last, "last");
+            }
+        }
+        (!links.is_empty()).then(|| links.join(", "))
+    }
+}
+
+/// `uri` with its `start` and `limit` query parameters replaced.
+fn page_uri(uri: &Uri, start: u32, limit: u32) -> String {
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| {
+            let key = param.split('=').next().unwrap_or_default();
+            !param.is_empty() && key != "start" && key != "limit"
+        })
+        .collect();
+    let paging = format!("start={start}&limit={limit}");
+    params.push(&paging);
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+impl<T> ApiResponse<T> {
+    pub fn ok(data: T) -> Self {
+        Self {
+            data: Some(data),
+            meta: Meta {
+                request_id: current_request_id(),
+                pagination: None,
+            },
+            errors: Vec::new(),
+            status: StatusCode::OK,
+            links: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sends `pagination` in `meta` and as `Link` (pages of `uri`) and
+    /// `X-Total-Count` headers, for clients that don't read the envelope.
+    pub fn with_pagination(mut self, pagination: Pagination, uri: &Uri) -> Self {
+        self.meta.pagination = Some(pagination);
+        self.links = pagination.links(uri);
+        self
+    }
+}
+
+impl ApiResponse<()> {
+    pub fn error(problem: Problem) -> Self {
+        let status =
+            StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        Self {
+            data: None,
+            meta: Meta {
+                request_id: current_request_id(),
+                pagination: None,
+            },
+            errors: vec![problem],
+            status,
+            links: None,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        let links = self.links.clone();
+        let total = self.meta.pagination.and_then(|p| p.total);
+        let mut response = (self.status, Json(self)).into_response();
+        let headers = response.headers_mut();
+        if let Some(links) = links.and_then(|links| HeaderValue::from_str(&links).ok()) {
+            headers.insert(header::LINK, links);
+        }
+        if let Some(total) = total {
+            headers.insert(TOTAL_COUNT, HeaderValue::from(total));
+        }
+        response
+    }
+}
+// endregion: -- ApiResponse
+
+// region: -- Created
+/// `201 Created` with `Location` and `Content-Location` pointing at the new
+/// resource and its representation as the body. A create that found the
+/// resource already there is a `200 OK` with the same headers.
+#[derive(Debug)]
+pub struct Created<T> {
+    pub location: String,
+    pub body: T,
+    pub existed: bool,
+}
+
+impl<T> Created<T> {
+    pub fn new(location: impl Into<String>, body: T) -> Self {
+        Self {
+            location: location.into(),
+            body,
+            existed: false,
+        }
+    }
+
+    pub fn existing(location: impl Into<String>, body: T) -> Self {
+        Self {
+            existed: true,
+            ..Self::new(location, body)
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Created<T> {
+    fn into_response(self) -> Response {
+        let status = if self.existed {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        };
+        let mut response = ApiResponse::ok(self.body)
+            .with_status(status)
+            .into_response();
+        if let Ok(location) = HeaderValue::from_str(&self.location) {
+            let headers = response.headers_mut();
+            headers.insert(header::LOCATION, location.clone());
+            headers.insert(header::CONTENT_LOCATION, location);
+        }
+        response
+    }
+}
+// endregion: -- Created
+
+// region: -- Streamed
+/// An [`ApiResponse`] whose `data` array is written page by page as the
+/// pages arrive, so a list of any length is served in bounded memory.
+///
+/// The status is sent before the first page is read. An error after that
+/// can't become a problem response, so it is logged and the body is cut
+/// short instead, which clients see as truncated JSON.
+pub struct Streamed<S> {
+    pages: S,
+    total: Option<usize>,
+}
+
+impl<S> Streamed<S> {
+    pub fn new(pages: S) -> Self {
+        Self { pages, total: None }
+    }
+
+    /// Sends `X-Total-Count`, as paginated lists do.
+    pub fn with_total(mut self, total: usize) -> Self {
+        self.total = Some(total);
+        self
+    }
+}
+
+impl<S, T> IntoResponse for Streamed<S>
+where
+    S: Stream<Item = Result<Vec<T>, Error>> + Send + 'static,
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        let meta = Meta {
+            request_id: current_request_id(),
+            pagination: None,
+        };
+        // Rows are renamed here because the body goes out after the
+        // `casing` middleware has returned.
+        let case = current_case();
+        let mut meta = serde_json::to_value(&meta).unwrap_or_else(|_| json!({}));
+        case.apply(&mut meta);
+        let tail = format!("],\"meta\":{meta},\"errors\":[]}}");
+
+        let mut first = true;
+        let rows = self.pages.map(move |page| {
+            let page = page.map_err(|error| {
+                tracing::error!(%error, "Streamed response cut short");
+                error
+            })?;
+            let mut chunk = Vec::new();
+            for row in page {
+                if !std::mem::take(&mut first) {
+                    chunk.push(b',');
+                }
+                let mut row = serde_json::to_value(&row).map_err(|_| Error::Db)?;
+                case.apply(&mut row);
+                serde_json::to_writer(&mut chunk, &row).map_err(|_| Error::Db)?;
+            }
+            Ok::<_, Error>(Bytes::from(chunk))
+        });
+        let body = stream::once(async { Ok(Bytes::from_static(b"{\"data\":[")) })
+            .chain(rows)
+            .chain(stream::once(async move { Ok(Bytes::from(tail)) }));
+
+        let mut response = (
+            [(header::CONTENT_TYPE, "application/json")],
+            Extension(Cased),
+            StreamBody::new(body),
+        )
+            .into_response();
+        if let Some(total) = self.total {
+            response
+                .headers_mut()
+                .insert(TOTAL_COUNT, HeaderValue::from(total));
+        }
+        response
+    }
+}
+// endregion: -- Streamed
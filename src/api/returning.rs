@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+/// What an update responds with, from `?return=`: the record `after` the
+/// update (the default), as it was `before`, the JSON Patch `diff` between
+/// the two, or `none`. Maps straight onto SurrealQL's `RETURN` clause, so no
+/// second read is needed.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnMode {
+    Diff,
+    Before,
+    #[default]
+    After,
+    None,
+}
+
+impl ReturnMode {
+    pub fn clause(self) -> &'static str {
+        match self {
+            ReturnMode::Diff => "RETURN DIFF",
+            ReturnMode::Before => "RETURN BEFORE",
+            ReturnMode::After => "RETURN AFTER",
+            ReturnMode::None => "RETURN NONE",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ReturnQuery {
+    #[serde(default, rename = "return")]
+    pub mode: ReturnMode,
+}
@@ -0,0 +1,160 @@
+use axum::body::{Body, HttpBody};
+use axum::handler::Handler;
+use axum::http::{header, Method, StatusCode};
+use axum::routing::MethodRouter;
+use axum::Router;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+/// Every path built with [`ResourceRoutes`] in this process and the methods
+/// it answers. axum can't list a router's routes, so they're noted as they
+/// are added instead.
+pub static ROUTE_TABLE: Lazy<RouteTable> = Lazy::new(RouteTable::default);
+
+// region: -- ResourceRoutes
+/// Builds a resource's routes and keeps track of the methods each path
+/// serves, so every path also answers `OPTIONS` with an `Allow` header
+/// listing them. `GET` routes answer `HEAD` as well: axum runs the `GET`
+/// handler and drops the body, keeping its headers.
+#[must_use]
+pub struct ResourceRoutes<S = crate::state::AppState, B = Body> {
+    router: Router<S, B>,
+    allowed: BTreeMap<&'static str, BTreeSet<String>>,
+}
+
+impl<S, B> ResourceRoutes<S, B>
+where
+    S: Clone + Send + Sync + 'static,
+    B: HttpBody + Send + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            router: Router::new(),
+            allowed: BTreeMap::new(),
+        }
+    }
+
+    pub fn get<H, T>(self, path: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, S, B>,
+        T: 'static,
+    {
+        self.add(path, Method::GET, axum::routing::get(handler))
+    }
+
+    pub fn post<H, T>(self, path: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, S, B>,
+        T: 'static,
+    {
+        self.add(path, Method::POST, axum::routing::post(handler))
+    }
+
+    pub fn put<H, T>(self, path: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, S, B>,
+        T: 'static,
+    {
+        self.add(path, Method::PUT, axum::routing::put(handler))
+    }
+
+    pub fn delete<H, T>(self, path: &'static str, handler: H) -> Self
+    where
+        H: Handler<T, S, B>,
+        T: 'static,
+    {
+        self.add(path, Method::DELETE, axum::routing::delete(handler))
+    }
+
+    fn add(mut self, path: &'static str, method: Method, route: MethodRouter<S, B>) -> Self {
+        let methods = self.allowed.entry(path).or_default();
+        if method == Method::GET {
+            methods.insert(Method::HEAD.to_string());
+        }
+        methods.insert(method.to_string());
+        methods.insert(Method::OPTIONS.to_string());
+        self.router = self.router.route(path, route);
+        self
+    }
+
+    /// The methods `path` answers, as sent in `Allow`.
+    pub fn allow(&self, path: &str) -> Option<String> {
+        let methods = self.allowed.get(path)?;
+        Some(methods.iter().cloned().collect::<Vec<_>>().join(", "))
+    }
+
+    /// Builds the router, noting its routes in [`ROUTE_TABLE`].
+    pub fn into_router(self) -> Router<S, B> {
+        for (path, methods) in &self.allowed {
+            ROUTE_TABLE.record(path, methods);
+        }
+        let allows: Vec<_> = self
+            .allowed
+            .keys()
+            .map(|path| (*path, self.allow(path).unwrap_or_default()))
+            .collect();
+        let mut router = self.router;
+        for (path, allow) in allows {
+            router = router.route(
+                path,
+                axum::routing::options(move || {
+                    let allow = allow.clone();
+                    async move { (StatusCode::NO_CONTENT, [(header::ALLOW, allow)]) }
+                }),
+            );
+        }
+        router
+    }
+}
+
+impl<S, B> Default for ResourceRoutes<S, B>
+where
+    S: Clone + Send + Sync + 'static,
+    B: HttpBody + Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+// endregion: -- ResourceRoutes
+
+// region: -- RouteTable
+#[derive(Debug, Default)]
+pub struct RouteTable {
+    routes: Mutex<BTreeMap<String, BTreeSet<String>>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RouteEntry {
+    pub path: String,
+    pub methods: Vec<String>,
+}
+
+impl RouteTable {
+    /// Notes that `path` answers `methods`, on top of whatever it already
+    /// answered. Building the same routes again changes nothing.
+    pub fn record(&self, path: &str, methods: &BTreeSet<String>) {
+        self.routes
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default()
+            .extend(methods.iter().cloned());
+    }
+
+    /// The routes noted so far, by path.
+    pub fn entries(&self) -> Vec<RouteEntry> {
+        self.routes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, methods)| RouteEntry {
+                path: path.clone(),
+                methods: methods.iter().cloned().collect(),
+            })
+            .collect()
+    }
+}
+// endregion: -- RouteTable
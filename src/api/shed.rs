@@ -0,0 +1,166 @@
+use crate::error::Error;
+use axum::http::{header, HeaderValue, Request};
+use axum::response::{IntoResponse, Response};
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+pub static LIMITS: Lazy<Limits> = Lazy::new(|| Limits::new(&LimitSettings::default()));
+
+// region: -- LimitSettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct LimitSettings {
+    /// In-flight requests across the whole server.
+    pub global: usize,
+    /// In-flight requests per batch route.
+    pub batch: usize,
+    pub retry_after_secs: u64,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            global: 512,
+            batch: 4,
+            retry_after_secs: 1,
+        }
+    }
+}
+// endregion: -- LimitSettings
+
+// region: -- Limits
+/// The limits in effect, set from `limits` at startup and on reload. Every
+/// request reads them, so a reload applies to the next one.
+#[derive(Debug)]
+pub struct Limits {
+    settings: RwLock<LimitSettings>,
+}
+
+impl Limits {
+    pub fn new(settings: &LimitSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+        }
+    }
+
+    pub fn configure(&self, settings: &LimitSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    pub fn settings(&self) -> LimitSettings {
+        self.settings.read().unwrap().clone()
+    }
+}
+// endregion: -- Limits
+
+// region: -- layers
+/// Which of the [`LimitSettings`] a layer enforces.
+type Limit = fn(&LimitSettings) -> usize;
+
+/// Requests beyond the limit are turned away with 503 right away instead of
+/// queueing behind the ones already running.
+#[derive(Clone)]
+pub struct ShedLayer {
+    limit: Limit,
+    /// Shared by every route the layer is applied to, or `None` for a
+    /// count of their own.
+    in_flight: Option<Arc<AtomicUsize>>,
+}
+
+/// One limit, `limits.global`, shared by every route the layer is applied to.
+pub fn global_shed() -> ShedLayer {
+    ShedLayer {
+        limit: |settings| settings.global,
+        in_flight: Some(Arc::default()),
+    }
+}
+
+/// A separate `limits.batch` for each route the layer is applied to.
+pub fn batch_shed() -> ShedLayer {
+    ShedLayer {
+        limit: |settings| settings.batch,
+        in_flight: None,
+    }
+}
+
+impl<S> Layer<S> for ShedLayer {
+    type Service = Shed<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Shed {
+            inner,
+            limit: self.limit,
+            in_flight: self.in_flight.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Shed<S> {
+    inner: S,
+    limit: Limit,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl<S, B> Service<Request<B>> for Shed<S>
+where
+    S: Service<Request<B>>,
+    S::Response: IntoResponse + 'static,
+    S::Error: 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let settings = LIMITS.settings();
+        let Some(slot) = InFlight::take(&self.in_flight, (self.limit)(&settings)) else {
+            let response = overloaded(settings.retry_after_secs);
+            return Box::pin(async move { Ok(response) });
+        };
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = future.await;
+            drop(slot);
+            response.map(IntoResponse::into_response)
+        })
+    }
+}
+
+/// A request counted against a limit until it is dropped.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn take(in_flight: &Arc<AtomicUsize>, limit: usize) -> Option<Self> {
+        in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < limit).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(in_flight.clone()))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn overloaded(retry_after_secs: u64) -> Response {
+    let mut response = Error::Overloaded("service overloaded".into()).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+// endregion: -- layers
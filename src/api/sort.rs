@@ -0,0 +1,148 @@
+use crate::error::Error;
+use crate::surreal::schema::tables::known_fields;
+
+/// Most keys one `?sort=` may give.
+pub const MAX_SORT_KEYS: usize = 4;
+
+// region: -- Sort
+/// How values of a sort key compare.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collation {
+    /// `ci`: compares the lowercased value, so `alice` comes before `Bob`.
+    pub case_insensitive: bool,
+    /// `numeric`: runs of digits compare as numbers, so `item9` comes before
+    /// `item10`.
+    pub numeric: bool,
+    /// `unicode`: compares letters before accents and case, so `Émile` comes
+    /// between `eve` and `Zoë` rather than after both.
+    pub unicode: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: &'static str,
+    pub descending: bool,
+    pub collation: Collation,
+}
+
+/// `?sort=` on list endpoints: comma-separated `field:asc|desc[:collation]`
+/// keys, e.g. `?sort=name:asc:ci,date_of_birth:desc`. Collations are `ci`,
+/// `numeric` and `unicode`, and can be combined, e.g. `name:asc:ci:numeric`.
+/// Without any, values compare byte by byte.
+///
+/// Records that tie on every key are ordered by `id`, so a page boundary
+/// never falls between equals in a different place from one request to the
+/// next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    keys: Vec<SortKey>,
+}
+
+impl Sort {
+    /// `sort`, checked against the fields `table` records can have, or
+    /// `default` when it is left out.
+    pub fn parse(table: &str, sort: Option<&str>, default: &str) -> Result<Self, Error> {
+        let known = known_fields(table);
+        let invalid = |message: String| Error::InvalidQuery(format!("`sort`: {message}"));
+        let mut keys: Vec<SortKey> = Vec::new();
+        for key in sort.unwrap_or(default).split(',').map(str::trim) {
+            let mut parts = key.split(':');
+            let field = parts.next().unwrap_or_default();
+            let Some(field) = known.iter().find(|known| **known == field) else {
+                return Err(invalid(format!(
+                    "`{field}` isn't a field of {table}; it has {}",
+                    known.join(", ")
+                )));
+            };
+            let descending = match parts.next() {
+                None | Some("asc") => false,
+                Some("desc") => true,
+                Some(other) => {
+                    return Err(invalid(format!(
+                        "`{other}` isn't a direction; use `asc` or `desc`"
+                    )))
+                }
+            };
+            let mut collation = Collation::default();
+            for option in parts {
+                match option {
+                    "ci" => collation.case_insensitive = true,
+                    "numeric" => collation.numeric = true,
+                    "unicode" => collation.unicode = true,
+                    other => {
+                        return Err(invalid(format!(
+                            "`{other}` isn't a collation; use `ci`, `numeric` or `unicode`"
+                        )))
+                    }
+                }
+            }
+            if keys.iter().any(|seen| seen.field == *field) {
+                return Err(invalid(format!("`{field}` is given more than once")));
+            }
+            keys.push(SortKey {
+                field,
+                descending,
+                collation,
+            });
+        }
+        if keys.len() > MAX_SORT_KEYS {
+            return Err(invalid(format!("at most {MAX_SORT_KEYS} keys")));
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn keys(&self) -> &[SortKey] {
+        &self.keys
+    }
+
+    /// The fields the keys sort on, to be selected along with the rest.
+    pub fn fields(&self) -> Vec<&'static str> {
+        self.keys.iter().map(|key| key.field).collect()
+    }
+
+    /// What the `SELECT` list needs on top of the fields: `ORDER BY` only
+    /// takes selected fields, so case-insensitive keys are selected
+    /// lowercased under an alias of their own, e.g.
+    /// `string::lowercase(name) AS sort_0`.
+    pub fn computed(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| key.collation.case_insensitive)
+            .map(|(i, key)| format!("string::lowercase({}) AS {}", key.field, alias(i)))
+            .collect()
+    }
+
+    /// ` ORDER BY ...`, ending on `id`.
+    pub fn order_by(&self) -> String {
+        let mut terms: Vec<String> = self
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let mut term = if key.collation.case_insensitive {
+                    alias(i)
+                } else {
+                    key.field.to_string()
+                };
+                if key.collation.unicode {
+                    term.push_str(" COLLATE");
+                }
+                if key.collation.numeric {
+                    term.push_str(" NUMERIC");
+                }
+                term.push_str(if key.descending { " DESC" } else { " ASC" });
+                term
+            })
+            .collect();
+        if !self.keys.iter().any(|key| key.field == "id") {
+            terms.push("id ASC".into());
+        }
+        format!(" ORDER BY {}", terms.join(", "))
+    }
+}
+
+fn alias(i: usize) -> String {
+    format!("sort_{i}")
+}
+// endregion: -- Sort
@@ -0,0 +1,20 @@
+use crate::surreal::db::RequestTransaction;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+// region: -- Middleware
+/// Opens a [`RequestTransaction`] to the handler, for [`crate::api::Tx`].
+/// What the handler left queued is sent as one transaction when the response
+/// is a success or a redirect, and dropped otherwise, before the response is
+/// sent; a failed commit replaces the response with its error.
+pub async fn transaction<B>(request: Request<B>, next: Next<B>) -> Response {
+    let (response, transaction) = RequestTransaction::run(next.run(request)).await;
+    let status = response.status();
+    let commit = status.is_success() || status.is_redirection();
+    match transaction.end(commit).await {
+        Ok(()) => response,
+        Err(error) => error.into_response(),
+    }
+}
+// endregion: -- Middleware
@@ -0,0 +1,66 @@
+use crate::surreal::edge::parse_record;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use surrealdb::sql::Thing;
+
+// region: -- WithId
+/// A record together with its id. Record structs only hold the fields that
+/// are written, so a response built from one alone has no `id`; wrapped, it
+/// answers `{"id": "person:abc", ...fields}`.
+///
+/// The id is always written as a plain `table:id` string, and read from
+/// either that or the `{"tb": ..., "id": ...}` object SurrealDB sends.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WithId<T> {
+    #[serde(with = "record_id")]
+    pub id: Thing,
+    #[serde(flatten)]
+    pub record: T,
+}
+
+impl<T> WithId<T> {
+    pub fn new(id: Thing, record: T) -> Self {
+        Self { id, record }
+    }
+}
+
+/// Writes every record id in `value`, as SurrealDB's `{"tb": ..., "id": ...}`
+/// object, as a plain `table:id` string instead: the record's own `id`, and
+/// any it points to like an edge's `in` and `out` or an included record's.
+pub fn plain_ids(value: &mut Value) {
+    match value {
+        Value::Object(fields) if fields.len() == 2 && fields.contains_key("tb") => {
+            if let Ok(record) = serde_json::from_value::<Thing>(value.clone()) {
+                *value = Value::String(record.to_string());
+            }
+        }
+        Value::Object(fields) => fields.values_mut().for_each(plain_ids),
+        Value::Array(items) => items.iter_mut().for_each(plain_ids),
+        _ => {}
+    }
+}
+
+/// `#[serde(with)]` for a [`Thing`] written as `table:id` and read from
+/// that or SurrealDB's object form.
+pub mod record_id {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Form {
+        Plain(String),
+        Object(Thing),
+    }
+
+    pub fn serialize<S: Serializer>(record: &Thing, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(record)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Thing, D::Error> {
+        match Form::deserialize(deserializer)? {
+            Form::Plain(record) => parse_record(&record).map_err(serde::de::Error::custom),
+            Form::Object(record) => Ok(record),
+        }
+    }
+}
+// endregion: -- WithId
@@ -0,0 +1,401 @@
+use crate::api::fanout::{Fanout, TableEvent};
+use crate::api::hooks::{Mutation, MutationHook};
+use crate::api::ResourceRoutes;
+use crate::error::Error;
+use crate::state::AppState;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::Router;
+use axum_macros::debug_handler;
+use futures_core::future::BoxFuture;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Messages queued for a connection before new events for it are dropped.
+const OUTBOX_SIZE: usize = 64;
+const MAX_TOPICS: usize = 100;
+
+pub fn ws_routes() -> Router<AppState> {
+    ResourceRoutes::new().get("/ws", ws).into_router()
+}
+
+// region: -- Protocol
+/// What clients send, as JSON text frames tagged by `type`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// `topic` is a table, e.g. `person`, or one record, e.g. `person:john`.
+    Subscribe {
+        topic: String,
+    },
+    Unsubscribe {
+        topic: String,
+    },
+    /// Answered with a `pong` carrying the same `id`.
+    Ping {
+        id: Option<Value>,
+    },
+}
+
+/// What the server sends.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        topic: String,
+    },
+    Unsubscribed {
+        topic: String,
+    },
+    Pong {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<Value>,
+    },
+    /// A change to a record the connection is subscribed to.
+    Event {
+        topic: String,
+        action: Action,
+        record: String,
+        /// The record as written; `null` for deletes.
+        data: Value,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Tables whose writes all run the [`crate::api::hooks::MutationHooks`], so
+/// their subscribers hear of every change. Writes to other tables, e.g.
+/// through `/registry` or `/relate`, don't publish events.
+pub const TOPIC_TABLES: &[&str] = &["person"];
+
+/// Checks `topic` is one of the [`TOPIC_TABLES`], or a record of one.
+pub fn parse_topic(topic: &str) -> Result<(&str, Option<&str>), String> {
+    let (table, id) = match topic.split_once(':') {
+        Some((table, id)) if !id.is_empty() => (table, Some(id)),
+        Some(_) => return Err(format!("`{topic}` has no record id")),
+        None => (topic, None),
+    };
+    if !TOPIC_TABLES.contains(&table) {
+        return Err(format!(
+            "`{table}` is not a table that can be subscribed to"
+        ));
+    }
+    Ok((table, id))
+}
+// endregion: -- Protocol
+
+// region: -- ConnectionRegistry
+#[derive(Debug)]
+struct Connection {
+    topics: BTreeSet<String>,
+    outbox: mpsc::Sender<ServerMessage>,
+    /// What forwards each table's feed here, while any topic is on it.
+    forwarders: HashMap<String, JoinHandle<()>>,
+}
+
+#[derive(Debug, Default)]
+struct Connections {
+    next_id: u64,
+    open: HashMap<u64, Connection>,
+}
+
+/// The open `/ws` connections and what each is subscribed to. Registered as
+/// a [`MutationHook`], so every write made through the API goes out on the
+/// [`Fanout`] and reaches the connections subscribed to its table or record.
+/// A connection takes each table's feed once, however many of its topics are
+/// on that table.
+///
+/// Delivery is best effort: a connection that doesn't keep up misses the
+/// events that don't fit in its outbox.
+#[derive(Clone, Default)]
+pub struct ConnectionRegistry {
+    connections: Arc<Mutex<Connections>>,
+    fanout: Fanout,
+}
+
+impl ConnectionRegistry {
+    /// A registry whose events stay in this instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_fanout(fanout: Fanout) -> Self {
+        Self {
+            connections: Arc::default(),
+            fanout,
+        }
+    }
+
+    pub fn fanout(&self) -> &Fanout {
+        &self.fanout
+    }
+
+    /// Opens a connection, returning its id and the messages to send it.
+    pub fn connect(&self) -> (u64, mpsc::Receiver<ServerMessage>) {
+        let (outbox, messages) = mpsc::channel(OUTBOX_SIZE);
+        let mut connections = self.connections.lock().unwrap();
+        connections.next_id += 1;
+        let id = connections.next_id;
+        connections.open.insert(
+            id,
+            Connection {
+                topics: BTreeSet::new(),
+                outbox,
+                forwarders: HashMap::new(),
+            },
+        );
+        (id, messages)
+    }
+
+    pub fn disconnect(&self, id: u64) {
+        let Some(connection) = self.connections.lock().unwrap().open.remove(&id) else {
+            return;
+        };
+        for (table, forwarder) in connection.forwarders {
+            forwarder.abort();
+            self.fanout.leave(&table);
+        }
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.lock().unwrap().open.len()
+    }
+
+    /// Subscriptions across every connection.
+    pub fn subscriptions(&self) -> usize {
+        let connections = self.connections.lock().unwrap();
+        connections
+            .open
+            .values()
+            .map(|connection| connection.topics.len())
+            .sum()
+    }
+
+    /// Connections subscribed to `topic` itself.
+    pub fn subscribers(&self, topic: &str) -> usize {
+        let connections = self.connections.lock().unwrap();
+        connections
+            .open
+            .values()
+            .filter(|connection| connection.topics.contains(topic))
+            .count()
+    }
+
+    /// Applies a message from connection `id` and returns the reply.
+    pub fn handle(&self, id: u64, message: ClientMessage) -> ServerMessage {
+        let mut connections = self.connections.lock().unwrap();
+        let Some(connection) = connections.open.get_mut(&id) else {
+            return ServerMessage::Error {
+                message: "connection is closed".into(),
+            };
+        };
+        match message {
+            ClientMessage::Subscribe { topic } => {
+                let table = match parse_topic(&topic) {
+                    Ok((table, _)) => table.to_string(),
+                    Err(message) => return ServerMessage::Error { message },
+                };
+                if connection.topics.len() >= MAX_TOPICS && !connection.topics.contains(&topic) {
+                    return ServerMessage::Error {
+                        message: format!("at most {MAX_TOPICS} topics per connection"),
+                    };
+                }
+                connection.topics.insert(topic.clone());
+                if let Entry::Vacant(forwarder) = connection.forwarders.entry(table) {
+                    let events = self.fanout.join(forwarder.key());
+                    forwarder.insert(self.forward(id, events));
+                }
+                ServerMessage::Subscribed { topic }
+            }
+            ClientMessage::Unsubscribe { topic } => {
+                connection.topics.remove(&topic);
+                if let Ok((table, _)) = parse_topic(&topic) {
+                    let watched = connection
+                        .topics
+                        .iter()
+                        .any(|topic| parse_topic(topic).is_ok_and(|(other, _)| other == table));
+                    if !watched {
+                        if let Some(forwarder) = connection.forwarders.remove(table) {
+                            forwarder.abort();
+                            self.fanout.leave(table);
+                        }
+                    }
+                }
+                ServerMessage::Unsubscribed { topic }
+            }
+            ClientMessage::Ping { id } => ServerMessage::Pong { id },
+        }
+    }
+
+    /// Queues `reply` for connection `id`, dropping it if the outbox is full.
+    fn reply(&self, id: u64, reply: ServerMessage) {
+        let connections = self.connections.lock().unwrap();
+        if let Some(connection) = connections.open.get(&id) {
+            let _ = connection.outbox.try_send(reply);
+        }
+    }
+
+    /// Sends an event for `record` to every connection subscribed to its
+    /// table or to it, on every instance sharing the fanout's bus.
+    pub fn publish(&self, action: Action, mutation: Mutation<'_>, data: &Value) {
+        self.fanout.publish(TableEvent {
+            action,
+            table: mutation.table.to_string(),
+            record: mutation.to_string(),
+            data: data.clone(),
+        });
+    }
+
+    /// Hands connection `id` the events from one table's feed until aborted.
+    fn forward(&self, id: u64, mut events: broadcast::Receiver<TableEvent>) -> JoinHandle<()> {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => registry.deliver(id, event),
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!(
+                            connection = id,
+                            missed,
+                            "Dropped events for a slow connection"
+                        )
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Queues `event` for connection `id` once for each of its topics it
+    /// matches: its table, its record or both.
+    fn deliver(&self, id: u64, event: TableEvent) {
+        let connections = self.connections.lock().unwrap();
+        let Some(connection) = connections.open.get(&id) else {
+            return;
+        };
+        for topic in [event.table.as_str(), event.record.as_str()] {
+            if !connection.topics.contains(topic) {
+                continue;
+            }
+            let message = ServerMessage::Event {
+                topic: topic.to_string(),
+                action: event.action,
+                record: event.record.clone(),
+                data: event.data.clone(),
+            };
+            if connection.outbox.try_send(message).is_err() {
+                tracing::debug!(connection = id, record = %event.record, "Dropped event for a slow connection");
+            }
+        }
+    }
+
+    /// Runs one connection until either side closes it.
+    pub async fn serve(self, socket: WebSocket) {
+        let (id, mut messages) = self.connect();
+        tracing::debug!(connection = id, "WebSocket connected");
+        let (mut sink, mut stream) = socket.split();
+
+        let writer = tokio::spawn(async move {
+            while let Some(message) = messages.recv().await {
+                let Ok(text) = serde_json::to_string(&message) else {
+                    continue;
+                };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(Ok(message)) = stream.next().await {
+            let reply = match message {
+                Message::Text(text) => match serde_json::from_str(&text) {
+                    Ok(message) => self.handle(id, message),
+                    Err(error) => ServerMessage::Error {
+                        message: format!("invalid message: {error}"),
+                    },
+                },
+                Message::Close(_) => break,
+                // Protocol pings are answered by the socket itself.
+                Message::Binary(_) | Message::Ping(_) | Message::Pong(_) => continue,
+            };
+            self.reply(id, reply);
+        }
+
+        self.disconnect(id);
+        writer.abort();
+        tracing::debug!(connection = id, "WebSocket disconnected");
+    }
+}
+
+impl fmt::Debug for ConnectionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionRegistry")
+            .field("connections", &self.connections())
+            .field("feeds", &self.fanout.feeds())
+            .finish()
+    }
+}
+
+impl MutationHook for ConnectionRegistry {
+    fn name(&self) -> &'static str {
+        "ws_events"
+    }
+
+    fn after_create<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.publish(Action::Create, mutation, record);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn after_update<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.publish(Action::Update, mutation, record);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn after_delete<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.publish(Action::Delete, mutation, &Value::Null);
+        Box::pin(async { Ok(()) })
+    }
+}
+// endregion: -- ConnectionRegistry
+
+// region: -- Handler
+/// Upgrades to a WebSocket speaking [`ClientMessage`] and [`ServerMessage`].
+#[debug_handler(state = AppState)]
+#[tracing::instrument(name = "WebSocket", skip(connections, upgrade))]
+pub async fn ws(
+    State(connections): State<ConnectionRegistry>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| connections.serve(socket))
+}
+// endregion: -- Handler
@@ -0,0 +1,325 @@
+mod report;
+
+pub use report::*;
+
+use crate::api;
+use crate::api::auth::AdminAuth;
+use crate::api::hooks::{AuditLog, CountInvalidation, MutationHooks};
+use crate::api::shed::LIMITS;
+use crate::api::{
+    Catalogs, ConnectionRegistry, Fanout, ResourceRoutes, CASING, FAULTS, MAINTENANCE,
+};
+use crate::config::{ConfigReloader, Settings};
+use crate::server::Listener;
+use crate::state::AppState;
+use crate::surreal;
+use crate::surreal::admin::{AdminDatabase, Scope};
+use crate::surreal::breaker::BREAKER;
+use crate::surreal::count::COUNTS;
+use crate::surreal::db::Database;
+use crate::surreal::edge::EdgeAllowList;
+use crate::surreal::flags::FeatureFlags;
+use crate::surreal::ids::IdGenerator;
+use crate::surreal::queries::QUERIES;
+use crate::surreal::query_manager::TRANSACTIONS;
+use crate::surreal::repository::Repository;
+use crate::surreal::request_log::REQUEST_LOG;
+use crate::surreal::request_session::RequestSessions;
+use crate::surreal::schema::tables::SchemaGuard;
+use crate::surreal::session::SESSION;
+use crate::surreal::slow_log::SLOW_QUERIES;
+use crate::surreal::ttl::TTL;
+use crate::surreal::write_behind::WriteBehind;
+use crate::telemetry;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::middleware;
+use axum::response::IntoResponse;
+use axum::Router;
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use std::sync::Arc;
+use tower_http::trace::TraceLayer;
+
+// region: -- run
+/// Everything the server does once its configuration is loaded and valid:
+/// builds the app and serves it where `server` says until it stops.
+pub async fn run(configuration: Settings) -> Result<()> {
+    if let Some(level) = &configuration.log_level {
+        telemetry::set_log_level(level).map_err(|e| eyre!(e))?;
+    }
+
+    let app = build(&configuration).await?;
+    #[cfg(unix)]
+    app.state.config.spawn_on_hangup()?;
+
+    let listener = Listener::bind(&configuration.server)?;
+    tracing::info!("Listening on {}", listener);
+    listener.serve(app.into_router()).await?;
+
+    Ok(())
+}
+// endregion: -- run
+
+// region: -- snapshot command
+const SNAPSHOT_USAGE: &str = "usage: snapshot export <file> [<namespace> <database>]\n       \
+                              snapshot import <file> <namespace> <database>";
+
+/// `snapshot export` and `snapshot import`: the admin snapshot endpoints for
+/// when the server isn't running. Exports default to the configured scope.
+pub async fn snapshot_command(configuration: &Settings, args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let scope = |namespace: &str, database: &str| Scope {
+        namespace: namespace.into(),
+        database: database.into(),
+    };
+    let admin = AdminDatabase::new(&configuration.database).await?;
+
+    match args.as_slice() {
+        ["export", path, rest @ ..] => {
+            let from = match rest {
+                [] => None,
+                [namespace, database] => Some(scope(namespace, database)),
+                _ => bail!(SNAPSHOT_USAGE),
+            };
+            let snapshot = admin.export(from).await?;
+            std::fs::write(path, &snapshot.surql)?;
+            eprintln!(
+                "exported {} tables and {} records from {}/{} to {path}",
+                snapshot.tables,
+                snapshot.records,
+                snapshot.scope.namespace,
+                snapshot.scope.database
+            );
+        }
+        ["import", path, namespace, database] => {
+            let surql = std::fs::read_to_string(path)?;
+            let report = admin.restore(scope(namespace, database), &surql).await?;
+            eprintln!(
+                "ran {} statements from {path} in {namespace}/{database}",
+                report.statements
+            );
+        }
+        _ => bail!(SNAPSHOT_USAGE),
+    }
+    Ok(())
+}
+// endregion: -- snapshot command
+
+// region: -- restore command
+const RESTORE_USAGE: &str = "usage: restore <snapshot>";
+
+/// `restore <snapshot>`: stages a backup from `backup.target` and checks it
+/// like `POST /admin/restore`. With no server to switch, the report says
+/// which database to point `database.database` at instead.
+pub async fn restore_command(configuration: &Settings, args: &[String]) -> Result<()> {
+    let [snapshot] = args else {
+        bail!(RESTORE_USAGE)
+    };
+    let admin = AdminDatabase::new(&configuration.database).await?;
+    let store = surreal::backup::store::open(&configuration.backup.target)?;
+    let live = Scope {
+        namespace: configuration.database.namespace.clone(),
+        database: configuration.database.database.clone(),
+    };
+
+    let plan = surreal::restore::stage(&admin, store.as_ref(), snapshot, &live).await?;
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+    if !plan.passed {
+        bail!(
+            "restored {snapshot} into {}/{}, but its record counts don't match",
+            plan.staging.namespace,
+            plan.staging.database
+        );
+    }
+    eprintln!(
+        "restored {snapshot} into {}/{}; set `database.database: {}` to use it",
+        plan.staging.namespace, plan.staging.database, plan.staging.database
+    );
+    Ok(())
+}
+// endregion: -- restore command
+
+// region: -- App
+/// A fully wired application: the state every handler shares and its routes,
+/// not yet wrapped in middleware. Merge extra routes before calling
+/// [`App::into_router`] so they get the same middleware as the rest.
+pub struct App {
+    pub state: AppState,
+    pub routes: Router<AppState>,
+    settings: Settings,
+}
+
+/// Connects to the database and gets everything ready to serve: pings the
+/// connection, checks the server version, applies the schema, connects the
+/// cache backend, primes the flag cache, starts the background refresh,
+/// expiry, TTL, backup and write-behind tasks and logs the [`StartupReport`].
+/// Fails before anything listens if any of that goes wrong.
+///
+/// Used by `main` and by the test harness, so both run the same app.
+#[tracing::instrument(name = "App: Build", skip(configuration))]
+pub async fn build(configuration: &Settings) -> Result<App> {
+    SLOW_QUERIES.configure(&configuration.slow_query);
+    TRANSACTIONS.configure(&configuration.transactions);
+    SESSION.configure(&configuration.session);
+    BREAKER.configure(&configuration.breaker);
+    REQUEST_LOG.configure(&configuration.request_log);
+    CASING.configure(&configuration.response);
+    COUNTS.configure(&configuration.counts);
+    MAINTENANCE.configure(&configuration.maintenance);
+    TTL.configure(&configuration.ttl);
+    FAULTS.configure(&configuration.faults);
+    LIMITS.configure(&configuration.limits);
+    QUERIES.load(&configuration.queries)?;
+
+    // region: -- pre-flight
+    let db = Database::new(&configuration.database).await?;
+    SESSION.register(db.client.clone(), &configuration.database);
+    db.ping().await?;
+    let version = surreal::version::check_version(&db.client, &configuration.version_check).await?;
+    let schema =
+        surreal::schema::apply(&db.client, &configuration.schema, &configuration.edges).await?;
+    let admin = AdminDatabase::new(&configuration.database).await?;
+    COUNTS.use_backend(surreal::cache::open(&configuration.cache).await?);
+    report_pending_batches();
+    // endregion: -- pre-flight
+
+    // region: -- warm-up
+    let flags = FeatureFlags::load(db.client.clone()).await?;
+    // The client can't receive `LIVE SELECT` notifications yet, so these
+    // intervals keep the caches and license statuses current instead.
+    flags.spawn_refresh(&configuration.flags);
+    surreal::licenses::spawn_expiry(db.client.clone(), &configuration.licenses);
+    surreal::ttl::spawn(db.client.clone());
+    surreal::backup::spawn_scheduler(
+        admin.clone(),
+        Scope {
+            namespace: configuration.database.namespace.clone(),
+            database: configuration.database.database.clone(),
+        },
+        &configuration.backup,
+    )?;
+    let write_behind = WriteBehind::new(&configuration.write_behind);
+    if write_behind.is_enabled() {
+        write_behind.spawn(db.client.clone());
+    }
+    // endregion: -- warm-up
+
+    let routes = routes(configuration);
+    let startup = StartupReport::new(configuration, version, schema);
+    startup.emit();
+
+    let connections = ConnectionRegistry::with_fanout(Fanout::connect(&configuration.live).await?);
+    let state = AppState {
+        db: db.client,
+        admin,
+        admin_auth: AdminAuth::new(&configuration.admin),
+        flags,
+        hooks: MutationHooks::new()
+            .with(AuditLog)
+            .with(CountInvalidation)
+            .with(connections.clone()),
+        catalogs: Catalogs::new(),
+        connections,
+        config: ConfigReloader::new(configuration.clone()),
+        ids: IdGenerator::new(&configuration.ids),
+        edges: EdgeAllowList::new(&configuration.edges),
+        schema: SchemaGuard::new(&configuration.schema),
+        write_behind,
+        repository: Repository::new(&configuration.repository),
+        sessions: RequestSessions::new(&configuration.request_sessions, &configuration.database),
+        startup: Arc::new(startup),
+    };
+
+    Ok(App {
+        state,
+        routes,
+        settings: configuration.clone(),
+    })
+}
+
+/// Every route the API serves, without middleware.
+pub fn routes(configuration: &Settings) -> Router<AppState> {
+    Router::new()
+        .merge(api::person_routes())
+        .merge(api::import_routes())
+        .merge(api::person_query_routes())
+        .merge(api::registry_routes())
+        .merge(api::relate_routes())
+        .merge(api::graph_routes())
+        .merge(api::admin_routes(&configuration.admin))
+        .merge(api::health_routes())
+        .merge(api::metrics_routes())
+        .merge(api::ws_routes())
+        .merge(
+            ResourceRoutes::new()
+                .get("/health_check", health_check)
+                .into_router(),
+        )
+}
+
+impl App {
+    pub fn merge(mut self, routes: Router<AppState>) -> Self {
+        self.routes = self.routes.merge(routes);
+        self
+    }
+
+    /// Wraps the routes in the middleware stack and hands them their state.
+    pub fn into_router(self) -> Router {
+        let Self {
+            state,
+            routes,
+            settings,
+        } = self;
+
+        routes
+            .layer(middleware::from_fn(api::transaction))
+            .layer(middleware::from_fn(api::read_only))
+            .layer(middleware::from_fn(api::inject_faults))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                api::request_session,
+            ))
+            .layer(middleware::from_fn_with_state(state.clone(), api::debug_db))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                api::auth::identify,
+            ))
+            .layer(middleware::from_fn(api::deadline))
+            .layer(middleware::from_fn(api::casing))
+            .layer(middleware::from_fn_with_state(state.clone(), api::locale))
+            .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span::<Body>))
+            .layer(api::compression::compression(&settings.compression))
+            .layer(api::shed::global_shed())
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                api::request_log,
+            ))
+            .layer(middleware::from_fn(api::request_id))
+            .with_state(state)
+    }
+}
+// endregion: -- App
+
+#[tracing::instrument(name = "health check")]
+pub async fn health_check() -> impl IntoResponse {
+    StatusCode::OK
+}
+
+/// Batches a previous run left in the journal aren't run again on their own:
+/// whether to resume or discard them is for an admin to decide.
+fn report_pending_batches() {
+    match surreal::query_manager::pending_batches() {
+        Ok(batches) if !batches.is_empty() => {
+            let ids: Vec<&str> = batches.iter().map(|batch| batch.id.as_str()).collect();
+            tracing::warn!(
+                ?ids,
+                "Unfinished batches in the journal; resume or discard them under /admin/batches"
+            );
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!(%error, "Failed to read the batch journal"),
+    }
+}
@@ -0,0 +1,120 @@
+use crate::api::{RouteEntry, ROUTE_TABLE};
+use crate::config::Settings;
+use crate::surreal::schema::SchemaReport;
+use serde::Serialize;
+use serde_json::Value;
+
+// region: -- StartupReport
+/// What this instance started with: its configuration, the database it
+/// talks to, what the schema sync did and the routes it serves. Logged once
+/// at startup and served at `GET /admin/routes`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StartupReport {
+    /// This build's version.
+    pub version: &'static str,
+    pub engine: Engine,
+    pub migrations: Migrations,
+    /// The resolved configuration, secrets redacted.
+    pub settings: Value,
+    pub routes: Vec<RouteEntry>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Engine {
+    /// `ws`, or `wss` with `database.ssl_mode`.
+    pub protocol: &'static str,
+    pub address: String,
+    pub namespace: String,
+    pub database: String,
+    /// The version the server reported.
+    pub server_version: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Migrations {
+    /// Indexes defined because the database lacked them.
+    pub indexes_created: Vec<String>,
+    /// Indexes in the database this build doesn't declare.
+    pub indexes_unknown: Vec<String>,
+    /// Indexes whose kind differs from the declaration; left as they are.
+    pub indexes_mismatched: Vec<String>,
+    /// Functions defined or redefined at a newer version.
+    pub functions_applied: Vec<String>,
+    /// Functions the database has at a newer version than this build.
+    pub functions_newer: Vec<String>,
+}
+
+impl From<SchemaReport> for Migrations {
+    fn from(report: SchemaReport) -> Self {
+        Self {
+            indexes_created: report.indexes.missing,
+            indexes_unknown: report.indexes.unknown,
+            indexes_mismatched: report.indexes.mismatched,
+            functions_applied: report.functions.applied,
+            functions_newer: report.functions.newer,
+        }
+    }
+}
+
+impl Migrations {
+    /// Whether the database needs nobody's attention: nothing unknown,
+    /// mismatched or newer than this build.
+    pub fn is_clean(&self) -> bool {
+        self.indexes_unknown.is_empty()
+            && self.indexes_mismatched.is_empty()
+            && self.functions_newer.is_empty()
+    }
+}
+
+impl StartupReport {
+    /// The report for `configuration`, listing the routes built so far.
+    pub fn new(
+        configuration: &Settings,
+        server_version: impl ToString,
+        schema: SchemaReport,
+    ) -> Self {
+        let database = &configuration.database;
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            engine: Engine {
+                protocol: if database.ssl_mode { "wss" } else { "ws" },
+                address: format!("{}:{}", database.host, database.port),
+                namespace: database.namespace.clone(),
+                database: database.database.clone(),
+                server_version: server_version.to_string(),
+            },
+            migrations: schema.into(),
+            settings: serde_json::to_value(configuration).unwrap_or_default(),
+            routes: ROUTE_TABLE.entries(),
+        }
+    }
+
+    /// Logs the report as a handful of events: a banner, the engine, the
+    /// migrations, the settings, then a debug event per route.
+    pub fn emit(&self) {
+        tracing::info!(
+            version = self.version,
+            routes = self.routes.len(),
+            "Starting {}",
+            env!("CARGO_PKG_NAME")
+        );
+        tracing::info!(
+            protocol = self.engine.protocol,
+            address = %self.engine.address,
+            namespace = %self.engine.namespace,
+            database = %self.engine.database,
+            server_version = %self.engine.server_version,
+            "Engine"
+        );
+        if self.migrations.is_clean() {
+            tracing::info!(migrations = ?self.migrations, "Schema in sync");
+        } else {
+            tracing::warn!(migrations = ?self.migrations, "Schema drift left in place");
+        }
+        tracing::info!(settings = %self.settings, "Resolved configuration");
+        for route in &self.routes {
+            tracing::debug!(path = %route.path, methods = ?route.methods, "Route");
+        }
+    }
+}
+// endregion: -- StartupReport
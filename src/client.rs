@@ -0,0 +1,308 @@
+use crate::api::{Pagination, PeopleQuery, X_CASE};
+use crate::error::Problem;
+use chrono::NaiveDate;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Characters left as they are in a path segment.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// region: -- Types
+/// A person to create or replace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewPerson {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_of_birth: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl NewPerson {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A person as the API answers with it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PersonRecord {
+    /// `person:id`.
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub date_of_birth: Option<NaiveDate>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub age: Option<u32>,
+}
+
+/// One page of a list, and where it sits among the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Left out when the whole list was sent.
+    pub pagination: Option<Pagination>,
+}
+
+/// Answer to [`SurrealThingClient::lookup_people`].
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lookup {
+    pub found: Vec<PersonRecord>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Envelope<T> {
+    data: Option<T>,
+    #[serde(default)]
+    meta: EnvelopeMeta,
+    #[serde(default)]
+    errors: Vec<Problem>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct EnvelopeMeta {
+    pagination: Option<Pagination>,
+}
+// endregion: -- Types
+
+// region: -- ClientError
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("{} {}: {}", .0.status, .0.title, .0.detail)]
+    Api(Problem),
+
+    #[error("unexpected response: {0}")]
+    Unexpected(String),
+}
+
+impl ClientError {
+    /// The status the API answered with, if it answered.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Api(problem) => Some(problem.status),
+            Self::Transport(error) => error.status().map(|status| status.as_u16()),
+            Self::Unexpected(_) => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND.as_u16())
+    }
+}
+// endregion: -- ClientError
+
+// region: -- SurrealThingClient
+/// A typed client for the HTTP API, for other services and the integration
+/// tests:
+///
+/// ```ignore
+/// let client = SurrealThingClient::new("http://127.0.0.1:8000");
+/// let jane = client.create_person_with_id("jane", &NewPerson::new("Jane")).await?;
+/// let page = client.search_people(&PeopleQuery {
+///     name_starts_with: Some("Ja".into()),
+///     ..PeopleQuery::default()
+/// }).await?;
+/// ```
+///
+/// Every request asks for `snake_case` keys, so it reads the same whatever
+/// `response.case` the server is set to. Failures the API reports come back
+/// as [`ClientError::Api`] with the problem it sent.
+#[derive(Debug, Clone)]
+pub struct SurrealThingClient {
+    base_url: String,
+    http: reqwest::Client,
+    bearer: Option<String>,
+}
+
+impl SurrealThingClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http(base_url, reqwest::Client::new())
+    }
+
+    /// Sends requests through `http`, e.g. one built with timeouts.
+    pub fn with_http(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+            bearer: None,
+        }
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn with_bearer(mut self, token: impl Into<String>) -> Self {
+        self.bearer = Some(token.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // region: -- people
+    /// `POST /person`: the server picks the id.
+    pub async fn create_person(&self, person: &NewPerson) -> Result<PersonRecord, ClientError> {
+        let request = self.request(Method::POST, "/person").json(person);
+        self.send::<PersonRecord>(request)
+            .await?
+            .ok_or_else(|| missing("the created person"))
+    }
+
+    /// `POST /person/:id`.
+    pub async fn create_person_with_id(
+        &self,
+        id: &str,
+        person: &NewPerson,
+    ) -> Result<PersonRecord, ClientError> {
+        let request = self
+            .request(Method::POST, &format!("/person/{}", segment(id)))
+            .json(person);
+        self.send::<PersonRecord>(request)
+            .await?
+            .ok_or_else(|| missing("the created person"))
+    }
+
+    /// `GET /person/:id`. A missing person is an error for which
+    /// [`ClientError::is_not_found`] holds.
+    pub async fn get_person(&self, id: &str) -> Result<PersonRecord, ClientError> {
+        let request = self.request(Method::GET, &format!("/person/{}", segment(id)));
+        self.send::<PersonRecord>(request)
+            .await?
+            .ok_or_else(|| missing("the person"))
+    }
+
+    /// `PUT /person/:id`, replacing the person; answers with the person as
+    /// it now is. A missing person isn't created but is an error for which
+    /// [`ClientError::is_not_found`] holds.
+    pub async fn update_person(
+        &self,
+        id: &str,
+        person: &NewPerson,
+    ) -> Result<PersonRecord, ClientError> {
+        let request = self
+            .request(Method::PUT, &format!("/person/{}", segment(id)))
+            .query(&[("return", "after")])
+            .json(person);
+        self.send::<PersonRecord>(request)
+            .await?
+            .ok_or_else(|| missing("the updated person"))
+    }
+
+    /// `DELETE /person/:id`. Answers with who was deleted; a missing person
+    /// is an error for which [`ClientError::is_not_found`] holds.
+    pub async fn delete_person(&self, id: &str) -> Result<PersonRecord, ClientError> {
+        let request = self.request(Method::DELETE, &format!("/person/{}", segment(id)));
+        self.send::<PersonRecord>(request)
+            .await?
+            .ok_or_else(|| missing("the deleted person"))
+    }
+
+    /// `GET /people` with the filters, sort and page in `query`. Without
+    /// any, every person is sent and the page has no `pagination`.
+    pub async fn search_people(
+        &self,
+        query: &PeopleQuery,
+    ) -> Result<Page<PersonRecord>, ClientError> {
+        let request = self.request(Method::GET, "/people").query(query);
+        let (items, pagination) = self.send_with_pagination(request).await?;
+        Ok(Page {
+            items: items.unwrap_or_default(),
+            pagination,
+        })
+    }
+
+    /// `POST /people/lookup`: the people with these ids, in one request.
+    pub async fn lookup_people(&self, ids: &[&str]) -> Result<Lookup, ClientError> {
+        let request = self.request(Method::POST, "/people/lookup").json(ids);
+        self.send::<Lookup>(request)
+            .await?
+            .ok_or_else(|| missing("the lookup"))
+    }
+
+    /// `POST /person/qry/batch_up`: creates everyone in `people` in as few
+    /// transactions as the server's limits allow. The batch endpoint only
+    /// keeps `name`, and answers with every person in the table.
+    pub async fn batch_create(
+        &self,
+        people: &[NewPerson],
+    ) -> Result<Vec<PersonRecord>, ClientError> {
+        let names: Vec<NewPerson> = people
+            .iter()
+            .map(|person| NewPerson::new(&person.name))
+            .collect();
+        let request = self
+            .request(Method::POST, "/person/qry/batch_up")
+            .json(&names);
+        Ok(self
+            .send::<Vec<PersonRecord>>(request)
+            .await?
+            .unwrap_or_default())
+    }
+    // endregion: -- people
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .header(X_CASE.as_str(), "snake");
+        match &self.bearer {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Option<T>, ClientError> {
+        Ok(self.send_with_pagination(request).await?.0)
+    }
+
+    async fn send_with_pagination<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(Option<T>, Option<Pagination>), ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        let envelope: Envelope<T> = serde_json::from_slice(&body).map_err(|e| {
+            ClientError::Unexpected(format!(
+                "{status} with a body that isn't an API envelope ({e}): {}",
+                String::from_utf8_lossy(&body)
+            ))
+        })?;
+        if let Some(problem) = envelope.errors.into_iter().next() {
+            return Err(ClientError::Api(problem));
+        }
+        if !status.is_success() {
+            return Err(ClientError::Unexpected(format!(
+                "{status} without a problem"
+            )));
+        }
+        Ok((envelope.data, envelope.meta.pagination))
+    }
+}
+
+fn segment(id: &str) -> String {
+    utf8_percent_encode(id, SEGMENT).to_string()
+}
+
+fn missing(what: &str) -> ClientError {
+    ClientError::Unexpected(format!("no data for {what}"))
+}
+// endregion: -- SurrealThingClient
@@ -0,0 +1,548 @@
+use crate::api::auth::AdminSettings;
+use crate::api::compression::CompressionSettings;
+use crate::api::shed::{LimitSettings, LIMITS};
+use crate::api::{
+    FaultSettings, LiveSettings, MaintenanceSettings, ResponseSettings, CASING, FAULTS, MAINTENANCE,
+};
+use crate::server::ServerSettings;
+use crate::surreal::backup::BackupSettings;
+use crate::surreal::breaker::{BreakerSettings, BREAKER};
+use crate::surreal::cache::CacheSettings;
+use crate::surreal::count::{CountSettings, COUNTS};
+use crate::surreal::database_url::{DatabaseUrlError, SURREAL_URL};
+use crate::surreal::db::{AuthMode, DatabaseSettings};
+use crate::surreal::edge::EdgeSettings;
+use crate::surreal::flags::FlagSettings;
+use crate::surreal::ids::{IdSettings, IdStrategy};
+use crate::surreal::licenses::LicenseSettings;
+use crate::surreal::queries::QuerySettings;
+use crate::surreal::query_manager::TransactionSettings;
+use crate::surreal::query_manager::TRANSACTIONS;
+use crate::surreal::repository::RepositorySettings;
+use crate::surreal::request_log::{RequestLogSettings, REQUEST_LOG};
+use crate::surreal::request_session::RequestSessionSettings;
+use crate::surreal::schema::tables::SchemaSettings;
+use crate::surreal::session::{SessionSettings, SESSION};
+use crate::surreal::slow_log::SlowQuerySettings;
+use crate::surreal::slow_log::SLOW_QUERIES;
+use crate::surreal::ttl::{TtlSettings, TTL};
+use crate::surreal::version::VersionSettings;
+use crate::surreal::write_behind::WriteBehindSettings;
+use crate::telemetry;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+// region: -- Settings
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Settings {
+    pub database: DatabaseSettings,
+    #[serde(default)]
+    pub server: ServerSettings,
+    /// Overrides the startup log filter (`RUST_LOG`, or `info`) when set.
+    #[serde(default)]
+    pub log_level: Option<String>,
+    #[serde(default)]
+    pub slow_query: SlowQuerySettings,
+    #[serde(default)]
+    pub admin: AdminSettings,
+    #[serde(default)]
+    pub flags: FlagSettings,
+    #[serde(default)]
+    pub version_check: VersionSettings,
+    #[serde(default)]
+    pub limits: LimitSettings,
+    #[serde(default)]
+    pub compression: CompressionSettings,
+    #[serde(default)]
+    pub transactions: TransactionSettings,
+    #[serde(default)]
+    pub licenses: LicenseSettings,
+    #[serde(default)]
+    pub ids: IdSettings,
+    #[serde(default)]
+    pub session: SessionSettings,
+    #[serde(default)]
+    pub breaker: BreakerSettings,
+    #[serde(default)]
+    pub edges: EdgeSettings,
+    #[serde(default)]
+    pub request_log: RequestLogSettings,
+    #[serde(default)]
+    pub schema: SchemaSettings,
+    #[serde(default)]
+    pub response: ResponseSettings,
+    #[serde(default)]
+    pub counts: CountSettings,
+    #[serde(default)]
+    pub backup: BackupSettings,
+    #[serde(default)]
+    pub write_behind: WriteBehindSettings,
+    #[serde(default)]
+    pub cache: CacheSettings,
+    #[serde(default)]
+    pub live: LiveSettings,
+    #[serde(default)]
+    pub maintenance: MaintenanceSettings,
+    #[serde(default)]
+    pub ttl: TtlSettings,
+    #[serde(default)]
+    pub repository: RepositorySettings,
+    #[serde(default)]
+    pub faults: FaultSettings,
+    #[serde(default)]
+    pub request_sessions: RequestSessionSettings,
+    #[serde(default)]
+    pub queries: QuerySettings,
+}
+
+/// Layers `configuration/base.yaml`, the file for `APP_ENVIRONMENT`
+/// (`local` by default) and `APP_`-prefixed environment variables, e.g.
+/// `APP_DATABASE__PORT=8001`. `SURREAL_URL`, when set, replaces all of
+/// `database` but `tls` and `on_missing`.
+pub fn get_configuration() -> Result<Settings, config::ConfigError> {
+    let base_path = std::env::current_dir().expect("Failed to determine the current directory");
+    let configuration_directory = base_path.join("configuration");
+
+    let environment = Environment::current().map_err(config::ConfigError::Message)?;
+    let environment_filename = format!("{}.yaml", environment.as_str());
+
+    let settings = config::Config::builder()
+        .add_source(config::File::from(
+            configuration_directory.join("base.yaml"),
+        ))
+        .add_source(
+            config::File::from(configuration_directory.join(environment_filename)).required(false),
+        )
+        .add_source(
+            config::Environment::with_prefix("APP")
+                .prefix_separator("_")
+                .separator("__"),
+        )
+        .build()?;
+
+    let mut settings = settings.try_deserialize::<Settings>()?;
+    if let Ok(url) = std::env::var(SURREAL_URL) {
+        let database: DatabaseSettings = url.parse().map_err(|e: DatabaseUrlError| {
+            config::ConfigError::Message(format!("{SURREAL_URL}: {e}"))
+        })?;
+        settings.database = DatabaseSettings {
+            tls: settings.database.tls,
+            on_missing: settings.database.on_missing,
+            ..database
+        };
+    }
+    Ok(settings)
+}
+
+impl Settings {
+    /// Catches settings that would only fail later, or fail confusingly, once
+    /// the server is running. Reports every problem at once.
+    pub fn validate(&self) -> Result<(), InvalidSettings> {
+        let mut problems = Vec::new();
+        let database = &self.database;
+
+        for (name, value) in [
+            ("database.host", &database.host),
+            ("database.username", &database.username),
+            ("database.password", database.password.expose()),
+            ("database.namespace", &database.namespace),
+            ("database.database", &database.database),
+        ] {
+            if value.trim().is_empty() {
+                problems.push(format!("`{name}` must not be empty"));
+            }
+        }
+        if database.port == 0 {
+            problems.push("`database.port` must not be 0".into());
+        }
+        match (database.ssl_mode, database.port) {
+            (true, 80) => {
+                problems.push("`database.ssl_mode` is on but `database.port` is 80".into())
+            }
+            (false, 443) => {
+                problems.push("`database.ssl_mode` is off but `database.port` is 443".into())
+            }
+            _ => {}
+        }
+        match &self.server.unix_socket {
+            Some(path) if cfg!(not(unix)) => problems.push(format!(
+                "`server.unix_socket` ({}) needs a Unix platform",
+                path.display()
+            )),
+            Some(path) if path.as_os_str().is_empty() => {
+                problems.push("`server.unix_socket` is set but empty".into())
+            }
+            None if self.server.host.trim().is_empty() => {
+                problems.push("`server.host` must not be empty".into())
+            }
+            _ => {}
+        }
+        match (database.auth, &database.scope) {
+            (AuthMode::Scope, None) => {
+                problems.push("`database.auth` is `scope` but `database.scope` is not set".into())
+            }
+            (AuthMode::Scope, Some(scope)) if scope.trim().is_empty() => {
+                problems.push("`database.scope` must not be empty".into())
+            }
+            (AuthMode::Scope, Some(_)) | (_, None) => {}
+            (_, Some(_)) => {
+                problems.push("`database.scope` is only used with `auth: scope`".into())
+            }
+        }
+        problems.extend(database.tls.problems());
+        if database.tls.is_configured() && !database.ssl_mode {
+            problems.push("`database.tls` is set but `database.ssl_mode` is off".into());
+        }
+        if self.limits.global == 0 || self.limits.batch == 0 {
+            problems.push("`limits.global` and `limits.batch` must be at least 1".into());
+        }
+        if self.transactions.max_statements == 0 || self.transactions.max_bytes == 0 {
+            problems.push(
+                "`transactions.max_statements` and `transactions.max_bytes` must be at least 1"
+                    .into(),
+            );
+        }
+        match &self.transactions.journal_dir {
+            Some(dir) if dir.as_os_str().is_empty() => {
+                problems.push("`transactions.journal_dir` is set but empty".into())
+            }
+            Some(dir) if dir.exists() && !dir.is_dir() => problems.push(format!(
+                "`transactions.journal_dir` ({}) is not a directory",
+                dir.display()
+            )),
+            _ => {}
+        }
+        if let Some(level) = &self.log_level {
+            if let Err(e) = EnvFilter::try_new(level) {
+                problems.push(format!("`log_level` is not a valid filter: {e}"));
+            }
+        }
+        if self.flags.refresh_secs == 0 {
+            problems.push("`flags.refresh_secs` must be at least 1".into());
+        }
+        if self.ids.snowflake_node >= 1024 {
+            problems.push("`ids.snowflake_node` must be below 1024".into());
+        }
+        for (table, strategy) in &self.ids.tables {
+            let has_key = self
+                .ids
+                .natural_keys
+                .get(table)
+                .is_some_and(|fields| !fields.is_empty());
+            if *strategy == IdStrategy::Natural && !has_key {
+                problems.push(format!(
+                    "`ids.tables.{table}` is `natural` but `ids.natural_keys.{table}` is not set"
+                ));
+            }
+        }
+        if self.session.max_reauths == 0 || self.session.window_secs == 0 {
+            problems
+                .push("`session.max_reauths` and `session.window_secs` must be at least 1".into());
+        }
+        let breaker = &self.breaker;
+        if breaker.window == 0 || breaker.min_calls == 0 || breaker.min_calls > breaker.window {
+            problems.push("`breaker.min_calls` must be between 1 and `breaker.window`".into());
+        }
+        if breaker.error_rate_percent > 100 || breaker.slow_rate_percent > 100 {
+            problems.push(
+                "`breaker.error_rate_percent` and `breaker.slow_rate_percent` must be at most 100"
+                    .into(),
+            );
+        }
+        if breaker.probes == 0 {
+            problems.push("`breaker.probes` must be at least 1".into());
+        }
+        if self.request_log.sample_percent > 100 {
+            problems.push("`request_log.sample_percent` must be at most 100".into());
+        }
+        if self.request_log.max_records == 0 {
+            problems.push("`request_log.max_records` must be at least 1".into());
+        }
+        for (edge, endpoints) in &self.edges.allowed {
+            let tables = [edge, &endpoints.from, &endpoints.to];
+            if tables.iter().any(|table| {
+                table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }) {
+                problems.push(format!(
+                    "`edges.allowed.{edge}` must only name tables made of letters, digits and `_`"
+                ));
+            }
+        }
+        problems.extend(self.schema.problems());
+        problems.extend(self.backup.problems());
+        problems.extend(self.write_behind.problems());
+        problems.extend(self.cache.problems());
+        problems.extend(self.live.problems());
+        problems.extend(self.maintenance.problems());
+        problems.extend(self.ttl.problems());
+        problems.extend(self.request_sessions.problems());
+        problems.extend(self.queries.problems());
+        problems.extend(
+            self.faults
+                .problems(&Environment::current().unwrap_or(Environment::Local)),
+        );
+        if self.licenses.expiry_check_secs == 0 {
+            problems.push("`licenses.expiry_check_secs` must be at least 1".into());
+        }
+        if self
+            .admin
+            .token
+            .as_ref()
+            .is_some_and(|t| t.expose().trim().is_empty())
+        {
+            problems.push("`admin.token` is set but empty; leave it out to disable admin".into());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidSettings(problems))
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+pub struct InvalidSettings(pub Vec<String>);
+// endregion: -- Settings
+
+// region: -- Reload
+/// What a reload changed. Settings in `restart_required` keep their startup
+/// values until the server is restarted.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    pub applied: Vec<&'static str>,
+    pub restart_required: Vec<&'static str>,
+}
+
+/// Re-reads the configuration on `SIGHUP` or `POST /admin/reload` and applies
+/// the settings that can change while running: `log_level`, `slow_query`,
+/// `transactions`, `session`, `breaker` and `limits`, among others.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    current: Arc<Mutex<Settings>>,
+}
+
+impl ConfigReloader {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            current: Arc::new(Mutex::new(settings)),
+        }
+    }
+
+    /// The settings in effect.
+    pub fn settings(&self) -> Settings {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Records a switch the app made to another database, e.g. a confirmed
+    /// restore, so the settings in effect name the database in use.
+    pub fn use_database(&self, namespace: &str, database: &str) {
+        let mut current = self.current.lock().unwrap();
+        current.database.namespace = namespace.to_string();
+        current.database.database = database.to_string();
+    }
+
+    /// An invalid configuration is rejected as a whole and nothing changes.
+    pub fn reload(&self) -> Result<ReloadReport, crate::error::Error> {
+        use crate::error::Error::InvalidConfiguration;
+
+        let settings = get_configuration().map_err(|e| InvalidConfiguration(e.to_string()))?;
+        settings
+            .validate()
+            .map_err(|e| InvalidConfiguration(e.to_string()))?;
+        Ok(self.apply(settings))
+    }
+
+    pub fn apply(&self, new: Settings) -> ReloadReport {
+        let mut current = self.current.lock().unwrap();
+        let mut report = ReloadReport::default();
+
+        if current.log_level != new.log_level {
+            // Without `log_level` the startup filter applies again.
+            let applied = match &new.log_level {
+                Some(level) => telemetry::set_log_level(level),
+                None => telemetry::reset_log_level(),
+            };
+            // Already validated, so this only fails without a subscriber.
+            if let Err(e) = applied {
+                tracing::warn!(error = %e, "Failed to apply `log_level`");
+            }
+            current.log_level = new.log_level.clone();
+            report.applied.push("log_level");
+        }
+        if changed(&current.slow_query, &new.slow_query) {
+            SLOW_QUERIES.configure(&new.slow_query);
+            current.slow_query = new.slow_query.clone();
+            report.applied.push("slow_query");
+        }
+        if changed(&current.transactions, &new.transactions) {
+            TRANSACTIONS.configure(&new.transactions);
+            current.transactions = new.transactions.clone();
+            report.applied.push("transactions");
+        }
+        if changed(&current.session, &new.session) {
+            SESSION.configure(&new.session);
+            current.session = new.session.clone();
+            report.applied.push("session");
+        }
+        if changed(&current.breaker, &new.breaker) {
+            BREAKER.configure(&new.breaker);
+            current.breaker = new.breaker.clone();
+            report.applied.push("breaker");
+        }
+        if changed(&current.request_log, &new.request_log) {
+            REQUEST_LOG.configure(&new.request_log);
+            current.request_log = new.request_log.clone();
+            report.applied.push("request_log");
+        }
+        if changed(&current.response, &new.response) {
+            CASING.configure(&new.response);
+            current.response = new.response.clone();
+            report.applied.push("response");
+        }
+        if changed(&current.counts, &new.counts) {
+            COUNTS.configure(&new.counts);
+            current.counts = new.counts.clone();
+            report.applied.push("counts");
+        }
+        if changed(&current.maintenance, &new.maintenance) {
+            MAINTENANCE.configure(&new.maintenance);
+            current.maintenance = new.maintenance.clone();
+            report.applied.push("maintenance");
+        }
+        if changed(&current.ttl, &new.ttl) {
+            TTL.configure(&new.ttl);
+            current.ttl = new.ttl.clone();
+            report.applied.push("ttl");
+        }
+        if changed(&current.faults, &new.faults) {
+            FAULTS.configure(&new.faults);
+            current.faults = new.faults.clone();
+            report.applied.push("faults");
+        }
+        if changed(&current.limits, &new.limits) {
+            LIMITS.configure(&new.limits);
+            current.limits = new.limits.clone();
+            report.applied.push("limits");
+        }
+
+        let database_changed = changed(&current.database, &new.database)
+            || current.database.password.expose() != new.database.password.expose();
+        let admin_changed = current.admin.token.as_ref().map(|t| t.expose())
+            != new.admin.token.as_ref().map(|t| t.expose());
+        for (name, restart) in [
+            ("database", database_changed),
+            ("server", changed(&current.server, &new.server)),
+            ("admin", admin_changed),
+            ("flags", changed(&current.flags, &new.flags)),
+            (
+                "version_check",
+                changed(&current.version_check, &new.version_check),
+            ),
+            (
+                "compression",
+                changed(&current.compression, &new.compression),
+            ),
+            ("licenses", changed(&current.licenses, &new.licenses)),
+            ("ids", changed(&current.ids, &new.ids)),
+            ("edges", changed(&current.edges, &new.edges)),
+            ("schema", changed(&current.schema, &new.schema)),
+            ("backup", current.backup != new.backup),
+            (
+                "write_behind",
+                changed(&current.write_behind, &new.write_behind),
+            ),
+            ("cache", current.cache != new.cache),
+            ("live", current.live != new.live),
+            ("repository", current.repository != new.repository),
+            (
+                "request_sessions",
+                current.request_sessions != new.request_sessions,
+            ),
+            ("queries", current.queries != new.queries),
+        ] {
+            if restart {
+                report.restart_required.push(name);
+            }
+        }
+
+        tracing::info!(applied = ?report.applied, "Reloaded configuration");
+        if !report.restart_required.is_empty() {
+            tracing::warn!(
+                settings = ?report.restart_required,
+                "Changed settings only take effect after a restart"
+            );
+        }
+        report
+    }
+
+    /// Reloads whenever the process receives `SIGHUP`.
+    #[cfg(unix)]
+    pub fn spawn_on_hangup(&self) -> std::io::Result<()> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        let reloader = self.clone();
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                if let Err(error) = reloader.reload() {
+                    tracing::error!(%error, "Keeping the current configuration");
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ConfigReloader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigReloader").finish_non_exhaustive()
+    }
+}
+
+/// Secrets serialize redacted, so compare them separately.
+fn changed<T: Serialize>(current: &T, new: &T) -> bool {
+    serde_json::to_value(current).ok() != serde_json::to_value(new).ok()
+}
+// endregion: -- Reload
+
+// region: -- Environment
+pub enum Environment {
+    Local,
+    Production,
+}
+
+impl Environment {
+    /// The environment named by `APP_ENVIRONMENT`, `local` by default.
+    pub fn current() -> Result<Self, String> {
+        std::env::var("APP_ENVIRONMENT")
+            .unwrap_or_else(|_| "local".into())
+            .try_into()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Local => "local",
+            Environment::Production => "production",
+        }
+    }
+}
+
+impl TryFrom<String> for Environment {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "production" => Ok(Self::Production),
+            other => Err(format!(
+                "{other} is not a supported environment. Use either `local` or `production`."
+            )),
+        }
+    }
+}
+// endregion: -- Environment
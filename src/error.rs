@@ -1,7 +1,15 @@
-use axum::http::StatusCode;
+use crate::api::{current_deadline, is_read_only_rejection, localize, ApiResponse, MAINTENANCE};
+use crate::surreal::breaker::{is_circuit_open, BREAKER};
+use crate::surreal::connection::ErrorKind;
+use crate::surreal::schema::indexes::index_violation;
+use crate::surreal::version::SUPPORTED_VERSIONS;
+use axum::extract::rejection::JsonRejection;
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::IntoResponse;
 use axum::response::Response;
-use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use surrealdb::error::Db;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,19 +17,263 @@ pub enum Error {
     #[error("database error")]
     Db,
 
+    #[error("the database is unreachable")]
+    DbUnavailable,
+
+    #[error("the database could not parse the query: {0}")]
+    QuerySyntax(String),
+
+    #[error("the database user lacks permission for this query")]
+    PermissionDenied,
+
+    #[error("`{0}` statements are not allowed here")]
+    StatementNotAllowed(&'static str),
+
     #[error("QueryManager error")]
     QueryManagerError,
+
+    #[error("batch journal: {0}")]
+    Journal(String),
+
+    #[error("backup: {0}")]
+    Backup(String),
+
+    #[error("cache: {0}")]
+    Cache(String),
+
+    #[error("transaction {failed} of {total} failed after {committed} statements were committed")]
+    PartiallyCommitted {
+        failed: usize,
+        total: usize,
+        committed: usize,
+    },
+
+    #[error("transaction aborted: {0}")]
+    Aborted(String),
+
+    #[error("configuration not reloaded: {0}")]
+    InvalidConfiguration(String),
+
+    #[error("namespace/database not found: {0}")]
+    ScopeNotFound(String),
+
+    #[error("`{field}` must be unique but {value} is already taken")]
+    Conflict { field: String, value: String },
+
+    #[error("`{0}` already exists")]
+    AlreadyExists(String),
+
+    #[error("`{record}` already exists with a different `{field}`")]
+    NaturalKeyConflict { record: String, field: String },
+
+    #[error("`{record}` still has {relations} relations")]
+    StillRelated { record: String, relations: usize },
+
+    #[error("`{side}` record `{record}` does not exist")]
+    MissingEndpoint { side: &'static str, record: String },
+
+    #[error("`{table}` has no field `{field}`")]
+    UnknownField { table: String, field: String },
+
+    #[error("`{0}` not found")]
+    NotFound(String),
+
+    #[error("`{0}` has been deleted")]
+    Deleted(String),
+
+    #[error("invalid id: {0}")]
+    InvalidId(String),
+
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("invalid request body: {0}")]
+    InvalidBody(String),
+
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+
+    #[error("SurrealDB {0} is not supported (supported: {SUPPORTED_VERSIONS})")]
+    UnsupportedVersion(String),
+
+    #[error("not ready: {0}")]
+    NotReady(String),
+
+    #[error("request deadline of {budget_ms} ms exceeded after {elapsed_ms} ms")]
+    DeadlineExceeded { budget_ms: u128, elapsed_ms: u128 },
+
+    #[error("the database is unavailable; retry in {retry_after_secs}s")]
+    CircuitOpen { retry_after_secs: u64 },
+
+    #[error("{message}")]
+    ReadOnly {
+        message: String,
+        retry_after_secs: u64,
+    },
+
+    #[error("server is at capacity: {0}")]
+    Overloaded(String),
+
+    #[error("missing or invalid admin token")]
+    Unauthorized,
+
+    #[error("missing or invalid token")]
+    InvalidToken,
+
+    #[error("the restore confirmation token is wrong or has expired")]
+    InvalidConfirmation,
+
+    #[error("fault injected on `{0}`")]
+    FaultInjected(String),
+
+    #[error("fault injection is not available in production")]
+    FaultsUnavailable,
+
+    #[error("query template: {0}")]
+    QueryTemplate(String),
+}
+
+impl Error {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::ScopeNotFound(_) | Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Deleted(_) => StatusCode::GONE,
+            Error::Conflict { .. }
+            | Error::AlreadyExists(_)
+            | Error::NaturalKeyConflict { .. }
+            | Error::StillRelated { .. } => StatusCode::CONFLICT,
+            Error::Unauthorized | Error::InvalidToken => StatusCode::UNAUTHORIZED,
+            Error::InvalidConfirmation | Error::PermissionDenied | Error::FaultsUnavailable => {
+                StatusCode::FORBIDDEN
+            }
+            Error::InvalidId(_)
+            | Error::InvalidQuery(_)
+            | Error::QuerySyntax(_)
+            | Error::StatementNotAllowed(_) => StatusCode::BAD_REQUEST,
+            Error::InvalidBody(_) | Error::MissingEndpoint { .. } | Error::UnknownField { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Error::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Error::NotReady(_)
+            | Error::Overloaded(_)
+            | Error::CircuitOpen { .. }
+            | Error::ReadOnly { .. }
+            | Error::DbUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Error::DeadlineExceeded { .. } => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// region: -- Problem
+/// RFC 7807 problem details, reported in the `errors` of an [`ApiResponse`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl From<&Error> for Problem {
+    fn from(error: &Error) -> Self {
+        let status = error.status();
+        let field = match error {
+            Error::Conflict { field, .. } | Error::NaturalKeyConflict { field, .. } => {
+                Some(field.clone())
+            }
+            Error::MissingEndpoint { side, .. } => Some(side.to_string()),
+            Error::UnknownField { field, .. } => Some(field.clone()),
+            _ => None,
+        };
+        let (title, detail) = localize(error);
+        Self {
+            kind: "about:blank".into(),
+            title,
+            status: status.as_u16(),
+            detail,
+            field,
+        }
+    }
 }
+// endregion: -- Problem
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(self.to_string())).into_response()
+        let mut response = ApiResponse::error(Problem::from(&self)).into_response();
+        if let Error::CircuitOpen { retry_after_secs }
+        | Error::ReadOnly {
+            retry_after_secs, ..
+        } = self
+        {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
     }
 }
 
+/// Sorted by [`ErrorKind`]. Parse errors are the client's: the app binds
+/// what it is sent, so only SurrealQL a client wrote, as for
+/// `POST /admin/explain`, gets one back.
 impl From<surrealdb::Error> for Error {
     fn from(error: surrealdb::Error) -> Self {
-        eprintln!("{error}");
-        Self::Db
+        if is_circuit_open(&error) {
+            let retry_after = BREAKER.retry_after(Instant::now()).unwrap_or_default();
+            return Self::CircuitOpen {
+                retry_after_secs: retry_after.as_secs().max(1),
+            };
+        }
+        if is_read_only_rejection(&error) {
+            return MAINTENANCE.rejection();
+        }
+        let kind = ErrorKind::of(&error);
+        tracing::error!(%error, kind = ?kind, "Database error");
+        match kind {
+            ErrorKind::Connection => Self::DbUnavailable,
+            ErrorKind::Parse => Self::QuerySyntax(error.to_string()),
+            ErrorKind::Permission => Self::PermissionDenied,
+            ErrorKind::RecordExists => {
+                existing_record(&error).map_or(Self::Db, Self::AlreadyExists)
+            }
+            ErrorKind::IndexViolation => index_violation(&error)
+                .map_or(Self::Db, |(field, value)| Self::Conflict { field, value }),
+            ErrorKind::Cancelled => match current_deadline().filter(|d| d.expired()) {
+                Some(deadline) => deadline.exceeded(),
+                None => Self::Db,
+            },
+            ErrorKind::Auth | ErrorKind::Other => Self::Db,
+        }
+    }
+}
+
+/// Malformed and mistyped bodies are both reported as 422 so clients only
+/// have one status to handle for "fix your payload".
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::MissingJsonContentType(rejection) => {
+                Self::UnsupportedMediaType(rejection.body_text())
+            }
+            rejection => Self::InvalidBody(rejection.body_text()),
+        }
+    }
+}
+
+/// The record a `CREATE` collided with. Remote engines only send the message,
+/// e.g. "Database record `person:1` already exists".
+pub(crate) fn existing_record(error: &surrealdb::Error) -> Option<String> {
+    if let surrealdb::Error::Db(Db::RecordExists { thing }) = error {
+        return Some(thing.clone());
     }
+    let message = error.to_string();
+    let rest = message.split("Database record `").nth(1)?;
+    let (thing, rest) = rest.split_once('`')?;
+    rest.trim_start()
+        .starts_with("already exists")
+        .then(|| thing.to_string())
 }
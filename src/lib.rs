@@ -1,4 +1,13 @@
 pub mod api;
+pub mod app;
+pub mod client;
+pub mod config;
 pub mod error;
+pub mod secret;
+pub mod server;
+pub mod state;
 pub mod surreal;
 pub mod telemetry;
+
+pub use app::run;
+pub use surreal::db;
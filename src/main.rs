@@ -1,27 +1,8 @@
-use axum::body::Body;
-use axum_macros::FromRef;
 use once_cell::sync::Lazy;
-use surrealdb::Surreal;
-use surrealdb::engine::remote::ws::Client;
-use telemetry::{get_subscriber, init_subscriber};
-use tower_http::trace::TraceLayer;
+use surreal_simple::config::get_configuration;
+use surreal_simple::telemetry::{get_subscriber, init_subscriber};
 use tracing::info;
 
-pub mod api;
-// pub mod db2;
-pub mod error;
-pub mod surreal;
-pub mod telemetry;
-
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::routing::get;
-use axum::{Router, Server};
-use std::net::SocketAddr;
-use uuid::Uuid;
-
-use surreal::db::{Database, DatabaseSettings};
-
 // region: -- conditional tracing for tests
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
@@ -36,46 +17,60 @@ static TRACING: Lazy<()> = Lazy::new(|| {
 });
 // endregion: -- conditional tracing for tests
 
-#[derive(Debug, Clone, FromRef)]
-pub struct AppState {
-    pub db: Surreal<Client>,
-}
-
-
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Lazy::force(&TRACING);
 
-    let db_settings = DatabaseSettings::default();
-    let db = Database::new(&db_settings).await?;
-
-    let app = Router::new()
-        .merge(api::person_routes())
-        .merge(api::person_query_routes())
-        .route("/health_check", get(health_check))
-        .layer(
-            TraceLayer::new_for_http().make_span_with(|request: &hyper::Request<Body>| {
-                let uuid = Uuid::new_v4();
-                tracing::info_span!(
-                    "request",
-                    uuid = %uuid,
-                    method = %request.method(),
-                    uri = %request.uri(),
-                )
-            }),
-        )
-        .with_state(db.client);
-
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
-
-    info!("Listening on {}", addr);
-    Server::bind(&addr).serve(app.into_make_service()).await?;
+    let check_config = std::env::args().any(|arg| arg == "--check-config");
+    let configuration = get_configuration()?;
+    let validation = configuration.validate();
+    if check_config {
+        println!("{}", serde_json::to_string_pretty(&configuration)?);
+        if let Err(e) = validation {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        eprintln!("configuration OK");
+        return Ok(());
+    }
+    validation?;
+    if std::env::args().any(|arg| arg == "--check-queries") {
+        let dir = &configuration.queries.dir;
+        match surreal_simple::surreal::queries::read_dir(dir) {
+            Ok(templates) => eprintln!("{} query templates OK", templates.len()),
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{diagnostic}");
+                }
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    if std::env::args().any(|arg| arg == "--self-test") {
+        let report = surreal_simple::surreal::self_test::self_test(&configuration.database).await;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        if !report.passed {
+            eprintln!("self-test FAILED");
+            std::process::exit(1);
+        }
+        eprintln!("self-test OK");
+        return Ok(());
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("snapshot") {
+        surreal_simple::app::snapshot_command(&configuration, &args[1..]).await?;
+        return Ok(());
+    }
+    if args.first().map(String::as_str) == Some("restore") {
+        surreal_simple::app::restore_command(&configuration, &args[1..]).await?;
+        return Ok(());
+    }
+    info!(
+        configuration = %serde_json::to_string(&configuration)?,
+        "Loaded configuration"
+    );
+    surreal_simple::run(configuration).await?;
 
     Ok(())
 }
-
-#[tracing::instrument(name = "health check")]
-pub async fn health_check() -> impl IntoResponse {
-    StatusCode::OK
-}
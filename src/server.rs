@@ -0,0 +1,133 @@
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use std::fmt;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+// region: -- ServerSettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ServerSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    /// Listen on this Unix domain socket instead of `host:port`, e.g. for a
+    /// sidecar proxy on the same machine. A stale socket file is replaced.
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".into(),
+            port: 8080,
+            unix_socket: None,
+        }
+    }
+}
+// endregion: -- ServerSettings
+
+// region: -- Listener
+/// Where the server accepts connections, bound as configured.
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
+
+impl Listener {
+    /// Must be called inside a Tokio runtime when binding a Unix socket.
+    pub fn bind(settings: &ServerSettings) -> std::io::Result<Self> {
+        if let Some(path) = &settings.unix_socket {
+            return Self::bind_unix(path);
+        }
+        let listener = TcpListener::bind((settings.host.as_str(), settings.port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self::Tcp(listener))
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(path: &std::path::Path) -> std::io::Result<Self> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = tokio::net::UnixListener::bind(path)?;
+        Ok(Self::Unix(listener, path.to_path_buf()))
+    }
+
+    #[cfg(not(unix))]
+    fn bind_unix(_: &std::path::Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Unix domain sockets are not supported on this platform",
+        ))
+    }
+
+    /// The bound address, which tells you the port when 0 was asked for.
+    /// `None` for Unix sockets.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix(..) => None,
+        }
+    }
+
+    pub async fn serve(self, router: Router) -> hyper::Result<()> {
+        match self {
+            Self::Tcp(listener) => {
+                axum::Server::from_tcp(listener)?
+                    .serve(router.into_make_service())
+                    .await
+            }
+            #[cfg(unix)]
+            Self::Unix(listener, _) => {
+                axum::Server::builder(unix::UnixAccept(listener))
+                    .serve(router.into_make_service())
+                    .await
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "http://{addr}"),
+                Err(_) => write!(f, "tcp (unknown address)"),
+            },
+            #[cfg(unix)]
+            Self::Unix(_, path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use hyper::server::accept::Accept;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// Lets hyper accept connections from a [`UnixListener`].
+    pub struct UnixAccept(pub UnixListener);
+
+    impl Accept for UnixAccept {
+        type Conn = UnixStream;
+        type Error = std::io::Error;
+
+        fn poll_accept(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+            self.0
+                .poll_accept(cx)
+                .map(|accepted| Some(accepted.map(|(stream, _)| stream)))
+        }
+    }
+}
+// endregion: -- Listener
@@ -0,0 +1,36 @@
+use axum_macros::FromRef;
+use std::sync::Arc;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use crate::api::auth::AdminAuth;
+use crate::api::hooks::MutationHooks;
+use crate::api::{Catalogs, ConnectionRegistry};
+use crate::app::StartupReport;
+use crate::config::ConfigReloader;
+use crate::surreal::admin::AdminDatabase;
+use crate::surreal::edge::EdgeAllowList;
+use crate::surreal::flags::FeatureFlags;
+use crate::surreal::ids::IdGenerator;
+use crate::surreal::repository::Repository;
+use crate::surreal::request_session::RequestSessions;
+use crate::surreal::schema::tables::SchemaGuard;
+use crate::surreal::write_behind::WriteBehind;
+
+#[derive(Debug, Clone, FromRef)]
+pub struct AppState {
+    pub db: Surreal<Client>,
+    pub admin: AdminDatabase,
+    pub admin_auth: AdminAuth,
+    pub flags: FeatureFlags,
+    pub hooks: MutationHooks,
+    pub catalogs: Catalogs,
+    pub connections: ConnectionRegistry,
+    pub config: ConfigReloader,
+    pub ids: IdGenerator,
+    pub edges: EdgeAllowList,
+    pub schema: SchemaGuard,
+    pub write_behind: WriteBehind,
+    pub repository: Repository,
+    pub sessions: RequestSessions,
+    pub startup: Arc<StartupReport>,
+}
@@ -0,0 +1,227 @@
+use crate::error::Error;
+use crate::surreal::db::{Database, DatabaseSettings};
+use crate::surreal::explain::{self, QueryPlan};
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::snapshot::{self, RestoreReport, Snapshot};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use surrealdb::{engine::remote::ws::Client, Response, Surreal};
+use tokio::sync::Mutex;
+
+// region: -- Scope
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub namespace: String,
+    pub database: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NamespaceInfo {
+    pub name: String,
+    pub databases: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct KvInfo {
+    #[serde(alias = "namespaces")]
+    pub ns: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct NsInfo {
+    #[serde(alias = "databases")]
+    pub db: BTreeMap<String, String>,
+}
+// endregion: -- Scope
+
+// region: -- AdminDatabase
+/// Dedicated connection for maintenance work. Re-scoping it never touches the
+/// main client held in `AppState`; the mutex keeps a scope switch from
+/// landing in the middle of another admin query.
+#[derive(Clone, Debug)]
+pub struct AdminDatabase {
+    client: Surreal<Client>,
+    scope: Arc<Mutex<Scope>>,
+}
+
+impl AdminDatabase {
+    #[tracing::instrument(
+        name = "Creating admin SurrealDB Client",
+        skip(configuration),
+        fields(
+            db = %configuration.database
+        )
+    )]
+    pub async fn new(configuration: &DatabaseSettings) -> color_eyre::Result<Self> {
+        let db = Database::new(configuration).await?;
+        let scope = Scope {
+            namespace: configuration.namespace.clone(),
+            database: configuration.database.clone(),
+        };
+
+        Ok(Self {
+            client: db.client,
+            scope: Arc::new(Mutex::new(scope)),
+        })
+    }
+
+    pub async fn scope(&self) -> Scope {
+        self.scope.lock().await.clone()
+    }
+
+    #[tracing::instrument(name = "Admin: List Namespaces", skip(self))]
+    pub async fn namespaces(&self) -> Result<Vec<NamespaceInfo>, Error> {
+        let current = self.scope.lock().await;
+        list_namespaces(&self.client, &current).await
+    }
+
+    #[tracing::instrument(name = "Admin: Use Scope", skip(self))]
+    pub async fn use_scope(&self, scope: Scope) -> Result<Scope, Error> {
+        let mut current = self.scope.lock().await;
+
+        let namespaces = list_namespaces(&self.client, &current).await?;
+        let exists = namespaces
+            .iter()
+            .any(|ns| ns.name == scope.namespace && ns.databases.contains(&scope.database));
+        if !exists {
+            return Err(Error::ScopeNotFound(format!(
+                "{}/{}",
+                scope.namespace, scope.database
+            )));
+        }
+
+        self.client
+            .use_ns(&scope.namespace)
+            .use_db(&scope.database)
+            .await?;
+        *current = scope.clone();
+
+        Ok(scope)
+    }
+
+    #[tracing::instrument(name = "Admin: Query", skip(self, sql))]
+    pub async fn query(&self, sql: &str) -> Result<Response, Error> {
+        let _current = self.scope.lock().await;
+        tracing::info!(sql);
+        let response = traced(sql, self.client.query(sql).bind(correlation())).await?;
+        Ok(response)
+    }
+
+    #[tracing::instrument(name = "Admin: Explain", skip(self, sql))]
+    pub async fn explain(&self, sql: &str, full: bool) -> Result<QueryPlan, Error> {
+        let _current = self.scope.lock().await;
+        tracing::info!(sql);
+        explain::explain(&self.client, sql, full).await
+    }
+
+    /// Dumps `from`, or the current scope, into a [`Snapshot`].
+    #[tracing::instrument(name = "Admin: Export Snapshot", skip(self))]
+    pub async fn export(&self, from: Option<Scope>) -> Result<Snapshot, Error> {
+        let current = self.scope.lock().await;
+        let from = from.unwrap_or_else(|| current.clone());
+
+        self.client
+            .use_ns(&from.namespace)
+            .use_db(&from.database)
+            .await?;
+        let result = snapshot::export(&self.client, from).await;
+        self.client
+            .use_ns(&current.namespace)
+            .use_db(&current.database)
+            .await?;
+
+        result
+    }
+
+    /// Records per table in `scope`.
+    #[tracing::instrument(name = "Admin: Count Tables", skip(self))]
+    pub async fn table_counts(&self, scope: Scope) -> Result<BTreeMap<String, usize>, Error> {
+        let current = self.scope.lock().await;
+
+        self.client
+            .use_ns(&scope.namespace)
+            .use_db(&scope.database)
+            .await?;
+        let result = snapshot::table_counts(&self.client).await;
+        self.client
+            .use_ns(&current.namespace)
+            .use_db(&current.database)
+            .await?;
+
+        result
+    }
+
+    /// Restores a snapshot into `into`, which is created if it doesn't exist,
+    /// e.g. to clone production data into a local database. Snapshots with
+    /// statements an export doesn't write are refused before anything runs.
+    #[tracing::instrument(name = "Admin: Restore Snapshot", skip(self, surql))]
+    pub async fn restore(&self, into: Scope, surql: &str) -> Result<RestoreReport, Error> {
+        snapshot::check_restorable(surql)?;
+        let current = self.scope.lock().await;
+
+        self.client
+            .use_ns(&into.namespace)
+            .use_db(&into.database)
+            .await?;
+        let result = snapshot::restore(&self.client, surql).await;
+        self.client
+            .use_ns(&current.namespace)
+            .use_db(&current.database)
+            .await?;
+
+        Ok(RestoreReport {
+            scope: into,
+            statements: result?,
+        })
+    }
+}
+
+async fn list_namespaces(
+    client: &Surreal<Client>,
+    current: &Scope,
+) -> Result<Vec<NamespaceInfo>, Error> {
+    let kv: Option<KvInfo> = traced(
+        "INFO FOR KV;",
+        client.query("INFO FOR KV;").bind(correlation()),
+    )
+    .await?
+    .take(0)?;
+    let names: Vec<String> = kv.map(|kv| kv.ns.into_keys().collect()).unwrap_or_default();
+
+    // INFO FOR NS only reports on the namespace in use, so hop through each one
+    // and put the connection back where it was afterwards, even on failure.
+    let mut namespaces = Vec::with_capacity(names.len());
+    let mut result = Ok(());
+    for name in names {
+        match list_databases(client, &name, &current.database).await {
+            Ok(databases) => namespaces.push(NamespaceInfo { name, databases }),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    client
+        .use_ns(&current.namespace)
+        .use_db(&current.database)
+        .await?;
+
+    result.map(|_| namespaces)
+}
+
+async fn list_databases(
+    client: &Surreal<Client>,
+    namespace: &str,
+    database: &str,
+) -> Result<Vec<String>, Error> {
+    client.use_ns(namespace).use_db(database).await?;
+    let ns: Option<NsInfo> = traced(
+        "INFO FOR NS;",
+        client.query("INFO FOR NS;").bind(correlation()),
+    )
+    .await?
+    .take(0)?;
+    Ok(ns.map(|ns| ns.db.into_keys().collect()).unwrap_or_default())
+}
+// endregion: -- AdminDatabase
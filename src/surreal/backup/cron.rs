@@ -0,0 +1,150 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, TimeZone, Timelike, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// Furthest a schedule is searched ahead before giving up, e.g. for
+/// `0 0 31 2 *`, which never fires.
+const MAX_SEARCH_DAYS: i64 = 366 * 4;
+
+// region: -- Schedule
+/// A five-field cron expression, evaluated in UTC: minute, hour, day of
+/// month, month and day of week (0 or 7 is Sunday). Each field takes `*`,
+/// numbers, `a-b` ranges, `/n` steps and comma-separated lists. As in cron,
+/// when both day fields are restricted a day matching either one counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    /// The first time after `after`, to the minute, that the schedule fires.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limit = after + Duration::days(MAX_SEARCH_DAYS);
+        while at <= limit {
+            if !has(self.months, at.month()) {
+                let (year, month) = match at.month() {
+                    12 => (at.year() + 1, 1),
+                    month => (at.year(), month + 1),
+                };
+                at = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(at) {
+                at = start_of_day(at) + Duration::days(1);
+            } else if !has(self.hours, at.hour()) {
+                at = at.duration_trunc(Duration::hours(1)).ok()? + Duration::hours(1);
+            } else if !has(self.minutes, at.minute()) {
+                at += Duration::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, at: DateTime<Utc>) -> bool {
+        let day = has(self.days, at.day());
+        let weekday = has(self.weekdays, at.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn start_of_day(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(Duration::days(1)).unwrap_or(at)
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "`{expression}` needs 5 fields (minute hour day month weekday), not {}",
+                fields.len()
+            ));
+        };
+        let mut weekdays = field("weekday", weekday, 0, 7)?;
+        // Sunday is both 0 and 7.
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: field("minute", minute, 0, 59)?,
+            hours: field("hour", hour, 0, 23)?,
+            days: field("day", day, 1, 31)?,
+            months: field("month", month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// The values `spec` allows, as bits.
+fn field(name: &str, spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = |why: &str| format!("{name} `{spec}` {why}");
+    let mut set = 0;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| invalid("has a step that isn't a positive number"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| invalid(&format!("must be within {min}-{max}")))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `5/15` runs from 5 to the end, like `5-59/15`.
+                None if step > 1 => (number(range)?, max),
+                None => {
+                    let value = number(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid("has a range that runs backwards"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+// endregion: -- Schedule
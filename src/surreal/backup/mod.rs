@@ -0,0 +1,310 @@
+pub mod cron;
+pub mod s3;
+pub mod store;
+
+use crate::error::Error;
+use crate::surreal::admin::{AdminDatabase, Scope};
+use crate::surreal::backup::cron::Schedule;
+use crate::surreal::backup::s3::S3Settings;
+use crate::surreal::backup::store::BackupStore;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+const SNAPSHOT_EXTENSION: &str = ".surql";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub static BACKUPS: Lazy<BackupMonitor> = Lazy::new(BackupMonitor::default);
+
+// region: -- BackupSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// When to back up, as a five-field cron expression in UTC.
+    pub schedule: String,
+    pub retention: RetentionSettings,
+    pub target: BackupTarget,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: "0 3 * * *".into(),
+            retention: RetentionSettings::default(),
+            target: BackupTarget::Directory {
+                path: "backups".into(),
+            },
+        }
+    }
+}
+
+impl BackupSettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.enabled {
+            return problems;
+        }
+        if let Err(e) = self.schedule.parse::<Schedule>() {
+            problems.push(format!("`backup.schedule` is not a cron expression: {e}"));
+        }
+        if self.retention.keep_last == 0 {
+            problems.push("`backup.retention.keep_last` must be at least 1".into());
+        }
+        match &self.target {
+            BackupTarget::Directory { path } if path.as_os_str().is_empty() => {
+                problems.push("`backup.target.path` must not be empty".into())
+            }
+            BackupTarget::Directory { path } if path.exists() && !path.is_dir() => {
+                problems.push(format!(
+                    "`backup.target.path` ({}) is not a directory",
+                    path.display()
+                ))
+            }
+            BackupTarget::Directory { .. } => {}
+            BackupTarget::S3(s3) => problems.extend(s3.problems()),
+        }
+        problems
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RetentionSettings {
+    /// Newest snapshots always kept.
+    pub keep_last: usize,
+    /// Snapshots older than this are removed, past `keep_last` or not. The
+    /// newest snapshot is never removed.
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            keep_last: 7,
+            max_age_days: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackupTarget {
+    Directory { path: PathBuf },
+    S3(S3Settings),
+}
+// endregion: -- BackupSettings
+
+// region: -- Snapshot names
+/// `{namespace}-{database}-{time}.surql`, with the time in UTC to the second,
+/// so names sort by age within a scope.
+pub fn snapshot_name(scope: &Scope, at: DateTime<Utc>) -> String {
+    format!(
+        "{}-{}-{}{SNAPSHOT_EXTENSION}",
+        scope.namespace,
+        scope.database,
+        at.format(SNAPSHOT_TIME_FORMAT)
+    )
+}
+
+/// When a snapshot of `scope` named `name` was taken, or `None` if it isn't
+/// one, e.g. a snapshot of another database sharing the store.
+pub fn snapshot_time(scope: &Scope, name: &str) -> Option<DateTime<Utc>> {
+    let time = name
+        .strip_prefix(&format!("{}-{}-", scope.namespace, scope.database))?
+        .strip_suffix(SNAPSHOT_EXTENSION)?;
+    let time = NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT).ok()?;
+    Some(Utc.from_utc_datetime(&time))
+}
+
+/// The snapshots of `scope` among `names` that `retention` says to remove
+/// at `now`, oldest first. Other names are left alone.
+pub fn prune_plan(
+    scope: &Scope,
+    names: &[String],
+    now: DateTime<Utc>,
+    retention: &RetentionSettings,
+) -> Vec<String> {
+    let mut snapshots: Vec<(DateTime<Utc>, &String)> = names
+        .iter()
+        .filter_map(|name| Some((snapshot_time(scope, name)?, name)))
+        .collect();
+    snapshots.sort_by(|a, b| b.cmp(a));
+    let oldest_kept = retention
+        .max_age_days
+        .map(|days| now - Duration::days(days.into()));
+
+    let mut expired: Vec<String> = snapshots
+        .into_iter()
+        .enumerate()
+        .filter(|(index, (taken_at, _))| {
+            *index > 0
+                && (*index >= retention.keep_last
+                    || oldest_kept.is_some_and(|oldest| *taken_at < oldest))
+        })
+        .map(|(_, (_, name))| name.clone())
+        .collect();
+    expired.reverse();
+    expired
+}
+// endregion: -- Snapshot names
+
+// region: -- BackupMonitor
+/// The outcome of one backup.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackupRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub tables: usize,
+    pub records: usize,
+    pub bytes: usize,
+    /// Old snapshots removed afterwards.
+    pub pruned: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupStatus {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<DateTime<Utc>>,
+    /// The last backup since startup, if one has run.
+    pub last: Option<BackupRun>,
+}
+
+/// What the scheduler last did and will do next, for `/health/ready`.
+#[derive(Debug, Default)]
+pub struct BackupMonitor {
+    status: RwLock<BackupStatus>,
+}
+
+impl BackupMonitor {
+    pub fn status(&self) -> BackupStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    pub fn scheduled(&self, schedule: &Schedule, next_run: Option<DateTime<Utc>>) {
+        let mut status = self.status.write().unwrap();
+        status.enabled = true;
+        status.schedule = Some(schedule.to_string());
+        status.next_run = next_run;
+    }
+
+    pub fn record(&self, run: BackupRun) {
+        self.status.write().unwrap().last = Some(run);
+    }
+}
+// endregion: -- BackupMonitor
+
+// region: -- Scheduler
+/// Exports `scope`, writes it to `store` and prunes what `retention` no
+/// longer keeps. Failing to prune is logged but doesn't fail the backup,
+/// which has been written by then.
+#[tracing::instrument(name = "Backup: Run", skip(admin, store))]
+pub async fn back_up(
+    admin: &AdminDatabase,
+    scope: &Scope,
+    store: &dyn BackupStore,
+    retention: &RetentionSettings,
+) -> BackupRun {
+    let started_at = Utc::now();
+    let mut run = BackupRun {
+        started_at,
+        finished_at: started_at,
+        ok: false,
+        name: None,
+        tables: 0,
+        records: 0,
+        bytes: 0,
+        pruned: 0,
+        error: None,
+    };
+
+    let written = async {
+        let snapshot = admin.export(Some(scope.clone())).await?;
+        let name = snapshot_name(scope, started_at);
+        run.tables = snapshot.tables;
+        run.records = snapshot.records;
+        run.bytes = snapshot.surql.len();
+        store.put(&name, snapshot.surql.into_bytes()).await?;
+        Ok::<_, Error>(name)
+    }
+    .await;
+    match written {
+        Ok(name) => {
+            run.ok = true;
+            run.name = Some(name);
+        }
+        Err(error) => run.error = Some(error.to_string()),
+    }
+
+    if run.ok {
+        match store.list().await {
+            Ok(names) => {
+                for name in prune_plan(scope, &names, Utc::now(), retention) {
+                    match store.delete(&name).await {
+                        Ok(()) => run.pruned += 1,
+                        Err(error) => tracing::warn!(%error, name, "Failed to prune backup"),
+                    }
+                }
+            }
+            Err(error) => tracing::warn!(%error, "Failed to list backups to prune"),
+        }
+    }
+
+    run.finished_at = Utc::now();
+    run
+}
+
+/// Backs up `scope` on `settings.schedule` for as long as the server runs.
+/// Does nothing if backups are disabled.
+pub fn spawn_scheduler(
+    admin: AdminDatabase,
+    scope: Scope,
+    settings: &BackupSettings,
+) -> Result<(), Error> {
+    if !settings.enabled {
+        return Ok(());
+    }
+    let schedule: Schedule = settings
+        .schedule
+        .parse()
+        .map_err(|e| Error::Backup(format!("`backup.schedule`: {e}")))?;
+    let store = store::open(&settings.target)?;
+    let retention = settings.retention.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let next_run = schedule.next_after(Utc::now());
+            BACKUPS.scheduled(&schedule, next_run);
+            let Some(next_run) = next_run else {
+                tracing::warn!(%schedule, "Backup schedule never fires again");
+                return;
+            };
+            let wait = (next_run - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let run = back_up(&admin, &scope, store.as_ref(), &retention).await;
+            match &run.error {
+                None => tracing::info!(
+                    name = run.name.as_deref(),
+                    bytes = run.bytes,
+                    pruned = run.pruned,
+                    "Backed up the database"
+                ),
+                Some(error) => tracing::error!(%error, "Backup failed"),
+            }
+            BACKUPS.record(run);
+        }
+    });
+    Ok(())
+}
+// endregion: -- Scheduler
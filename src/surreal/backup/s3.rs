@@ -0,0 +1,294 @@
+use crate::error::Error;
+use crate::secret::Secret;
+use crate::surreal::backup::store::BackupStore;
+use chrono::Utc;
+use futures_core::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Characters SigV4 leaves unencoded, everywhere but key paths.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+/// Key paths also keep their `/`.
+const KEY: &AsciiSet = &UNRESERVED.remove(b'/');
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+/// Most of an error response body kept in the error.
+const MAX_ERROR_BODY: usize = 512;
+
+// region: -- S3Settings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct S3Settings {
+    /// Base URL of the service, e.g. `https://s3.eu-west-1.amazonaws.com` or
+    /// `http://localhost:9000` for MinIO. Buckets are addressed path-style.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Put in front of every snapshot name, e.g. `surreal/`.
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: Secret<String>,
+}
+
+impl S3Settings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match Url::parse(&self.endpoint) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {}
+            _ => problems.push(format!(
+                "`backup.target.endpoint` ({}) must be an http or https URL",
+                self.endpoint
+            )),
+        }
+        for (name, value) in [
+            ("backup.target.region", &self.region),
+            ("backup.target.bucket", &self.bucket),
+            ("backup.target.access_key_id", &self.access_key_id),
+            (
+                "backup.target.secret_access_key",
+                self.secret_access_key.expose(),
+            ),
+        ] {
+            if value.trim().is_empty() {
+                problems.push(format!("`{name}` must not be empty"));
+            }
+        }
+        problems
+    }
+}
+// endregion: -- S3Settings
+
+// region: -- S3Store
+/// Snapshots as objects in an S3-compatible bucket, with requests signed
+/// with AWS Signature Version 4.
+pub struct S3Store {
+    client: reqwest::Client,
+    endpoint: Url,
+    settings: S3Settings,
+}
+
+impl S3Store {
+    pub fn new(settings: &S3Settings) -> Result<Self, Error> {
+        let endpoint = Url::parse(&settings.endpoint)
+            .map_err(|e| Error::Backup(format!("invalid S3 endpoint: {e}")))?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint,
+            settings: settings.clone(),
+        })
+    }
+
+    fn object_path(&self, name: &str) -> String {
+        let key = format!("{}{name}", self.settings.prefix);
+        format!(
+            "/{}/{}",
+            self.settings.bucket,
+            utf8_percent_encode(&key, KEY)
+        )
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<String, Error> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let mut pairs: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (encode(name), encode(value)))
+            .collect();
+        pairs.sort();
+        let query = pairs
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{port}", self.endpoint.host_str().unwrap_or_default()),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let canonical_request = format!(
+            "{method}\n{path}\n{query}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\n\
+             x-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{payload_hash}"
+        );
+        let credential_scope = format!("{date}/{}/s3/aws4_request", self.settings.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{credential_scope}\n{}",
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(
+            self.settings.secret_access_key.expose(),
+            &date,
+            &self.settings.region,
+            "s3",
+        );
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{credential_scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+            self.settings.access_key_id
+        );
+
+        let mut url = format!("{}{path}", self.endpoint.origin().ascii_serialization());
+        if !query.is_empty() {
+            url = format!("{url}?{query}");
+        }
+        let response = self
+            .client
+            .request(method.clone(), url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::Backup(format!("S3 {method} {path} failed: {e}")))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| Error::Backup(format!("S3 {method} {path} failed: {e}")))?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(format!("S3 {path}")));
+        }
+        if !status.is_success() {
+            let text: String = text.chars().take(MAX_ERROR_BODY).collect();
+            return Err(Error::Backup(format!(
+                "S3 {method} {path} answered {status}: {text}"
+            )));
+        }
+        Ok(text)
+    }
+}
+
+impl BackupStore for S3Store {
+    fn put<'a>(&'a self, name: &'a str, body: Vec<u8>) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.send(Method::PUT, &self.object_path(name), &[], body)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<String, Error>> {
+        Box::pin(async move {
+            self.send(Method::GET, &self.object_path(name), &[], Vec::new())
+                .await
+        })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let path = format!("/{}", self.settings.bucket);
+            let prefix = self.settings.prefix.as_str();
+            let mut names = Vec::new();
+            let mut token: Option<String> = None;
+            loop {
+                let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+                if let Some(token) = &token {
+                    query.push(("continuation-token", token));
+                }
+                let page =
+                    parse_list_page(&self.send(Method::GET, &path, &query, Vec::new()).await?);
+                names.extend(page.keys.iter().filter_map(|key| {
+                    key.strip_prefix(prefix)
+                        .filter(|name| !name.contains('/'))
+                        .map(str::to_string)
+                }));
+                match page.next {
+                    Some(next) => token = Some(next),
+                    None => return Ok(names),
+                }
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            // S3 answers `204` whether or not the object existed.
+            self.send(Method::DELETE, &self.object_path(name), &[], Vec::new())
+                .await?;
+            Ok(())
+        })
+    }
+}
+// endregion: -- S3Store
+
+// region: -- Signing
+/// The SigV4 key for one day, region and service.
+pub fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, UNRESERVED).to_string()
+}
+// endregion: -- Signing
+
+// region: -- ListObjectsV2
+/// One page of a `ListObjectsV2` answer.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ListPage {
+    pub keys: Vec<String>,
+    /// Where the next page starts, if the listing was truncated.
+    pub next: Option<String>,
+}
+
+/// Picks the keys and continuation token out of a `ListObjectsV2` body.
+/// Only those few elements are needed, so this isn't a general XML parser.
+pub fn parse_list_page(xml: &str) -> ListPage {
+    let keys = elements(xml, "Key").map(unescape).collect();
+    let truncated = elements(xml, "IsTruncated").next() == Some("true");
+    let next = elements(xml, "NextContinuationToken")
+        .next()
+        .filter(|_| truncated)
+        .map(unescape);
+    ListPage { keys, next }
+}
+
+fn elements<'a>(xml: &'a str, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let end = rest[start..].find(&close)? + start;
+        let text = &rest[start..end];
+        rest = &rest[end + close.len()..];
+        Some(text)
+    })
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+// endregion: -- ListObjectsV2
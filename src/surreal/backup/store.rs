@@ -0,0 +1,122 @@
+use crate::error::Error;
+use crate::surreal::backup::s3::S3Store;
+use crate::surreal::backup::BackupTarget;
+use futures_core::future::BoxFuture;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// region: -- BackupStore
+/// Where snapshots are kept. Names are plain file names, such as those made
+/// by [`crate::surreal::backup::snapshot_name`].
+pub trait BackupStore: Send + Sync {
+    fn put<'a>(&'a self, name: &'a str, body: Vec<u8>) -> BoxFuture<'a, Result<(), Error>>;
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<String, Error>>;
+
+    /// Every snapshot in the store, in no particular order.
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, Error>>;
+
+    /// Removing a snapshot that is already gone is not an error.
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+pub fn open(target: &BackupTarget) -> Result<Arc<dyn BackupStore>, Error> {
+    Ok(match target {
+        BackupTarget::Directory { path } => Arc::new(DirectoryStore::new(path)),
+        BackupTarget::S3(settings) => Arc::new(S3Store::new(settings)?),
+    })
+}
+
+fn io_error(action: &str, path: &Path, error: io::Error) -> Error {
+    Error::Backup(format!("failed to {action} {}: {error}", path.display()))
+}
+// endregion: -- BackupStore
+
+// region: -- DirectoryStore
+/// Snapshots as `.surql` files in one local directory, created on the first
+/// write. Like the batch journal, files are written under a temporary name
+/// and renamed into place, so a listing never sees half a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+}
+
+impl DirectoryStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(Error::Backup(format!("`{name}` is not a snapshot name")));
+        }
+        Ok(self.dir.join(name))
+    }
+}
+
+impl BackupStore for DirectoryStore {
+    fn put<'a>(&'a self, name: &'a str, body: Vec<u8>) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let path = self.path(name)?;
+            let temporary = self.dir.join(format!(".{name}.tmp"));
+            tokio::fs::create_dir_all(&self.dir)
+                .await
+                .map_err(|e| io_error("create", &self.dir, e))?;
+            tokio::fs::write(&temporary, body)
+                .await
+                .map_err(|e| io_error("write", &temporary, e))?;
+            tokio::fs::rename(&temporary, &path)
+                .await
+                .map_err(|e| io_error("rename into", &path, e))
+        })
+    }
+
+    fn get<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<String, Error>> {
+        Box::pin(async move {
+            let path = self.path(name)?;
+            match tokio::fs::read_to_string(&path).await {
+                Ok(surql) => Ok(surql),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                    Err(Error::NotFound(format!("backup {name}")))
+                }
+                Err(error) => Err(io_error("read", &path, error)),
+            }
+        })
+    }
+
+    fn list(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let mut entries = match tokio::fs::read_dir(&self.dir).await {
+                Ok(entries) => entries,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(error) => return Err(io_error("list", &self.dir, error)),
+            };
+            let mut names = Vec::new();
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| io_error("list", &self.dir, e))?
+            {
+                if let Some(name) = entry.file_name().to_str() {
+                    if !name.starts_with('.') {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+            Ok(names)
+        })
+    }
+
+    fn delete<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let path = self.path(name)?;
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => Ok(()),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(error) => Err(io_error("delete", &path, error)),
+            }
+        })
+    }
+}
+// endregion: -- DirectoryStore
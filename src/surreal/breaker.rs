@@ -0,0 +1,340 @@
+use crate::surreal::retry::is_retryable;
+use crate::surreal::session::is_auth_error;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use surrealdb::error::Api;
+
+// Sent back instead of running a query while the breaker is open, so the
+// rejection travels through `surrealdb::Result` like any other failure.
+const CIRCUIT_OPEN: &str = "database circuit breaker is open";
+
+pub static BREAKER: Lazy<CircuitBreaker> =
+    Lazy::new(|| CircuitBreaker::new(&BreakerSettings::default()));
+
+// region: -- BreakerSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BreakerSettings {
+    pub enabled: bool,
+    /// How many of the latest queries the rates are taken over.
+    pub window: usize,
+    /// Queries in the window before the breaker can open.
+    pub min_calls: usize,
+    /// Share of failed queries in the window that opens the breaker.
+    pub error_rate_percent: u8,
+    /// Queries at least this slow count towards `slow_rate_percent`.
+    pub slow_ms: u64,
+    /// Share of slow queries in the window that opens the breaker.
+    pub slow_rate_percent: u8,
+    /// How long the breaker stays open before probing.
+    pub open_secs: u64,
+    /// Queries let through while half-open; all of them must succeed to close.
+    pub probes: u32,
+}
+
+impl Default for BreakerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: 50,
+            min_calls: 20,
+            error_rate_percent: 50,
+            slow_ms: 1000,
+            slow_rate_percent: 50,
+            open_secs: 10,
+            probes: 3,
+        }
+    }
+}
+// endregion: -- BreakerSettings
+
+// region: -- CircuitBreaker
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BreakerMetrics {
+    pub state: CircuitState,
+    pub trips: u64,
+    pub rejected: u64,
+    /// Over the current window.
+    pub error_rate_percent: u8,
+    pub slow_rate_percent: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Outcome {
+    failed: bool,
+    slow: bool,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, succeeded: u32 },
+}
+
+#[derive(Debug)]
+struct Inner {
+    settings: BreakerSettings,
+    state: State,
+    outcomes: VecDeque<Outcome>,
+}
+
+/// Stops sending queries to a database that is failing or crawling. Closed,
+/// it watches the latest queries; once too many of them fail or run slow it
+/// opens and every query fails fast for `open_secs`. Then it half-opens and
+/// lets `probes` queries through: if they all succeed it closes again, and
+/// the first one to fail or run slow opens it for another `open_secs`.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    inner: Mutex<Inner>,
+    trips: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// Permission to run one query. Hand it back through [`Permit::record`]; a
+/// permit dropped without an outcome (the query was abandoned) frees its
+/// probe slot without counting either way.
+#[must_use]
+#[derive(Debug)]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    recorded: bool,
+}
+
+/// The breaker is open; try again after `retry_after`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected {
+    pub retry_after: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: &BreakerSettings) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                settings: settings.clone(),
+                state: State::Closed,
+                outcomes: VecDeque::new(),
+            }),
+            trips: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn configure(&self, settings: &BreakerSettings) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.settings = settings.clone();
+        if !settings.enabled {
+            inner.state = State::Closed;
+            inner.outcomes.clear();
+        }
+    }
+
+    pub fn settings(&self) -> BreakerSettings {
+        self.inner.lock().unwrap().settings.clone()
+    }
+
+    pub fn admit(&self, now: Instant) -> Result<Permit<'_>, Rejected> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.settings.enabled {
+            return Ok(self.permit(false));
+        }
+
+        if let State::Open { until } = inner.state {
+            if now < until {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(Rejected {
+                    retry_after: until - now,
+                });
+            }
+            tracing::info!("Database circuit breaker half-open; probing");
+            inner.state = State::HalfOpen {
+                in_flight: 0,
+                succeeded: 0,
+            };
+        }
+
+        let probes = inner.settings.probes.max(1);
+        match &mut inner.state {
+            State::HalfOpen {
+                in_flight,
+                succeeded,
+            } => {
+                if *in_flight + *succeeded >= probes {
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    return Err(Rejected {
+                        retry_after: Duration::from_secs(1),
+                    });
+                }
+                *in_flight += 1;
+                Ok(self.permit(true))
+            }
+            _ => Ok(self.permit(false)),
+        }
+    }
+
+    fn permit(&self, probe: bool) -> Permit<'_> {
+        Permit {
+            breaker: self,
+            probe,
+            recorded: false,
+        }
+    }
+
+    fn record(&self, probe: bool, now: Instant, elapsed: Duration, failed: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.settings.enabled {
+            return;
+        }
+        let outcome = Outcome {
+            failed,
+            slow: elapsed >= Duration::from_millis(inner.settings.slow_ms),
+        };
+        let probes = inner.settings.probes.max(1);
+
+        let trip = match (&mut inner.state, probe) {
+            (
+                State::HalfOpen {
+                    in_flight,
+                    succeeded,
+                },
+                true,
+            ) => {
+                *in_flight = in_flight.saturating_sub(1);
+                if outcome.failed || outcome.slow {
+                    true
+                } else {
+                    *succeeded += 1;
+                    if *succeeded >= probes {
+                        tracing::info!("Database circuit breaker closed");
+                        inner.state = State::Closed;
+                        inner.outcomes.clear();
+                    }
+                    false
+                }
+            }
+            (State::Closed, false) => {
+                inner.outcomes.push_back(outcome);
+                while inner.outcomes.len() > inner.settings.window.max(1) {
+                    inner.outcomes.pop_front();
+                }
+                let (error_rate, slow_rate) = rates(&inner.outcomes);
+                inner.outcomes.len() >= inner.settings.min_calls
+                    && (error_rate >= inner.settings.error_rate_percent
+                        || slow_rate >= inner.settings.slow_rate_percent)
+            }
+            // Queries admitted before the breaker changed state.
+            _ => false,
+        };
+        if trip {
+            self.trip(&mut inner, now);
+        }
+    }
+
+    fn release(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if let State::HalfOpen { in_flight, .. } = &mut inner.state {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+
+    fn trip(&self, inner: &mut Inner, now: Instant) {
+        let (error_rate, slow_rate) = rates(&inner.outcomes);
+        tracing::error!(
+            error_rate_percent = error_rate,
+            slow_rate_percent = slow_rate,
+            open_secs = inner.settings.open_secs,
+            "Database circuit breaker opened"
+        );
+        self.trips.fetch_add(1, Ordering::Relaxed);
+        inner.state = State::Open {
+            until: now + Duration::from_secs(inner.settings.open_secs),
+        };
+        inner.outcomes.clear();
+    }
+
+    pub fn state(&self, now: Instant) -> CircuitState {
+        match self.inner.lock().unwrap().state {
+            State::Closed => CircuitState::Closed,
+            State::Open { until } if now < until => CircuitState::Open,
+            State::Open { .. } | State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// How long until a query may be tried again, if the breaker is open.
+    pub fn retry_after(&self, now: Instant) -> Option<Duration> {
+        match self.inner.lock().unwrap().state {
+            State::Open { until } if now < until => Some(until - now),
+            State::HalfOpen { .. } => Some(Duration::from_secs(1)),
+            _ => None,
+        }
+    }
+
+    pub fn metrics(&self) -> BreakerMetrics {
+        let (error_rate_percent, slow_rate_percent) = rates(&self.inner.lock().unwrap().outcomes);
+        BreakerMetrics {
+            state: self.state(Instant::now()),
+            trips: self.trips.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            error_rate_percent,
+            slow_rate_percent,
+        }
+    }
+}
+
+impl Permit<'_> {
+    pub fn record(mut self, elapsed: Duration, failed: bool) {
+        self.recorded = true;
+        self.breaker
+            .record(self.probe, Instant::now(), elapsed, failed);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.recorded {
+            self.breaker.release();
+        }
+    }
+}
+
+impl Rejected {
+    pub fn into_error(self) -> surrealdb::Error {
+        surrealdb::Error::Api(Api::InternalError(CIRCUIT_OPEN.into()))
+    }
+}
+
+/// Errors that say the database is in trouble, rather than that the query or
+/// its caller was wrong. Expired sessions are left to [`crate::surreal::session`].
+pub fn counts_as_failure(error: &surrealdb::Error) -> bool {
+    is_retryable(error) && !is_auth_error(error)
+}
+
+/// Whether `error` is a query the breaker turned away.
+pub fn is_circuit_open(error: &surrealdb::Error) -> bool {
+    matches!(error, surrealdb::Error::Api(Api::InternalError(message)) if message == CIRCUIT_OPEN)
+}
+
+/// Failed and slow shares of `outcomes`, in percent.
+fn rates(outcomes: &VecDeque<Outcome>) -> (u8, u8) {
+    if outcomes.is_empty() {
+        return (0, 0);
+    }
+    let percent = |count: usize| (count * 100 / outcomes.len()) as u8;
+    (
+        percent(outcomes.iter().filter(|o| o.failed).count()),
+        percent(outcomes.iter().filter(|o| o.slow).count()),
+    )
+}
+// endregion: -- CircuitBreaker
@@ -0,0 +1,93 @@
+use crate::surreal::cache::Cache;
+use futures_core::future::BoxFuture;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Entries held at once; past this, expired ones are dropped and then, if
+/// still full, everything.
+const MAX_ENTRIES: usize = 1000;
+
+// region: -- MemoryCache
+/// Entries in this process only. Each instance of a deployment has its own,
+/// so a write through one leaves the others' entries until they expire.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    expires_at: Instant,
+    value: Value,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value under `key` in `group`, if it is still fresh at `now`.
+    pub fn get_at(&self, group: &str, key: &str, now: Instant) -> Option<Value> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&(group.to_string(), key.to_string()))
+            .filter(|entry| now < entry.expires_at)
+            .map(|entry| entry.value.clone())
+    }
+
+    pub fn put_at(&self, group: &str, key: &str, value: Value, ttl: Duration, now: Instant) {
+        if ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| now < entry.expires_at);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(
+            (group.to_string(), key.to_string()),
+            Entry {
+                expires_at: now + ttl,
+                value,
+            },
+        );
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get<'a>(&'a self, group: &'a str, key: &'a str) -> BoxFuture<'a, Option<Value>> {
+        let value = self.get_at(group, key, Instant::now());
+        Box::pin(async move { value })
+    }
+
+    fn put<'a>(
+        &'a self,
+        group: &'a str,
+        key: &'a str,
+        value: Value,
+        ttl: Duration,
+    ) -> BoxFuture<'a, ()> {
+        self.put_at(group, key, value, ttl, Instant::now());
+        Box::pin(async {})
+    }
+
+    fn invalidate(&self, group: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(entry_group, _), _| entry_group != group);
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
+// endregion: -- MemoryCache
@@ -0,0 +1,141 @@
+pub mod memory;
+pub mod redis;
+
+use crate::error::Error;
+use crate::secret::Secret;
+use ::redis::IntoConnectionInfo;
+use futures_core::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use self::memory::MemoryCache;
+pub use self::redis::RedisCache;
+
+// region: -- CacheSettings
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheSettings {
+    pub backend: CacheBackend,
+}
+
+/// Where cached entries live. `memory` is enough for one instance; behind a
+/// load balancer, `redis` lets a write through any instance invalidate the
+/// entries of all of them.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CacheBackend {
+    #[default]
+    Memory,
+    Redis(RedisSettings),
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RedisSettings {
+    /// `redis://host:port/db`, or `rediss://` for TLS.
+    pub url: String,
+    #[serde(default)]
+    pub password: Option<Secret<String>>,
+    /// Prefixes every key, so deployments can share a server.
+    #[serde(default = "default_key_prefix")]
+    pub key_prefix: String,
+    /// The pub/sub channel invalidations are announced on.
+    #[serde(default = "default_channel")]
+    pub channel: String,
+}
+
+fn default_key_prefix() -> String {
+    "surreal-simple:".into()
+}
+
+fn default_channel() -> String {
+    "surreal-simple:invalidate".into()
+}
+
+impl CacheSettings {
+    pub fn problems(&self) -> Vec<String> {
+        match &self.backend {
+            CacheBackend::Memory => Vec::new(),
+            CacheBackend::Redis(redis) => redis.problems("cache.backend"),
+        }
+    }
+}
+
+impl RedisSettings {
+    /// Problems with these settings, found under `section` of the
+    /// configuration.
+    pub fn problems(&self, section: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(self.url.starts_with("redis://") || self.url.starts_with("rediss://")) {
+            problems.push(format!(
+                "`{section}.url` ({}) must be a redis:// or rediss:// URL",
+                self.url
+            ));
+        }
+        if self.channel.trim().is_empty() {
+            problems.push(format!("`{section}.channel` must not be empty"));
+        }
+        problems
+    }
+
+    /// A client for these settings, not yet connected.
+    pub fn client(&self) -> Result<::redis::Client, Error> {
+        let mut info = self
+            .url
+            .as_str()
+            .into_connection_info()
+            .map_err(redis_error)?;
+        if let Some(password) = &self.password {
+            info.redis.password = Some(password.expose().clone());
+        }
+        ::redis::Client::open(info).map_err(redis_error)
+    }
+}
+
+pub(crate) fn redis_error(error: ::redis::RedisError) -> Error {
+    Error::Cache(format!("redis: {error}"))
+}
+// endregion: -- CacheSettings
+
+// region: -- Cache
+/// Short-lived entries kept to save the database work, grouped so a write
+/// can drop everything that depends on what it wrote; groups are table
+/// names. A cache that can't be reached acts as one that is empty: nothing
+/// here fails a request.
+pub trait Cache: Send + Sync {
+    /// The value under `key` in `group`, if it is still fresh.
+    fn get<'a>(&'a self, group: &'a str, key: &'a str) -> BoxFuture<'a, Option<Value>>;
+
+    /// Keeps `value` for `ttl`; a zero `ttl` keeps nothing.
+    fn put<'a>(
+        &'a self,
+        group: &'a str,
+        key: &'a str,
+        value: Value,
+        ttl: Duration,
+    ) -> BoxFuture<'a, ()>;
+
+    /// Forgets every entry in `group`, on every instance sharing the cache.
+    /// This process stops seeing them at once; others shortly after.
+    fn invalidate(&self, group: &str);
+
+    /// Forgets everything, everywhere.
+    fn clear(&self);
+
+    /// Entries held in this process.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Connects the configured backend. Redis is reached before this returns,
+/// so a wrong URL stops startup rather than every request missing.
+pub async fn open(settings: &CacheSettings) -> Result<Arc<dyn Cache>, Error> {
+    Ok(match &settings.backend {
+        CacheBackend::Memory => Arc::new(MemoryCache::new()),
+        CacheBackend::Redis(redis) => Arc::new(RedisCache::connect(redis).await?),
+    })
+}
+// endregion: -- Cache
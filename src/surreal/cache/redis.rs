@@ -0,0 +1,210 @@
+use crate::error::Error;
+use crate::surreal::cache::{redis_error, Cache, MemoryCache, RedisSettings};
+use ::redis::aio::MultiplexedConnection;
+use ::redis::{AsyncCommands, Client};
+use chrono::Utc;
+use futures_core::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Announced on the channel by [`Cache::clear`], in place of a group.
+const ALL_GROUPS: &str = "*";
+
+/// How long the subscriber waits before reconnecting.
+const RESUBSCRIBE_AFTER: Duration = Duration::from_secs(1);
+
+// region: -- RedisCache
+/// Entries shared by every instance through Redis, one hash per group with
+/// the group's key prefix, e.g. `surreal-simple:person`. Each instance also
+/// keeps what it has read in a [`MemoryCache`] in front, so a hot entry costs
+/// no round trip; invalidations are announced on `channel` so every
+/// instance drops its own copies too.
+///
+/// While the subscription is down an instance could miss an announcement, so
+/// it clears its copies whenever it subscribes again.
+#[derive(Clone)]
+pub struct RedisCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    connection: MultiplexedConnection,
+    near: MemoryCache,
+    key_prefix: String,
+    channel: String,
+}
+
+/// An entry as stored in its group's hash. Redis expires whole hashes, so
+/// each entry carries its own deadline.
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    expires_at_ms: i64,
+    value: Value,
+}
+
+impl RedisCache {
+    /// Connects, and starts listening for invalidations until the process
+    /// exits.
+    pub async fn connect(settings: &RedisSettings) -> Result<Self, Error> {
+        let client = settings.client()?;
+        let connection = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .map_err(redis_error)?;
+
+        let cache = Self {
+            inner: Arc::new(Inner {
+                connection,
+                near: MemoryCache::new(),
+                key_prefix: settings.key_prefix.clone(),
+                channel: settings.channel.clone(),
+            }),
+        };
+        cache.spawn_subscriber(client);
+        Ok(cache)
+    }
+
+    fn hash(&self, group: &str) -> String {
+        format!("{}{group}", self.inner.key_prefix)
+    }
+
+    fn spawn_subscriber(&self, client: Client) {
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(error) = subscribe(&client, &inner).await {
+                    tracing::warn!(%error, "Cache invalidation subscription lost; resubscribing");
+                }
+                tokio::time::sleep(RESUBSCRIBE_AFTER).await;
+            }
+        });
+    }
+
+    /// Deletes `group`'s hash, or every hash under the prefix for
+    /// [`ALL_GROUPS`], then tells the other instances.
+    fn announce(&self, group: &str) {
+        let cache = self.clone();
+        let group = group.to_string();
+        tokio::spawn(async move {
+            let mut connection = cache.inner.connection.clone();
+            let deleted: ::redis::RedisResult<()> = async {
+                let hashes = if group == ALL_GROUPS {
+                    let pattern = format!("{}*", cache.inner.key_prefix);
+                    let mut keys = connection.scan_match::<_, String>(pattern).await?;
+                    let mut hashes = Vec::new();
+                    while let Some(key) = keys.next().await {
+                        hashes.push(key);
+                    }
+                    hashes
+                } else {
+                    vec![cache.hash(&group)]
+                };
+                if !hashes.is_empty() {
+                    connection.del::<_, ()>(hashes).await?;
+                }
+                connection
+                    .publish::<_, _, ()>(&cache.inner.channel, &group)
+                    .await
+            }
+            .await;
+            if let Err(error) = deleted {
+                tracing::warn!(%error, group, "Failed to invalidate cached entries in Redis");
+            }
+        });
+    }
+}
+
+async fn subscribe(client: &Client, inner: &Inner) -> ::redis::RedisResult<()> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(&inner.channel).await?;
+    inner.near.clear();
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let group: String = message.get_payload()?;
+        if group == ALL_GROUPS {
+            inner.near.clear();
+        } else {
+            inner.near.invalidate(&group);
+        }
+    }
+    Ok(())
+}
+
+impl Cache for RedisCache {
+    fn get<'a>(&'a self, group: &'a str, key: &'a str) -> BoxFuture<'a, Option<Value>> {
+        Box::pin(async move {
+            if let Some(value) = self.inner.near.get_at(group, key, Instant::now()) {
+                return Some(value);
+            }
+            let mut connection = self.inner.connection.clone();
+            let stored: Option<String> = match connection.hget(self.hash(group), key).await {
+                Ok(stored) => stored,
+                Err(error) => {
+                    tracing::warn!(%error, group, "Failed to read from the Redis cache");
+                    return None;
+                }
+            };
+            let stored: Stored = serde_json::from_str(&stored?).ok()?;
+            let left = stored.expires_at_ms - Utc::now().timestamp_millis();
+            let left = Duration::from_millis(u64::try_from(left).ok()?);
+            self.inner
+                .near
+                .put_at(group, key, stored.value.clone(), left, Instant::now());
+            Some(stored.value)
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        group: &'a str,
+        key: &'a str,
+        value: Value,
+        ttl: Duration,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if ttl.is_zero() {
+                return;
+            }
+            self.inner
+                .near
+                .put_at(group, key, value.clone(), ttl, Instant::now());
+            let stored = Stored {
+                expires_at_ms: Utc::now().timestamp_millis() + ttl.as_millis() as i64,
+                value,
+            };
+            let Ok(stored) = serde_json::to_string(&stored) else {
+                return;
+            };
+            let hash = self.hash(group);
+            let mut connection = self.inner.connection.clone();
+            let written: ::redis::RedisResult<()> = ::redis::pipe()
+                .hset(&hash, key, stored)
+                .ignore()
+                .pexpire(&hash, ttl.as_millis() as usize)
+                .ignore()
+                .query_async(&mut connection)
+                .await;
+            if let Err(error) = written {
+                tracing::warn!(%error, group, "Failed to write to the Redis cache");
+            }
+        })
+    }
+
+    fn invalidate(&self, group: &str) {
+        self.inner.near.invalidate(group);
+        self.announce(group);
+    }
+
+    fn clear(&self) {
+        self.inner.near.clear();
+        self.announce(ALL_GROUPS);
+    }
+
+    fn len(&self) -> usize {
+        self.inner.near.len()
+    }
+}
+// endregion: -- RedisCache
@@ -0,0 +1,257 @@
+use crate::error::existing_record;
+use crate::surreal::schema::indexes::index_violation;
+use crate::surreal::session::is_auth_error;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use surrealdb::error::{Api, Db};
+
+// The WebSocket engine reports a dropped connection as a string.
+const CONNECTION_MESSAGES: [&str; 5] = [
+    "connection reset",
+    "connection closed",
+    "connection refused",
+    "broken pipe",
+    "websocket",
+];
+
+// Remote engines send every database error as its message.
+const PERMISSION_MESSAGES: [&str; 2] = ["you don't have permission", "not enough permissions"];
+const PARSE_MESSAGES: [&str; 1] = ["parse error"];
+
+pub static CONNECTION: Lazy<ConnectionMonitor> = Lazy::new(ConnectionMonitor::new);
+
+// region: -- ConnectionMonitor
+/// What went wrong with a query, from the client's error or, for remote
+/// engines, its message. Decides both the response's status and the `kind`
+/// label of `surrealdb_query_errors_total`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The transport failed: the connection dropped or couldn't be made.
+    Connection,
+    Auth,
+    Cancelled,
+    Parse,
+    Permission,
+    RecordExists,
+    IndexViolation,
+    Other,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 8] = [
+        Self::Connection,
+        Self::Auth,
+        Self::Cancelled,
+        Self::Parse,
+        Self::Permission,
+        Self::RecordExists,
+        Self::IndexViolation,
+        Self::Other,
+    ];
+
+    pub fn of(error: &surrealdb::Error) -> Self {
+        if is_connection_error(error) {
+            Self::Connection
+        } else if is_auth_error(error) {
+            Self::Auth
+        } else if matches!(error, surrealdb::Error::Db(Db::QueryCancelled)) {
+            Self::Cancelled
+        } else if existing_record(error).is_some() {
+            Self::RecordExists
+        } else if index_violation(error).is_some() {
+            Self::IndexViolation
+        } else if is_permission_error(error) {
+            Self::Permission
+        } else if is_parse_error(error) {
+            Self::Parse
+        } else {
+            Self::Other
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Connection => "connection",
+            Self::Auth => "auth",
+            Self::Cancelled => "cancelled",
+            Self::Parse => "parse",
+            Self::Permission => "permission",
+            Self::RecordExists => "record_exists",
+            Self::IndexViolation => "index_violation",
+            Self::Other => "other",
+        }
+    }
+}
+
+fn is_permission_error(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(Db::QueryPermissions) => true,
+        other => {
+            let message = other.to_string().to_lowercase();
+            PERMISSION_MESSAGES.iter().any(|m| message.contains(m))
+        }
+    }
+}
+
+fn is_parse_error(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(Db::InvalidQuery { .. }) => true,
+        other => {
+            let message = other.to_string().to_lowercase();
+            PARSE_MESSAGES.iter().any(|m| message.contains(m))
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionMetrics {
+    pub connected: bool,
+    pub last_change: Option<DateTime<Utc>>,
+    pub disconnects: u64,
+    pub reconnects: u64,
+    /// Queries sent while the connection was down.
+    pub reconnect_attempts: u64,
+    pub in_flight: u64,
+    pub queries: u64,
+    /// Failed queries, by [`ErrorKind`], in [`ErrorKind::ALL`] order.
+    pub errors: [u64; ErrorKind::ALL.len()],
+}
+
+/// Tracks the WebSocket connection to SurrealDB from the queries that go
+/// over it. The client reconnects on its own and doesn't say when, so a
+/// query failing with a connection error marks the connection down and the
+/// next query the server answers, successfully or not, marks it up again.
+/// Each change is logged.
+#[derive(Debug)]
+pub struct ConnectionMonitor {
+    connected: AtomicBool,
+    last_change: Mutex<Option<DateTime<Utc>>>,
+    disconnects: AtomicU64,
+    reconnects: AtomicU64,
+    reconnect_attempts: AtomicU64,
+    in_flight: AtomicU64,
+    queries: AtomicU64,
+    errors: [AtomicU64; ErrorKind::ALL.len()],
+}
+
+/// One query on the wire; dropping it, finished or abandoned, takes it off
+/// the in-flight gauge.
+#[must_use]
+#[derive(Debug)]
+pub struct InFlight<'a> {
+    monitor: &'a ConnectionMonitor,
+}
+
+impl ConnectionMonitor {
+    pub fn new() -> Self {
+        Self {
+            connected: AtomicBool::new(false),
+            last_change: Mutex::new(None),
+            disconnects: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            reconnect_attempts: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+            queries: AtomicU64::new(0),
+            errors: Default::default(),
+        }
+    }
+
+    /// Call when a query is sent.
+    pub fn begin(&self) -> InFlight<'_> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        if !self.is_connected() && self.last_change.lock().unwrap().is_some() {
+            self.reconnect_attempts.fetch_add(1, Ordering::Relaxed);
+        }
+        InFlight { monitor: self }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Records a state change, if it is one.
+    pub fn set_connected(&self, connected: bool) {
+        if self.connected.swap(connected, Ordering::Relaxed) == connected {
+            return;
+        }
+        let first = self
+            .last_change
+            .lock()
+            .unwrap()
+            .replace(Utc::now())
+            .is_none();
+        match (connected, first) {
+            (true, true) => tracing::info!("SurrealDB connection established"),
+            (true, false) => {
+                self.reconnects.fetch_add(1, Ordering::Relaxed);
+                tracing::info!(
+                    attempts = self.reconnect_attempts.load(Ordering::Relaxed),
+                    "SurrealDB connection restored"
+                );
+            }
+            (false, _) => {
+                self.disconnects.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("SurrealDB connection lost");
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> ConnectionMetrics {
+        ConnectionMetrics {
+            connected: self.is_connected(),
+            last_change: *self.last_change.lock().unwrap(),
+            disconnects: self.disconnects.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            reconnect_attempts: self.reconnect_attempts.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queries: self.queries.load(Ordering::Relaxed),
+            errors: std::array::from_fn(|i| self.errors[i].load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl Default for ConnectionMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InFlight<'_> {
+    /// Records how the query ended. A cancelled query says nothing about the
+    /// connection.
+    pub fn finish(self, error: Option<&surrealdb::Error>) {
+        let kind = error.map(ErrorKind::of);
+        if let Some(kind) = kind {
+            if let Some(index) = ErrorKind::ALL.iter().position(|k| *k == kind) {
+                self.monitor.errors[index].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        match kind {
+            Some(ErrorKind::Connection) => self.monitor.set_connected(false),
+            Some(ErrorKind::Cancelled) => {}
+            _ => self.monitor.set_connected(true),
+        }
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.monitor.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn is_connection_error(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Api(Api::Ws(_) | Api::Http(_) | Api::ConnectionUninitialised) => true,
+        other => {
+            let message = other.to_string().to_lowercase();
+            CONNECTION_MESSAGES.iter().any(|m| message.contains(m))
+        }
+    }
+}
+// endregion: -- ConnectionMonitor
@@ -0,0 +1,160 @@
+use crate::surreal::cache::{Cache, MemoryCache};
+use crate::surreal::instrument::{correlation, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+pub static COUNTS: Lazy<CountCache> = Lazy::new(|| CountCache::new(&CountSettings::default()));
+
+// region: -- CountSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CountSettings {
+    /// How long a count is reused; 0 counts every time.
+    pub cache_secs: u64,
+}
+
+impl Default for CountSettings {
+    fn default() -> Self {
+        Self { cache_secs: 5 }
+    }
+}
+// endregion: -- CountSettings
+
+// region: -- CountCache
+/// What was counted: a table, a `WHERE` clause and its bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CountKey {
+    table: String,
+    filter: String,
+    bindings: String,
+}
+
+impl CountKey {
+    pub fn new(table: &str, filter: &str, bindings: &BTreeMap<String, Value>) -> Self {
+        Self {
+            table: table.to_string(),
+            filter: filter.to_string(),
+            bindings: serde_json::to_string(bindings).unwrap_or_default(),
+        }
+    }
+
+    /// The key within the table's group.
+    fn entry(&self) -> String {
+        format!("{}{}", self.filter, self.bindings)
+    }
+}
+
+/// Recent `count()`s by table, filter and bindings, kept in the configured
+/// [`Cache`] with a table per group. Writes through the API drop their
+/// table's counts; others are seen once `cache_secs` is up.
+pub struct CountCache {
+    settings: RwLock<CountSettings>,
+    backend: RwLock<Arc<dyn Cache>>,
+}
+
+impl CountCache {
+    /// A cache held in memory until [`CountCache::use_backend`] says otherwise.
+    pub fn new(settings: &CountSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+            backend: RwLock::new(Arc::new(MemoryCache::new())),
+        }
+    }
+
+    pub fn configure(&self, settings: &CountSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+        self.clear();
+    }
+
+    pub fn use_backend(&self, backend: Arc<dyn Cache>) {
+        *self.backend.write().unwrap() = backend;
+    }
+
+    pub fn settings(&self) -> CountSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.settings.read().unwrap().cache_secs)
+    }
+
+    fn backend(&self) -> Arc<dyn Cache> {
+        self.backend.read().unwrap().clone()
+    }
+
+    /// The remembered count, if it is still fresh.
+    pub async fn get(&self, key: &CountKey) -> Option<u64> {
+        let count = self.backend().get(&key.table, &key.entry()).await?;
+        count.as_u64()
+    }
+
+    pub async fn put(&self, key: CountKey, count: u64) {
+        self.backend()
+            .put(&key.table, &key.entry(), count.into(), self.ttl())
+            .await;
+    }
+
+    /// Forgets every count of `table`.
+    pub fn invalidate(&self, table: &str) {
+        self.backend().invalidate(table);
+    }
+
+    pub fn clear(&self) {
+        self.backend().clear();
+    }
+
+    /// Counts held in this process.
+    pub fn len(&self) -> usize {
+        self.backend().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+// endregion: -- CountCache
+
+// region: -- count
+/// Records of `table` matching `filter`, a `WHERE` clause (or nothing) over
+/// `bindings`, counted by the database with `GROUP ALL` so no rows come back.
+/// Served from [`COUNTS`] while fresh.
+#[tracing::instrument(name = "Query: Count", skip(db, bindings))]
+pub async fn count(
+    db: &Surreal<Client>,
+    table: &str,
+    filter: &str,
+    bindings: &BTreeMap<String, Value>,
+) -> surrealdb::Result<u64> {
+    let key = CountKey::new(table, filter, bindings);
+    if let Some(count) = COUNTS.get(&key).await {
+        return Ok(count);
+    }
+
+    let sql = count_statement(table, filter);
+    let metadata = bindings
+        .iter()
+        .map(|(name, value)| Binding::new(name, value))
+        .collect();
+    let count: Option<u64> = traced_with_bindings(&sql, metadata, async {
+        db.query(&sql)
+            .bind(correlation())
+            .bind(bindings)
+            .await?
+            .take((0, "count"))
+    })
+    .await?;
+    // `GROUP ALL` over no records returns no group rather than 0.
+    let count = count.unwrap_or(0);
+    COUNTS.put(key, count).await;
+    Ok(count)
+}
+
+pub fn count_statement(table: &str, filter: &str) -> String {
+    format!("SELECT count() FROM {table}{filter} GROUP ALL")
+}
+// endregion: -- count
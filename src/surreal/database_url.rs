@@ -0,0 +1,231 @@
+use crate::surreal::db::{AuthMode, DatabaseSettings};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::fmt;
+use std::str::FromStr;
+
+/// Environment variable holding the whole connection as one URL, replacing
+/// the `database` settings other than `tls` and `on_missing`.
+pub const SURREAL_URL: &str = "SURREAL_URL";
+
+/// Escaped in user names, passwords, namespaces and databases: everything but
+/// the unreserved characters of RFC 3986.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// region: -- DatabaseUrlError
+/// What is wrong with a connection URL, and in which part of it.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("`{component}` of the database URL {message}")]
+pub struct DatabaseUrlError {
+    /// `scheme`, `username`, `password`, `host`, `port`, `namespace`,
+    /// `database`, `path`, or the name of a query parameter.
+    pub component: String,
+    pub message: String,
+}
+
+impl DatabaseUrlError {
+    fn new(component: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            component: component.into(),
+            message: message.into(),
+        }
+    }
+}
+// endregion: -- DatabaseUrlError
+
+// region: -- Parsing
+/// `ws[s]://user:pass@host[:port]/namespace/database[?ssl=&auth=&scope=]`.
+/// The port defaults to 443 with TLS and 8000 without; `wss` turns TLS on
+/// and `?ssl=` may only agree with it. Reserved characters in the user,
+/// password, namespace and database must be percent-encoded.
+impl FromStr for DatabaseSettings {
+    type Err = DatabaseUrlError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = url.split_once("://").ok_or_else(|| {
+            DatabaseUrlError::new("scheme", "is missing; start with `ws://` or `wss://`")
+        })?;
+        let mut ssl_mode = match scheme.to_ascii_lowercase().as_str() {
+            "ws" => false,
+            "wss" => true,
+            other => {
+                return Err(DatabaseUrlError::new(
+                    "scheme",
+                    format!("must be `ws` or `wss`, not `{other}`"),
+                ))
+            }
+        };
+
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (userinfo, rest) = rest
+            .rsplit_once('@')
+            .ok_or_else(|| DatabaseUrlError::new("username", "is missing; use `user:pass@host`"))?;
+        let (username, password) = userinfo
+            .split_once(':')
+            .ok_or_else(|| DatabaseUrlError::new("password", "is missing; use `user:pass@host`"))?;
+        let username = decode("username", username)?;
+        let password = decode("password", password)?;
+
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = split_host(authority)?;
+
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let namespace = decode("namespace", segments.next().unwrap_or_default())?;
+        let database = decode("database", segments.next().unwrap_or_default())?;
+        if segments.next().is_some() {
+            return Err(DatabaseUrlError::new(
+                "path",
+                format!("`/{path}` has more than `/namespace/database`"),
+            ));
+        }
+
+        let mut settings = DatabaseSettings {
+            host: host.to_string(),
+            username,
+            password: password.into(),
+            namespace,
+            database,
+            ..DatabaseSettings::default()
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "ssl" => {
+                    let ssl = value.parse::<bool>().map_err(|_| {
+                        DatabaseUrlError::new(
+                            "ssl",
+                            format!("must be `true` or `false`, not `{value}`"),
+                        )
+                    })?;
+                    if scheme.eq_ignore_ascii_case("wss") && !ssl {
+                        return Err(DatabaseUrlError::new(
+                            "ssl",
+                            "is `false` but the scheme is `wss`",
+                        ));
+                    }
+                    ssl_mode = ssl;
+                }
+                "auth" => {
+                    settings.auth = match value {
+                        "root" => AuthMode::Root,
+                        "namespace" => AuthMode::Namespace,
+                        "database" => AuthMode::Database,
+                        "scope" => AuthMode::Scope,
+                        other => {
+                            return Err(DatabaseUrlError::new(
+                                "auth",
+                                format!(
+                                "must be `root`, `namespace`, `database` or `scope`, not `{other}`"
+                            ),
+                            ))
+                        }
+                    }
+                }
+                "scope" => settings.scope = Some(decode("scope", value)?),
+                other => return Err(DatabaseUrlError::new(other, "is not a known parameter")),
+            }
+        }
+        settings.ssl_mode = ssl_mode;
+        settings.port = match port {
+            Some(port) => port,
+            None if ssl_mode => 443,
+            None => 8000,
+        };
+        Ok(settings)
+    }
+}
+
+/// Host and port of `authority`, which may be a bracketed IPv6 address.
+fn split_host(authority: &str) -> Result<(&str, Option<u16>), DatabaseUrlError> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed
+                .split_once(']')
+                .ok_or_else(|| DatabaseUrlError::new("host", "has an unclosed `[`"))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(DatabaseUrlError::new("host", "is missing"));
+    }
+    let port = port
+        .map(|port| match port.parse::<u16>() {
+            Ok(0) | Err(_) => Err(DatabaseUrlError::new(
+                "port",
+                format!("must be a number from 1 to 65535, not `{port}`"),
+            )),
+            Ok(port) => Ok(port),
+        })
+        .transpose()?;
+    Ok((host, port))
+}
+
+fn decode(component: &str, value: &str) -> Result<String, DatabaseUrlError> {
+    let decoded = percent_decode_str(value)
+        .decode_utf8()
+        .map_err(|_| DatabaseUrlError::new(component, "is not valid UTF-8 once decoded"))?;
+    if decoded.trim().is_empty() {
+        return Err(DatabaseUrlError::new(component, "is missing"));
+    }
+    Ok(decoded.into_owned())
+}
+// endregion: -- Parsing
+
+// region: -- Formatting
+impl DatabaseSettings {
+    /// The settings as a connection URL that parses back to them, password
+    /// included. `tls` has no place in the URL and is left out.
+    pub fn expose_url(&self) -> String {
+        self.url(&encode(self.password.expose()))
+    }
+
+    fn url(&self, password: &str) -> String {
+        let scheme = if self.ssl_mode { "wss" } else { "ws" };
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        let mut url = format!(
+            "{scheme}://{}:{password}@{host}:{}/{}/{}",
+            encode(&self.username),
+            self.port,
+            encode(&self.namespace),
+            encode(&self.database),
+        );
+        let mut params = Vec::new();
+        match self.auth {
+            AuthMode::Root => {}
+            AuthMode::Namespace => params.push("auth=namespace".to_string()),
+            AuthMode::Database => params.push("auth=database".to_string()),
+            AuthMode::Scope => params.push("auth=scope".to_string()),
+        }
+        if let Some(scope) = &self.scope {
+            params.push(format!("scope={}", encode(scope)));
+        }
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
+        url
+    }
+}
+
+/// The connection URL with the password redacted, safe for logs.
+impl fmt::Display for DatabaseSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.url(&self.password.to_string()))
+    }
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, COMPONENT).to_string()
+}
+// endregion: -- Formatting
@@ -1,22 +1,54 @@
 use crate::error::Error;
+use crate::secret::Secret;
+use crate::surreal::admin::{KvInfo, NsInfo};
+use crate::surreal::connection::CONNECTION;
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use crate::surreal::sql::escape_ident;
+use crate::surreal::tls::DatabaseTlsSettings;
 use color_eyre::{eyre::Context, Result};
 use futures_core::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use surrealdb::{
     engine::remote::ws::{Client, Ws, Wss},
-    opt::auth::Root,
-    Surreal,
+    method::Query,
+    opt::auth::{self, Namespace, Root, Scope},
+    opt::Tls,
+    Response, Surreal,
 };
 
 // region: -- DatabaseSettings
+/// Also parsed from, and printed as, a connection URL; see
+/// [`crate::surreal::database_url`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct DatabaseSettings {
     pub host: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub username: String,
-    pub password: String,
+    pub password: Secret<String>,
     pub namespace: String,
     pub database: String,
     pub ssl_mode: bool,
+    /// Which kind of user `username`/`password` belong to.
+    #[serde(default)]
+    pub auth: AuthMode,
+    /// The scope to sign in to with `auth: scope`.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Only used with `ssl_mode`.
+    #[serde(default)]
+    pub tls: DatabaseTlsSettings,
+    /// What startup does when `namespace` or `database` doesn't exist.
+    #[serde(default)]
+    pub on_missing: MissingScope,
 }
 
 impl Default for DatabaseSettings {
@@ -29,6 +61,54 @@ impl Default for DatabaseSettings {
             namespace: "namespace".into(),
             database: "database".into(),
             ssl_mode: false,
+            auth: AuthMode::Root,
+            scope: None,
+            tls: DatabaseTlsSettings::default(),
+            on_missing: MissingScope::Fail,
+        }
+    }
+}
+
+/// Where the application's database user is defined. Anything below `root`
+/// keeps root credentials out of the application, but also limits the admin
+/// endpoints to what that user may see.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    #[default]
+    Root,
+    /// A user defined with `DEFINE LOGIN ... ON NAMESPACE`.
+    Namespace,
+    /// A user defined with `DEFINE LOGIN ... ON DATABASE`.
+    Database,
+    /// A record user signing in through `DEFINE SCOPE`. The scope's `SIGNIN`
+    /// clause receives `username` and `password` as `$user` and `$pass`.
+    Scope,
+}
+
+/// SurrealDB happily switches to a namespace or database that doesn't exist
+/// and only complains once a query runs, so startup checks for them first.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingScope {
+    /// Stop, naming what is missing.
+    #[default]
+    Fail,
+    /// `DEFINE` what is missing. A namespace needs a `root` user and a
+    /// database a `root` or `namespace` one.
+    Create,
+}
+
+impl MissingScope {
+    /// The statement defining the missing `level` (`NAMESPACE` or
+    /// `DATABASE`) called `name`, or the error to stop with.
+    pub fn resolve(self, level: &str, name: &str) -> Result<String, Error> {
+        match self {
+            Self::Create => Ok(format!("DEFINE {level} {};", escape_ident(name))),
+            Self::Fail => Err(Error::ScopeNotFound(format!(
+                "{} `{name}` does not exist; create it or set `database.on_missing: create`",
+                level.to_lowercase()
+            ))),
         }
     }
 }
@@ -50,72 +130,439 @@ impl Database {
         )
       )]
     pub async fn new(configuration: &DatabaseSettings) -> Result<Self> {
-        let connection_string = format!("{}:{}", configuration.host, configuration.port);
-
-        let client = match configuration.ssl_mode {
-            true => Surreal::new::<Wss>(connection_string)
-                .await
-                .context("Failed to make Wss connection")?,
-            false => Surreal::new::<Ws>(connection_string)
-                .await
-                .context("Failed to make Ws connection")?,
-        };
+        let client = connect(configuration).await?;
 
-        client
-            .signin(Root {
-                username: &configuration.username,
-                password: &configuration.password,
-            })
+        signin(&client, configuration)
             .await
-            .context("Failed to Sign-In")?;
+            .with_context(|| format!("Failed to Sign-In as a {:?} user", configuration.auth))?;
+        ensure_scope(&client, configuration).await?;
 
         client
             .use_ns(&configuration.namespace)
             .use_db(&configuration.database)
             .await
             .context("Failed to set namespace & database")?;
+        CONNECTION.set_connected(true);
 
         Ok(Self { client })
     }
+
+    /// Round-trips a trivial query. Signing in succeeds even when the session
+    /// can't run queries, so this catches that at startup instead of on the
+    /// first request.
+    #[tracing::instrument(name = "Query: Ping", skip(self))]
+    pub async fn ping(&self) -> Result<(), Error> {
+        let sql = "RETURN true;";
+        let pong: Option<bool> = traced(sql, async {
+            self.client.query(sql).bind(correlation()).await?.take(0)
+        })
+        .await?;
+        match pong {
+            Some(true) => Ok(()),
+            _ => Err(Error::NotReady("database did not answer the ping".into())),
+        }
+    }
+}
+
+/// Checks the configured namespace and database exist, as far as the user
+/// can see: only `root` can list namespaces, and `database` and `scope` users
+/// can't sign in to a missing scope at all.
+#[tracing::instrument(name = "Query: Ensure Scope", skip(client, configuration))]
+async fn ensure_scope(
+    client: &Surreal<Client>,
+    configuration: &DatabaseSettings,
+) -> Result<(), Error> {
+    let namespace = &configuration.namespace;
+    let database = &configuration.database;
+
+    if configuration.auth == AuthMode::Root {
+        let sql = "INFO FOR KV;";
+        let kv: Option<KvInfo> = traced(sql, client.query(sql).bind(correlation()))
+            .await?
+            .take(0)?;
+        if !kv.is_some_and(|kv| kv.ns.contains_key(namespace)) {
+            define(
+                client,
+                configuration.on_missing.resolve("NAMESPACE", namespace)?,
+            )
+            .await?;
+        }
+    }
+    if matches!(configuration.auth, AuthMode::Root | AuthMode::Namespace) {
+        client.use_ns(namespace).use_db(database).await?;
+        let sql = "INFO FOR NS;";
+        let ns: Option<NsInfo> = traced(sql, client.query(sql).bind(correlation()))
+            .await?
+            .take(0)?;
+        if !ns.is_some_and(|ns| ns.db.contains_key(database)) {
+            define(
+                client,
+                configuration.on_missing.resolve("DATABASE", database)?,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn define(client: &Surreal<Client>, sql: String) -> Result<(), Error> {
+    traced(&sql, async {
+        client.query(&sql).bind(correlation()).await?.check()
+    })
+    .await?;
+    tracing::info!(sql, "Created a missing scope");
+    Ok(())
+}
+
+/// Opens the connection without signing in.
+pub(crate) async fn connect(configuration: &DatabaseSettings) -> Result<Surreal<Client>> {
+    let connection_string = format!("{}:{}", configuration.host, configuration.port);
+
+    let client = match (configuration.ssl_mode, configuration.tls.is_configured()) {
+        (true, true) => {
+            let tls = configuration
+                .tls
+                .client_config()
+                .context("Failed to load database TLS settings")?;
+            Surreal::new::<Wss>((connection_string, Tls::Rust(tls)))
+                .await
+                .context("Failed to make Wss connection")?
+        }
+        (true, false) => Surreal::new::<Wss>(connection_string)
+            .await
+            .context("Failed to make Wss connection")?,
+        (false, _) => Surreal::new::<Ws>(connection_string)
+            .await
+            .context("Failed to make Ws connection")?,
+    };
+    Ok(client)
+}
+
+pub(crate) async fn signin(
+    client: &Surreal<Client>,
+    configuration: &DatabaseSettings,
+) -> surrealdb::Result<()> {
+    let username = configuration.username.as_str();
+    let password = configuration.password.expose().as_str();
+    let namespace = configuration.namespace.as_str();
+    let database = configuration.database.as_str();
+
+    match configuration.auth {
+        AuthMode::Root => {
+            client.signin(Root { username, password }).await?;
+        }
+        AuthMode::Namespace => {
+            client
+                .signin(Namespace {
+                    namespace,
+                    username,
+                    password,
+                })
+                .await?;
+        }
+        AuthMode::Database => {
+            client
+                .signin(auth::Database {
+                    namespace,
+                    database,
+                    username,
+                    password,
+                })
+                .await?;
+        }
+        AuthMode::Scope => {
+            client
+                .signin(Scope {
+                    namespace,
+                    database,
+                    scope: configuration.scope.as_deref().unwrap_or_default(),
+                    params: json!({ "user": username, "pass": password }),
+                })
+                .await?;
+        }
+    }
+    Ok(())
 }
 // endregion: -- Database
 
 // region: -- Transaction
-pub struct Transaction<'c> {
-    pub conn: &'c Surreal<Client>,
-    pub open: bool,
+/// Binds one parameter on the query a [`Transaction`] is sent as.
+type Bind = Box<dyn for<'r> FnOnce(Query<'r, Client>) -> Query<'r, Client> + Send>;
+
+/// Statements sent together as one `BEGIN ... COMMIT` query. A SurrealDB
+/// transaction only lasts for the query it was begun in, so nothing is sent
+/// until [`Self::commit`], and one dropped before then leaves nothing behind
+/// on the connection:
+///
+/// ```ignore
+/// let mut transaction = Transaction::new(&db);
+/// transaction.query("DELETE licenses WHERE out = $record");
+/// let deleted = transaction.query("DELETE $record RETURN BEFORE");
+/// transaction.bind("record", record.clone());
+/// let person: Option<Person> = transaction.commit().await?.take(deleted)?;
+/// ```
+pub struct Transaction {
+    conn: Surreal<Client>,
+    statements: Vec<String>,
+    binds: Vec<Bind>,
+    bindings: Vec<Binding>,
 }
 
-impl<'c> Transaction<'c> {
-    pub fn begin(conn: &'c Surreal<Client>) -> BoxFuture<'c, Result<Self, Error>> {
-        Box::pin(async move {
-            let sql = "BEGIN TRANSACTION;".to_string();
-            let response = conn.query(sql).await?;
-            response.check()?;
+impl fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("statements", &self.statements)
+            .field("bindings", &self.bindings)
+            .finish()
+    }
+}
 
-            Ok(Self { conn, open: true })
-        })
+impl Transaction {
+    pub fn new(conn: &Surreal<Client>) -> Self {
+        Self {
+            conn: conn.clone(),
+            statements: Vec::new(),
+            binds: Vec::new(),
+            bindings: Vec::new(),
+        }
     }
 
-    pub async fn commit(mut self) -> BoxFuture<'c, Result<(), Error>> {
-        Box::pin(async move {
-            let sql = "COMMIT TRANSACTION;";
-            let response = self.conn.query(sql).await?;
-            response.check()?;
-            self.open = false;
+    /// Queues `sql`, a single statement, and returns the index of its result
+    /// in the response [`Self::commit`] gives back. `BEGIN` and `COMMIT` have
+    /// no results, so the first statement queued is at 0.
+    pub fn query(&mut self, sql: impl Into<String>) -> usize {
+        let sql = sql.into();
+        self.statements
+            .push(sql.trim().trim_end_matches(';').trim_end().to_string());
+        self.statements.len() - 1
+    }
 
-            Ok(())
-        })
+    /// Binds `$name` for every statement queued. Statements queued by
+    /// different callers share them, so the names should say whose they are.
+    pub fn bind(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Serialize + Send + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.bindings.push(Binding::new(&name, &value));
+        self.binds
+            .push(boxed(move |query| query.bind((name, value))));
+        self
     }
 
-    pub async fn rollback(mut self) -> BoxFuture<'c, Result<(), Error>> {
-        Box::pin(async move {
-            let sql = "CANCEL TRANSACTION;";
-            let response = self.conn.query(sql).await?;
-            response.check()?;
-            self.open = false;
-            Ok(())
-        })
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Sends the queued statements as one transaction. If one fails, none of
+    /// them are kept, and its error is returned; a `THROW` gives
+    /// [`Error::Aborted`] with the thrown message.
+    pub async fn commit(self) -> Result<Response, Error> {
+        let sql = std::iter::once("BEGIN TRANSACTION")
+            .chain(self.statements.iter().map(String::as_str))
+            .chain(std::iter::once("COMMIT TRANSACTION"))
+            .collect::<Vec<_>>()
+            .join(";\n")
+            + ";";
+        let mut query = self.conn.query(&sql).bind(correlation());
+        for bind in self.binds {
+            query = bind(query);
+        }
+        let mut response = traced_with_bindings(&sql, self.bindings, query).await?;
+        match at_fault(response.take_errors()) {
+            None => Ok(response),
+            Some(error) => match thrown(&error) {
+                Some(reason) => Err(Error::Aborted(reason)),
+                None => Err(error.into()),
+            },
+        }
+    }
+}
+
+/// Gives the closure its signature, which isn't inferred inside `Box::new`.
+fn boxed(
+    bind: impl for<'r> FnOnce(Query<'r, Client>) -> Query<'r, Client> + Send + 'static,
+) -> Bind {
+    Box::new(bind)
+}
+
+/// A failed statement fails the whole transaction, and the others only say
+/// they weren't executed, so the error worth returning is the one that
+/// doesn't.
+fn at_fault(errors: HashMap<usize, surrealdb::Error>) -> Option<surrealdb::Error> {
+    let mut errors: Vec<_> = errors.into_iter().collect();
+    errors.sort_by_key(|(i, _)| *i);
+    let at_fault = errors
+        .iter()
+        .position(|(_, error)| !error.to_string().contains(NOT_EXECUTED))
+        .unwrap_or(0);
+    errors.into_iter().nth(at_fault).map(|(_, error)| error)
+}
+
+const NOT_EXECUTED: &str = "was not executed due to a";
+
+/// The message of the `THROW` that failed a transaction. Remote engines
+/// only send it prefixed, e.g. "An error occurred: person:1 is related".
+fn thrown(error: &surrealdb::Error) -> Option<String> {
+    let message = error.to_string();
+    let (_, thrown) = message.split_once("An error occurred: ")?;
+    Some(thrown.to_string())
+}
+// endregion: -- Transaction
+
+// region: -- RequestTransaction
+tokio::task_local! {
+    static REQUEST_TRANSACTION: RequestTransaction;
+}
+
+/// The transaction of the request being handled. Nothing is begun until
+/// the handler asks for it with [`RequestTransaction::begin`]; statements
+/// queued on it are sent as one [`Transaction`] by [`Self::commit`], or by
+/// [`Self::end`] once [`Self::run`] hands it back.
+#[derive(Clone, Debug, Default)]
+pub struct RequestTransaction {
+    state: Arc<Mutex<RequestTransactionState>>,
+}
+
+#[derive(Default)]
+struct RequestTransactionState {
+    transaction: Option<Transaction>,
+    cancelled: bool,
+    /// Run once what is queued has committed; see
+    /// [`RequestTransaction::after_commit`].
+    on_commit: Vec<BoxFuture<'static, ()>>,
+}
+
+impl fmt::Debug for RequestTransactionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestTransactionState")
+            .field("transaction", &self.transaction)
+            .field("cancelled", &self.cancelled)
+            .field("on_commit", &self.on_commit.len())
+            .finish()
+    }
+}
+
+impl RequestTransaction {
+    /// Runs `future` with a request transaction open to it.
+    pub async fn run<F: Future>(future: F) -> (F::Output, Self) {
+        let transaction = Self::default();
+        let output = REQUEST_TRANSACTION.scope(transaction.clone(), future).await;
+        (output, transaction)
+    }
+
+    /// The request transaction open to the current task, if any.
+    pub fn current() -> Option<Self> {
+        REQUEST_TRANSACTION.try_with(Clone::clone).ok()
+    }
+
+    /// Begins the transaction on `conn`, unless it already has been.
+    pub fn begin(&self, conn: &Surreal<Client>) {
+        self.state
+            .lock()
+            .unwrap()
+            .transaction
+            .get_or_insert_with(|| Transaction::new(conn));
+    }
+
+    pub fn is_begun(&self) -> bool {
+        self.state.lock().unwrap().transaction.is_some()
+    }
+
+    /// Queues statements on the transaction with `queue`.
+    pub fn queue<R>(&self, queue: impl FnOnce(&mut Transaction) -> R) -> Result<R, Error> {
+        let mut state = self.state.lock().unwrap();
+        match state.transaction.as_mut() {
+            Some(transaction) => Ok(queue(transaction)),
+            None => {
+                tracing::error!("Queued on a request transaction that wasn't begun");
+                Err(Error::Db)
+            }
+        }
+    }
+
+    /// Sends what is queued now, for a handler that needs the results, and
+    /// runs the [`Self::after_commit`] tasks waiting on it. Anything queued
+    /// afterwards goes in a transaction of its own.
+    pub async fn commit(&self) -> Result<Response, Error> {
+        let (transaction, on_commit) = {
+            let mut guard = self.state.lock().unwrap();
+            let state = &mut *guard;
+            if state.cancelled {
+                return Err(Error::Aborted(
+                    "the request transaction was cancelled".into(),
+                ));
+            }
+            let Some(transaction) = state.transaction.as_mut() else {
+                tracing::error!("Committed a request transaction that wasn't begun");
+                return Err(Error::Db);
+            };
+            let next = Transaction::new(&transaction.conn);
+            (
+                std::mem::replace(transaction, next),
+                std::mem::take(&mut state.on_commit),
+            )
+        };
+        let response = transaction.commit().await?;
+        for task in on_commit {
+            task.await;
+        }
+        Ok(response)
+    }
+
+    /// Drops what is queued, along with anything queued later, and the
+    /// [`Self::after_commit`] tasks waiting on it. What [`Self::commit`]
+    /// already sent stays.
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        if let Some(transaction) = state.transaction.as_mut() {
+            *transaction = Transaction::new(&transaction.conn);
+        }
+        state.on_commit.clear();
+    }
+
+    /// Runs `task` once what the current task's request transaction has
+    /// queued commits, or straight away when nothing is queued, e.g. to drop
+    /// cached counts only when nothing can re-cache the old ones. A cancelled
+    /// transaction never runs it.
+    pub async fn after_commit(task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(request) = Self::current() {
+            let mut state = request.state.lock().unwrap();
+            if state.cancelled {
+                return;
+            }
+            if state.transaction.as_ref().is_some_and(|t| !t.is_empty()) {
+                state.on_commit.push(Box::pin(task));
+                return;
+            }
+        }
+        task.await
+    }
+
+    /// Sends what is queued as one transaction, unless `commit` is false or
+    /// something cancelled it. Tasks left by [`Self::after_commit`] run once
+    /// the commit succeeds.
+    pub async fn end(self, commit: bool) -> Result<(), Error> {
+        let (transaction, cancelled, on_commit) = {
+            let mut state = self.state.lock().unwrap();
+            (
+                state.transaction.take(),
+                state.cancelled,
+                std::mem::take(&mut state.on_commit),
+            )
+        };
+        if !commit || cancelled {
+            return Ok(());
+        }
+        if let Some(transaction) = transaction.filter(|t| !t.is_empty()) {
+            transaction.commit().await?;
+        }
+        for task in on_commit {
+            task.await;
+        }
+        Ok(())
     }
 }
-// endregion: -- Transaction
\ No newline at end of file
+// endregion: -- RequestTransaction
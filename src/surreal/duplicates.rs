@@ -0,0 +1,129 @@
+use crate::error::Error;
+use crate::from_response;
+use crate::surreal::from_response::FromResponse;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::schema::tables::{declared, known_fields};
+use crate::surreal::sql::escape_ident;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+const DEFAULT_CLUSTER_LIMIT: u32 = 20;
+const MAX_CLUSTER_LIMIT: u32 = 100;
+
+// region: -- Query
+/// `GET /admin/duplicates`, e.g. `?table=person&key=name&start=20`.
+#[derive(Deserialize, Debug, Default)]
+pub struct DuplicatesQuery {
+    pub table: String,
+    /// The field records are grouped on; one of the table's known fields
+    /// but `id`. Declared string fields are grouped on their value as
+    /// `fn::normalize_name` has it, so `Jane Doe` and ` jane doe` cluster
+    /// just like two exact copies do.
+    pub key: String,
+    pub start: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+impl DuplicatesQuery {
+    pub fn start(&self) -> u32 {
+        self.start.unwrap_or(0)
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_CLUSTER_LIMIT)
+            .clamp(1, MAX_CLUSTER_LIMIT)
+    }
+
+    /// A page of the values of `key` more than one record has, most shared
+    /// first, each with the records that have it; then how many such values
+    /// there are.
+    pub fn statement(&self) -> Result<String, Error> {
+        let table = &self.table;
+        if declared(table).is_none() {
+            return Err(Error::InvalidQuery(format!(
+                "`table`: `{table}` isn't a table duplicates can be looked for in"
+            )));
+        }
+        let known = known_fields(table);
+        if self.key == "id" || !known.contains(&self.key.as_str()) {
+            let keys: Vec<&str> = known.into_iter().filter(|field| *field != "id").collect();
+            return Err(Error::InvalidQuery(format!(
+                "`key`: `{}` isn't a field of {table} to group on; it has {}",
+                self.key,
+                keys.join(", ")
+            )));
+        }
+
+        let string = declared(table)
+            .and_then(|definition| definition.fields.iter().find(|(name, _)| *name == self.key))
+            .is_some_and(|(_, kind)| matches!(*kind, "string" | "option<string>"));
+        let (table, key) = (escape_ident(table), escape_ident(&self.key));
+        let value = if string {
+            format!("fn::normalize_name({key})")
+        } else {
+            key.clone()
+        };
+        let groups = format!(
+            "SELECT {value} AS value, count() AS count FROM {table} \
+             WHERE {key} != NONE GROUP BY value"
+        );
+        Ok(format!(
+            "SELECT value, count, \
+                 (SELECT VALUE id FROM {table} WHERE {value} = $parent.value ORDER BY id) AS ids \
+             FROM ({groups}) WHERE count > 1 \
+             ORDER BY count DESC, value ASC LIMIT {} START {};\
+             SELECT count() AS total FROM ({groups}) WHERE count > 1 GROUP ALL;",
+            self.limit(),
+            self.start(),
+        ))
+    }
+}
+// endregion: -- Query
+
+// region: -- Clusters
+/// Records that share a value of the key, and may be the same one entered
+/// more than once.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateCluster {
+    pub value: serde_json::Value,
+    pub count: u64,
+    /// The records' ids, e.g. `person:abc`, as the merge takes them.
+    pub ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ClusterRow {
+    value: serde_json::Value,
+    count: u64,
+    ids: Vec<Thing>,
+}
+
+from_response! {
+    struct DuplicatesResponse {
+        rows: Vec<ClusterRow> = 0,
+        total: Option<u64> = (1, "total"),
+    }
+}
+
+/// A page of the clusters `query` asks for, and how many there are in all.
+#[tracing::instrument(name = "Query: Duplicates", skip(db))]
+pub async fn find_duplicates(
+    db: &Surreal<Client>,
+    query: &DuplicatesQuery,
+) -> Result<(Vec<DuplicateCluster>, u64), Error> {
+    let sql = query.statement()?;
+    let response = traced(&sql, db.query(&sql).bind(correlation())).await?;
+    let DuplicatesResponse { rows, total } = DuplicatesResponse::from_response(response)?;
+    let clusters = rows
+        .into_iter()
+        .map(|row| DuplicateCluster {
+            value: row.value,
+            count: row.count,
+            ids: row.ids.iter().map(ToString::to_string).collect(),
+        })
+        .collect();
+    Ok((clusters, total.unwrap_or(0)))
+}
+// endregion: -- Clusters
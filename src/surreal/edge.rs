@@ -0,0 +1,317 @@
+use crate::error::Error;
+use crate::surreal::db::Transaction;
+use crate::surreal::model::SurrealModel;
+use crate::surreal::sql::escape_ident;
+use futures_core::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- EdgeSettings
+/// The node tables an edge table may connect.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EdgeEndpoints {
+    pub from: String,
+    pub to: String,
+}
+
+/// Edge tables that `POST /relate` may write to. Anything not listed is
+/// refused, so the endpoint can't be used to write arbitrary tables.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct EdgeSettings {
+    pub allowed: BTreeMap<String, EdgeEndpoints>,
+    /// What deleting a node does to the allowed edges touching it.
+    #[serde(default)]
+    pub on_delete: OnDelete,
+    /// Also assert in the schema that edges only connect existing records of
+    /// the allowed tables, so writes that bypass the API are checked too.
+    #[serde(default)]
+    pub enforce_in_schema: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDelete {
+    /// Delete the edges along with the node.
+    #[default]
+    Cascade,
+    /// Refuse to delete a node that still has edges.
+    Restrict,
+}
+
+impl Default for EdgeSettings {
+    fn default() -> Self {
+        Self {
+            on_delete: OnDelete::default(),
+            enforce_in_schema: false,
+            allowed: BTreeMap::from([(
+                "licenses".into(),
+                EdgeEndpoints {
+                    from: "registry".into(),
+                    to: "person".into(),
+                },
+            )]),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EdgeAllowList {
+    allowed: Arc<BTreeMap<String, EdgeEndpoints>>,
+    on_delete: OnDelete,
+}
+
+impl EdgeAllowList {
+    pub fn new(settings: &EdgeSettings) -> Self {
+        Self {
+            allowed: Arc::new(settings.allowed.clone()),
+            on_delete: settings.on_delete,
+        }
+    }
+
+    pub fn on_delete(&self) -> OnDelete {
+        self.on_delete
+    }
+
+    pub fn endpoints(&self, edge: &str) -> Option<&EdgeEndpoints> {
+        self.allowed.get(edge)
+    }
+
+    /// The edge tables that can point from or to records of `table`.
+    pub fn touching(&self, table: &str) -> Vec<&str> {
+        self.allowed
+            .iter()
+            .filter(|(_, endpoints)| endpoints.from == table || endpoints.to == table)
+            .map(|(edge, _)| edge.as_str())
+            .collect()
+    }
+
+    /// Checks that `edge` is allowed between the tables of `from` and `to`.
+    pub fn check(&self, from: &Thing, edge: &str, to: &Thing) -> Result<(), Error> {
+        let endpoints = self.allowed.get(edge).ok_or_else(|| {
+            Error::InvalidBody(format!("`{edge}` is not an edge that can be related"))
+        })?;
+        if from.tb != endpoints.from || to.tb != endpoints.to {
+            return Err(Error::InvalidBody(format!(
+                "`{edge}` goes from `{}` to `{}`, not from `{}` to `{}`",
+                endpoints.from, endpoints.to, from.tb, to.tb
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Parses `table:id` into a record id. The id may be wrapped in `⟨⟩`.
+pub fn parse_record(input: &str) -> Result<Thing, Error> {
+    let invalid = || Error::InvalidId(format!("`{input}` is not a `table:id` record id"));
+    let (table, id) = input.split_once(':').ok_or_else(invalid)?;
+    let id = id
+        .strip_prefix('⟨')
+        .and_then(|id| id.strip_suffix('⟩'))
+        .unwrap_or(id);
+    let is_table =
+        !table.is_empty() && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_table || id.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Thing::from((table, id)))
+}
+// endregion: -- EdgeSettings
+
+// region: -- Edge
+/// A graph edge table between two [`SurrealModel`] tables. Only `RELATE` statements
+/// built here go `From -> TABLE -> To`, so the direction can't be mixed up:
+///
+/// ```ignore
+/// let license = Licenses::relate(&db, "dmv", "john", &LicenseProps::default()).await?;
+/// ```
+pub trait Edge {
+    const TABLE: &'static str;
+    type From: SurrealModel;
+    type To: SurrealModel;
+    /// Stored on the edge record itself. Must serialize to an object.
+    type Props: Serialize + Sync;
+
+    fn statement() -> String {
+        relate_statement(Self::TABLE)
+    }
+
+    /// Relates `from` to `to` by id and returns the new edge record's id.
+    /// Fails with [`Error::MissingEndpoint`] if either record doesn't exist.
+    fn relate<'a>(
+        db: &'a Surreal<Client>,
+        from: &'a str,
+        to: &'a str,
+        props: &'a Self::Props,
+    ) -> BoxFuture<'a, Result<Thing, Error>> {
+        Box::pin(async move {
+            let from = Thing::from((Self::From::TABLE, from));
+            let to = Thing::from((Self::To::TABLE, to));
+            relate_records(db, &from, Self::TABLE, &to, props).await
+        })
+    }
+}
+
+fn relate_statement(edge: &str) -> String {
+    format!(
+        "RELATE $from->{}->$to CONTENT $props RETURN id",
+        escape_ident(edge)
+    )
+}
+// endregion: -- Edge
+
+// region: -- relate_records
+/// `RELATE` for an edge only known at runtime, as checked by
+/// [`EdgeAllowList::check`]. Both records must exist, or this fails with
+/// [`Error::MissingEndpoint`] naming the missing side; the check and the
+/// `RELATE` are sent as one transaction, so neither can be deleted in
+/// between. Returns the new edge record's id.
+#[tracing::instrument(name = "Query: Relate", skip(db, props))]
+pub async fn relate_records<P: Serialize + Sync>(
+    db: &Surreal<Client>,
+    from: &Thing,
+    edge: &str,
+    to: &Thing,
+    props: &P,
+) -> Result<Thing, Error> {
+    let props = serde_json::to_value(props).map_err(|e| Error::InvalidBody(e.to_string()))?;
+    let mut transaction = Transaction::new(db);
+    transaction
+        .bind("from", from.clone())
+        .bind("to", to.clone())
+        .bind("props", props);
+    for side in ["from", "to"] {
+        transaction.query(format!(
+            "IF (SELECT VALUE id FROM ${side}) = [] {{ THROW \"{MISSING_ENDPOINT}{side}\" }}"
+        ));
+    }
+    let related = transaction.query(relate_statement(edge));
+
+    let id: Option<Thing> = match transaction.commit().await {
+        Ok(mut response) => response.take((related, "id"))?,
+        Err(Error::Aborted(reason)) if reason.starts_with(MISSING_ENDPOINT) => {
+            let (side, record) = match &reason[MISSING_ENDPOINT.len()..] {
+                "from" => ("from", from),
+                _ => ("to", to),
+            };
+            return Err(Error::MissingEndpoint {
+                side,
+                record: record.to_string(),
+            });
+        }
+        Err(error) => return Err(error),
+    };
+    id.ok_or(Error::Db)
+}
+
+/// What the `THROW` for a missing endpoint starts with, followed by its side.
+const MISSING_ENDPOINT: &str = "missing endpoint: ";
+// endregion: -- relate_records
+
+// region: -- delete_node
+/// Deletes `record` and returns it as it was, handling the edges touching it
+/// as [`EdgeAllowList::on_delete`] says, all in one transaction: either they
+/// are deleted with it, or the delete is refused while any exist. Fails with
+/// [`Error::NotFound`] if there is no such record.
+#[tracing::instrument(name = "Query: Delete Node", skip(db, edges))]
+pub async fn delete_node<T: DeserializeOwned>(
+    db: &Surreal<Client>,
+    edges: &EdgeAllowList,
+    record: &Thing,
+) -> Result<T, Error> {
+    let mut transaction = Transaction::new(db);
+    transaction.bind("record", record.clone());
+    let cascaded = queue_edge_policy(&mut transaction, edges, &record.tb, "[$record]");
+    let deleted = transaction.query("DELETE $record RETURN BEFORE");
+    let mut response = transaction.commit().await.map_err(still_related)?;
+
+    let relations = cascaded
+        .into_iter()
+        .map(|i| {
+            response
+                .take::<Vec<serde_json::Value>>(i)
+                .map(|edges| edges.len())
+        })
+        .sum::<Result<usize, _>>()?;
+    if relations > 0 {
+        tracing::info!(relations, "Deleted edges along with the node");
+    }
+    let deleted: Option<T> = response.take(deleted)?;
+    deleted.ok_or_else(|| Error::NotFound(record.to_string()))
+}
+
+/// Queues what [`EdgeAllowList::on_delete`] says happens to the allowed
+/// edges touching `nodes`, records of `table` deleted later in the same
+/// transaction: the edges are deleted too, or the transaction is aborted
+/// while any exist, which [`still_related`] turns into
+/// [`Error::StillRelated`]. `nodes` is SurrealQL for an array of their ids,
+/// e.g. `[$record]`. Returns where the results of the edge deletes will be,
+/// each the edges it deleted.
+pub fn queue_edge_policy(
+    transaction: &mut Transaction,
+    edges: &EdgeAllowList,
+    table: &str,
+    nodes: &str,
+) -> Vec<usize> {
+    let touching = edges.touching(table);
+    match edges.on_delete() {
+        OnDelete::Cascade => touching
+            .into_iter()
+            .map(|edge| {
+                transaction.query(format!(
+                    "DELETE {} WHERE in INSIDE {nodes} OR out INSIDE {nodes} RETURN BEFORE",
+                    escape_ident(edge)
+                ))
+            })
+            .collect(),
+        OnDelete::Restrict if touching.is_empty() => Vec::new(),
+        OnDelete::Restrict => {
+            let related: Vec<String> = touching
+                .into_iter()
+                .map(|edge| {
+                    format!(
+                        "(SELECT VALUE (IF in INSIDE {nodes} THEN in ELSE out END) FROM {} \
+                         WHERE in INSIDE {nodes} OR out INSIDE {nodes})",
+                        escape_ident(edge)
+                    )
+                })
+                .collect();
+            transaction.query(format!(
+                "LET $related = array::flatten([{}])",
+                related.join(", ")
+            ));
+            transaction.query(format!(
+                "IF array::len($related) > 0 {{ THROW \"{STILL_RELATED}\" + \
+                 <string> array::len($related) + \" \" + <string> $related[0] }}"
+            ));
+            Vec::new()
+        }
+    }
+}
+
+/// What the `THROW` [`queue_edge_policy`] queues starts with, followed by
+/// the number of edges and the first node they touch.
+const STILL_RELATED: &str = "still related: ";
+
+/// Turns the abort [`queue_edge_policy`] queues into
+/// [`Error::StillRelated`], and passes anything else through.
+pub fn still_related(error: Error) -> Error {
+    let Error::Aborted(reason) = &error else {
+        return error;
+    };
+    let parsed = reason
+        .strip_prefix(STILL_RELATED)
+        .and_then(|rest| rest.split_once(' '))
+        .and_then(|(relations, record)| Some((relations.parse().ok()?, record)));
+    match parsed {
+        Some((relations, record)) => Error::StillRelated {
+            record: record.to_string(),
+            relations,
+        },
+        None => error,
+    }
+}
+// endregion: -- delete_node
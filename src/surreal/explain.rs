@@ -0,0 +1,98 @@
+use crate::api::writes;
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::query_manager::StatementKind;
+use crate::surreal::session::SESSION;
+use serde::Serialize;
+use surrealdb::sql::Statement;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- explain
+#[derive(Serialize, Debug, Clone)]
+pub struct QueryPlan {
+    pub query: String,
+    /// As SurrealDB reports it: one entry per step, e.g. which index or table
+    /// iterator is used.
+    pub plan: serde_json::Value,
+}
+
+/// Function namespaces whose calls may write or reach out of the database,
+/// which `EXPLAIN FULL` would do since it runs the query.
+const SIDE_EFFECTS: &[&str] = &["fn::", "http::"];
+
+/// `sql` with `EXPLAIN` (or `EXPLAIN FULL`) appended. SurrealDB only explains
+/// `SELECT`, and only one statement is accepted. `EXPLAIN FULL` runs the
+/// query, so one with a write anywhere in it, e.g. in a subquery, or a call
+/// to a custom or `http` function is refused as well.
+pub fn explain_statement(sql: &str, full: bool) -> Result<String, Error> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    let only_select =
+        || Error::InvalidQuery("only a single SELECT statement can be explained".into());
+    let query = surrealdb::sql::parse(sql).map_err(|_| only_select())?;
+    let statements: Vec<&Statement> = query.iter().collect();
+    let [statement] = statements.as_slice() else {
+        return Err(only_select());
+    };
+    if StatementKind::of(statement) != StatementKind::Select {
+        return Err(only_select());
+    }
+    // Rendered by the parser, so comments are gone and strings are quoted
+    // the way `writes` expects.
+    let rendered = statement.to_string();
+    if writes(&rendered) || SIDE_EFFECTS.iter().any(|prefix| rendered.contains(prefix)) {
+        return Err(Error::InvalidQuery(
+            "only reads can be explained; the query writes or calls a function that might".into(),
+        ));
+    }
+    if sql
+        .to_ascii_uppercase()
+        .split_whitespace()
+        .any(|word| word == "EXPLAIN")
+    {
+        return Err(Error::InvalidQuery(
+            "leave `EXPLAIN` out of the query".into(),
+        ));
+    }
+    let explain = if full { "EXPLAIN FULL" } else { "EXPLAIN" };
+    Ok(format!("{sql} {explain}"))
+}
+
+#[tracing::instrument(name = "Query: Explain", skip(db))]
+pub async fn explain(db: &Surreal<Client>, sql: &str, full: bool) -> Result<QueryPlan, Error> {
+    let statement = explain_statement(sql, full)?;
+    let plan: Vec<serde_json::Value> = traced(&statement, async {
+        db.query(&statement).bind(correlation()).await?.take(0)
+    })
+    .await?;
+    Ok(QueryPlan {
+        query: sql.to_string(),
+        plan: serde_json::Value::Array(plan),
+    })
+}
+
+/// Logs the plan of a slow `SELECT` in the background, on the client
+/// registered with [`SESSION`]. Bound parameters aren't known here and
+/// explain as `NONE`, so index use shown for filters on them is indicative
+/// only. Runs outside [`traced`] so the explain itself is never explained.
+pub fn spawn_log_plan(sql: &str) {
+    let Ok(statement) = explain_statement(sql, false) else {
+        return;
+    };
+    let Some(db) = SESSION.client() else {
+        return;
+    };
+    let sql = sql.to_string();
+    tokio::spawn(async move {
+        let plan: surrealdb::Result<Vec<serde_json::Value>> =
+            async { db.query(&statement).bind(correlation()).await?.take(0) }.await;
+        match plan {
+            Ok(plan) => tracing::warn!(
+                query = %sql,
+                plan = %serde_json::Value::Array(plan),
+                "Plan of slow SurrealDB query"
+            ),
+            Err(error) => tracing::debug!(%error, "Failed to explain slow query"),
+        }
+    });
+}
+// endregion: -- explain
@@ -0,0 +1,166 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::model::SurrealModel;
+use crate::surreal::slow_log::Binding;
+use crate::surreal::sql::escape_str;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- FlagSettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct FlagSettings {
+    pub refresh_secs: u64,
+}
+
+impl Default for FlagSettings {
+    fn default() -> Self {
+        Self { refresh_secs: 30 }
+    }
+}
+// endregion: -- FlagSettings
+
+// region: -- Flag
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Flag {
+    pub name: String,
+    pub enabled: bool,
+}
+
+impl SurrealModel for Flag {
+    const TABLE: &'static str = "flags";
+}
+// endregion: -- Flag
+
+// region: -- FeatureFlags
+/// In-memory view of the `flags` table. Lookups never hit the database; the
+/// cache is reloaded after every toggle made through this service and on a
+/// fixed interval to pick up changes made elsewhere.
+///
+/// The interval stands in for a `LIVE SELECT`, which the client we're on can
+/// issue but not yet receive notifications for.
+#[derive(Clone, Debug)]
+pub struct FeatureFlags {
+    client: Surreal<Client>,
+    cache: Arc<RwLock<BTreeMap<String, bool>>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+/// Lookups of flags the cache knows are hits; unknown flags are misses.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlagCacheMetrics {
+    pub flags: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate_percent: u8,
+}
+
+impl FeatureFlags {
+    #[tracing::instrument(name = "Flags: Load", skip(client))]
+    pub async fn load(client: Surreal<Client>) -> Result<Self, Error> {
+        let flags = Self {
+            client,
+            cache: Arc::default(),
+            hits: Arc::default(),
+            misses: Arc::default(),
+        };
+        flags.refresh().await?;
+        Ok(flags)
+    }
+
+    /// Unknown flags are off.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let cached = self.cache.read().unwrap().get(name).copied();
+        let counter = if cached.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached.unwrap_or(false)
+    }
+
+    pub fn metrics(&self) -> FlagCacheMetrics {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        FlagCacheMetrics {
+            flags: self.cache.read().unwrap().len(),
+            hits,
+            misses,
+            hit_rate_percent: (hits * 100).checked_div(lookups).unwrap_or(100) as u8,
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, bool> {
+        self.cache.read().unwrap().clone()
+    }
+
+    pub fn all(&self) -> Vec<Flag> {
+        self.snapshot()
+            .into_iter()
+            .map(|(name, enabled)| Flag { name, enabled })
+            .collect()
+    }
+
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let sql = format!("SELECT name, enabled FROM {}", Flag::TABLE);
+        let flags: Vec<Flag> = traced(&sql, async {
+            self.client.query(&sql).bind(correlation()).await?.take(0)
+        })
+        .await?;
+
+        let flags = flags.into_iter().map(|f| (f.name, f.enabled)).collect();
+        *self.cache.write().unwrap() = flags;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Flags: Set", skip(self))]
+    pub async fn set(&self, name: &str, enabled: bool) -> Result<Flag, Error> {
+        let sql = format!(
+            "UPDATE type::thing({}, $name) SET name = $name, enabled = $enabled",
+            escape_str(Flag::TABLE)
+        );
+        let bindings = vec![
+            Binding::new("name", &name),
+            Binding::new("enabled", &enabled),
+        ];
+        traced_with_bindings(&sql, bindings, async {
+            self.client
+                .query(&sql)
+                .bind(correlation())
+                .bind(("name", name))
+                .bind(("enabled", enabled))
+                .await?
+                .check()
+        })
+        .await?;
+
+        self.refresh().await?;
+        Ok(Flag {
+            name: name.to_string(),
+            enabled,
+        })
+    }
+
+    /// Keeps the cache in step with the table until the process exits.
+    pub fn spawn_refresh(&self, settings: &FlagSettings) {
+        let flags = self.clone();
+        let period = Duration::from_secs(settings.refresh_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(error) = flags.refresh().await {
+                    tracing::warn!(%error, "Failed to refresh feature flags");
+                }
+            }
+        });
+    }
+}
+// endregion: -- FeatureFlags
@@ -0,0 +1,74 @@
+use surrealdb::opt::QueryResult;
+use surrealdb::Response;
+
+// region: -- FromResponse
+/// A type made from the results of a multi-statement query, each field
+/// taken from one statement's result. Declared with [`from_response!`].
+pub trait FromResponse: Sized {
+    fn from_response(response: Response) -> surrealdb::Result<Self>;
+}
+
+/// Takes one field of a [`FromResponse`] type out of `response`, logging
+/// where it was expected when it can't be had.
+#[doc(hidden)]
+pub fn take<R>(
+    response: &mut Response,
+    index: impl QueryResult<R>,
+    field: &'static str,
+    expected: &'static str,
+) -> surrealdb::Result<R> {
+    response.take(index).map_err(|error| {
+        tracing::warn!(%error, field, expected, "Unexpected query response");
+        error
+    })
+}
+
+/// Declares a struct and its [`FromResponse`] impl, each field followed by
+/// the statement result it is taken from, as `Response::take` indexes them:
+///
+/// ```ignore
+/// from_response! {
+///     struct Stats {
+///         total: Option<u64> = (0, "total"),
+///         initials: Vec<InitialCount> = 1,
+///     }
+/// }
+/// let stats = Stats::from_response(response)?;
+/// ```
+#[macro_export]
+macro_rules! from_response {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty = $index:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::surreal::from_response::FromResponse for $name {
+            fn from_response(
+                mut response: ::surrealdb::Response,
+            ) -> ::surrealdb::Result<Self> {
+                $(
+                    let $field: $ty = $crate::surreal::from_response::take(
+                        &mut response,
+                        $index,
+                        concat!(stringify!($name), ".", stringify!($field), " = ", stringify!($index)),
+                        stringify!($ty),
+                    )?;
+                )*
+                Ok(Self { $($field),* })
+            }
+        }
+    };
+}
+// endregion: -- FromResponse
@@ -0,0 +1,269 @@
+use crate::error::Error;
+use crate::surreal::edge::{parse_record, EdgeAllowList};
+use crate::surreal::instrument::{correlation, traced_with_bindings};
+use crate::surreal::retry::{retry, READ_RETRY};
+use crate::surreal::slow_log::Binding;
+use crate::surreal::sql::escape_ident;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Most hops an export may walk from its root. Every hop is a query per
+/// edge table touching the records it reaches.
+pub const MAX_GRAPH_DEPTH: usize = 4;
+
+/// Most nodes an export holds. Past this it stops walking and says so.
+pub const MAX_GRAPH_NODES: usize = 500;
+
+// region: -- Graph
+/// Records and the edges between them, shaped for D3 and Cytoscape: nodes
+/// carry an `id` and edges a `source` and `target` naming them.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub root: String,
+    pub depth: usize,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Set when [`MAX_GRAPH_NODES`] cut the walk short.
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    /// The record id, e.g. `person:john`.
+    pub id: String,
+    pub table: String,
+    /// Hops from the root.
+    pub depth: usize,
+    /// The record without its `id`.
+    pub data: Value,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    /// The edge record id, e.g. `licenses:abc`.
+    pub id: String,
+    pub table: String,
+    /// The record the edge comes from, its `in`.
+    pub source: String,
+    /// The record the edge goes to, its `out`.
+    pub target: String,
+    /// Whether the edge leaves or enters the node it was reached from.
+    pub direction: Direction,
+    /// The edge's own fields, without `id`, `in` and `out`.
+    pub data: Value,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+/// An edge record as the traversal reads it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EdgeRow {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub record: Value,
+}
+
+impl Graph {
+    pub fn new(root: &Thing, depth: usize) -> Self {
+        Self {
+            root: root.to_string(),
+            depth,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub fn contains(&self, record: &str) -> bool {
+        self.nodes.iter().any(|node| node.id == record)
+    }
+
+    pub fn add_node(&mut self, record: &Thing, depth: usize, mut data: Value) {
+        strip(&mut data, &["id"]);
+        self.nodes.push(GraphNode {
+            id: record.to_string(),
+            table: record.tb.clone(),
+            depth,
+            data,
+        });
+    }
+
+    /// Adds to `found` the records at the far end of `rows` from `frontier`
+    /// that aren't in the graph or `found` yet, as long as the graph has room
+    /// for them.
+    pub fn discover(
+        &mut self,
+        frontier: &BTreeSet<String>,
+        rows: &[EdgeRow],
+        found: &mut Vec<String>,
+    ) {
+        for row in rows {
+            for far in [&row.source, &row.target] {
+                if frontier.contains(far) || self.contains(far) || found.contains(far) {
+                    continue;
+                }
+                if self.nodes.len() + found.len() >= MAX_GRAPH_NODES {
+                    self.truncated = true;
+                    return;
+                }
+                found.push(far.clone());
+            }
+        }
+    }
+
+    /// Adds the edges of `table` among `rows` whose ends are both in the
+    /// graph, each once, with its direction seen from `frontier`.
+    pub fn add_edges(&mut self, table: &str, frontier: &BTreeSet<String>, rows: Vec<EdgeRow>) {
+        for mut row in rows {
+            if !self.contains(&row.source)
+                || !self.contains(&row.target)
+                || self.edges.iter().any(|edge| edge.id == row.id)
+            {
+                continue;
+            }
+            strip(&mut row.record, &["id", "in", "out"]);
+            self.edges.push(GraphEdge {
+                direction: if frontier.contains(&row.source) {
+                    Direction::Outgoing
+                } else {
+                    Direction::Incoming
+                },
+                id: row.id,
+                table: table.to_string(),
+                source: row.source,
+                target: row.target,
+                data: row.record,
+            });
+        }
+    }
+}
+
+fn strip(data: &mut Value, fields: &[&str]) {
+    if let Value::Object(data) = data {
+        for field in fields {
+            data.remove(*field);
+        }
+    }
+}
+// endregion: -- Graph
+
+// region: -- export
+/// Walks up to `depth` hops out from `root` along the edges under
+/// `edges.allowed`, both ways, level by level.
+#[tracing::instrument(name = "Query: Graph Export", skip(db, edges))]
+pub async fn export(
+    db: &Surreal<Client>,
+    edges: &EdgeAllowList,
+    root: &Thing,
+    depth: usize,
+) -> Result<Graph, Error> {
+    let mut graph = Graph::new(root, depth);
+    let Some(data) = read_nodes(db, std::slice::from_ref(root))
+        .await?
+        .remove(&root.to_string())
+    else {
+        return Err(Error::NotFound(root.to_string()));
+    };
+    graph.add_node(root, 0, data);
+
+    let mut frontier = vec![root.clone()];
+    for level in 1..=depth {
+        let ids: BTreeSet<String> = frontier.iter().map(ToString::to_string).collect();
+        let tables: BTreeSet<&str> = frontier.iter().map(|record| record.tb.as_str()).collect();
+        let mut touching: Vec<&str> = tables
+            .iter()
+            .flat_map(|table| edges.touching(table))
+            .collect();
+        touching.sort_unstable();
+        touching.dedup();
+
+        let mut found = Vec::new();
+        let mut rows_by_edge = Vec::new();
+        for edge in touching {
+            let rows = read_edges(db, edge, &frontier).await?;
+            graph.discover(&ids, &rows, &mut found);
+            rows_by_edge.push((edge, rows));
+        }
+
+        let next: Vec<Thing> = found
+            .iter()
+            .filter_map(|record| parse_record(record).ok())
+            .collect();
+        let mut data = read_nodes(db, &next).await?;
+        for record in &next {
+            if let Some(data) = data.remove(&record.to_string()) {
+                graph.add_node(record, level, data);
+            }
+        }
+        for (edge, rows) in rows_by_edge {
+            graph.add_edges(edge, &ids, rows);
+        }
+
+        frontier = next;
+        if frontier.is_empty() || graph.truncated {
+            break;
+        }
+    }
+    Ok(graph)
+}
+
+/// The records among `records` that exist, by id.
+async fn read_nodes(
+    db: &Surreal<Client>,
+    records: &[Thing],
+) -> Result<BTreeMap<String, Value>, Error> {
+    if records.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    #[derive(Deserialize)]
+    struct Row {
+        record: String,
+        data: Value,
+    }
+    let sql = "SELECT <string> id AS record, $this AS data FROM $records";
+    let rows: Vec<Row> = retry(&READ_RETRY, || {
+        traced_with_bindings(sql, vec![Binding::new("records", &records)], async {
+            db.query(sql)
+                .bind(correlation())
+                .bind(("records", records))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+    Ok(rows.into_iter().map(|row| (row.record, row.data)).collect())
+}
+
+/// The records of edge table `edge` that start or end at any of `records`.
+async fn read_edges(
+    db: &Surreal<Client>,
+    edge: &str,
+    records: &[Thing],
+) -> Result<Vec<EdgeRow>, Error> {
+    let sql = format!(
+        "SELECT <string> id AS id, <string> in AS source, <string> out AS target, \
+         $this AS record FROM {} WHERE in INSIDE $records OR out INSIDE $records",
+        escape_ident(edge)
+    );
+    let rows: Vec<EdgeRow> = retry(&READ_RETRY, || {
+        traced_with_bindings(&sql, vec![Binding::new("records", &records)], async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(("records", records))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+    Ok(rows)
+}
+// endregion: -- export
@@ -0,0 +1,189 @@
+use crate::error::Error;
+use crate::surreal::db::Transaction;
+use crate::surreal::instrument::{correlation, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// The table earlier versions of `table`'s records are kept in, e.g.
+/// `person_history`.
+pub fn history_table(table: &str) -> String {
+    format!("{table}_history")
+}
+
+// region: -- Version
+/// A record as it was before one of its updates. Versions are numbered from
+/// 1, the record as first created; the record itself is always one version
+/// ahead of its newest entry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Version {
+    pub version: u64,
+    /// Who made the update that replaced this version, when known.
+    pub actor: Option<String>,
+    /// When it was replaced.
+    pub replaced_at: DateTime<Utc>,
+    pub data: Value,
+}
+// endregion: -- Version
+
+// region: -- update_with_history
+/// `UPDATE record CONTENT data`, first writing the record as it was into its
+/// history table, in the same transaction. Fails with [`Error::NotFound`]
+/// rather than creating a record that doesn't exist. `returning` is the
+/// `RETURN` clause.
+#[tracing::instrument(name = "Query: Update With History", skip(db, data))]
+pub async fn update_with_history(
+    db: &Surreal<Client>,
+    record: &Thing,
+    data: &Value,
+    returning: &str,
+    actor: Option<&str>,
+) -> Result<Option<Value>, Error> {
+    replace(db, record, data, returning, actor, Error::NotFound).await
+}
+
+/// Writes `data`, an earlier version of `record`, back as a new update, so
+/// the version being replaced is kept like any other. Fails with
+/// [`Error::Deleted`] if the record is gone.
+#[tracing::instrument(name = "Query: Revert", skip(db, data))]
+pub async fn revert(
+    db: &Surreal<Client>,
+    record: &Thing,
+    data: &Value,
+    returning: &str,
+    actor: Option<&str>,
+) -> Result<Option<Value>, Error> {
+    replace(db, record, data, returning, actor, Error::Deleted).await
+}
+
+async fn replace(
+    db: &Surreal<Client>,
+    record: &Thing,
+    data: &Value,
+    returning: &str,
+    actor: Option<&str>,
+    missing: fn(String) -> Error,
+) -> Result<Option<Value>, Error> {
+    let sql = format!("UPDATE $record CONTENT $data {returning}");
+    with_history(db, record, &sql, data, actor, missing).await
+}
+
+/// `UPDATE record SET set RETURN AFTER`, keeping the record as it was like
+/// [`update_with_history`]. `set` reads its value as `$data`, e.g.
+/// `tags = array::union(tags OR [], $data)`. Fails with [`Error::NotFound`]
+/// rather than creating a record that doesn't exist.
+#[tracing::instrument(name = "Query: Set With History", skip(db, data))]
+pub async fn set_with_history(
+    db: &Surreal<Client>,
+    record: &Thing,
+    set: &str,
+    data: &Value,
+    actor: Option<&str>,
+) -> Result<Option<Value>, Error> {
+    let sql = format!("UPDATE $record SET {set} RETURN AFTER");
+    with_history(db, record, &sql, data, actor, Error::NotFound).await
+}
+
+/// Runs `sql`, an update of `record` binding `$record` and `$data`, after
+/// keeping the record as it was, all in one transaction. If the record
+/// doesn't exist, `missing` gives the error. The unique index on
+/// `record, version` fails the transaction if a concurrent update took the
+/// same version first.
+async fn with_history(
+    db: &Surreal<Client>,
+    record: &Thing,
+    sql: &str,
+    data: &Value,
+    actor: Option<&str>,
+    missing: fn(String) -> Error,
+) -> Result<Option<Value>, Error> {
+    let mut transaction = Transaction::new(db);
+    transaction
+        .bind("record", record.clone())
+        .bind("data", data.clone())
+        .bind("actor", actor.map(str::to_string));
+    for statement in keep_previous(&record.tb, "") {
+        transaction.query(statement);
+    }
+    let updated = transaction.query(sql);
+
+    match transaction.commit().await {
+        Ok(mut response) => Ok(response.take(updated)?),
+        Err(Error::Aborted(reason)) if reason == MISSING => Err(missing(record.to_string())),
+        Err(error) => Err(error),
+    }
+}
+
+/// The statements that keep `$record{suffix}`, a record of `table`, as it
+/// was before an update queued after them, with `$actor{suffix}` as who
+/// made it. They `THROW` [`MISSING`] if the record doesn't exist. The
+/// suffix keeps apart the variables of updates sharing a transaction.
+pub(crate) fn keep_previous(table: &str, suffix: &str) -> Vec<String> {
+    let history = history_table(table);
+    vec![
+        format!("LET $previous{suffix} = (SELECT * OMIT id FROM $record{suffix})[0]"),
+        format!("IF $previous{suffix} = NONE {{ THROW \"{MISSING}\" }}"),
+        format!(
+            "LET $version{suffix} = array::len((SELECT VALUE id FROM {history} \
+             WHERE record = $record{suffix})) + 1"
+        ),
+        format!(
+            "CREATE {history} SET record = $record{suffix}, version = $version{suffix}, \
+             actor = $actor{suffix}, replaced_at = time::now(), data = $previous{suffix}"
+        ),
+    ]
+}
+
+/// What the transaction throws when there is no record to update.
+pub(crate) const MISSING: &str = "no record to update";
+// endregion: -- update_with_history
+
+// region: -- Reads
+/// Every kept version of `record`, oldest first.
+#[tracing::instrument(name = "Query: History", skip(db))]
+pub async fn history(db: &Surreal<Client>, record: &Thing) -> surrealdb::Result<Vec<Version>> {
+    let sql = format!(
+        "SELECT version, actor, replaced_at, data FROM {} WHERE record = $record \
+         ORDER BY version",
+        history_table(&record.tb)
+    );
+    traced_with_bindings(&sql, vec![Binding::new("record", record)], async {
+        db.query(&sql)
+            .bind(correlation())
+            .bind(("record", record))
+            .await?
+            .take(0)
+    })
+    .await
+}
+
+/// Version `version` of `record`, if it was kept.
+#[tracing::instrument(name = "Query: Version", skip(db))]
+pub async fn version(
+    db: &Surreal<Client>,
+    record: &Thing,
+    version: u64,
+) -> surrealdb::Result<Option<Version>> {
+    let sql = format!(
+        "SELECT version, actor, replaced_at, data FROM {} \
+         WHERE record = $record AND version = $version",
+        history_table(&record.tb)
+    );
+    let bindings = vec![
+        Binding::new("record", record),
+        Binding::new("version", &version),
+    ];
+    traced_with_bindings(&sql, bindings, async {
+        db.query(&sql)
+            .bind(correlation())
+            .bind(("record", record))
+            .bind(("version", version))
+            .await?
+            .take(0)
+    })
+    .await
+}
+// endregion: -- Reads
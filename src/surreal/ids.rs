@@ -0,0 +1,315 @@
+use crate::error::Error;
+use crate::surreal::instrument::traced;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use surrealdb::sql::Id;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use ulid::Ulid;
+use uuid::Uuid;
+
+/// Snowflake timestamps count milliseconds from 2023-01-01T00:00:00Z.
+const SNOWFLAKE_EPOCH_MS: u64 = 1_672_531_200_000;
+const SNOWFLAKE_NODE_BITS: u32 = 10;
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+const MAX_CLIENT_ID_LEN: usize = 64;
+
+// region: -- IdSettings
+/// How a table's record ids are chosen.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// Clients pick ids (letters, digits, `-` and `_`); a random UUID is used
+    /// when they don't.
+    #[default]
+    Client,
+    /// Random UUIDs. Supplied ids must be UUIDs too.
+    Uuid,
+    /// Time-ordered 64-bit ids: milliseconds, node and sequence. Supplied ids
+    /// must be numbers.
+    Snowflake,
+    /// Time-ordered ULIDs, monotonic within a millisecond, so ids sort in
+    /// creation order and work as keyset pagination cursors. Supplied ids must
+    /// be ULIDs.
+    Ulid,
+    /// Derived from the record's `ids.natural_keys` fields, e.g. `registry:7`
+    /// for the registry with registration 7, so creating the same record
+    /// twice finds the first one instead of making a duplicate. Supplied ids
+    /// must match.
+    Natural,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct IdSettings {
+    /// Strategy per table; tables not listed use [`IdStrategy::Client`].
+    pub tables: BTreeMap<String, IdStrategy>,
+    /// Distinguishes snowflakes made by different instances, below 1024.
+    pub snowflake_node: u16,
+    /// The fields whose values make up the id of each [`IdStrategy::Natural`]
+    /// table, joined with `_` when there are several.
+    #[serde(default)]
+    pub natural_keys: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for IdSettings {
+    fn default() -> Self {
+        Self {
+            tables: BTreeMap::from([
+                ("person".into(), IdStrategy::Client),
+                ("registry".into(), IdStrategy::Client),
+            ]),
+            snowflake_node: 0,
+            natural_keys: BTreeMap::from([("registry".into(), vec!["registration".into()])]),
+        }
+    }
+}
+// endregion: -- IdSettings
+
+// region: -- IdGenerator
+/// Hands out the record id for every create, so all code paths agree on what
+/// ids look like. Ids are always plain strings, never `table:id`.
+#[derive(Clone, Debug)]
+pub struct IdGenerator {
+    settings: Arc<IdSettings>,
+    /// Last snowflake millisecond and the sequence used within it.
+    snowflake: Arc<Mutex<(u64, u64)>>,
+    /// Last ULID handed out.
+    ulid: Arc<Mutex<Ulid>>,
+}
+
+impl IdGenerator {
+    pub fn new(settings: &IdSettings) -> Self {
+        Self {
+            settings: Arc::new(settings.clone()),
+            snowflake: Arc::new(Mutex::new((0, 0))),
+            ulid: Arc::new(Mutex::new(Ulid::nil())),
+        }
+    }
+
+    pub fn strategy(&self, table: &str) -> IdStrategy {
+        self.settings.tables.get(table).copied().unwrap_or_default()
+    }
+
+    /// Like [`Self::resolve`], for a record about to be created with
+    /// `content`: natural keys are derived from it, and a supplied id must be
+    /// the same.
+    pub fn resolve_record(
+        &self,
+        table: &str,
+        supplied: Option<&str>,
+        content: &Value,
+    ) -> Result<String, Error> {
+        if self.strategy(table) != IdStrategy::Natural {
+            return self.resolve(table, supplied);
+        }
+        let id = self.natural_key(table, content)?;
+        match supplied {
+            Some(supplied) if supplied != id => Err(Error::InvalidId(format!(
+                "`{supplied}` is not the natural key of this `{table}`, `{id}`"
+            ))),
+            _ => Ok(id),
+        }
+    }
+
+    /// The values of `table`'s natural key fields in `content`.
+    pub fn natural_key(&self, table: &str, content: &Value) -> Result<String, Error> {
+        let fields = self
+            .settings
+            .natural_keys
+            .get(table)
+            .filter(|fields| !fields.is_empty())
+            .ok_or_else(|| Error::InvalidId(format!("`{table}` has no natural key")))?;
+        let values = fields
+            .iter()
+            .map(|field| match content.get(field) {
+                Some(Value::String(value)) if !value.is_empty() => Ok(value.clone()),
+                Some(value @ (Value::Number(_) | Value::Bool(_))) => Ok(value.to_string()),
+                _ => Err(Error::InvalidBody(format!(
+                    "`{field}` is needed for the id of a `{table}`"
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(values.join("_"))
+    }
+
+    /// Checks a client-supplied id against the table's strategy, or makes a
+    /// new one when there is none. Natural keys need the record, so without
+    /// one only a supplied id will do; see [`Self::resolve_record`].
+    pub fn resolve(&self, table: &str, supplied: Option<&str>) -> Result<String, Error> {
+        let strategy = self.strategy(table);
+        let Some(id) = supplied else {
+            if strategy == IdStrategy::Natural {
+                return Err(Error::InvalidId(format!(
+                    "`{table}` ids come from the record's natural key"
+                )));
+            }
+            return Ok(self.generate(strategy));
+        };
+
+        let valid = match strategy {
+            IdStrategy::Client => {
+                !id.is_empty()
+                    && id.len() <= MAX_CLIENT_ID_LEN
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }
+            IdStrategy::Uuid => Uuid::parse_str(id).is_ok(),
+            IdStrategy::Snowflake => id.parse::<u64>().is_ok(),
+            IdStrategy::Ulid => Ulid::from_string(id).is_ok(),
+            IdStrategy::Natural => !id.is_empty(),
+        };
+        if valid {
+            Ok(id.to_string())
+        } else {
+            Err(Error::InvalidId(format!(
+                "`{id}` is not a valid {strategy:?} id for `{table}`"
+            )))
+        }
+    }
+
+    /// A fresh id. Natural keys can't be made up, so they get a UUID.
+    pub fn generate(&self, strategy: IdStrategy) -> String {
+        match strategy {
+            IdStrategy::Client | IdStrategy::Uuid | IdStrategy::Natural => {
+                Uuid::new_v4().to_string()
+            }
+            IdStrategy::Snowflake => self.snowflake().to_string(),
+            IdStrategy::Ulid => self.ulid().to_string(),
+        }
+    }
+
+    fn ulid(&self) -> Ulid {
+        let mut last = self.ulid.lock().unwrap();
+        let mut next = Ulid::new();
+        if next <= *last {
+            // Same millisecond (or a clock step back): count up from the last
+            // one. Only a full random part overflows, and then a fresh ULID is
+            // still unique, just not ordered.
+            next = last.increment().unwrap_or(next);
+        }
+        *last = next;
+        next
+    }
+
+    fn snowflake(&self) -> u64 {
+        let max_sequence = (1 << SNOWFLAKE_SEQUENCE_BITS) - 1;
+        let node = u64::from(self.settings.snowflake_node) & ((1 << SNOWFLAKE_NODE_BITS) - 1);
+
+        let mut last = self.snowflake.lock().unwrap();
+        let mut now = now_ms();
+        // Never go backwards, even if the clock does.
+        if now <= last.0 {
+            now = last.0;
+            last.1 += 1;
+            if last.1 > max_sequence {
+                // Sequence exhausted for this millisecond: borrow the next one.
+                now += 1;
+                last.1 = 0;
+            }
+        } else {
+            last.1 = 0;
+        }
+        last.0 = now;
+
+        ((now - SNOWFLAKE_EPOCH_MS) << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS))
+            | (node << SNOWFLAKE_SEQUENCE_BITS)
+            | last.1
+    }
+}
+
+fn now_ms() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    (since_epoch.as_millis() as u64).max(SNOWFLAKE_EPOCH_MS)
+}
+// endregion: -- IdGenerator
+
+// region: -- ULID helpers
+pub fn ulid_to_id(ulid: Ulid) -> Id {
+    Id::String(ulid.to_string())
+}
+
+/// `None` for ids that aren't ULIDs, e.g. records created before the table
+/// switched strategy.
+pub fn ulid_from_id(id: &Id) -> Option<Ulid> {
+    match id {
+        Id::String(id) => Ulid::from_string(id).ok(),
+        _ => None,
+    }
+}
+
+/// When a ULID was generated, to millisecond precision. Handy for telling
+/// when a record was created from its id alone.
+pub fn ulid_timestamp(ulid: Ulid) -> DateTime<Utc> {
+    let millis = i64::try_from(ulid.timestamp_ms()).unwrap_or(i64::MAX);
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+// endregion: -- ULID helpers
+
+// region: -- create_or_match
+/// How [`create_or_match`] went.
+#[derive(Debug)]
+pub enum Creation<T> {
+    Created(T),
+    /// A record with the same id and content was already there.
+    Existing(T),
+}
+
+/// Creates `table:id` with `content`, or, if it already exists with the same
+/// values for every field in `content`, returns it instead. Any difference
+/// is an [`Error::NaturalKeyConflict`] naming the first differing field.
+/// Meant for natural keys, where the same id means the same record.
+#[tracing::instrument(name = "Query: Create Or Match", skip(db, content))]
+pub async fn create_or_match<T: DeserializeOwned + Send + Sync>(
+    db: &Surreal<Client>,
+    table: &str,
+    id: &str,
+    content: &Value,
+) -> Result<Creation<Option<T>>, Error> {
+    let created = traced("CREATE table:? CONTENT $data", async {
+        db.create((table, id)).content(content).await
+    })
+    .await
+    .map_err(Error::from);
+    match created {
+        Ok(record) => return Ok(Creation::Created(record)),
+        Err(Error::AlreadyExists(_)) => {}
+        Err(error) => return Err(error),
+    }
+
+    let existing: Option<Value> = traced("SELECT * FROM table:?", async {
+        db.select((table, id)).await
+    })
+    .await?;
+    // Deleted again in between: the caller can simply retry.
+    let existing = existing.ok_or_else(|| Error::AlreadyExists(format!("{table}:{id}")))?;
+    if let Some(field) = differing_field(content, &existing) {
+        return Err(Error::NaturalKeyConflict {
+            record: format!("{table}:{id}"),
+            field,
+        });
+    }
+    let existing = serde_json::from_value(existing).map_err(|_| Error::Db)?;
+    Ok(Creation::Existing(Some(existing)))
+}
+
+/// The first field of `content` that `existing` has a different value for.
+/// Fields `content` leaves out, like computed ones, aren't compared.
+pub fn differing_field(content: &Value, existing: &Value) -> Option<String> {
+    let Value::Object(content) = content else {
+        return None;
+    };
+    content
+        .iter()
+        .find(|(field, value)| existing.get(field.as_str()).unwrap_or(&Value::Null) != *value)
+        .map(|(field, _)| field.clone())
+}
+// endregion: -- create_or_match
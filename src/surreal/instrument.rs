@@ -0,0 +1,258 @@
+use crate::api::{current_deadline, current_request_id, FAULTS, MAINTENANCE};
+use crate::surreal::breaker::{counts_as_failure, BREAKER};
+use crate::surreal::connection::CONNECTION;
+use crate::surreal::explain::spawn_log_plan;
+use crate::surreal::request_session::in_request_session;
+use crate::surreal::session::{is_auth_error, SESSION};
+use crate::surreal::slow_log::{Binding, SLOW_QUERIES};
+use crate::surreal::stats;
+use std::future::{Future, IntoFuture};
+use std::panic::Location;
+use std::time::Instant;
+use surrealdb::Response;
+use tracing::{field, Instrument};
+
+// region: -- RowCount
+/// How many rows a query result carries, recorded on the query span.
+pub trait RowCount {
+    fn row_count(&self) -> usize;
+}
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> RowCount for Option<T> {
+    fn row_count(&self) -> usize {
+        usize::from(self.is_some())
+    }
+}
+
+impl RowCount for Response {
+    fn row_count(&self) -> usize {
+        self.num_statements()
+    }
+}
+
+impl RowCount for () {
+    fn row_count(&self) -> usize {
+        0
+    }
+}
+// endregion: -- RowCount
+
+// region: -- correlation
+/// The parameter every query is sent with: `$correlation_id`, the id of the
+/// request it runs for, or `NONE` outside of one. SurrealDB's trace logs
+/// show each query's parameters, so a slow or failing statement there can be
+/// matched with the request's trace and its `X-Request-Id`:
+///
+/// ```ignore
+/// db.query(sql).bind(correlation()).bind(("record", record)).await?
+/// ```
+pub fn correlation() -> (&'static str, Option<String>) {
+    ("correlation_id", current_request_id())
+}
+// endregion: -- correlation
+
+// region: -- traced
+/// Runs `operation` inside a `surrealdb.query` span carrying the statement
+/// fingerprint, the row count and the elapsed time. The raw statement is never
+/// recorded, so bound values and literals stay out of the traces. Queries over
+/// the slow threshold are also kept in the slow query log with their call site,
+/// and every query counts towards the request's stats when they are collected.
+/// A query failing on an expired session signs the client in again, and none
+/// run while the circuit [`BREAKER`] is open, nor writes from requests while
+/// the service is read-only. Those of a request given the `query` fault fail
+/// as if the connection was lost. Queries in flight and how they end feed the
+/// [`CONNECTION`] metrics.
+#[track_caller]
+pub fn traced<'a, T, Fut>(
+    sql: &'a str,
+    operation: Fut,
+) -> impl Future<Output = surrealdb::Result<T>> + 'a
+where
+    T: RowCount,
+    Fut: IntoFuture<Output = surrealdb::Result<T>> + 'a,
+{
+    traced_with_bindings(sql, Vec::new(), operation)
+}
+
+#[track_caller]
+pub fn traced_with_bindings<'a, T, Fut>(
+    sql: &'a str,
+    bindings: Vec<Binding>,
+    operation: Fut,
+) -> impl Future<Output = surrealdb::Result<T>> + 'a
+where
+    T: RowCount,
+    Fut: IntoFuture<Output = surrealdb::Result<T>> + 'a,
+{
+    let call_site = Location::caller();
+
+    async move {
+        let fingerprint = fingerprint(sql);
+        let span = tracing::info_span!(
+            "surrealdb.query",
+            fingerprint = %fingerprint,
+            rows = field::Empty,
+            duration_ms = field::Empty,
+            error = field::Empty,
+        );
+
+        if let Some(rejected) = MAINTENANCE.reject_write(sql) {
+            span.record("error", field::display(&rejected));
+            return Err(rejected);
+        }
+        let start = Instant::now();
+        let permit = match BREAKER.admit(start) {
+            Ok(permit) => permit,
+            Err(rejected) => {
+                span.record("error", field::display(&rejected.into_error()));
+                return Err(rejected.into_error());
+            }
+        };
+        let in_flight = CONNECTION.begin();
+        let operation = operation.into_future().instrument(span.clone());
+        // Queries past the request's deadline aren't worth starting, and are
+        // abandoned when it passes mid-flight. Those of a request given the
+        // `query` fault fail without running, after the breaker let them in.
+        let result = match (FAULTS.fail_query(), current_deadline()) {
+            (Some(injected), _) => Err(injected),
+            (None, Some(deadline)) if deadline.expired() => Err(cancelled()),
+            (None, Some(deadline)) => tokio::time::timeout_at(deadline.at(), operation)
+                .await
+                .unwrap_or_else(|_| Err(cancelled())),
+            (None, None) => operation.await,
+        };
+        let elapsed = start.elapsed();
+
+        span.record("duration_ms", elapsed.as_secs_f64() * 1000.0);
+        match &result {
+            Ok(value) => span.record("rows", value.row_count()),
+            Err(error) => span.record("error", field::display(error)),
+        };
+        permit.record(elapsed, result.as_ref().is_err_and(counts_as_failure));
+        in_flight.finish(result.as_ref().err());
+        if SLOW_QUERIES.observe(&fingerprint, elapsed, &bindings, call_site)
+            && SLOW_QUERIES.explains()
+        {
+            spawn_log_plan(sql);
+        }
+        stats::record(elapsed);
+        // The query still fails, but the next one (or a retry) can succeed.
+        // A request session's token is the caller's to renew.
+        if result.as_ref().is_err_and(is_auth_error) && !in_request_session() {
+            SESSION.recover(start).await;
+        }
+
+        result
+    }
+}
+
+fn cancelled() -> surrealdb::Error {
+    surrealdb::Error::Db(surrealdb::error::Db::QueryCancelled)
+}
+// endregion: -- traced
+
+// region: -- fingerprint
+/// Normalizes a SurrealQL statement so that queries differing only by their
+/// literals share a fingerprint: strings, numbers and record ids become `?`
+/// and whitespace is collapsed. Parameters (`$name`) are kept as written.
+pub fn fingerprint(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                skip_quoted(&mut chars, c);
+                out.push('?');
+            }
+            '⟨' => {
+                skip_quoted(&mut chars, '⟩');
+                out.push('?');
+            }
+            '`' => {
+                out.push('`');
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if c == '`' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if !out.is_empty() {
+                    out.push(' ');
+                }
+            }
+            c if c.is_ascii_digit() => {
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '_')
+                {
+                    chars.next();
+                }
+                out.push('?');
+            }
+            '$' => {
+                out.push('$');
+                while let Some(c) = chars.next_if(|c| is_ident(*c)) {
+                    out.push(c);
+                }
+            }
+            c if is_ident(c) => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|c| is_ident(*c)) {
+                    word.push(c);
+                }
+                // `table:id` record ids keep the table and drop the id.
+                if chars.peek() == Some(&':') {
+                    chars.next();
+                    out.push_str(&word);
+                    out.push(':');
+                    match chars.peek() {
+                        Some(c) if is_ident(*c) => {
+                            while chars.next_if(|c| is_ident(*c)).is_some() {}
+                            out.push('?');
+                        }
+                        Some('⟨') => {
+                            chars.next();
+                            skip_quoted(&mut chars, '⟩');
+                            out.push('?');
+                        }
+                        _ => {}
+                    }
+                } else {
+                    out.push_str(&word);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn skip_quoted(chars: &mut std::iter::Peekable<std::str::Chars>, close: char) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == close => break,
+            _ => {}
+        }
+    }
+}
+// endregion: -- fingerprint
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use ulid::Ulid;
+
+// region: -- PendingBatch
+/// A [`crate::surreal::query_manager::QueryManager`] batch that was started
+/// but hasn't finished, as written to the journal before it runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingBatch {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    /// The statements of each transaction the batch runs as.
+    pub chunks: Vec<Vec<String>>,
+    /// Transactions known to have committed; a resume starts after them.
+    pub committed: usize,
+}
+
+impl PendingBatch {
+    pub fn new(chunks: Vec<Vec<String>>) -> Self {
+        Self {
+            id: Ulid::new().to_string(),
+            started_at: Utc::now(),
+            chunks,
+            committed: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> &[Vec<String>] {
+        &self.chunks[self.committed.min(self.chunks.len())..]
+    }
+}
+// endregion: -- PendingBatch
+
+// region: -- BatchJournal
+/// A directory with one JSON file per pending batch. Files are written to a
+/// temporary name and renamed into place, so a crash mid-write never leaves
+/// a torn entry behind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchJournal {
+    dir: PathBuf,
+}
+
+impl BatchJournal {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes `batch`, replacing what was recorded for it before.
+    pub fn record(&self, batch: &PendingBatch) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(&batch.id)?;
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(batch)?)?;
+        fs::rename(temporary, path)
+    }
+
+    /// Forgets batch `id`. Returns whether it was there.
+    pub fn remove(&self, id: &str) -> io::Result<bool> {
+        match fs::remove_file(self.path(id)?) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> io::Result<Option<PendingBatch>> {
+        match fs::read(self.path(id)?) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Every pending batch, oldest first. Unreadable entries are logged and
+    /// skipped rather than hiding the rest.
+    pub fn pending(&self) -> io::Result<Vec<PendingBatch>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        let mut batches = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+            {
+                Ok(batch) => batches.push(batch),
+                Err(error) => {
+                    tracing::warn!(path = %path.display(), %error, "Skipping unreadable batch journal entry")
+                }
+            }
+        }
+        batches.sort_by(|a: &PendingBatch, b| (a.started_at, &a.id).cmp(&(b.started_at, &b.id)));
+        Ok(batches)
+    }
+
+    /// Ids are ULIDs, which keeps them from naming paths outside the journal.
+    fn path(&self, id: &str) -> io::Result<PathBuf> {
+        Ulid::from_string(id).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{id}` is not a batch id"),
+            )
+        })?;
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+}
+// endregion: -- BatchJournal
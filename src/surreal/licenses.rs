@@ -0,0 +1,55 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- LicenseSettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct LicenseSettings {
+    /// How often active licenses past their `expires_at` are marked expired.
+    pub expiry_check_secs: u64,
+}
+
+impl Default for LicenseSettings {
+    fn default() -> Self {
+        Self {
+            expiry_check_secs: 60,
+        }
+    }
+}
+// endregion: -- LicenseSettings
+
+// region: -- Expiry
+/// Flips active licenses whose `expires_at` has passed to `expired`. Returns
+/// how many were flipped.
+#[tracing::instrument(name = "Query: Expire Licenses", skip(db))]
+pub async fn expire_licenses(db: &Surreal<Client>) -> Result<usize, Error> {
+    let sql = "UPDATE licenses SET status = 'expired' \
+               WHERE status = 'active' AND expires_at != NONE AND expires_at <= time::now() \
+               RETURN id";
+    let expired: Vec<Thing> = traced(sql, async {
+        db.query(sql).bind(correlation()).await?.take((0, "id"))
+    })
+    .await?;
+    Ok(expired.len())
+}
+
+/// Runs [`expire_licenses`] on a fixed interval. Reads don't wait for it:
+/// `GET /person/:id/licenses?active=true` also checks `expires_at` itself.
+pub fn spawn_expiry(db: Surreal<Client>, settings: &LicenseSettings) {
+    let period = Duration::from_secs(settings.expiry_check_secs.max(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match expire_licenses(&db).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "Expired licenses"),
+                Err(error) => tracing::warn!(%error, "Failed to expire licenses"),
+            }
+        }
+    });
+}
+// endregion: -- Expiry
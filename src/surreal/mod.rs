@@ -1,2 +1,40 @@
+pub mod admin;
+pub mod backup;
+pub mod breaker;
+pub mod cache;
+pub mod connection;
+pub mod count;
+pub mod database_url;
 pub mod db;
-
+pub mod duplicates;
+pub mod edge;
+pub mod explain;
+pub mod flags;
+pub mod from_response;
+pub mod graph;
+pub mod history;
+pub mod ids;
+pub mod instrument;
+pub mod journal;
+pub mod licenses;
+pub mod model;
+pub mod paging;
+pub mod queries;
+pub mod query_manager;
+pub mod repository;
+pub mod request_log;
+pub mod request_session;
+pub mod restore;
+pub mod retry;
+pub mod saga;
+pub mod schema;
+pub mod self_test;
+pub mod session;
+pub mod slow_log;
+pub mod snapshot;
+pub mod sql;
+pub mod stats;
+pub mod tls;
+pub mod ttl;
+pub mod version;
+pub mod write_behind;
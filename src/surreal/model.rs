@@ -0,0 +1,42 @@
+use crate::error::Error;
+use crate::surreal::schema::tables::{declared, known_fields, TableDefinition};
+use surrealdb::sql::Thing;
+
+// region: -- SurrealModel
+/// A struct stored as the records of one table. Code that needs the table,
+/// a record id or what the schema declares asks the model, so a handler
+/// can't name one table and write another's struct to it:
+///
+/// ```ignore
+/// impl SurrealModel for Registry {
+///     const TABLE: &'static str = "registry";
+/// }
+///
+/// let registry: Option<Registry> = db.select(Registry::record("dmv")).await?;
+/// registry.ok_or_else(|| Registry::not_found("dmv"))?;
+/// ```
+pub trait SurrealModel {
+    const TABLE: &'static str;
+
+    /// The record `TABLE:id`.
+    fn record(id: &str) -> Thing {
+        Thing::from((Self::TABLE, id))
+    }
+
+    /// The `404` for a missing `TABLE:id`.
+    fn not_found(id: &str) -> Error {
+        Error::NotFound(format!("{}:{id}", Self::TABLE))
+    }
+
+    /// The fields and types the schema declares for the table, if any.
+    fn definition() -> Option<&'static TableDefinition> {
+        declared(Self::TABLE)
+    }
+
+    /// Every field a record can be read with: `id`, the declared fields and
+    /// the computed ones.
+    fn fields() -> Vec<&'static str> {
+        known_fields(Self::TABLE)
+    }
+}
+// endregion: -- SurrealModel
@@ -0,0 +1,99 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::retry::{retry, READ_RETRY};
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Rows per query when streaming a whole table.
+pub const STREAM_PAGE_SIZE: u32 = 500;
+
+// region: -- stream_table
+/// Reads every record of `table` in id order, one page of `page_size` at a
+/// time, so only a page is ever held in memory. Pages are keyed on the last
+/// id seen rather than `START`, which would rescan every earlier row.
+///
+/// The stream ends after the first error.
+pub fn stream_table<T>(
+    db: Surreal<Client>,
+    table: &'static str,
+    page_size: u32,
+) -> impl Stream<Item = Result<Vec<T>, Error>> + Send
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    stream_projection(db, table, "*".into(), page_size)
+}
+
+/// [`stream_table`] selecting only `fields`, a SurrealQL projection that
+/// must include `id`.
+pub fn stream_projection<T>(
+    db: Surreal<Client>,
+    table: &'static str,
+    fields: String,
+    page_size: u32,
+) -> impl Stream<Item = Result<Vec<T>, Error>> + Send
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    // `None` once done, otherwise the id to continue after.
+    let start: Option<Option<Thing>> = Some(None);
+    futures_util::stream::unfold(start, move |cursor| {
+        let db = db.clone();
+        let fields = fields.clone();
+        async move {
+            let after = cursor?;
+            match page::<T>(&db, table, &fields, after, page_size).await {
+                Ok((rows, _)) if rows.is_empty() => None,
+                Ok((rows, last)) => {
+                    let next = (rows.len() as u32 == page_size).then_some(last);
+                    Some((Ok(rows), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    })
+}
+
+async fn page<T: DeserializeOwned>(
+    db: &Surreal<Client>,
+    table: &str,
+    fields: &str,
+    after: Option<Thing>,
+    page_size: u32,
+) -> Result<(Vec<T>, Option<Thing>), Error> {
+    let sql = match after {
+        Some(_) => format!(
+            "SELECT {fields} FROM type::table($table) WHERE id > $after ORDER BY id LIMIT {page_size}"
+        ),
+        None => format!("SELECT {fields} FROM type::table($table) ORDER BY id LIMIT {page_size}"),
+    };
+    // Rows come back as JSON first so the cursor can be read off the last
+    // one before they become `T`, which needn't have an `id`.
+    let rows: Vec<serde_json::Value> = retry(&READ_RETRY, || {
+        traced(&sql, async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(("table", table))
+                .bind(("after", &after))
+                .await?
+                .take(0)
+        })
+    })
+    .await?;
+
+    let last = rows
+        .last()
+        .and_then(|row| serde_json::from_value(row["id"].clone()).ok());
+    let rows = rows
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<Vec<T>, _>>()
+        .map_err(|e| {
+            tracing::error!(error = %e, table, "Failed to read a streamed row");
+            Error::Db
+        })?;
+    Ok((rows, last))
+}
+// endregion: -- stream_table
@@ -0,0 +1,420 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced_with_bindings};
+use crate::surreal::query_manager::{StatementKind, StatementPolicy};
+use crate::surreal::slow_log::Binding;
+use futures_core::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+pub static QUERIES: Lazy<QueryTemplates> = Lazy::new(QueryTemplates::default);
+
+/// Parameters SurrealDB sets itself, and the one [`correlation`] binds, so
+/// callers never pass them.
+const PROVIDED: &[&str] = &[
+    "auth",
+    "session",
+    "scope",
+    "token",
+    "this",
+    "parent",
+    "value",
+    "before",
+    "after",
+    "event",
+    "input",
+    "correlation_id",
+];
+
+// region: -- QuerySettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuerySettings {
+    /// Where the `*.surql` templates are, each named after its file.
+    pub dir: PathBuf,
+}
+
+impl Default for QuerySettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("queries"),
+        }
+    }
+}
+
+impl QuerySettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.dir.as_os_str().is_empty() {
+            problems.push("`queries.dir` is empty".into());
+        } else if self.dir.exists() && !self.dir.is_dir() {
+            problems.push(format!(
+                "`queries.dir` ({}) is not a directory",
+                self.dir.display()
+            ));
+        }
+        problems
+    }
+}
+// endregion: -- QuerySettings
+
+// region: -- QueryDiagnostic
+/// Something wrong with a template, at `file:line`. Line 0 is the file as
+/// a whole.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryDiagnostic {
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for QueryDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}: {}", self.file.display(), self.message),
+            line => write!(f, "{}:{line}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+fn report(diagnostics: &[QueryDiagnostic]) -> String {
+    let lines: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+    lines.join("\n")
+}
+// endregion: -- QueryDiagnostic
+
+// region: -- QueryTemplate
+/// A named SurrealQL query kept out of the Rust code, with the `$params` its
+/// callers bind. Each is declared in a comment, e.g. `-- @param $registry`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryTemplate {
+    pub name: String,
+    pub sql: String,
+    pub params: BTreeSet<String>,
+}
+
+impl QueryTemplate {
+    /// Parses the template in `file`, named after it, refusing statements a
+    /// request couldn't run. The `@param`s it declares must be the `$names`
+    /// it uses but neither `LET`s nor gets from SurrealDB.
+    pub fn parse(file: &Path, sql: &str) -> Result<Self, Vec<QueryDiagnostic>> {
+        let diagnostic = |line: usize, message: String| QueryDiagnostic {
+            file: file.to_path_buf(),
+            line,
+            message,
+        };
+        let name = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let query = surrealdb::sql::parse(sql).map_err(|error| {
+            let message = error.to_string();
+            vec![diagnostic(error_line(&message), message)]
+        })?;
+        let scan = scan(sql);
+        let mut diagnostics = Vec::new();
+        if query.is_empty() {
+            diagnostics.push(diagnostic(1, "has no statements".into()));
+        }
+        let policy = StatementPolicy::request_path();
+        for (i, kind) in query.iter().map(StatementKind::of).enumerate() {
+            if !policy.allows(kind) {
+                let line = scan.statements.get(i).copied().unwrap_or(1);
+                let message = format!("`{}` statements are not allowed", kind.keyword());
+                diagnostics.push(diagnostic(line, message));
+            }
+        }
+
+        let mut declared = BTreeMap::new();
+        for (line, param) in declarations(sql) {
+            if declared.insert(param.clone(), line).is_some() {
+                diagnostics.push(diagnostic(line, format!("`${param}` is declared twice")));
+            }
+        }
+        for (param, line) in &scan.params {
+            if !declared.contains_key(param) {
+                let message = format!("`${param}` is used but not declared with `@param`");
+                diagnostics.push(diagnostic(*line, message));
+            }
+        }
+        for (param, line) in &declared {
+            if !scan.params.contains_key(param) {
+                diagnostics.push(diagnostic(
+                    *line,
+                    format!("`${param}` is declared but never used"),
+                ));
+            }
+        }
+        if !diagnostics.is_empty() {
+            diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+            return Err(diagnostics);
+        }
+
+        Ok(Self {
+            name: name.into(),
+            sql: sql.trim().into(),
+            params: declared.into_keys().collect(),
+        })
+    }
+
+    /// Refuses `bindings` that leave out a parameter or bind one the
+    /// template doesn't have.
+    pub fn check(&self, bindings: &BTreeMap<String, serde_json::Value>) -> Result<(), Error> {
+        if let Some(missing) = self.params.iter().find(|p| !bindings.contains_key(*p)) {
+            return Err(Error::QueryTemplate(format!(
+                "`{}` needs `${missing}` bound",
+                self.name
+            )));
+        }
+        if let Some(unknown) = bindings.keys().find(|b| !self.params.contains(*b)) {
+            return Err(Error::QueryTemplate(format!(
+                "`{}` has no parameter `${unknown}`",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The line SurrealDB's parse error points at, e.g. `Parse error on line 3
+/// at character 7`, or the first.
+fn error_line(message: &str) -> usize {
+    message
+        .split("line ")
+        .nth(1)
+        .and_then(|rest| {
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .unwrap_or(1)
+}
+
+/// The `-- @param $name` declarations in `sql`, with their lines. What
+/// follows the name describes the parameter.
+fn declarations(sql: &str) -> Vec<(usize, String)> {
+    sql.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.trim_start();
+            let comment = ["--", "//", "#"]
+                .iter()
+                .find_map(|marker| line.strip_prefix(marker))?;
+            let param = comment.trim_start().strip_prefix("@param")?.trim_start();
+            let name: String = param
+                .strip_prefix('$')?
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            Some((i + 1, name))
+        })
+        .collect()
+}
+
+/// What [`scan`] finds in a template outside strings and comments.
+#[derive(Debug, Default)]
+struct Scan {
+    /// The `$names` it doesn't `LET` and SurrealDB doesn't provide, with the
+    /// line each is first used on.
+    params: BTreeMap<String, usize>,
+    /// The line each top-level statement starts on.
+    statements: Vec<usize>,
+}
+
+fn scan(sql: &str) -> Scan {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(sql.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |i: usize| line_starts.partition_point(|start| *start <= i);
+
+    let mut scan = Scan::default();
+    let mut defined = BTreeSet::new();
+    let mut previous_word = String::new();
+    let mut depth = 0usize;
+    let mut statement_starts = true;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let comment = matches!(c, '#')
+            || sql[i..].starts_with("--")
+            || sql[i..].starts_with("//")
+            || sql[i..].starts_with("/*");
+        if statement_starts && depth == 0 && !comment && c != ';' {
+            scan.statements.push(line_of(i));
+            statement_starts = false;
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                let mut escaped = false;
+                for (_, n) in chars.by_ref() {
+                    match n {
+                        '\\' if !escaped => escaped = true,
+                        n if n == c && !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '/' if sql[i..].starts_with("/*") => {
+                let end = sql[i + 2..].find("*/").map_or(sql.len(), |end| i + 4 + end);
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+            }
+            _ if comment => skip_line(&mut chars),
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => statement_starts = true,
+            '$' => {
+                let mut name = String::new();
+                while let Some((_, n)) = chars.next_if(|(_, n)| n.is_alphanumeric() || *n == '_') {
+                    name.push(n);
+                }
+                if previous_word.eq_ignore_ascii_case("LET") {
+                    defined.insert(name.clone());
+                } else if !name.is_empty()
+                    && !defined.contains(&name)
+                    && !PROVIDED.contains(&name.as_str())
+                {
+                    scan.params.entry(name.clone()).or_insert(line_of(i));
+                }
+                previous_word = format!("${name}");
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some((_, n)) = chars.next_if(|(_, n)| n.is_alphanumeric() || *n == '_') {
+                    word.push(n);
+                }
+                previous_word = word;
+            }
+            _ => {}
+        }
+    }
+    scan
+}
+
+fn skip_line(chars: &mut impl Iterator<Item = (usize, char)>) {
+    for (_, c) in chars {
+        if c == '\n' {
+            break;
+        }
+    }
+}
+// endregion: -- QueryTemplate
+
+// region: -- QueryTemplates
+/// The templates loaded from `queries.dir`, by name.
+#[derive(Debug, Default)]
+pub struct QueryTemplates {
+    templates: RwLock<BTreeMap<String, Arc<QueryTemplate>>>,
+}
+
+impl QueryTemplates {
+    /// Replaces the templates with the `*.surql` files in `settings.dir`.
+    /// Nothing is replaced unless every file checks out; each diagnostic is
+    /// logged, and the error lists them all.
+    pub fn load(&self, settings: &QuerySettings) -> Result<usize, Error> {
+        let templates = read_dir(&settings.dir).map_err(|diagnostics| {
+            for diagnostic in &diagnostics {
+                tracing::error!(%diagnostic, "Invalid query template");
+            }
+            Error::QueryTemplate(report(&diagnostics))
+        })?;
+        let count = templates.len();
+        *self.templates.write().unwrap() = templates;
+        tracing::info!(count, dir = %settings.dir.display(), "Loaded query templates");
+        Ok(count)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<QueryTemplate>, Error> {
+        self.templates
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::QueryTemplate(format!("no template named `{name}`")))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.templates.read().unwrap().keys().cloned().collect()
+    }
+}
+
+/// The templates in `dir`, each named after its file, or the diagnostics
+/// of every file that doesn't check out, in file order.
+pub fn read_dir(dir: &Path) -> Result<BTreeMap<String, Arc<QueryTemplate>>, Vec<QueryDiagnostic>> {
+    let unreadable = |path: &Path, error: std::io::Error| {
+        vec![QueryDiagnostic {
+            file: path.to_path_buf(),
+            line: 0,
+            message: format!("can't be read: {error}"),
+        }]
+    };
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|error| unreadable(dir, error))? {
+        let path = entry.map_err(|error| unreadable(dir, error))?.path();
+        if path.extension().and_then(|extension| extension.to_str()) == Some("surql") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut templates = BTreeMap::new();
+    let mut diagnostics = Vec::new();
+    for path in paths {
+        let parsed = fs::read_to_string(&path)
+            .map_err(|error| unreadable(&path, error))
+            .and_then(|sql| QueryTemplate::parse(&path, &sql));
+        match parsed {
+            Ok(template) => {
+                templates.insert(template.name.clone(), Arc::new(template));
+            }
+            Err(found) => diagnostics.extend(found),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(templates)
+}
+// endregion: -- QueryTemplates
+
+// region: -- RunQuery
+/// Runs the templates in [`QUERIES`] by name.
+pub trait RunQuery {
+    /// Runs template `name` with `bindings`, which must bind exactly its
+    /// parameters.
+    fn run<'a>(
+        &'a self,
+        name: &'a str,
+        bindings: BTreeMap<String, serde_json::Value>,
+    ) -> BoxFuture<'a, Result<surrealdb::Response, Error>>;
+}
+
+impl RunQuery for Surreal<Client> {
+    fn run<'a>(
+        &'a self,
+        name: &'a str,
+        bindings: BTreeMap<String, serde_json::Value>,
+    ) -> BoxFuture<'a, Result<surrealdb::Response, Error>> {
+        Box::pin(async move {
+            let template = QUERIES.get(name)?;
+            template.check(&bindings)?;
+            let metadata = bindings
+                .iter()
+                .map(|(name, value)| Binding::new(name, value))
+                .collect();
+            let response = traced_with_bindings(&template.sql, metadata, async {
+                self.query(&template.sql)
+                    .bind(correlation())
+                    .bind(&bindings)
+                    .await
+            })
+            .await?;
+            Ok(response.check()?)
+        })
+    }
+}
+// endregion: -- RunQuery
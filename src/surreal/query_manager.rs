@@ -0,0 +1,529 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::journal::{BatchJournal, PendingBatch};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use surrealdb::sql::Statement;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+pub static TRANSACTIONS: Lazy<TransactionMonitor> =
+    Lazy::new(|| TransactionMonitor::new(&TransactionSettings::default()));
+
+// region: -- TransactionSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransactionSettings {
+    /// Statements in one transaction before it counts as oversized.
+    pub max_statements: usize,
+    /// Total statement bytes in one transaction before it counts as oversized.
+    pub max_bytes: usize,
+    /// Run oversized transactions as several sequential transactions that each
+    /// stay under the limits, instead of only warning about them.
+    pub split: bool,
+    /// Directory to journal batches in before they run, so one cut short by
+    /// a crash can be found and resumed or discarded afterwards.
+    #[serde(default)]
+    pub journal_dir: Option<PathBuf>,
+}
+
+impl Default for TransactionSettings {
+    fn default() -> Self {
+        Self {
+            max_statements: 500,
+            max_bytes: 1024 * 1024,
+            split: false,
+            journal_dir: None,
+        }
+    }
+}
+// endregion: -- TransactionSettings
+
+// region: -- TransactionMonitor
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionSize {
+    pub statements: usize,
+    pub bytes: usize,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionMetrics {
+    pub transactions: u64,
+    pub statements: u64,
+    pub bytes: u64,
+    pub oversized: u64,
+    pub split: u64,
+    pub largest: TransactionSize,
+}
+
+/// Counts the size of every [`QueryManager`] transaction and warns about the
+/// ones over the configured limits.
+#[derive(Debug)]
+pub struct TransactionMonitor {
+    max_statements: AtomicUsize,
+    max_bytes: AtomicUsize,
+    split: AtomicBool,
+    transactions: AtomicU64,
+    statements: AtomicU64,
+    bytes: AtomicU64,
+    oversized: AtomicU64,
+    splits: AtomicU64,
+    largest_statements: AtomicUsize,
+    largest_bytes: AtomicUsize,
+    journal: RwLock<Option<BatchJournal>>,
+}
+
+impl TransactionMonitor {
+    pub fn new(settings: &TransactionSettings) -> Self {
+        Self {
+            max_statements: AtomicUsize::new(settings.max_statements),
+            max_bytes: AtomicUsize::new(settings.max_bytes),
+            split: AtomicBool::new(settings.split),
+            transactions: AtomicU64::new(0),
+            statements: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            oversized: AtomicU64::new(0),
+            splits: AtomicU64::new(0),
+            largest_statements: AtomicUsize::new(0),
+            largest_bytes: AtomicUsize::new(0),
+            journal: RwLock::new(settings.journal_dir.clone().map(BatchJournal::new)),
+        }
+    }
+
+    pub fn configure(&self, settings: &TransactionSettings) {
+        self.max_statements
+            .store(settings.max_statements, Ordering::Relaxed);
+        self.max_bytes.store(settings.max_bytes, Ordering::Relaxed);
+        self.split.store(settings.split, Ordering::Relaxed);
+        *self.journal.write().unwrap() = settings.journal_dir.clone().map(BatchJournal::new);
+    }
+
+    pub fn settings(&self) -> TransactionSettings {
+        TransactionSettings {
+            max_statements: self.max_statements.load(Ordering::Relaxed),
+            max_bytes: self.max_bytes.load(Ordering::Relaxed),
+            split: self.split.load(Ordering::Relaxed),
+            journal_dir: self.journal().map(|journal| journal.dir().to_path_buf()),
+        }
+    }
+
+    /// Where batches are journalled, if anywhere.
+    pub fn journal(&self) -> Option<BatchJournal> {
+        self.journal.read().unwrap().clone()
+    }
+
+    /// Records a transaction about to run. Returns whether it is oversized.
+    pub fn observe(&self, size: TransactionSize) -> bool {
+        let settings = self.settings();
+        self.transactions.fetch_add(1, Ordering::Relaxed);
+        self.statements
+            .fetch_add(size.statements as u64, Ordering::Relaxed);
+        self.bytes.fetch_add(size.bytes as u64, Ordering::Relaxed);
+        self.largest_statements
+            .fetch_max(size.statements, Ordering::Relaxed);
+        self.largest_bytes.fetch_max(size.bytes, Ordering::Relaxed);
+
+        let oversized =
+            size.statements > settings.max_statements || size.bytes > settings.max_bytes;
+        if oversized {
+            self.oversized.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                statements = size.statements,
+                bytes = size.bytes,
+                max_statements = settings.max_statements,
+                max_bytes = settings.max_bytes,
+                split = settings.split,
+                "Oversized SurrealDB transaction"
+            );
+        }
+        oversized
+    }
+
+    pub fn record_split(&self) {
+        self.splits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> TransactionMetrics {
+        TransactionMetrics {
+            transactions: self.transactions.load(Ordering::Relaxed),
+            statements: self.statements.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            oversized: self.oversized.load(Ordering::Relaxed),
+            split: self.splits.load(Ordering::Relaxed),
+            largest: TransactionSize {
+                statements: self.largest_statements.load(Ordering::Relaxed),
+                bytes: self.largest_bytes.load(Ordering::Relaxed),
+            },
+        }
+    }
+}
+// endregion: -- TransactionMonitor
+
+// region: -- StatementPolicy
+/// The kinds of SurrealQL statement, as [`Statement`] has them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementKind {
+    Use,
+    Let,
+    Info,
+    Live,
+    Kill,
+    Begin,
+    Cancel,
+    Commit,
+    Return,
+    If,
+    Select,
+    Create,
+    Update,
+    Relate,
+    Delete,
+    Insert,
+    Define,
+    Remove,
+    Option,
+}
+
+impl StatementKind {
+    pub fn of(statement: &Statement) -> Self {
+        match statement {
+            Statement::Use(_) => Self::Use,
+            Statement::Set(_) => Self::Let,
+            Statement::Info(_) => Self::Info,
+            Statement::Live(_) => Self::Live,
+            Statement::Kill(_) => Self::Kill,
+            Statement::Begin(_) => Self::Begin,
+            Statement::Cancel(_) => Self::Cancel,
+            Statement::Commit(_) => Self::Commit,
+            Statement::Output(_) => Self::Return,
+            Statement::Ifelse(_) => Self::If,
+            Statement::Select(_) => Self::Select,
+            Statement::Create(_) => Self::Create,
+            Statement::Update(_) => Self::Update,
+            Statement::Relate(_) => Self::Relate,
+            Statement::Delete(_) => Self::Delete,
+            Statement::Insert(_) => Self::Insert,
+            Statement::Define(_) => Self::Define,
+            Statement::Remove(_) => Self::Remove,
+            Statement::Option(_) => Self::Option,
+        }
+    }
+
+    /// The statement's keyword, e.g. `REMOVE`.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Use => "USE",
+            Self::Let => "LET",
+            Self::Info => "INFO",
+            Self::Live => "LIVE",
+            Self::Kill => "KILL",
+            Self::Begin => "BEGIN",
+            Self::Cancel => "CANCEL",
+            Self::Commit => "COMMIT",
+            Self::Return => "RETURN",
+            Self::If => "IF",
+            Self::Select => "SELECT",
+            Self::Create => "CREATE",
+            Self::Update => "UPDATE",
+            Self::Relate => "RELATE",
+            Self::Delete => "DELETE",
+            Self::Insert => "INSERT",
+            Self::Define => "DEFINE",
+            Self::Remove => "REMOVE",
+            Self::Option => "OPTION",
+        }
+    }
+}
+
+/// The statement kinds a [`QueryManager`] may run. Statements are parsed
+/// before anything is sent, so a batch with one kind outside the list is
+/// refused whole; this also stops values spliced into a statement from
+/// smuggling in another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementPolicy {
+    allowed: Vec<StatementKind>,
+}
+
+impl StatementPolicy {
+    pub fn allow(allowed: impl IntoIterator<Item = StatementKind>) -> Self {
+        Self {
+            allowed: allowed.into_iter().collect(),
+        }
+    }
+
+    /// For batches built while handling a request: reading and writing
+    /// records, but no schema changes, `INFO`, scope switches or transaction
+    /// control, which [`QueryManager::execute`] adds itself.
+    pub fn request_path() -> Self {
+        Self::allow([
+            StatementKind::Let,
+            StatementKind::Return,
+            StatementKind::If,
+            StatementKind::Select,
+            StatementKind::Create,
+            StatementKind::Update,
+            StatementKind::Relate,
+            StatementKind::Delete,
+            StatementKind::Insert,
+        ])
+    }
+
+    pub fn allows(&self, kind: StatementKind) -> bool {
+        self.allowed.contains(&kind)
+    }
+
+    /// Parses `sql` and refuses it if any statement in it isn't allowed.
+    pub fn check(&self, sql: &str) -> Result<(), Error> {
+        let query =
+            surrealdb::sql::parse(sql).map_err(|error| Error::QuerySyntax(error.to_string()))?;
+        match query
+            .iter()
+            .map(StatementKind::of)
+            .find(|kind| !self.allows(*kind))
+        {
+            Some(kind) => Err(Error::StatementNotAllowed(kind.keyword())),
+            None => Ok(()),
+        }
+    }
+}
+// endregion: -- StatementPolicy
+
+// region: -- QueryManager
+/// What a [`QueryManager`] committed. `chunks` has one entry per transaction
+/// that was run, so more than one means the batch was split.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionReport {
+    pub size: TransactionSize,
+    pub chunks: Vec<TransactionSize>,
+}
+
+impl TransactionReport {
+    pub fn was_split(&self) -> bool {
+        self.chunks.len() > 1
+    }
+}
+
+/// Collects statements and runs them as one transaction, or as several when
+/// the batch is oversized and splitting is enabled in [`TRANSACTIONS`].
+#[derive(Debug, Default, Clone)]
+pub struct QueryManager {
+    statements: Vec<String>,
+    policy: Option<StatementPolicy>,
+}
+
+impl QueryManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuses to run the batch if any of its statements is outside `policy`.
+    pub fn with_policy(mut self, policy: StatementPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Checks every statement against the policy, if there is one.
+    pub fn check(&self) -> Result<(), Error> {
+        match &self.policy {
+            Some(policy) => self
+                .statements
+                .iter()
+                .try_for_each(|statement| policy.check(statement)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn add_query(&mut self, sql: impl Into<String>) {
+        let sql = sql.into();
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        if !sql.is_empty() {
+            self.statements.push(sql.to_string());
+        }
+    }
+
+    pub fn size(&self) -> TransactionSize {
+        size_of(&self.statements)
+    }
+
+    /// The statements grouped into transactions that each stay under the
+    /// limits. A single statement over `max_bytes` still gets a chunk of its
+    /// own.
+    pub fn partition(&self, settings: &TransactionSettings) -> Vec<&[String]> {
+        let mut chunks = Vec::new();
+        let (mut start, mut bytes) = (0, 0);
+        for (i, statement) in self.statements.iter().enumerate() {
+            let full = i - start >= settings.max_statements.max(1)
+                || bytes + statement.len() > settings.max_bytes;
+            if full && i > start {
+                chunks.push(&self.statements[start..i]);
+                (start, bytes) = (i, 0);
+            }
+            bytes += statement.len();
+        }
+        if start < self.statements.len() {
+            chunks.push(&self.statements[start..]);
+        }
+        chunks
+    }
+
+    #[tracing::instrument(
+        name = "Query: Transaction",
+        skip_all,
+        fields(statements = self.statements.len())
+    )]
+    pub async fn execute(self, conn: &Surreal<Client>) -> Result<TransactionReport, Error> {
+        let mut report = TransactionReport {
+            size: self.size(),
+            chunks: Vec::new(),
+        };
+        if self.statements.is_empty() {
+            return Ok(report);
+        }
+        self.check()?;
+
+        let settings = TRANSACTIONS.settings();
+        let oversized = TRANSACTIONS.observe(report.size);
+        let chunks = if oversized && settings.split {
+            self.partition(&settings)
+        } else {
+            vec![&self.statements[..]]
+        };
+        if chunks.len() > 1 {
+            TRANSACTIONS.record_split();
+            tracing::info!(chunks = chunks.len(), "Splitting oversized transaction");
+        }
+
+        let batch = PendingBatch::new(chunks.into_iter().map(<[String]>::to_vec).collect());
+        let journal = TRANSACTIONS.journal();
+        if let Some(journal) = &journal {
+            journal.record(&batch).map_err(journal_error)?;
+        }
+        run_batch(conn, batch, journal.as_ref(), &mut report).await?;
+        Ok(report)
+    }
+}
+
+/// Runs the transactions of `batch` not yet committed, recording progress in
+/// `journal`. The entry is dropped once the batch is done or fails without
+/// committing anything, and kept when it fails part way, so the rest can be
+/// resumed.
+async fn run_batch(
+    conn: &Surreal<Client>,
+    mut batch: PendingBatch,
+    journal: Option<&BatchJournal>,
+    report: &mut TransactionReport,
+) -> Result<(), Error> {
+    let total = batch.chunks.len();
+    while batch.committed < total {
+        let i = batch.committed;
+        let chunk = &batch.chunks[i];
+        let sql = format!(
+            "BEGIN TRANSACTION;\n{};\nCOMMIT TRANSACTION;",
+            chunk.join(";\n")
+        );
+        let result = traced(&sql, conn.query(&sql).bind(correlation()))
+            .await
+            .and_then(|response| response.check());
+        if let Err(error) = result {
+            if i == 0 {
+                forget(journal, &batch.id);
+                return Err(error.into());
+            }
+            let committed = batch.chunks[..i].iter().map(Vec::len).sum();
+            tracing::error!(%error, batch = %batch.id, chunk = i + 1, total, committed, "Split transaction failed");
+            return Err(Error::PartiallyCommitted {
+                failed: i + 1,
+                total,
+                committed,
+            });
+        }
+        report.chunks.push(size_of(chunk));
+        batch.committed += 1;
+        if let Some(journal) = journal.filter(|_| batch.committed < total) {
+            // The transaction has committed either way; a stale entry only
+            // means a resume would run it again.
+            if let Err(error) = journal.record(&batch) {
+                tracing::warn!(%error, batch = %batch.id, "Failed to record batch progress");
+            }
+        }
+    }
+    forget(journal, &batch.id);
+    Ok(())
+}
+
+fn forget(journal: Option<&BatchJournal>, id: &str) {
+    if let Some(Err(error)) = journal.map(|journal| journal.remove(id)) {
+        tracing::warn!(%error, batch = id, "Failed to remove the batch journal entry");
+    }
+}
+
+fn size_of(statements: &[String]) -> TransactionSize {
+    TransactionSize {
+        statements: statements.len(),
+        bytes: statements.iter().map(String::len).sum(),
+    }
+}
+
+fn size_of_chunks(chunks: &[Vec<String>]) -> TransactionSize {
+    TransactionSize {
+        statements: chunks.iter().map(Vec::len).sum(),
+        bytes: chunks.iter().flatten().map(String::len).sum(),
+    }
+}
+// endregion: -- QueryManager
+
+// region: -- Pending batches
+/// Batches in the journal that never finished: cut short by a crash, or
+/// failed part way through a split.
+pub fn pending_batches() -> Result<Vec<PendingBatch>, Error> {
+    match TRANSACTIONS.journal() {
+        Some(journal) => journal.pending().map_err(journal_error),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Runs the transactions of batch `id` that hadn't committed. One that was
+/// running when the process died may have committed without the journal
+/// knowing, and runs again.
+#[tracing::instrument(name = "Query: Resume Batch", skip(conn))]
+pub async fn resume_batch(conn: &Surreal<Client>, id: &str) -> Result<TransactionReport, Error> {
+    let (journal, batch) = find_batch(id)?;
+    let mut report = TransactionReport {
+        size: size_of_chunks(batch.remaining()),
+        chunks: Vec::new(),
+    };
+    tracing::info!(
+        batch = id,
+        remaining = batch.remaining().len(),
+        "Resuming batch"
+    );
+    run_batch(conn, batch, Some(&journal), &mut report).await?;
+    Ok(report)
+}
+
+/// Drops batch `id` from the journal without running any more of it.
+pub fn discard_batch(id: &str) -> Result<PendingBatch, Error> {
+    let (journal, batch) = find_batch(id)?;
+    journal.remove(id).map_err(journal_error)?;
+    tracing::info!(batch = id, committed = batch.committed, "Discarded batch");
+    Ok(batch)
+}
+
+fn find_batch(id: &str) -> Result<(BatchJournal, PendingBatch), Error> {
+    let not_found = || Error::NotFound(format!("batch {id}"));
+    let journal = TRANSACTIONS.journal().ok_or_else(not_found)?;
+    match journal.get(id) {
+        Ok(Some(batch)) => Ok((journal, batch)),
+        Ok(None) => Err(not_found()),
+        Err(error) if error.kind() == io::ErrorKind::InvalidInput => Err(not_found()),
+        Err(error) => Err(journal_error(error)),
+    }
+}
+
+fn journal_error(error: io::Error) -> Error {
+    Error::Journal(error.to_string())
+}
+// endregion: -- Pending batches
@@ -0,0 +1,116 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::retry::{retry, READ_RETRY};
+use crate::surreal::slow_log::Binding;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- RepositorySettings
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepositorySettings {
+    pub strategy: Strategy,
+}
+
+/// How records are created and read.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// The SDK's `create` and `select`.
+    #[default]
+    Sdk,
+    /// `CREATE $record CONTENT $data` and `SELECT * FROM $record`, bound
+    /// like every other query.
+    Query,
+}
+// endregion: -- RepositorySettings
+
+// region: -- Repository
+/// Creates and reads single records the way `repository.strategy` says, so
+/// the routes behave the same whichever way the database is reached.
+/// Updates and deletes keep history and handle edges in a transaction, so
+/// they are SurrealQL either way.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Repository {
+    strategy: Strategy,
+}
+
+impl Repository {
+    pub fn new(settings: &RepositorySettings) -> Self {
+        Self {
+            strategy: settings.strategy,
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Creates `record` with `data` and returns it as written.
+    #[tracing::instrument(name = "Query: Create Record", skip(self, db, data))]
+    pub async fn create<T: DeserializeOwned + Send + Sync>(
+        &self,
+        db: &Surreal<Client>,
+        record: &Thing,
+        data: &Value,
+    ) -> Result<T, Error> {
+        let created: Option<T> = match self.strategy {
+            Strategy::Sdk => {
+                traced("CREATE $record CONTENT $data", async {
+                    db.create(record.clone()).content(data).await
+                })
+                .await?
+            }
+            Strategy::Query => {
+                let sql = "CREATE $record CONTENT $data";
+                let bindings = vec![Binding::new("record", record), Binding::new("data", data)];
+                traced_with_bindings(sql, bindings, async {
+                    db.query(sql)
+                        .bind(correlation())
+                        .bind(("record", record))
+                        .bind(("data", data))
+                        .await?
+                        .take(0)
+                })
+                .await?
+            }
+        };
+        created.ok_or(Error::Db)
+    }
+
+    /// Reads `record`, retrying as reads do.
+    #[tracing::instrument(name = "Query: Select Record", skip(self, db))]
+    pub async fn select<T: DeserializeOwned + Send + Sync>(
+        &self,
+        db: &Surreal<Client>,
+        record: &Thing,
+    ) -> Result<Option<T>, Error> {
+        let selected: Option<T> = match self.strategy {
+            Strategy::Sdk => {
+                retry(&READ_RETRY, || {
+                    traced("SELECT * FROM $record", async {
+                        db.select(record.clone()).await
+                    })
+                })
+                .await?
+            }
+            Strategy::Query => {
+                let sql = "SELECT * FROM $record";
+                retry(&READ_RETRY, || {
+                    traced_with_bindings(sql, vec![Binding::new("record", record)], async {
+                        db.query(sql)
+                            .bind(correlation())
+                            .bind(("record", record))
+                            .await?
+                            .take(0)
+                    })
+                })
+                .await?
+            }
+        };
+        Ok(selected)
+    }
+}
+// endregion: -- Repository
@@ -0,0 +1,307 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// The table is trimmed back to `max_records` once per this many writes, so
+/// it can run this far over in between.
+const TRIM_EVERY: u64 = 100;
+const DEFAULT_QUERY_LIMIT: u32 = 100;
+const MAX_QUERY_LIMIT: u32 = 1000;
+/// How far back [`RequestLog::recent_errors`] counts.
+pub const RECENT_ERRORS_WINDOW: Duration = Duration::from_secs(300);
+/// Error responses remembered for the window; older ones go first.
+const MAX_RECENT_ERRORS: usize = 10_000;
+
+pub static REQUEST_LOG: Lazy<RequestLog> =
+    Lazy::new(|| RequestLog::new(&RequestLogSettings::default()));
+
+// region: -- RequestLogSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RequestLogSettings {
+    pub enabled: bool,
+    /// Share of requests kept, in percent.
+    pub sample_percent: u8,
+    /// Keep every `5xx` whatever the sample.
+    pub keep_errors: bool,
+    /// Newest requests kept in the table.
+    pub max_records: usize,
+    /// Routes never kept, as matched, e.g. `/person/:id`.
+    pub exclude: Vec<String>,
+}
+
+impl Default for RequestLogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_percent: 10,
+            keep_errors: true,
+            max_records: 10_000,
+            exclude: vec![
+                "/health_check".into(),
+                "/health/ready".into(),
+                "/metrics".into(),
+            ],
+        }
+    }
+}
+// endregion: -- RequestLogSettings
+
+// region: -- RequestLog
+/// Summary of one API request, as kept in the `requests` table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RequestRecord {
+    /// Set by the database when the record is written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<DateTime<Utc>>,
+    pub method: String,
+    /// The matched route, e.g. `/person/:id`, or the path if none matched.
+    pub route: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub user: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestLogMetrics {
+    pub written: u64,
+    /// Background writes started but not finished.
+    pub pending_writes: u64,
+}
+
+/// Error responses over the last [`RECENT_ERRORS_WINDOW`], sampled or not.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecentErrors {
+    pub window_secs: u64,
+    pub client_errors: usize,
+    pub server_errors: usize,
+}
+
+/// A sampled trail of API requests in the database itself, capped at
+/// `max_records`, for looking into an incident without log infrastructure.
+/// Writes happen in the background and a failed one is only logged.
+#[derive(Debug)]
+pub struct RequestLog {
+    settings: RwLock<RequestLogSettings>,
+    written: AtomicU64,
+    pending: AtomicU64,
+    errors: Mutex<VecDeque<(Instant, u16)>>,
+}
+
+impl RequestLog {
+    pub fn new(settings: &RequestLogSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+            written: AtomicU64::new(0),
+            pending: AtomicU64::new(0),
+            errors: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn configure(&self, settings: &RequestLogSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    pub fn settings(&self) -> RequestLogSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Whether a request to `route` answered with `status` is kept.
+    pub fn keeps(&self, route: &str, status: u16) -> bool {
+        let settings = self.settings.read().unwrap();
+        if !settings.enabled || settings.exclude.iter().any(|r| r == route) {
+            return false;
+        }
+        if settings.keep_errors && status >= 500 {
+            return true;
+        }
+        rand::thread_rng().gen_range(0..100) < settings.sample_percent
+    }
+
+    pub fn spawn_write(&'static self, db: Surreal<Client>, record: RequestRecord) {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+            if let Err(error) = self.write(&db, &record).await {
+                tracing::warn!(%error, "Failed to store the request record");
+            }
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+
+    /// Counts every response towards [`Self::recent_errors`], whether it is
+    /// kept or not.
+    pub fn record_status(&self, now: Instant, status: u16) {
+        if status < 400 {
+            return;
+        }
+        let mut errors = self.errors.lock().unwrap();
+        if errors.len() >= MAX_RECENT_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back((now, status));
+    }
+
+    pub fn recent_errors(&self, now: Instant) -> RecentErrors {
+        let mut errors = self.errors.lock().unwrap();
+        while errors
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > RECENT_ERRORS_WINDOW)
+        {
+            errors.pop_front();
+        }
+        let server_errors = errors.iter().filter(|(_, status)| *status >= 500).count();
+        RecentErrors {
+            window_secs: RECENT_ERRORS_WINDOW.as_secs(),
+            client_errors: errors.len() - server_errors,
+            server_errors,
+        }
+    }
+
+    pub fn metrics(&self) -> RequestLogMetrics {
+        RequestLogMetrics {
+            written: self.written.load(Ordering::Relaxed),
+            pending_writes: self.pending.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn write(&self, db: &Surreal<Client>, record: &RequestRecord) -> Result<(), Error> {
+        let sql = "CREATE requests CONTENT $record RETURN NONE;";
+        traced(sql, async {
+            db.query(sql)
+                .bind(correlation())
+                .bind(("record", record))
+                .await?
+                .check()
+        })
+        .await?;
+
+        if self.written.fetch_add(1, Ordering::Relaxed) % TRIM_EVERY == TRIM_EVERY - 1 {
+            let max_records = self.settings().max_records;
+            let trimmed = trim(db, max_records).await?;
+            if trimmed > 0 {
+                tracing::debug!(trimmed, max_records, "Trimmed the request log");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Deletes all but the newest `max_records` requests. Returns how many went.
+#[tracing::instrument(name = "Query: Trim Requests", skip(db))]
+pub async fn trim(db: &Surreal<Client>, max_records: usize) -> Result<usize, Error> {
+    let sql = "DELETE requests WHERE at < \
+               (SELECT VALUE at FROM requests ORDER BY at DESC LIMIT 1 START $keep)[0] \
+               RETURN id;";
+    let deleted: Vec<Thing> = traced(sql, async {
+        db.query(sql)
+            .bind(correlation())
+            .bind(("keep", max_records.saturating_sub(1)))
+            .await?
+            .take((0, "id"))
+    })
+    .await?;
+    Ok(deleted.len())
+}
+// endregion: -- RequestLog
+
+// region: -- Query
+/// Query string for `GET /admin/requests`.
+#[derive(Deserialize, Debug, Default)]
+pub struct RequestsQuery {
+    pub status: Option<u16>,
+    /// Only requests answered with at least this status, e.g. `500`.
+    pub min_status: Option<u16>,
+    /// RFC 3339.
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub route: Option<String>,
+    pub user: Option<String>,
+    pub request_id: Option<String>,
+    pub limit: Option<u32>,
+}
+
+impl RequestsQuery {
+    /// The `SELECT`, newest first, and the values to bind.
+    pub fn statement(&self) -> (String, BTreeMap<String, serde_json::Value>) {
+        let mut conditions = Vec::new();
+        let mut bindings = BTreeMap::new();
+        let mut condition = |clause: &'static str, name: &str, value: serde_json::Value| {
+            conditions.push(clause);
+            bindings.insert(name.to_string(), value);
+        };
+
+        if let Some(status) = self.status {
+            condition("status = $status", "status", status.into());
+        }
+        if let Some(status) = self.min_status {
+            condition("status >= $min_status", "min_status", status.into());
+        }
+        if let Some(since) = self.since {
+            condition(
+                "at >= <datetime> $since",
+                "since",
+                since.to_rfc3339().into(),
+            );
+        }
+        if let Some(until) = self.until {
+            condition("at < <datetime> $until", "until", until.to_rfc3339().into());
+        }
+        if let Some(route) = &self.route {
+            condition("route = $route", "route", route.clone().into());
+        }
+        if let Some(user) = &self.user {
+            condition("user = $user", "user", user.clone().into());
+        }
+        if let Some(request_id) = &self.request_id {
+            condition(
+                "request_id = $request_id",
+                "request_id",
+                request_id.clone().into(),
+            );
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        let limit = self
+            .limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .clamp(1, MAX_QUERY_LIMIT);
+        let sql = format!("SELECT * FROM requests{filter} ORDER BY at DESC LIMIT {limit};");
+        (sql, bindings)
+    }
+}
+
+#[tracing::instrument(name = "Query: Requests", skip(db))]
+pub async fn query_requests(
+    db: &Surreal<Client>,
+    query: &RequestsQuery,
+) -> Result<Vec<RequestRecord>, Error> {
+    let (sql, bindings) = query.statement();
+    let metadata = bindings
+        .iter()
+        .map(|(name, value)| Binding::new(name, value))
+        .collect();
+    let records = traced_with_bindings(&sql, metadata, async {
+        db.query(&sql)
+            .bind(correlation())
+            .bind(&bindings)
+            .await?
+            .take(0)
+    })
+    .await?;
+    Ok(records)
+}
+// endregion: -- Query
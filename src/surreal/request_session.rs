@@ -0,0 +1,218 @@
+use crate::error::Error;
+use crate::surreal::db::{connect, DatabaseSettings};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Routes that always run on the application's connection: admin, whose
+/// token isn't a database one, and health, which probes that connection.
+const EXEMPT: &[&str] = &["/admin", "/health"];
+
+tokio::task_local! {
+    static SCOPED: ();
+}
+
+// region: -- RequestSessionSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RequestSessionSettings {
+    /// Runs the requests under `routes` with the caller's token.
+    pub enabled: bool,
+    /// Path prefixes of the route groups that run with the caller's token.
+    pub routes: Vec<String>,
+    /// Connections kept for those requests. Requests past it wait for one.
+    pub pool_size: usize,
+}
+
+impl Default for RequestSessionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            routes: vec!["/person".into(), "/people".into()],
+            pool_size: 8,
+        }
+    }
+}
+
+impl RequestSessionSettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.pool_size == 0 {
+            problems.push("`request_sessions.pool_size` must be at least 1".into());
+        }
+        for route in &self.routes {
+            if !route.starts_with('/') {
+                problems.push(format!(
+                    "`request_sessions.routes` entry `{route}` must start with `/`"
+                ));
+            } else if EXEMPT.iter().any(|exempt| route.starts_with(exempt)) {
+                problems.push(format!(
+                    "`request_sessions.routes` entry `{route}` can't be an admin or health route"
+                ));
+            }
+        }
+        problems
+    }
+}
+// endregion: -- RequestSessionSettings
+
+// region: -- RequestSessions
+/// Connections that run a request's queries as the caller. Each is signed
+/// in with the request's token for the request, so SurrealDB's
+/// `PERMISSIONS` see the caller's `$auth`, and signed out again before it
+/// serves another. Connections are opened as needed, up to `pool_size`, in
+/// the database the app currently uses.
+#[derive(Clone, Debug)]
+pub struct RequestSessions {
+    pool: Arc<Pool>,
+}
+
+#[derive(Debug)]
+struct Pool {
+    settings: RequestSessionSettings,
+    database: Mutex<DatabaseSettings>,
+    /// Bumped on every switch of database, so connections opened before it
+    /// aren't pooled again.
+    generation: AtomicU64,
+    idle: Mutex<Vec<Surreal<Client>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl RequestSessions {
+    pub fn new(settings: &RequestSessionSettings, database: &DatabaseSettings) -> Self {
+        Self {
+            pool: Arc::new(Pool {
+                settings: settings.clone(),
+                database: Mutex::new(database.clone()),
+                generation: AtomicU64::new(0),
+                idle: Mutex::new(Vec::new()),
+                permits: Arc::new(Semaphore::new(settings.pool_size)),
+            }),
+        }
+    }
+
+    /// Whether requests to `path` run with the caller's token.
+    pub fn applies_to(&self, path: &str) -> bool {
+        let settings = &self.pool.settings;
+        settings.enabled
+            && !EXEMPT.iter().any(|exempt| path.starts_with(exempt))
+            && settings
+                .routes
+                .iter()
+                .any(|route| path.starts_with(route.as_str()))
+    }
+
+    /// Opens connections in `namespace`/`database` from now on, e.g. once
+    /// a restore is confirmed. Idle connections are dropped, and those in
+    /// use are dropped when their request ends instead of being pooled.
+    pub fn use_database(&self, namespace: &str, database: &str) {
+        {
+            let mut current = self.pool.database.lock().unwrap();
+            current.namespace = namespace.to_string();
+            current.database = database.to_string();
+            self.pool.generation.fetch_add(1, Ordering::SeqCst);
+        }
+        self.pool.idle.lock().unwrap().clear();
+    }
+
+    /// A pooled connection signed in with `token`, waiting for one to be
+    /// free if they are all in use. A token the database refuses is a `401`.
+    #[tracing::instrument(name = "Query: Open Request Session", skip_all)]
+    pub async fn open(&self, token: &str) -> Result<RequestSession, Error> {
+        let permit = self
+            .pool
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the request session pool is never closed");
+        let generation = self.pool.generation.load(Ordering::SeqCst);
+        let idle = self.pool.idle.lock().unwrap().pop();
+        let client = match idle {
+            Some(client) => client,
+            None => self.connect().await?,
+        };
+        // Signed in or not, a connection whose token was refused is dropped
+        // rather than pooled.
+        client
+            .authenticate(token.to_string())
+            .await
+            .map_err(|_| Error::InvalidToken)?;
+        Ok(RequestSession {
+            client: Some(client),
+            pool: self.pool.clone(),
+            generation,
+            permit: Some(permit),
+        })
+    }
+
+    async fn connect(&self) -> Result<Surreal<Client>, Error> {
+        let database = self.pool.database.lock().unwrap().clone();
+        let client = connect(&database).await.map_err(|error| {
+            tracing::error!(%error, "Failed to open a request session connection");
+            Error::DbUnavailable
+        })?;
+        client
+            .use_ns(&database.namespace)
+            .use_db(&database.database)
+            .await?;
+        Ok(client)
+    }
+}
+
+/// A connection signed in as a request's caller. Dropping it signs it out
+/// and hands it back to the pool in the background, so it can live as long
+/// as a streamed response body that still queries.
+#[derive(Debug)]
+pub struct RequestSession {
+    client: Option<Surreal<Client>>,
+    pool: Arc<Pool>,
+    generation: u64,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RequestSession {
+    pub fn client(&self) -> &Surreal<Client> {
+        self.client.as_ref().expect("only taken on drop")
+    }
+}
+
+impl Drop for RequestSession {
+    fn drop(&mut self) {
+        let (Some(client), Some(permit)) = (self.client.take(), self.permit.take()) else {
+            return;
+        };
+        let pool = self.pool.clone();
+        let generation = self.generation;
+        tokio::spawn(async move {
+            if client.invalidate().await.is_ok() {
+                let mut idle = pool.idle.lock().unwrap();
+                // Checked under the lock `use_database` drains, so a switch
+                // can't slip in between.
+                if pool.generation.load(Ordering::SeqCst) == generation {
+                    idle.push(client);
+                }
+            }
+            drop(permit);
+        });
+    }
+}
+
+/// The connection a request runs its queries on, set as a request
+/// extension while it runs in a [`RequestSession`].
+#[derive(Clone, Debug)]
+pub struct SessionClient(pub Surreal<Client>);
+
+/// Runs `future` as part of a request in a [`RequestSession`].
+pub async fn scoped<F: Future>(future: F) -> F::Output {
+    SCOPED.scope((), future).await
+}
+
+/// Whether the current request runs with its caller's token, so writes
+/// mustn't take a detour through the application's own connection.
+pub fn in_request_session() -> bool {
+    SCOPED.try_with(|_| ()).is_ok()
+}
+// endregion: -- RequestSessions
@@ -0,0 +1,146 @@
+use crate::error::Error;
+use crate::surreal::admin::{AdminDatabase, Scope};
+use crate::surreal::backup::store::BackupStore;
+use crate::surreal::snapshot::expected_counts;
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+/// How long a staged restore waits to be confirmed.
+pub const CONFIRM_WITHIN_MINUTES: i64 = 15;
+
+pub static RESTORES: Lazy<PendingRestores> = Lazy::new(PendingRestores::default);
+
+// region: -- RestorePlan
+/// One table of a staged restore: the records the snapshot says it had
+/// against those that made it into the staging database.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TableCheck {
+    pub table: String,
+    /// `None` for snapshots taken before counts were noted in them.
+    pub expected: Option<usize>,
+    pub restored: usize,
+    pub ok: bool,
+}
+
+/// A snapshot restored into a staging database and checked, waiting to be
+/// switched to. Only a plan whose checks all passed gets a token.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RestorePlan {
+    pub snapshot: String,
+    pub staging: Scope,
+    pub statements: usize,
+    pub tables: Vec<TableCheck>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A database next to `live`'s, named after it and the time, e.g.
+/// `test_restore_20230510T030000`.
+pub fn staging_scope(live: &Scope, at: DateTime<Utc>) -> Scope {
+    Scope {
+        namespace: live.namespace.clone(),
+        database: format!("{}_restore_{}", live.database, at.format("%Y%m%dT%H%M%S")),
+    }
+}
+
+/// Compares what the snapshot noted per table with what was restored. A
+/// table the snapshot noted but the staging database lacks fails.
+pub fn check_tables(
+    expected: &BTreeMap<String, usize>,
+    restored: &BTreeMap<String, usize>,
+) -> Vec<TableCheck> {
+    let mut tables: Vec<&String> = expected.keys().chain(restored.keys()).collect();
+    tables.sort();
+    tables.dedup();
+    tables
+        .into_iter()
+        .map(|table| {
+            let expected = expected.get(table).copied();
+            let restored = restored.get(table).copied().unwrap_or(0);
+            TableCheck {
+                table: table.clone(),
+                expected,
+                restored,
+                ok: expected.unwrap_or(restored) == restored,
+            }
+        })
+        .collect()
+}
+// endregion: -- RestorePlan
+
+// region: -- stage
+/// Restores `snapshot` from `store` into a new staging database beside
+/// `live` and checks its record counts. The live database isn't touched; a
+/// staging database whose checks fail is left for inspection.
+#[tracing::instrument(name = "Restore: Stage", skip(admin, store))]
+pub async fn stage(
+    admin: &AdminDatabase,
+    store: &dyn BackupStore,
+    snapshot: &str,
+    live: &Scope,
+) -> Result<RestorePlan, Error> {
+    let surql = store.get(snapshot).await?;
+    let staging = staging_scope(live, Utc::now());
+    let report = admin.restore(staging.clone(), &surql).await?;
+    let restored = admin.table_counts(staging.clone()).await?;
+    let tables = check_tables(&expected_counts(&surql), &restored);
+
+    Ok(RestorePlan {
+        snapshot: snapshot.to_string(),
+        staging,
+        statements: report.statements,
+        passed: tables.iter().all(|table| table.ok),
+        tables,
+        token: None,
+        expires_at: None,
+    })
+}
+// endregion: -- stage
+
+// region: -- PendingRestores
+/// The staged restore awaiting confirmation, if any. Staging another replaces
+/// it, and a token is good for one switch.
+#[derive(Debug, Default)]
+pub struct PendingRestores {
+    pending: Mutex<Option<(String, DateTime<Utc>, Scope)>>,
+}
+
+impl PendingRestores {
+    /// Hands `plan` a token if its checks passed.
+    pub fn offer(&self, mut plan: RestorePlan, now: DateTime<Utc>) -> RestorePlan {
+        if plan.passed {
+            let token = Uuid::new_v4().simple().to_string();
+            let expires_at = now + Duration::minutes(CONFIRM_WITHIN_MINUTES);
+            *self.pending.lock().unwrap() = Some((token.clone(), expires_at, plan.staging.clone()));
+            plan.token = Some(token);
+            plan.expires_at = Some(expires_at);
+        }
+        plan
+    }
+
+    /// The staging database `token` confirms. Any use of the pending token,
+    /// right or wrong, after it expired clears it.
+    pub fn confirm(&self, token: &str, now: DateTime<Utc>) -> Result<Scope, Error> {
+        let mut pending = self.pending.lock().unwrap();
+        let matches = |expected: &str| bool::from(expected.as_bytes().ct_eq(token.as_bytes()));
+        match pending.take() {
+            Some((expected, expires_at, staging)) if matches(&expected) && now < expires_at => {
+                Ok(staging)
+            }
+            Some(entry) if now < entry.1 => {
+                *pending = Some(entry);
+                Err(Error::InvalidConfirmation)
+            }
+            _ => Err(Error::InvalidConfirmation),
+        }
+    }
+}
+// endregion: -- PendingRestores
@@ -0,0 +1,143 @@
+use crate::surreal::session::is_auth_error;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use surrealdb::error::{Api, Db};
+
+// Error messages SurrealDB sends back over the wire for conditions that are
+// safe to retry. Remote engines surface these as strings rather than variants.
+const RETRYABLE_MESSAGES: [&str; 5] = [
+    "can be retried",
+    "read or write conflict",
+    "exceeded the timeout",
+    "connection reset",
+    "connection closed",
+];
+
+pub static READ_RETRY: Lazy<RetryPolicy> = Lazy::new(RetryPolicy::default);
+
+// region: -- RetryPolicy
+#[derive(Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    budget: RetryBudget,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(50), Duration::from_secs(1), 20)
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration, budget: u32) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            max_delay,
+            budget: RetryBudget::new(budget),
+        }
+    }
+
+    /// Full-jitter exponential backoff: a random delay in `[0, base * 2^attempt]`,
+    /// capped at `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    pub fn remaining_budget(&self) -> u32 {
+        self.budget.remaining()
+    }
+}
+// endregion: -- RetryPolicy
+
+// region: -- RetryBudget
+/// Shared allowance of retries so a struggling database isn't hammered by
+/// every caller retrying at once. Each retry withdraws a whole token and each
+/// success deposits a tenth of one, so sustained retries are capped at roughly
+/// 10% of successful traffic once the initial reserve is spent.
+#[derive(Debug)]
+struct RetryBudget {
+    deci_tokens: AtomicU32,
+    max_deci_tokens: u32,
+}
+
+impl RetryBudget {
+    fn new(tokens: u32) -> Self {
+        Self {
+            deci_tokens: AtomicU32::new(tokens * 10),
+            max_deci_tokens: tokens * 10,
+        }
+    }
+
+    fn withdraw(&self) -> bool {
+        self.deci_tokens
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |t| t.checked_sub(10))
+            .is_ok()
+    }
+
+    fn deposit(&self) {
+        let _ = self
+            .deci_tokens
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |t| {
+                (t < self.max_deci_tokens).then_some(t + 1)
+            });
+    }
+
+    fn remaining(&self) -> u32 {
+        self.deci_tokens.load(Ordering::Acquire) / 10
+    }
+}
+// endregion: -- RetryBudget
+
+// region: -- retry
+pub fn is_retryable(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(Db::TxFailure | Db::QueryTimedout) => true,
+        surrealdb::Error::Api(Api::Ws(_) | Api::Http(_) | Api::ConnectionUninitialised) => true,
+        // `traced` has already tried to sign in again.
+        other if is_auth_error(other) => true,
+        other => {
+            let message = other.to_string().to_lowercase();
+            RETRYABLE_MESSAGES.iter().any(|m| message.contains(m))
+        }
+    }
+}
+
+/// Re-runs `operation` on transient errors. Only use it for idempotent work.
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, mut operation: F) -> surrealdb::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = surrealdb::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Ok(value) => {
+                policy.budget.deposit();
+                return Ok(value);
+            }
+            Err(error)
+                if attempt + 1 < policy.max_attempts
+                    && is_retryable(&error)
+                    && policy.budget.withdraw() =>
+            {
+                let delay = policy.backoff(attempt);
+                tracing::warn!(%error, attempt, ?delay, "Retrying transient SurrealDB error");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+// endregion: -- retry
@@ -0,0 +1,87 @@
+use crate::error::Error;
+use crate::surreal::db::Transaction;
+use futures_core::future::BoxFuture;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- Step
+/// One unit of work in a [`Saga`]. Database writes are queued on `tx`, and
+/// only sent once every step has succeeded; anything done outside the
+/// database (webhooks, files, ...) must be undone in `compensate`.
+///
+/// `C` is the context threaded through every step, so later steps can use
+/// what earlier ones produced.
+pub trait Step<C>: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn action<'a>(
+        &'a self,
+        tx: &'a mut Transaction,
+        ctx: &'a mut C,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Only called for steps whose `action` succeeded. Defaults to doing
+    /// nothing, which is right for steps that only touch the database.
+    fn compensate<'a>(&'a self, _ctx: &'a mut C) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+// endregion: -- Step
+
+// region: -- Saga
+/// Runs its steps in order, queueing their writes on a single
+/// [`Transaction`]. If a step or the commit fails, nothing is written and the
+/// completed steps are compensated in reverse order before the original error
+/// is returned.
+pub struct Saga<C> {
+    name: &'static str,
+    steps: Vec<Box<dyn Step<C>>>,
+}
+
+impl<C: Send> Saga<C> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn step(mut self, step: impl Step<C> + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    #[tracing::instrument(name = "Saga", skip_all, fields(saga = self.name))]
+    pub async fn run(&self, conn: &Surreal<Client>, ctx: &mut C) -> Result<(), Error> {
+        let mut tx = Transaction::new(conn);
+
+        let mut completed = 0;
+        for step in &self.steps {
+            tracing::debug!(step = step.name(), "Running saga step");
+            if let Err(error) = step.action(&mut tx, ctx).await {
+                tracing::warn!(step = step.name(), %error, "Saga step failed");
+                self.compensate(completed, ctx).await;
+                return Err(error);
+            }
+            completed += 1;
+        }
+
+        if let Err(error) = tx.commit().await {
+            tracing::warn!(%error, "Saga commit failed");
+            self.compensate(completed, ctx).await;
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Compensation failures are logged rather than returned so that the
+    /// remaining steps still get their chance to clean up.
+    async fn compensate(&self, completed: usize, ctx: &mut C) {
+        for step in self.steps[..completed].iter().rev() {
+            if let Err(error) = step.compensate(ctx).await {
+                tracing::error!(step = step.name(), %error, "Saga compensation failed");
+            }
+        }
+    }
+}
+// endregion: -- Saga
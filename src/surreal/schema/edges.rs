@@ -0,0 +1,44 @@
+use crate::error::Error;
+use crate::surreal::edge::{EdgeEndpoints, EdgeSettings};
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::sql::escape_ident;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- Declarations
+/// `in` and `out` definitions for an edge table that only accept records of
+/// the configured tables that exist when the edge is written, whoever writes
+/// it.
+pub fn define_statements(edge: &str, endpoints: &EdgeEndpoints) -> [String; 2] {
+    let define = |field: &str, table: &str| {
+        format!(
+            "DEFINE FIELD {field} ON TABLE {} TYPE record({}) \
+             ASSERT $value != NONE AND $value.id != NONE;",
+            escape_ident(edge),
+            escape_ident(table)
+        )
+    };
+    [define("in", &endpoints.from), define("out", &endpoints.to)]
+}
+// endregion: -- Declarations
+
+// region: -- Sync
+/// Defines the `in`/`out` assertions of every allowed edge when
+/// `edges.enforce_in_schema` is on. Turning it off later leaves existing
+/// definitions in place; remove them with `REMOVE FIELD`.
+#[tracing::instrument(name = "Schema: Sync Edges", skip(db, settings))]
+pub async fn sync_edges(db: &Surreal<Client>, settings: &EdgeSettings) -> Result<(), Error> {
+    if !settings.enforce_in_schema {
+        return Ok(());
+    }
+    for (edge, endpoints) in &settings.allowed {
+        tracing::info!(edge, from = %endpoints.from, to = %endpoints.to, "Defining edge endpoints");
+        for sql in define_statements(edge, endpoints) {
+            traced(&sql, async {
+                db.query(&sql).bind(correlation()).await?.check()
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+// endregion: -- Sync
@@ -0,0 +1,59 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use serde::Serialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- Declarations
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FieldDefinition {
+    pub table: &'static str,
+    pub name: &'static str,
+    pub statement: &'static str,
+}
+
+/// Fields the database computes itself, so they hold whichever path wrote
+/// the record. Write DTOs leave them out; read DTOs carry them.
+pub const FIELDS: &[FieldDefinition] = &[
+    FieldDefinition {
+        table: "person",
+        name: "updated_at",
+        // `VALUE` is recomputed on every write, including `UPDATE ... CONTENT`.
+        statement: "DEFINE FIELD updated_at ON TABLE person VALUE time::now();",
+    },
+    FieldDefinition {
+        table: "person",
+        name: "age",
+        // A future is evaluated on every read, so the age stays current
+        // without rewriting the record. Whole years, `NONE` without a
+        // `date_of_birth`.
+        statement: "DEFINE FIELD age ON TABLE person VALUE <future> { \
+                    IF date_of_birth THEN \
+                    time::year(time::now()) - time::year(<datetime> date_of_birth) - \
+                    (IF time::month(time::now()) * 100 + time::day(time::now()) \
+                    < time::month(<datetime> date_of_birth) * 100 + time::day(<datetime> date_of_birth) \
+                    THEN 1 ELSE 0 END) \
+                    END };",
+    },
+    FieldDefinition {
+        table: "requests",
+        name: "at",
+        statement: "DEFINE FIELD at ON TABLE requests VALUE $before OR time::now();",
+    },
+];
+// endregion: -- Declarations
+
+// region: -- Sync
+/// `DEFINE FIELD` replaces any earlier definition, so every declared field is
+/// simply defined again.
+#[tracing::instrument(name = "Schema: Sync Fields", skip(db))]
+pub async fn sync_fields(db: &Surreal<Client>) -> Result<(), Error> {
+    for field in FIELDS {
+        tracing::debug!(table = field.table, field = field.name, "Defining field");
+        traced(field.statement, async {
+            db.query(field.statement).bind(correlation()).await?.check()
+        })
+        .await?;
+    }
+    Ok(())
+}
+// endregion: -- Sync
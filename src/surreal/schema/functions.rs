@@ -0,0 +1,195 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use crate::surreal::sql::escape_str;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Where the applied version of every function is kept, one record per
+/// function name.
+const SCHEMA_FUNCTIONS: &str = "schema_functions";
+
+// region: -- Declarations
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FunctionDefinition {
+    /// Without the `fn::` prefix.
+    pub name: &'static str,
+    /// Bump whenever `statement` changes so databases pick up the new body.
+    pub version: u32,
+    pub statement: &'static str,
+}
+
+pub const FUNCTIONS: &[FunctionDefinition] = &[FunctionDefinition {
+    name: "normalize_name",
+    version: 1,
+    statement: "DEFINE FUNCTION fn::normalize_name($name: string) {
+        RETURN string::lowercase(string::trim($name));
+    };",
+}];
+// endregion: -- Declarations
+
+// region: -- Sync
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FunctionSync {
+    /// Defined or redefined because the database had an older version.
+    pub applied: Vec<String>,
+    /// The database has a newer version than this build declares.
+    pub newer: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AppliedVersion {
+    name: String,
+    version: u32,
+}
+
+/// Defines every declared function whose stored version is missing or older,
+/// then records the new version. Functions the database has a newer version
+/// of are left alone, so an older build can't roll them back.
+#[tracing::instrument(name = "Schema: Sync Functions", skip(db))]
+pub async fn sync_functions(db: &Surreal<Client>) -> Result<FunctionSync, Error> {
+    let sql = format!("SELECT name, version FROM {SCHEMA_FUNCTIONS}");
+    let applied: Vec<AppliedVersion> = traced(&sql, async {
+        db.query(&sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
+    let applied: BTreeMap<String, u32> = applied
+        .into_iter()
+        .map(|function| (function.name, function.version))
+        .collect();
+
+    let mut sync = FunctionSync::default();
+    for function in FUNCTIONS {
+        match applied.get(function.name) {
+            Some(&version) if version == function.version => continue,
+            Some(&version) if version > function.version => {
+                tracing::warn!(
+                    function = function.name,
+                    declared = function.version,
+                    applied = version,
+                    "Database has a newer function version than this build"
+                );
+                sync.newer.push(function.name.to_string());
+                continue;
+            }
+            _ => {}
+        }
+
+        tracing::info!(
+            function = function.name,
+            version = function.version,
+            "Defining function"
+        );
+        traced(function.statement, async {
+            db.query(function.statement)
+                .bind(correlation())
+                .await?
+                .check()
+        })
+        .await?;
+        let sql = format!(
+            "UPDATE type::thing({}, $name) SET name = $name, version = $version",
+            escape_str(SCHEMA_FUNCTIONS)
+        );
+        traced(&sql, async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(("name", function.name))
+                .bind(("version", function.version))
+                .await?
+                .check()
+        })
+        .await?;
+        sync.applied.push(function.name.to_string());
+    }
+
+    Ok(sync)
+}
+// endregion: -- Sync
+
+// region: -- Calls
+/// Arguments of a custom function, bound as `$arg0`, `$arg1`, ...
+pub trait FunctionArgs {
+    /// Fails if an argument doesn't serialize to JSON, e.g. a map with
+    /// non-string keys, rather than binding `NULL` in its place.
+    fn into_values(self) -> Result<Vec<Value>, serde_json::Error>;
+}
+
+impl FunctionArgs for () {
+    fn into_values(self) -> Result<Vec<Value>, serde_json::Error> {
+        Ok(Vec::new())
+    }
+}
+
+macro_rules! function_args {
+    ($($arg:ident),+) => {
+        impl<$($arg: Serialize),+> FunctionArgs for ($($arg,)+) {
+            #[allow(non_snake_case)]
+            fn into_values(self) -> Result<Vec<Value>, serde_json::Error> {
+                let ($($arg,)+) = self;
+                Ok(vec![$(serde_json::to_value($arg)?),+])
+            }
+        }
+    };
+}
+
+function_args!(A);
+function_args!(A, B);
+function_args!(A, B, C);
+function_args!(A, B, C, D);
+
+/// A custom function with its argument and result types, e.g.
+/// `NORMALIZE_NAME.call(&db, ("  Jane ".to_string(),))`.
+pub struct Function<A, R> {
+    pub name: &'static str,
+    types: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> Function<A, R> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            types: PhantomData,
+        }
+    }
+
+    pub fn statement(&self, arity: usize) -> String {
+        let params: Vec<String> = (0..arity).map(|i| format!("$arg{i}")).collect();
+        format!("RETURN fn::{}({});", self.name, params.join(", "))
+    }
+}
+
+impl<A: FunctionArgs, R: DeserializeOwned> Function<A, R> {
+    #[tracing::instrument(name = "Query: Function", skip_all, fields(function = self.name))]
+    pub async fn call(&self, db: &Surreal<Client>, args: A) -> Result<R, Error> {
+        let args: BTreeMap<String, Value> = args
+            .into_values()
+            .map_err(|e| Error::InvalidQuery(format!("`fn::{}` arguments: {e}", self.name)))?
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| (format!("arg{i}"), value))
+            .collect();
+        let sql = self.statement(args.len());
+        let bindings = args
+            .iter()
+            .map(|(name, value)| Binding::new(name, value))
+            .collect();
+
+        let result: Option<R> = traced_with_bindings(&sql, bindings, async {
+            db.query(&sql)
+                .bind(correlation())
+                .bind(&args)
+                .await?
+                .take(0)
+        })
+        .await?;
+        result.ok_or(Error::Db)
+    }
+}
+
+pub const NORMALIZE_NAME: Function<(String,), String> = Function::new("normalize_name");
+// endregion: -- Calls
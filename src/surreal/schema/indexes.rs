@@ -0,0 +1,260 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- Declarations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IndexKind {
+    Standard,
+    Unique,
+    Search { analyzer: &'static str },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IndexDefinition {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub fields: &'static [&'static str],
+    pub kind: IndexKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AnalyzerDefinition {
+    pub name: &'static str,
+    pub statement: &'static str,
+}
+
+pub const ANALYZERS: &[AnalyzerDefinition] = &[AnalyzerDefinition {
+    name: "name_search",
+    statement: "DEFINE ANALYZER name_search TOKENIZERS class FILTERS ascii,lowercase;",
+}];
+
+pub const INDEXES: &[IndexDefinition] = &[
+    IndexDefinition {
+        name: "registration",
+        table: "registry",
+        fields: &["registration"],
+        kind: IndexKind::Unique,
+    },
+    // Not unique: people may share a name, and `GET /admin/duplicates`
+    // is where likely copies are found.
+    IndexDefinition {
+        name: "name",
+        table: "person",
+        fields: &["name"],
+        kind: IndexKind::Standard,
+    },
+    IndexDefinition {
+        name: "person_tags",
+        table: "person",
+        fields: &["tags"],
+        kind: IndexKind::Standard,
+    },
+    // `GET /people/stats` reads the latest `updated_at`.
+    IndexDefinition {
+        name: "person_updated_at",
+        table: "person",
+        fields: &["updated_at"],
+        kind: IndexKind::Standard,
+    },
+    IndexDefinition {
+        name: "person_history_version",
+        table: "person_history",
+        fields: &["record", "version"],
+        kind: IndexKind::Unique,
+    },
+    IndexDefinition {
+        name: "person_name_search",
+        table: "person",
+        fields: &["name"],
+        kind: IndexKind::Search {
+            analyzer: "name_search",
+        },
+    },
+];
+
+impl IndexDefinition {
+    pub fn define_statement(&self) -> String {
+        let suffix = match self.kind {
+            IndexKind::Standard => String::new(),
+            IndexKind::Unique => " UNIQUE".into(),
+            IndexKind::Search { analyzer } => format!(" SEARCH ANALYZER {analyzer} BM25"),
+        };
+        format!(
+            "DEFINE INDEX {} ON TABLE {} FIELDS {}{};",
+            self.name,
+            self.table,
+            self.fields.join(", "),
+            suffix
+        )
+    }
+
+    fn matches(&self, definition: &str) -> bool {
+        let definition = definition.to_uppercase();
+        let unique = definition.contains(" UNIQUE");
+        let search = definition.contains(" SEARCH");
+        match self.kind {
+            IndexKind::Standard => !unique && !search,
+            IndexKind::Unique => unique,
+            IndexKind::Search { .. } => search,
+        }
+    }
+}
+// endregion: -- Declarations
+
+// region: -- Drift
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexDrift {
+    /// Declared in code but absent from the database.
+    pub missing: Vec<String>,
+    /// Present in the database but not declared in code.
+    pub unknown: Vec<String>,
+    /// Present on both sides with a different kind (unique/search/standard).
+    pub mismatched: Vec<String>,
+}
+
+impl IndexDrift {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.unknown.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct TableInfo {
+    #[serde(alias = "indexes", default)]
+    ix: BTreeMap<String, String>,
+}
+
+async fn live_indexes(
+    db: &Surreal<Client>,
+    table: &str,
+) -> Result<BTreeMap<String, String>, Error> {
+    let sql = format!("INFO FOR TABLE {table};");
+    let info: Option<TableInfo> = traced(&sql, async {
+        db.query(&sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
+    Ok(info.map(|info| info.ix).unwrap_or_default())
+}
+
+#[tracing::instrument(name = "Schema: Index Drift", skip(db))]
+pub async fn detect_drift(db: &Surreal<Client>) -> Result<IndexDrift, Error> {
+    let mut live = BTreeMap::new();
+    for table in declared_tables() {
+        live.insert(table.to_string(), live_indexes(db, table).await?);
+    }
+    Ok(diff_indexes(&live))
+}
+
+/// Compares the declared [`INDEXES`] with `live`, the index definitions
+/// per table as `INFO FOR TABLE` reports them.
+pub fn diff_indexes(live: &BTreeMap<String, BTreeMap<String, String>>) -> IndexDrift {
+    let empty = BTreeMap::new();
+    let mut drift = IndexDrift::default();
+    for table in declared_tables() {
+        let live = live.get(table).unwrap_or(&empty);
+        let declared: Vec<&IndexDefinition> = INDEXES
+            .iter()
+            .filter(|index| index.table == table)
+            .collect();
+
+        for index in &declared {
+            match live.get(index.name) {
+                None => drift.missing.push(format!("{table}.{}", index.name)),
+                Some(definition) if !index.matches(definition) => {
+                    drift.mismatched.push(format!("{table}.{}", index.name))
+                }
+                Some(_) => {}
+            }
+        }
+        for name in live.keys() {
+            if !declared.iter().any(|index| index.name == name) {
+                drift.unknown.push(format!("{table}.{name}"));
+            }
+        }
+    }
+    drift
+}
+
+fn declared_tables() -> Vec<&'static str> {
+    let mut tables: Vec<&str> = INDEXES.iter().map(|index| index.table).collect();
+    tables.sort_unstable();
+    tables.dedup();
+    tables
+}
+
+/// Creates declared indexes that are missing and warns about everything else
+/// that differs. Existing indexes are never dropped or redefined here.
+#[tracing::instrument(name = "Schema: Sync Indexes", skip(db))]
+pub async fn sync_indexes(db: &Surreal<Client>) -> Result<IndexDrift, Error> {
+    let drift = detect_drift(db).await?;
+
+    if !drift.missing.is_empty() {
+        for analyzer in ANALYZERS {
+            traced(analyzer.statement, async {
+                db.query(analyzer.statement)
+                    .bind(correlation())
+                    .await?
+                    .check()
+            })
+            .await?;
+        }
+    }
+    for index in INDEXES.iter().filter(|index| {
+        drift
+            .missing
+            .contains(&format!("{}.{}", index.table, index.name))
+    }) {
+        let sql = index.define_statement();
+        tracing::info!(sql, "Creating missing index");
+        traced(&sql, async {
+            db.query(&sql).bind(correlation()).await?.check()
+        })
+        .await?;
+    }
+
+    for name in &drift.unknown {
+        tracing::warn!(index = %name, "Index exists in the database but is not declared in code");
+    }
+    for name in &drift.mismatched {
+        tracing::warn!(index = %name, "Index definition differs from its declaration");
+    }
+
+    Ok(drift)
+}
+// endregion: -- Drift
+
+// region: -- Violations
+/// Recognizes a unique index violation and returns the offending field and
+/// value. Remote engines only hand back the message, which reads
+/// "Database index `name` already contains 'John', with record `person:1`".
+pub fn index_violation(error: &surrealdb::Error) -> Option<(String, String)> {
+    let (index, value) = match error {
+        surrealdb::Error::Db(surrealdb::error::Db::IndexExists { index, value, .. }) => {
+            (index.clone(), value.clone())
+        }
+        other => parse_index_violation(&other.to_string())?,
+    };
+
+    let field = INDEXES
+        .iter()
+        .find(|declared| declared.name == index)
+        .map(|declared| declared.fields.join(","))
+        .unwrap_or(index);
+
+    Some((field, value))
+}
+
+fn parse_index_violation(message: &str) -> Option<(String, String)> {
+    let rest = message.split_once("Database index `")?.1;
+    let (index, rest) = rest.split_once('`')?;
+    let rest = rest.trim_start().strip_prefix("already contains ")?;
+    let value = rest
+        .split_once(", with record")
+        .map_or(rest, |(value, _)| value);
+
+    Some((index.to_string(), value.trim().to_string()))
+}
+// endregion: -- Violations
@@ -0,0 +1,289 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::schema::fields::FIELDS;
+use crate::surreal::schema::indexes::{diff_indexes, IndexDrift};
+use crate::surreal::schema::tables::{declared, TableMode};
+use crate::surreal::sql::escape_ident;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- Info
+/// `INFO FOR DB`, as far as the schema goes.
+#[derive(Deserialize, Debug, Default)]
+pub struct DbInfo {
+    #[serde(alias = "tables", default)]
+    pub tb: BTreeMap<String, String>,
+}
+
+/// `INFO FOR TABLE`: the definitions of the table's fields, indexes and
+/// events by name.
+#[derive(Deserialize, Debug, Default)]
+pub struct TableInfo {
+    #[serde(alias = "fields", default)]
+    pub fd: BTreeMap<String, String>,
+    #[serde(alias = "indexes", default)]
+    pub ix: BTreeMap<String, String>,
+    #[serde(alias = "events", default)]
+    pub ev: BTreeMap<String, String>,
+}
+// endregion: -- Info
+
+// region: -- LiveSchema
+/// The schema the database reports, with each definition taken apart and
+/// compared with the one in code.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LiveSchema {
+    pub tables: Vec<LiveTable>,
+    pub drift: SchemaDrift,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LiveTable {
+    pub name: String,
+    pub mode: TableMode,
+    pub fields: Vec<LiveField>,
+    pub indexes: Vec<LiveIndex>,
+    pub events: Vec<LiveEvent>,
+    /// The `DEFINE TABLE` statement as the database reports it.
+    pub definition: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LiveField {
+    pub name: String,
+    /// The `TYPE`, for fields that have one.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub definition: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LiveIndex {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub unique: bool,
+    pub search: bool,
+    pub definition: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LiveEvent {
+    pub name: String,
+    pub when: Option<String>,
+    pub then: Option<String>,
+    pub definition: String,
+}
+
+/// How the live schema differs from the code-defined one. Entries read
+/// `table` or `table.name`.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SchemaDrift {
+    /// Tables `schema.tables` configures that the database doesn't define.
+    pub missing_tables: Vec<String>,
+    /// Tables defined schemafull where `schema.tables` says schemaless, or
+    /// the other way round.
+    pub mismatched_tables: Vec<String>,
+    /// Fields of schemafull tables and computed fields the database
+    /// doesn't define.
+    pub missing_fields: Vec<String>,
+    /// Fields of schemafull tables defined with another type.
+    pub mismatched_fields: Vec<String>,
+    pub indexes: IndexDrift,
+}
+
+impl SchemaDrift {
+    pub fn is_clean(&self) -> bool {
+        self.missing_tables.is_empty()
+            && self.mismatched_tables.is_empty()
+            && self.missing_fields.is_empty()
+            && self.mismatched_fields.is_empty()
+            && self.indexes.is_clean()
+    }
+}
+
+impl LiveTable {
+    /// Takes apart the `definition` of table `name` and what `INFO FOR
+    /// TABLE` reported for it.
+    pub fn new(name: &str, definition: &str, info: TableInfo) -> Self {
+        let mode = if keyword(definition, "SCHEMAFULL").is_some() {
+            TableMode::Schemafull
+        } else {
+            TableMode::Schemaless
+        };
+        let fields = info
+            .fd
+            .into_iter()
+            .map(|(name, definition)| LiveField {
+                name,
+                kind: clause(&definition, "TYPE", &[])
+                    .and_then(|kind| kind.split_whitespace().next().map(Into::into)),
+                definition,
+            })
+            .collect();
+        let indexes = info
+            .ix
+            .into_iter()
+            .map(|(name, definition)| LiveIndex {
+                name,
+                fields: clause(&definition, "FIELDS", INDEX_STOPS)
+                    .or_else(|| clause(&definition, "COLUMNS", INDEX_STOPS))
+                    .map(|fields| fields.split(',').map(|f| f.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                unique: keyword(&definition, "UNIQUE").is_some(),
+                search: keyword(&definition, "SEARCH").is_some(),
+                definition,
+            })
+            .collect();
+        let events = info
+            .ev
+            .into_iter()
+            .map(|(name, definition)| LiveEvent {
+                name,
+                when: clause(&definition, "WHEN", &["THEN"]),
+                then: clause(&definition, "THEN", &[]),
+                definition,
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            mode,
+            fields,
+            indexes,
+            events,
+            definition: definition.into(),
+        }
+    }
+}
+
+/// Compares `tables` with the tables `schema.tables` configures, their
+/// declared fields, the computed [`FIELDS`] and the declared indexes.
+pub fn diff(tables: &[LiveTable], configured: &BTreeMap<String, TableMode>) -> SchemaDrift {
+    let live = |name: &str| tables.iter().find(|table| table.name == name);
+    let mut drift = SchemaDrift::default();
+
+    for (name, mode) in configured {
+        let Some(table) = live(name) else {
+            drift.missing_tables.push(name.clone());
+            continue;
+        };
+        if table.mode != *mode {
+            drift.mismatched_tables.push(name.clone());
+        }
+        if *mode == TableMode::Schemaless {
+            continue;
+        }
+        for (field, kind) in declared(name).map_or(&[][..], |definition| definition.fields) {
+            match table.fields.iter().find(|live| live.name == *field) {
+                None => drift.missing_fields.push(format!("{name}.{field}")),
+                Some(live) if live.kind.as_deref() != Some(*kind) => {
+                    drift.mismatched_fields.push(format!("{name}.{field}"))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    for field in FIELDS {
+        let defined = live(field.table)
+            .is_some_and(|table| table.fields.iter().any(|live| live.name == field.name));
+        if !defined {
+            drift
+                .missing_fields
+                .push(format!("{}.{}", field.table, field.name));
+        }
+    }
+
+    let indexes = tables
+        .iter()
+        .map(|table| {
+            let definitions = table
+                .indexes
+                .iter()
+                .map(|index| (index.name.clone(), index.definition.clone()))
+                .collect();
+            (table.name.clone(), definitions)
+        })
+        .collect();
+    drift.indexes = diff_indexes(&indexes);
+    drift
+}
+// endregion: -- LiveSchema
+
+// region: -- describe
+/// Reads every table's definition from the database `db` is using and
+/// compares it with `configured`, the `schema.tables` in effect.
+#[tracing::instrument(name = "Schema: Describe", skip(db, configured))]
+pub async fn describe(
+    db: &Surreal<Client>,
+    configured: &BTreeMap<String, TableMode>,
+) -> Result<LiveSchema, Error> {
+    let sql = "INFO FOR DB;";
+    let info: Option<DbInfo> = traced(sql, async {
+        db.query(sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
+
+    let mut tables = Vec::new();
+    for (name, definition) in info.unwrap_or_default().tb {
+        let sql = format!("INFO FOR TABLE {};", escape_ident(&name));
+        let table: Option<TableInfo> = traced(&sql, async {
+            db.query(&sql).bind(correlation()).await?.take(0)
+        })
+        .await?;
+        tables.push(LiveTable::new(
+            &name,
+            &definition,
+            table.unwrap_or_default(),
+        ));
+    }
+
+    let drift = diff(&tables, configured);
+    Ok(LiveSchema { tables, drift })
+}
+// endregion: -- describe
+
+// region: -- Parsing
+/// Where the field list of a `DEFINE INDEX` ends.
+const INDEX_STOPS: &[&str] = &["UNIQUE", "SEARCH", "MTREE", "COMMENT"];
+
+/// Where `word` stands on its own in `definition`, outside of strings.
+fn keyword(definition: &str, word: &str) -> Option<usize> {
+    let mut in_string = None;
+    let mut start = None;
+    for (i, c) in definition.char_indices().chain([(definition.len(), ' ')]) {
+        if let Some(quote) = in_string {
+            if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            start.get_or_insert(i);
+            continue;
+        }
+        if let Some(from) = start.take() {
+            if definition[from..i].eq_ignore_ascii_case(word) {
+                return Some(from);
+            }
+        }
+        if matches!(c, '\'' | '"') {
+            in_string = Some(c);
+        }
+    }
+    None
+}
+
+/// What follows `word` in `definition`, up to the first of `stops` or the
+/// end, without the trailing `;`.
+fn clause(definition: &str, word: &str, stops: &[&str]) -> Option<String> {
+    let start = keyword(definition, word)? + word.len();
+    let rest = &definition[start..];
+    let end = stops
+        .iter()
+        .filter_map(|stop| keyword(rest, stop))
+        .min()
+        .unwrap_or(rest.len());
+    let clause = rest[..end].trim().trim_end_matches(';').trim();
+    (!clause.is_empty()).then(|| clause.to_string())
+}
+// endregion: -- Parsing
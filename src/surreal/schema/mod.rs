@@ -0,0 +1,40 @@
+pub mod edges;
+pub mod fields;
+pub mod functions;
+pub mod indexes;
+pub mod live;
+pub mod permissions;
+pub mod tables;
+
+use crate::error::Error;
+use crate::surreal::edge::EdgeSettings;
+use crate::surreal::schema::functions::FunctionSync;
+use crate::surreal::schema::indexes::IndexDrift;
+use crate::surreal::schema::tables::SchemaSettings;
+use serde::Serialize;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// What [`apply`] found and changed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SchemaReport {
+    /// The drift found before syncing; `missing` indexes have since been
+    /// defined.
+    pub indexes: IndexDrift,
+    pub functions: FunctionSync,
+}
+
+#[tracing::instrument(name = "Schema: Apply", skip(db, tables, edges))]
+pub async fn apply(
+    db: &Surreal<Client>,
+    tables: &SchemaSettings,
+    edges: &EdgeSettings,
+) -> Result<SchemaReport, Error> {
+    // Tables first: `DEFINE TABLE` replaces the table's definition, not its
+    // fields or indexes.
+    tables::sync_tables(db, tables).await?;
+    let indexes = indexes::sync_indexes(db).await?;
+    let functions = functions::sync_functions(db).await?;
+    fields::sync_fields(db).await?;
+    edges::sync_edges(db, edges).await?;
+    Ok(SchemaReport { indexes, functions })
+}
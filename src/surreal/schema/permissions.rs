@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+// region: -- Declarations
+/// Who may act on a record. Only scope users are bound by permissions;
+/// `root`, namespace and database users may do anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Rule {
+    Full,
+    None,
+    /// Allowed when the SurrealQL condition holds for the record, e.g.
+    /// `id = $auth.id`.
+    Where(&'static str),
+}
+
+/// Scope users may only act on the record they signed in as.
+pub const OWNER: Rule = Rule::Where("id = $auth.id");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Permissions {
+    pub select: Rule,
+    pub create: Rule,
+    pub update: Rule,
+    /// Fields can't be deleted on their own, so fields ignore it.
+    pub delete: Rule,
+}
+
+impl Permissions {
+    pub const FULL: Self = Self {
+        select: Rule::Full,
+        create: Rule::Full,
+        update: Rule::Full,
+        delete: Rule::Full,
+    };
+
+    /// The `PERMISSIONS` clause of a `DEFINE TABLE`.
+    pub fn table_clause(&self) -> String {
+        clause(&[
+            ("select", self.select),
+            ("create", self.create),
+            ("update", self.update),
+            ("delete", self.delete),
+        ])
+    }
+
+    /// The `PERMISSIONS` clause of a `DEFINE FIELD`.
+    pub fn field_clause(&self) -> String {
+        clause(&[
+            ("select", self.select),
+            ("create", self.create),
+            ("update", self.update),
+        ])
+    }
+}
+
+/// The permissions on a table's records and on some of their fields.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TablePermissions {
+    pub table: &'static str,
+    pub permissions: Permissions,
+    pub fields: &'static [(&'static str, Permissions)],
+}
+
+pub const PERMISSIONS: &[TablePermissions] = &[
+    TablePermissions {
+        table: "person",
+        // Signed in as a person, a scope user sees everyone but only changes
+        // themselves. They may add people, as the API's `/person` routes do
+        // with their token under request sessions.
+        permissions: Permissions {
+            update: OWNER,
+            delete: OWNER,
+            ..Permissions::FULL
+        },
+        fields: &[(
+            "date_of_birth",
+            Permissions {
+                select: OWNER,
+                ..Permissions::FULL
+            },
+        )],
+    },
+    TablePermissions {
+        table: "person_history",
+        // Their updates keep history like anyone's, readable only by them.
+        permissions: Permissions {
+            select: Rule::Where("record = $auth.id"),
+            create: Rule::Where("record = $auth.id"),
+            update: Rule::None,
+            delete: Rule::None,
+        },
+        fields: &[],
+    },
+    TablePermissions {
+        table: "registry",
+        // Registries are managed with the application's connection.
+        permissions: Permissions {
+            create: Rule::None,
+            update: Rule::None,
+            delete: Rule::None,
+            ..Permissions::FULL
+        },
+        fields: &[],
+    },
+    TablePermissions {
+        table: "licenses",
+        // Everyone sees every license, so a delete's cascade or restrict
+        // check sees the edges it has to. Scope users only remove their own,
+        // as deleting themselves cascades to.
+        permissions: Permissions {
+            create: Rule::None,
+            update: Rule::None,
+            delete: Rule::Where("out = $auth.id"),
+            ..Permissions::FULL
+        },
+        fields: &[],
+    },
+];
+
+pub fn declared_permissions(table: &str) -> Option<&'static TablePermissions> {
+    PERMISSIONS.iter().find(|declared| declared.table == table)
+}
+
+fn clause(rules: &[(&str, Rule)]) -> String {
+    let rules: Vec<String> = rules
+        .iter()
+        .map(|(action, rule)| match rule {
+            Rule::Full => format!("FOR {action} FULL"),
+            Rule::None => format!("FOR {action} NONE"),
+            Rule::Where(condition) => format!("FOR {action} WHERE {condition}"),
+        })
+        .collect();
+    format!("PERMISSIONS {}", rules.join(" "))
+}
+// endregion: -- Declarations
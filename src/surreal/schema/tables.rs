@@ -0,0 +1,214 @@
+use crate::error::Error;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::schema::fields::FIELDS;
+use crate::surreal::schema::permissions::{declared_permissions, PERMISSIONS};
+use crate::surreal::sql::escape_ident;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// region: -- SchemaSettings
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TableMode {
+    /// Any field may be written.
+    #[default]
+    Schemaless,
+    /// Only the [`TABLES`] fields may be written; the database drops any
+    /// other, so the API refuses them first.
+    Schemafull,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaSettings {
+    /// Mode per table. Tables left out aren't defined by the app.
+    pub tables: BTreeMap<String, TableMode>,
+}
+
+impl SchemaSettings {
+    /// Problems found without connecting, for [`crate::config::Settings::validate`].
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (table, mode) in &self.tables {
+            if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                problems.push(format!(
+                    "`schema.tables.{table}` must name a table made of letters, digits and `_`"
+                ));
+            } else if *mode == TableMode::Schemafull && declared(table).is_none() {
+                problems.push(format!(
+                    "`schema.tables.{table}` can't be schemafull: the app declares no fields for it"
+                ));
+            }
+        }
+        problems
+    }
+}
+// endregion: -- SchemaSettings
+
+// region: -- Declarations
+/// The fields a table is written with, and their types.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TableDefinition {
+    pub table: &'static str,
+    pub fields: &'static [(&'static str, &'static str)],
+}
+
+pub const TABLES: &[TableDefinition] = &[
+    TableDefinition {
+        table: "person",
+        // `date_of_birth` is an ISO 8601 date, e.g. `1990-05-01`.
+        fields: &[
+            ("name", "string"),
+            ("date_of_birth", "option<string>"),
+            ("tags", "option<array<string>>"),
+        ],
+    },
+    TableDefinition {
+        table: "registry",
+        fields: &[("registration", "int")],
+    },
+];
+
+pub(crate) fn declared(table: &str) -> Option<&'static TableDefinition> {
+    TABLES.iter().find(|definition| definition.table == table)
+}
+
+/// Every field a record of `table` can be read with: `id`, the declared
+/// fields and the computed [`FIELDS`].
+pub fn known_fields(table: &str) -> Vec<&'static str> {
+    let declared = declared(table).map_or(&[][..], |definition| definition.fields);
+    std::iter::once("id")
+        .chain(declared.iter().map(|(name, _)| *name))
+        .chain(FIELDS.iter().filter(|f| f.table == table).map(|f| f.name))
+        .collect()
+}
+
+/// `DEFINE TABLE` for `table`, followed by its field definitions when it is
+/// schemafull, each with their declared [`PERMISSIONS`]. Fields with
+/// permissions are defined in either mode, typed only when schemafull.
+/// Computed [`FIELDS`] are defined by their own sync.
+pub fn define_statements(table: &str, mode: TableMode) -> Vec<String> {
+    let permissions = declared_permissions(table);
+    let table_clause = permissions.map_or(String::new(), |declared| {
+        format!(" {}", declared.permissions.table_clause())
+    });
+    let field_permissions = permissions.map_or(&[][..], |declared| declared.fields);
+    let permitted = |field: &str| {
+        field_permissions
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, permissions)| permissions)
+    };
+    let define_field = |field: &str, kind: Option<&str>| {
+        let kind = kind.map_or(String::new(), |kind| format!(" TYPE {kind}"));
+        let clause = permitted(field).map_or(String::new(), |permissions| {
+            format!(" {}", permissions.field_clause())
+        });
+        format!(
+            "DEFINE FIELD {field} ON TABLE {}{kind}{clause};",
+            escape_ident(table)
+        )
+    };
+
+    match mode {
+        TableMode::Schemaless => std::iter::once(format!(
+            "DEFINE TABLE {} SCHEMALESS{table_clause};",
+            escape_ident(table)
+        ))
+        .chain(
+            field_permissions
+                .iter()
+                .map(|(field, _)| define_field(field, None)),
+        )
+        .collect(),
+        TableMode::Schemafull => {
+            let fields = declared(table).map_or(&[][..], |definition| definition.fields);
+            std::iter::once(format!(
+                "DEFINE TABLE {} SCHEMAFULL{table_clause};",
+                escape_ident(table)
+            ))
+            .chain(
+                fields
+                    .iter()
+                    .map(|(field, kind)| define_field(field, Some(kind))),
+            )
+            .collect()
+        }
+    }
+}
+// endregion: -- Declarations
+
+// region: -- Sync
+/// Defines every configured table in its mode, and every table with
+/// declared [`PERMISSIONS`] schemaless unless configured otherwise.
+/// `DEFINE TABLE` replaces the earlier definition, so switching a table back
+/// to schemaless takes effect on the next start; its field definitions stay.
+#[tracing::instrument(name = "Schema: Sync Tables", skip(db, settings))]
+pub async fn sync_tables(db: &Surreal<Client>, settings: &SchemaSettings) -> Result<(), Error> {
+    let mut tables = settings.tables.clone();
+    for declared in PERMISSIONS {
+        tables.entry(declared.table.to_string()).or_default();
+    }
+    for (table, mode) in &tables {
+        tracing::info!(table, ?mode, "Defining table");
+        for sql in define_statements(table, *mode) {
+            traced(&sql, async {
+                db.query(&sql).bind(correlation()).await?.check()
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+// endregion: -- Sync
+
+// region: -- SchemaGuard
+/// Refuses writes with fields a schemafull table doesn't define, with a `422`
+/// naming the field, instead of letting the database drop them.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaGuard {
+    tables: Arc<BTreeMap<String, TableMode>>,
+}
+
+impl SchemaGuard {
+    pub fn new(settings: &SchemaSettings) -> Self {
+        Self {
+            tables: Arc::new(settings.tables.clone()),
+        }
+    }
+
+    /// The configured tables and their modes.
+    pub fn tables(&self) -> &BTreeMap<String, TableMode> {
+        &self.tables
+    }
+
+    pub fn mode(&self, table: &str) -> TableMode {
+        self.tables.get(table).copied().unwrap_or_default()
+    }
+
+    /// Checks a record, or each record of an array, about to be written to
+    /// `table`.
+    pub fn check(&self, table: &str, body: &Value) -> Result<(), Error> {
+        if self.mode(table) == TableMode::Schemaless {
+            return Ok(());
+        }
+        match body {
+            Value::Array(records) => records.iter().try_for_each(|r| self.check(table, r)),
+            Value::Object(record) => match record.keys().find(|field| !allows(table, field)) {
+                Some(field) => Err(Error::UnknownField {
+                    table: table.to_string(),
+                    field: field.clone(),
+                }),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn allows(table: &str, field: &str) -> bool {
+    known_fields(table).contains(&field)
+}
+// endregion: -- SchemaGuard
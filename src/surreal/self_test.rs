@@ -0,0 +1,247 @@
+use crate::surreal::db::{connect, signin, DatabaseSettings};
+use crate::surreal::instrument::{correlation, traced};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Instant;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use uuid::Uuid;
+
+/// Probe records are written here and deleted again.
+pub const SCRATCH_TABLE: &str = "self_test";
+/// Every check, in the order they run. Each one needs the ones before it.
+pub const CHECKS: [&str; 6] = [
+    "connect",
+    "signin",
+    "create",
+    "read",
+    "delete",
+    "transaction",
+];
+
+// region: -- SelfTestReport
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier check failed.
+    Skipped,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub elapsed_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    /// Runs `check` unless an earlier one failed. Returns whether it passed.
+    pub async fn check<F>(&mut self, name: &'static str, check: F) -> bool
+    where
+        F: Future<Output = Result<(), String>>,
+    {
+        if self.failed() {
+            self.checks.push(CheckResult {
+                name,
+                status: CheckStatus::Skipped,
+                elapsed_ms: 0,
+                detail: None,
+            });
+            return false;
+        }
+
+        let start = Instant::now();
+        let outcome = check.await;
+        let elapsed_ms = start.elapsed().as_millis();
+        match &outcome {
+            Ok(()) => tracing::info!(check = name, elapsed_ms, "Self-test check passed"),
+            Err(detail) => tracing::error!(check = name, %detail, "Self-test check failed"),
+        }
+        self.checks.push(CheckResult {
+            name,
+            status: if outcome.is_ok() {
+                CheckStatus::Passed
+            } else {
+                CheckStatus::Failed
+            },
+            elapsed_ms,
+            detail: outcome.err(),
+        });
+        self.checks
+            .last()
+            .is_some_and(|c| c.status == CheckStatus::Passed)
+    }
+
+    fn failed(&self) -> bool {
+        self.checks.iter().any(|c| c.status != CheckStatus::Passed)
+    }
+
+    /// Marks whatever didn't get to run as skipped and settles `passed`.
+    pub fn finish(mut self) -> Self {
+        for name in CHECKS {
+            if !self.checks.iter().any(|c| c.name == name) {
+                self.checks.push(CheckResult {
+                    name,
+                    status: CheckStatus::Skipped,
+                    elapsed_ms: 0,
+                    detail: None,
+                });
+            }
+        }
+        self.passed = !self.failed();
+        self
+    }
+}
+// endregion: -- SelfTestReport
+
+// region: -- Self-test
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Probe {
+    token: String,
+}
+
+/// Exercises what the app can't serve without, against the configured
+/// database and with its credentials: connecting, signing in, writing,
+/// reading and deleting a probe record in [`SCRATCH_TABLE`], and rolling a
+/// transaction back. For deployment smoke tests; see `--self-test`.
+#[tracing::instrument(name = "Self-Test", skip(configuration))]
+pub async fn self_test(configuration: &DatabaseSettings) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    let mut client = None;
+    report
+        .check("connect", async {
+            client = Some(connect(configuration).await.map_err(|e| format!("{e:#}"))?);
+            Ok(())
+        })
+        .await;
+    let Some(client) = client else {
+        return report.finish();
+    };
+
+    report
+        .check("signin", async {
+            signin(&client, configuration)
+                .await
+                .map_err(|e| format!("signin as a {:?} user: {e}", configuration.auth))?;
+            client
+                .use_ns(&configuration.namespace)
+                .use_db(&configuration.database)
+                .await
+                .map_err(|e| {
+                    format!(
+                        "use {}/{}: {e}",
+                        configuration.namespace, configuration.database
+                    )
+                })
+        })
+        .await;
+
+    let probe = Probe {
+        token: Uuid::new_v4().simple().to_string(),
+    };
+    let rolled_back = Uuid::new_v4().simple().to_string();
+    report.check("create", create(&client, &probe)).await;
+    report.check("read", read(&client, &probe)).await;
+    report.check("delete", delete(&client, &probe)).await;
+    report
+        .check("transaction", transaction(&client, &rolled_back))
+        .await;
+
+    // Leave nothing behind, whichever check stopped the run.
+    if report.failed() {
+        for id in [&probe.token, &rolled_back] {
+            let _: surrealdb::Result<Option<Probe>> =
+                client.delete((SCRATCH_TABLE, id.as_str())).await;
+        }
+    }
+    report.finish()
+}
+
+async fn create(client: &Surreal<Client>, probe: &Probe) -> Result<(), String> {
+    let created: Option<Probe> = traced("CREATE self_test:? CONTENT $probe", async {
+        client
+            .create((SCRATCH_TABLE, probe.token.as_str()))
+            .content(probe)
+            .await
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match created {
+        Some(created) if created == *probe => Ok(()),
+        other => Err(format!("CREATE returned {other:?}")),
+    }
+}
+
+async fn read(client: &Surreal<Client>, probe: &Probe) -> Result<(), String> {
+    let read: Option<Probe> = traced("SELECT * FROM self_test:?", async {
+        client.select((SCRATCH_TABLE, probe.token.as_str())).await
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match read {
+        Some(read) if read == *probe => Ok(()),
+        other => Err(format!("SELECT returned {other:?}")),
+    }
+}
+
+async fn delete(client: &Surreal<Client>, probe: &Probe) -> Result<(), String> {
+    let _: Option<Probe> = traced("DELETE self_test:?", async {
+        client.delete((SCRATCH_TABLE, probe.token.as_str())).await
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    match exists(client, &probe.token).await? {
+        false => Ok(()),
+        true => Err("the probe record is still there after DELETE".into()),
+    }
+}
+
+/// A write inside a cancelled transaction must not be kept.
+async fn transaction(client: &Surreal<Client>, id: &str) -> Result<(), String> {
+    let sql = "BEGIN TRANSACTION; \
+               CREATE type::thing($table, $id) CONTENT { token: $id }; \
+               CANCEL TRANSACTION;";
+    let mut response = traced(sql, async {
+        client
+            .query(sql)
+            .bind(correlation())
+            .bind(("table", SCRATCH_TABLE))
+            .bind(("id", id))
+            .await
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    // Cancelling reports the `CREATE` as not executed; anything else failed
+    // it before the cancel was reached.
+    if let Some(error) = response
+        .take_errors()
+        .into_values()
+        .find(|error| !error.to_string().contains("cancelled transaction"))
+    {
+        return Err(error.to_string());
+    }
+    match exists(client, id).await? {
+        false => Ok(()),
+        true => Err("a write from a cancelled transaction was kept".into()),
+    }
+}
+
+async fn exists(client: &Surreal<Client>, id: &str) -> Result<bool, String> {
+    let found: Option<Probe> = traced("SELECT * FROM self_test:?", async {
+        client.select((SCRATCH_TABLE, id)).await
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(found.is_some())
+}
+// endregion: -- Self-test
@@ -0,0 +1,268 @@
+use crate::surreal::connection::CONNECTION;
+use crate::surreal::db::{signin, DatabaseSettings};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use surrealdb::error::Db;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+// Remote engines report an expired or revoked session as a string.
+const AUTH_MESSAGES: [&str; 3] = [
+    "problem with authentication",
+    "token has expired",
+    "session has expired",
+];
+
+pub static SESSION: Lazy<SessionMonitor> =
+    Lazy::new(|| SessionMonitor::new(&SessionSettings::default()));
+
+// region: -- SessionSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionSettings {
+    /// Re-signins allowed within `window_secs` before the breaker opens.
+    pub max_reauths: u32,
+    pub window_secs: u64,
+    /// How long an open breaker stops re-signins.
+    pub cooldown_secs: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            max_reauths: 3,
+            window_secs: 60,
+            cooldown_secs: 300,
+        }
+    }
+}
+// endregion: -- SessionSettings
+
+// region: -- ReauthBreaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Allowed,
+    /// This attempt opened the breaker.
+    Tripped,
+    Open,
+}
+
+/// Stops re-signing in when it keeps happening: a session that expires again
+/// right after every sign-in means something other than token expiry is wrong,
+/// and hammering the server with sign-ins won't fix it.
+#[derive(Debug, Default)]
+pub struct ReauthBreaker {
+    recent: VecDeque<Instant>,
+    open_until: Option<Instant>,
+}
+
+impl ReauthBreaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a re-signin may run at `now`. The attempt past `max_reauths`
+    /// within the window opens the breaker for `cooldown_secs` instead.
+    pub fn admit(&mut self, now: Instant, settings: &SessionSettings) -> Admission {
+        if let Some(until) = self.open_until {
+            if now < until {
+                return Admission::Open;
+            }
+            self.open_until = None;
+            self.recent.clear();
+        }
+
+        let window = Duration::from_secs(settings.window_secs);
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) >= window)
+        {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= settings.max_reauths as usize {
+            self.open_until = Some(now + Duration::from_secs(settings.cooldown_secs));
+            return Admission::Tripped;
+        }
+
+        self.recent.push_back(now);
+        Admission::Allowed
+    }
+
+    pub fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| now < until)
+    }
+}
+// endregion: -- ReauthBreaker
+
+// region: -- SessionMonitor
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionMetrics {
+    pub auth_errors: u64,
+    pub reauths: u64,
+    pub reauth_failures: u64,
+    pub breaker_trips: u64,
+    pub breaker_open: bool,
+    pub last_reauth: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    breaker: ReauthBreaker,
+    last_success: Option<Instant>,
+    last_reauth: Option<DateTime<Utc>>,
+}
+
+/// Signs the registered client in again when a query fails because its
+/// session expired. Concurrent failures share one re-signin, and
+/// [`ReauthBreaker`] gives up when re-signins keep failing or keep being
+/// needed.
+pub struct SessionMonitor {
+    max_reauths: AtomicU32,
+    window_secs: AtomicU64,
+    cooldown_secs: AtomicU64,
+    session: RwLock<Option<(Surreal<Client>, DatabaseSettings)>>,
+    state: tokio::sync::Mutex<SessionState>,
+    auth_errors: AtomicU64,
+    reauths: AtomicU64,
+    failures: AtomicU64,
+    trips: AtomicU64,
+}
+
+impl SessionMonitor {
+    pub fn new(settings: &SessionSettings) -> Self {
+        Self {
+            max_reauths: AtomicU32::new(settings.max_reauths),
+            window_secs: AtomicU64::new(settings.window_secs),
+            cooldown_secs: AtomicU64::new(settings.cooldown_secs),
+            session: RwLock::new(None),
+            state: tokio::sync::Mutex::new(SessionState::default()),
+            auth_errors: AtomicU64::new(0),
+            reauths: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            trips: AtomicU64::new(0),
+        }
+    }
+
+    pub fn configure(&self, settings: &SessionSettings) {
+        self.max_reauths
+            .store(settings.max_reauths, Ordering::Relaxed);
+        self.window_secs
+            .store(settings.window_secs, Ordering::Relaxed);
+        self.cooldown_secs
+            .store(settings.cooldown_secs, Ordering::Relaxed);
+    }
+
+    pub fn settings(&self) -> SessionSettings {
+        SessionSettings {
+            max_reauths: self.max_reauths.load(Ordering::Relaxed),
+            window_secs: self.window_secs.load(Ordering::Relaxed),
+            cooldown_secs: self.cooldown_secs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The client to sign in again, and the credentials to do it with. The
+    /// latest registration wins.
+    pub fn register(&self, client: Surreal<Client>, configuration: &DatabaseSettings) {
+        *self.session.write().unwrap() = Some((client, configuration.clone()));
+    }
+
+    /// Signs in to `namespace`/`database` from now on, e.g. once the client
+    /// has been switched to a restored database.
+    pub fn use_scope(&self, namespace: &str, database: &str) {
+        if let Some((_, configuration)) = self.session.write().unwrap().as_mut() {
+            configuration.namespace = namespace.to_string();
+            configuration.database = database.to_string();
+        }
+    }
+
+    /// The registered client, for background work that needs one.
+    pub fn client(&self) -> Option<Surreal<Client>> {
+        let session = self.session.read().unwrap();
+        session.as_ref().map(|(client, _)| client.clone())
+    }
+
+    /// `failed_at` is when the query that hit the auth error started. Returns
+    /// whether the session is usable again, either because this call signed in
+    /// or because another one did after that query started.
+    pub async fn recover(&self, failed_at: Instant) -> bool {
+        self.auth_errors.fetch_add(1, Ordering::Relaxed);
+        let Some((client, configuration)) = self.session.read().unwrap().clone() else {
+            return false;
+        };
+
+        let mut state = self.state.lock().await;
+        if state.last_success.is_some_and(|at| at >= failed_at) {
+            return true;
+        }
+        match state.breaker.admit(Instant::now(), &self.settings()) {
+            Admission::Allowed => {}
+            Admission::Tripped => {
+                self.trips.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(
+                    settings = ?self.settings(),
+                    "SurrealDB session keeps expiring; pausing re-signin"
+                );
+                return false;
+            }
+            Admission::Open => return false,
+        }
+
+        let signed_in = async {
+            signin(&client, &configuration).await?;
+            client
+                .use_ns(&configuration.namespace)
+                .use_db(&configuration.database)
+                .await
+        };
+        match signed_in.await {
+            Ok(()) => {
+                self.reauths.fetch_add(1, Ordering::Relaxed);
+                state.last_success = Some(Instant::now());
+                state.last_reauth = Some(Utc::now());
+                CONNECTION.set_connected(true);
+                tracing::info!("Signed in to SurrealDB again after an authentication error");
+                true
+            }
+            Err(error) => {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(%error, "Failed to sign in to SurrealDB again");
+                false
+            }
+        }
+    }
+
+    pub async fn metrics(&self) -> SessionMetrics {
+        let state = self.state.lock().await;
+        SessionMetrics {
+            auth_errors: self.auth_errors.load(Ordering::Relaxed),
+            reauths: self.reauths.load(Ordering::Relaxed),
+            reauth_failures: self.failures.load(Ordering::Relaxed),
+            breaker_trips: self.trips.load(Ordering::Relaxed),
+            breaker_open: state.breaker.is_open(Instant::now()),
+            last_reauth: state.last_reauth,
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionMonitor")
+            .field("settings", &self.settings())
+            .finish_non_exhaustive()
+    }
+}
+
+pub fn is_auth_error(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(Db::InvalidAuth) => true,
+        other => {
+            let message = other.to_string().to_lowercase();
+            AUTH_MESSAGES.iter().any(|m| message.contains(m))
+        }
+    }
+}
+// endregion: -- SessionMonitor
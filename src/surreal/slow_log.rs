@@ -0,0 +1,165 @@
+use crate::api::current_request_id;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub static SLOW_QUERIES: Lazy<SlowQueryLog> =
+    Lazy::new(|| SlowQueryLog::new(&SlowQuerySettings::default()));
+
+// region: -- SlowQuerySettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SlowQuerySettings {
+    pub threshold_ms: u64,
+    pub capacity: usize,
+    /// Also log the `EXPLAIN` plan of slow `SELECT`s.
+    #[serde(default)]
+    pub explain: bool,
+}
+
+impl Default for SlowQuerySettings {
+    fn default() -> Self {
+        Self {
+            threshold_ms: 100,
+            capacity: 100,
+            explain: false,
+        }
+    }
+}
+// endregion: -- SlowQuerySettings
+
+// region: -- Binding
+/// Metadata about a bound parameter. The value itself is never kept, only its
+/// name and JSON kind.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: &'static str,
+}
+
+impl Binding {
+    pub fn new(name: impl Into<String>, value: &impl Serialize) -> Self {
+        let kind = match serde_json::to_value(value) {
+            Ok(serde_json::Value::Null) => "null",
+            Ok(serde_json::Value::Bool(_)) => "bool",
+            Ok(serde_json::Value::Number(_)) => "number",
+            Ok(serde_json::Value::String(_)) => "string",
+            Ok(serde_json::Value::Array(_)) => "array",
+            Ok(serde_json::Value::Object(_)) => "object",
+            Err(_) => "unknown",
+        };
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+// endregion: -- Binding
+
+// region: -- SlowQueryLog
+#[derive(Serialize, Debug, Clone)]
+pub struct SlowQuery {
+    pub fingerprint: String,
+    pub duration_ms: f64,
+    pub bindings: Vec<Binding>,
+    pub call_site: String,
+    /// The request it ran for, also bound as `$correlation_id`.
+    pub request_id: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Fixed-size ring buffer of the most recent queries that ran longer than the
+/// configured threshold.
+#[derive(Debug)]
+pub struct SlowQueryLog {
+    threshold_ms: AtomicU64,
+    capacity: AtomicUsize,
+    explain: AtomicBool,
+    entries: Mutex<VecDeque<SlowQuery>>,
+}
+
+impl SlowQueryLog {
+    pub fn new(settings: &SlowQuerySettings) -> Self {
+        Self {
+            threshold_ms: AtomicU64::new(settings.threshold_ms),
+            capacity: AtomicUsize::new(settings.capacity),
+            explain: AtomicBool::new(settings.explain),
+            entries: Mutex::new(VecDeque::with_capacity(settings.capacity)),
+        }
+    }
+
+    pub fn configure(&self, settings: &SlowQuerySettings) {
+        self.threshold_ms
+            .store(settings.threshold_ms, Ordering::Relaxed);
+        self.capacity.store(settings.capacity, Ordering::Relaxed);
+        self.explain.store(settings.explain, Ordering::Relaxed);
+
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() > settings.capacity {
+            entries.pop_front();
+        }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        Duration::from_millis(self.threshold_ms.load(Ordering::Relaxed))
+    }
+
+    pub fn explains(&self) -> bool {
+        self.explain.load(Ordering::Relaxed)
+    }
+
+    /// Records the query if it exceeded the threshold. Returns whether it did.
+    pub fn observe(
+        &self,
+        fingerprint: &str,
+        elapsed: Duration,
+        bindings: &[Binding],
+        call_site: &Location<'_>,
+    ) -> bool {
+        if elapsed < self.threshold() {
+            return false;
+        }
+
+        let entry = SlowQuery {
+            fingerprint: fingerprint.to_string(),
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            bindings: bindings.to_vec(),
+            call_site: call_site.to_string(),
+            request_id: current_request_id(),
+            recorded_at: Utc::now(),
+        };
+        tracing::warn!(
+            fingerprint = %entry.fingerprint,
+            duration_ms = entry.duration_ms,
+            call_site = %entry.call_site,
+            request_id = entry.request_id.as_deref(),
+            "Slow SurrealDB query"
+        );
+
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return true;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() >= capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+
+        true
+    }
+
+    /// Most recent first.
+    pub fn entries(&self) -> Vec<SlowQuery> {
+        self.entries.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+// endregion: -- SlowQueryLog
@@ -0,0 +1,255 @@
+use crate::error::Error;
+use crate::surreal::admin::Scope;
+use crate::surreal::instrument::{correlation, traced};
+use crate::surreal::query_manager::StatementKind;
+use crate::surreal::sql::escape_ident;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use surrealdb::sql::statements::DefineStatement;
+use surrealdb::sql::Statement;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Follows each table's records with how many there were, so a restore
+/// can be checked against them: `-- count 12 person`.
+const COUNT_COMMENT: &str = "-- count ";
+
+// region: -- Snapshot
+/// A portable `.surql` dump of one database: every record, followed by its
+/// table, field, index, event and function definitions, all in one
+/// transaction. The client's native `export`/`import` isn't available over
+/// WebSocket, so records are read table by table and written back out as
+/// `INSERT` (or `RELATE`, for edges) statements. Edge tables come after the
+/// tables they connect, and the definitions after every record, so restoring
+/// doesn't stamp `VALUE` fields again, fire events or check assertions on
+/// records that are only being copied.
+#[derive(Serialize, Debug, Clone)]
+pub struct Snapshot {
+    pub scope: Scope,
+    pub tables: usize,
+    pub records: usize,
+    #[serde(skip)]
+    pub surql: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub scope: Scope,
+    pub statements: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct DbInfo {
+    #[serde(alias = "tables", default)]
+    tb: BTreeMap<String, String>,
+    #[serde(alias = "functions", default)]
+    r#fn: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct TableInfo {
+    #[serde(alias = "fields", default)]
+    fd: BTreeMap<String, String>,
+    #[serde(alias = "indexes", default)]
+    ix: BTreeMap<String, String>,
+    #[serde(alias = "events", default)]
+    ev: BTreeMap<String, String>,
+}
+
+/// One record rendered as SurrealQL by the database itself, so ids, links
+/// and datetimes round-trip exactly. `from`/`to` are `NONE` unless the
+/// record is an edge.
+#[derive(Deserialize, Debug)]
+struct ExportedRecord {
+    record: String,
+    from: String,
+    to: String,
+}
+
+impl ExportedRecord {
+    fn is_edge(&self) -> bool {
+        self.from != "NONE" && self.to != "NONE"
+    }
+}
+// endregion: -- Snapshot
+
+// region: -- export
+/// Dumps the database `db` is currently using. `scope` only labels the dump.
+#[tracing::instrument(name = "Snapshot: Export", skip(db))]
+pub async fn export(db: &Surreal<Client>, scope: Scope) -> Result<Snapshot, Error> {
+    let sql = "INFO FOR DB;";
+    let info: Option<DbInfo> = traced(sql, async {
+        db.query(sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
+    let info = info.unwrap_or_default();
+
+    let mut surql = format!(
+        "-- snapshot of {}/{} taken {}\nBEGIN TRANSACTION;\n",
+        scope.namespace,
+        scope.database,
+        Utc::now().to_rfc3339()
+    );
+
+    let mut tables = Vec::new();
+    for table in info.tb.keys() {
+        let sql = format!("INFO FOR TABLE {};", escape_ident(table));
+        let table_info: Option<TableInfo> = traced(&sql, async {
+            db.query(&sql).bind(correlation()).await?.take(0)
+        })
+        .await?;
+        let table_info = table_info.unwrap_or_default();
+
+        let sql = "SELECT <string> $this AS record, <string> $this.in AS from, \
+                   <string> $this.out AS to FROM type::table($table)";
+        let rows: Vec<ExportedRecord> = traced(sql, async {
+            db.query(sql)
+                .bind(correlation())
+                .bind(("table", table))
+                .await?
+                .take(0)
+        })
+        .await?;
+        tables.push((table, table_info, rows));
+    }
+    // Edges after the records they connect; the sort keeps the rest in order.
+    tables.sort_by_key(|(_, _, rows)| rows.iter().any(ExportedRecord::is_edge));
+
+    let mut records = 0;
+    for (table, _, rows) in &tables {
+        records += rows.len();
+        for row in rows {
+            push_statement(&mut surql, &record_statement(table, row));
+        }
+        surql.push_str(&format!("{COUNT_COMMENT}{} {table}\n", rows.len()));
+    }
+    for definition in info.tb.values().chain(info.r#fn.values()) {
+        push_statement(&mut surql, definition);
+    }
+    for (_, table_info, _) in &tables {
+        for definition in table_info
+            .fd
+            .values()
+            .chain(table_info.ix.values())
+            .chain(table_info.ev.values())
+        {
+            push_statement(&mut surql, definition);
+        }
+    }
+    surql.push_str("COMMIT TRANSACTION;\n");
+
+    Ok(Snapshot {
+        scope,
+        tables: info.tb.len(),
+        records,
+        surql,
+    })
+}
+
+fn record_statement(table: &str, row: &ExportedRecord) -> String {
+    if row.is_edge() {
+        format!(
+            "RELATE {}->{}->{} CONTENT {}",
+            row.from,
+            escape_ident(table),
+            row.to,
+            row.record
+        )
+    } else {
+        format!("INSERT INTO {} {}", escape_ident(table), row.record)
+    }
+}
+
+fn push_statement(surql: &mut String, statement: &str) {
+    surql.push_str(statement.trim().trim_end_matches(';'));
+    surql.push_str(";\n");
+}
+
+// endregion: -- export
+
+// region: -- counts
+/// Records per table as noted in a snapshot. Snapshots taken before the
+/// counts were noted give none.
+pub fn expected_counts(surql: &str) -> BTreeMap<String, usize> {
+    surql
+        .lines()
+        .filter_map(|line| {
+            let (count, table) = line.strip_prefix(COUNT_COMMENT)?.split_once(' ')?;
+            Some((table.to_string(), count.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Records per table in the database `db` is currently using.
+#[tracing::instrument(name = "Snapshot: Count Tables", skip(db))]
+pub async fn table_counts(db: &Surreal<Client>) -> Result<BTreeMap<String, usize>, Error> {
+    let sql = "INFO FOR DB;";
+    let info: Option<DbInfo> = traced(sql, async {
+        db.query(sql).bind(correlation()).await?.take(0)
+    })
+    .await?;
+
+    let mut counts = BTreeMap::new();
+    for table in info.unwrap_or_default().tb.into_keys() {
+        let sql = "SELECT count() FROM type::table($table) GROUP ALL";
+        let count: Option<usize> = traced(sql, async {
+            db.query(sql)
+                .bind(correlation())
+                .bind(("table", &table))
+                .await?
+                .take((0, "count"))
+        })
+        .await?;
+        counts.insert(table, count.unwrap_or(0));
+    }
+    Ok(counts)
+}
+// endregion: -- counts
+
+// region: -- restore
+/// Parses a snapshot and refuses it unless every statement is one [`export`]
+/// writes itself: the transaction around it, table, field, index, event and
+/// function definitions, and records. An upload can't switch scope, remove
+/// namespaces or databases, or define users, tokens and scopes that way.
+pub fn check_restorable(surql: &str) -> Result<(), Error> {
+    let query =
+        surrealdb::sql::parse(surql).map_err(|error| Error::QuerySyntax(error.to_string()))?;
+    for statement in query.iter() {
+        let kind = StatementKind::of(statement);
+        let allowed = match statement {
+            Statement::Define(definition) => matches!(
+                definition,
+                DefineStatement::Table(_)
+                    | DefineStatement::Field(_)
+                    | DefineStatement::Index(_)
+                    | DefineStatement::Event(_)
+                    | DefineStatement::Function(_)
+            ),
+            _ => matches!(
+                kind,
+                StatementKind::Begin
+                    | StatementKind::Commit
+                    | StatementKind::Insert
+                    | StatementKind::Create
+                    | StatementKind::Relate
+            ),
+        };
+        if !allowed {
+            return Err(Error::StatementNotAllowed(kind.keyword()));
+        }
+    }
+    Ok(())
+}
+
+/// Runs a snapshot against the database `db` is currently using and returns
+/// how many statements it ran. Restore into an empty database: a record that
+/// already exists fails the transaction and nothing is written.
+#[tracing::instrument(name = "Snapshot: Restore", skip(db, surql))]
+pub async fn restore(db: &Surreal<Client>, surql: &str) -> Result<usize, Error> {
+    let response = traced("-- snapshot restore", async {
+        db.query(surql).bind(correlation()).await?.check()
+    })
+    .await?;
+    Ok(response.num_statements())
+}
+// endregion: -- restore
@@ -0,0 +1,46 @@
+// region: -- escape
+// For SurrealQL built with `format!`. Values should be bound as parameters
+// wherever the statement allows it; these are for what can't be, like table
+// names, and for statements written out as text, like snapshots.
+
+/// `s` as a single-quoted SurrealQL string literal. Backslashes and quotes
+/// are escaped, so nothing in `s` can end the literal early.
+pub fn escape_str(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('\'');
+    for c in s.chars() {
+        if c == '\\' || c == '\'' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('\'');
+    escaped
+}
+
+/// `name` as a SurrealQL identifier: as is when it is letters, digits and
+/// `_` with at least one non-digit, otherwise in backticks with backslashes
+/// and backticks escaped.
+pub fn escape_ident(name: &str) -> String {
+    if is_plain_ident(name) {
+        return name.to_string();
+    }
+    let mut escaped = String::with_capacity(name.len() + 2);
+    escaped.push('`');
+    for c in name.chars() {
+        if c == '\\' || c == '`' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('`');
+    escaped
+}
+
+/// Whether `name` can be written without backticks. All digits would read as
+/// a number.
+fn is_plain_ident(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.chars().any(|c| !c.is_ascii_digit())
+}
+// endregion: -- escape
@@ -0,0 +1,47 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+tokio::task_local! {
+    static REQUEST_STATS: Arc<QueryStats>;
+}
+
+// region: -- QueryStats
+/// Query count and cumulative database time for a single request.
+#[derive(Debug, Default)]
+pub struct QueryStats {
+    count: AtomicU64,
+    micros: AtomicU64,
+}
+
+impl QueryStats {
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.micros.load(Ordering::Relaxed))
+    }
+
+    fn add(&self, elapsed: Duration) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+// endregion: -- QueryStats
+
+// region: -- scope
+/// Runs `operation` with `stats` collecting every query it issues. Queries on
+/// tasks spawned from inside `operation` are not counted.
+pub async fn collect<F: Future>(stats: Arc<QueryStats>, operation: F) -> F::Output {
+    REQUEST_STATS.scope(stats, operation).await
+}
+
+/// Adds a finished query to the current request's stats, if anyone is
+/// collecting them.
+pub fn record(elapsed: Duration) {
+    let _ = REQUEST_STATS.try_with(|stats| stats.add(elapsed));
+}
+// endregion: -- scope
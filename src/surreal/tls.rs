@@ -0,0 +1,192 @@
+use color_eyre::eyre::{bail, eyre, Context};
+use color_eyre::Result;
+use rustls::client::ResolvesClientCert;
+use rustls::sign::{any_supported_type, CertifiedKey};
+use rustls::{
+    Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+// region: -- DatabaseTlsSettings
+/// Extra TLS for the `Wss` connection, for deployments that run SurrealDB
+/// behind their own CA or require client certificates.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatabaseTlsSettings {
+    /// PEM bundle of the CAs to trust for the server's certificate, instead
+    /// of the public web roots.
+    pub ca_file: Option<PathBuf>,
+    /// PEM certificate chain presented to the server. Needs `key_file`.
+    pub cert_file: Option<PathBuf>,
+    /// PEM private key for `cert_file`.
+    pub key_file: Option<PathBuf>,
+}
+
+impl DatabaseTlsSettings {
+    pub fn is_configured(&self) -> bool {
+        self.ca_file.is_some() || self.cert_file.is_some() || self.key_file.is_some()
+    }
+
+    /// Problems found without connecting, for [`crate::config::Settings::validate`].
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.cert_file.is_some() != self.key_file.is_some() {
+            problems.push(
+                "`database.tls.cert_file` and `database.tls.key_file` must be set together".into(),
+            );
+        }
+        for (name, path) in [
+            ("database.tls.ca_file", &self.ca_file),
+            ("database.tls.cert_file", &self.cert_file),
+            ("database.tls.key_file", &self.key_file),
+        ] {
+            if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+                problems.push(format!("`{name}` ({}) is not a file", path.display()));
+            }
+        }
+        problems
+    }
+
+    pub fn client_config(&self) -> Result<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        match &self.ca_file {
+            Some(ca_file) => {
+                for cert in read_certs(ca_file)? {
+                    roots
+                        .add(&cert)
+                        .with_context(|| format!("Invalid CA in {}", ca_file.display()))?;
+                }
+            }
+            None => roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            })),
+        }
+
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let config = match (&self.cert_file, &self.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                let resolver = ReloadingClientCert::new(cert_file, key_file)?;
+                builder.with_client_cert_resolver(Arc::new(resolver))
+            }
+            _ => builder.with_no_client_auth(),
+        };
+        Ok(config)
+    }
+}
+// endregion: -- DatabaseTlsSettings
+
+// region: -- ReloadingClientCert
+/// Presents the client certificate currently on disk. The files are checked
+/// at every handshake and re-read when they change, so a rotated certificate
+/// is used from the next (re)connection on without restarting. If the new
+/// files don't load, the previous certificate keeps being used.
+pub struct ReloadingClientCert {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    loaded: RwLock<(Option<SystemTime>, Arc<CertifiedKey>)>,
+}
+
+impl ReloadingClientCert {
+    /// Fails if the files don't hold a usable certificate and key right now.
+    pub fn new(cert_file: &Path, key_file: &Path) -> Result<Self> {
+        let modified = last_modified(cert_file, key_file);
+        let key = load_certified_key(cert_file, key_file)?;
+        Ok(Self {
+            cert_file: cert_file.to_path_buf(),
+            key_file: key_file.to_path_buf(),
+            loaded: RwLock::new((modified, Arc::new(key))),
+        })
+    }
+
+    /// The certificate to present, re-read first if the files changed.
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        let modified = last_modified(&self.cert_file, &self.key_file);
+        {
+            let loaded = self.loaded.read().unwrap();
+            if loaded.0 == modified {
+                return loaded.1.clone();
+            }
+        }
+
+        let mut loaded = self.loaded.write().unwrap();
+        match load_certified_key(&self.cert_file, &self.key_file) {
+            Ok(key) => {
+                tracing::info!(
+                    cert_file = %self.cert_file.display(),
+                    "Loaded rotated database client certificate"
+                );
+                *loaded = (modified, Arc::new(key));
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Keeping the previous database client certificate");
+                // Don't retry until the files change again.
+                loaded.0 = modified;
+            }
+        }
+        loaded.1.clone()
+    }
+}
+
+impl ResolvesClientCert for ReloadingClientCert {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// The later of the two files' modification times.
+fn last_modified(cert_file: &Path, key_file: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    modified(cert_file).max(modified(key_file))
+}
+
+fn load_certified_key(cert_file: &Path, key_file: &Path) -> Result<CertifiedKey> {
+    let certs = read_certs(cert_file)?;
+    if certs.is_empty() {
+        bail!("No certificates in {}", cert_file.display());
+    }
+    let key = read_private_key(key_file)?;
+    let key = any_supported_type(&key)
+        .map_err(|_| eyre!("Unsupported private key type in {}", key_file.display()))?;
+    Ok(CertifiedKey::new(certs, key))
+}
+
+fn read_certs(path: &Path) -> Result<Vec<Certificate>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .with_context(|| format!("Invalid PEM in {}", path.display()))?;
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKey> {
+    use rustls_pemfile::Item;
+
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let items = rustls_pemfile::read_all(&mut BufReader::new(file))
+        .with_context(|| format!("Invalid PEM in {}", path.display()))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| eyre!("No private key in {}", path.display()))
+}
+// endregion: -- ReloadingClientCert
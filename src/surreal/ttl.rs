@@ -0,0 +1,312 @@
+use crate::api::MAINTENANCE;
+use crate::error::Error;
+use crate::surreal::count::COUNTS;
+use crate::surreal::instrument::{correlation, traced_with_bindings};
+use crate::surreal::slow_log::Binding;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+pub static TTL: Lazy<TtlJob> = Lazy::new(|| TtlJob::new(&TtlSettings::default()));
+
+// region: -- TtlSettings
+/// One table's time to live: records whose `field` is older than
+/// `older_than` are deleted.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TtlRule {
+    pub table: String,
+    /// The datetime field a record's age is taken from, e.g. `replaced_at`.
+    pub field: String,
+    /// A number and a unit: `s`, `m`, `h`, `d` or `w`, e.g. `90d`.
+    pub older_than: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TtlSettings {
+    pub enabled: bool,
+    /// Time between runs.
+    pub interval_secs: u64,
+    /// Records deleted per statement.
+    pub batch_size: usize,
+    /// Statements per rule and run; whatever is left waits for the next run.
+    pub max_batches: usize,
+    pub rules: Vec<TtlRule>,
+}
+
+impl Default for TtlSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 3600,
+            batch_size: 500,
+            max_batches: 100,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl TtlSettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.interval_secs == 0 || self.batch_size == 0 || self.max_batches == 0 {
+            problems.push(
+                "`ttl.interval_secs`, `ttl.batch_size` and `ttl.max_batches` must be at least 1"
+                    .into(),
+            );
+        }
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !is_identifier(&rule.table) || !is_identifier(&rule.field) {
+                problems.push(format!(
+                    "`ttl.rules[{i}]` must name a table and field made of letters, digits and `_`"
+                ));
+            }
+            if parse_age(&rule.older_than).is_none_or(|age| age.is_zero()) {
+                problems.push(format!(
+                    "`ttl.rules[{i}].older_than` ({}) must be a number and a unit, e.g. `90d`",
+                    rule.older_than
+                ));
+            }
+            if self.rules[..i].iter().any(|r| r.table == rule.table) {
+                problems.push(format!(
+                    "`ttl.rules` has more than one rule for `{}`",
+                    rule.table
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// `90d` and the like: a whole number and one of `s`, `m`, `h`, `d` or `w`.
+pub fn parse_age(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit_at = value.len().checked_sub(1)?;
+    if !value.is_char_boundary(unit_at) {
+        return None;
+    }
+    let (amount, unit) = value.split_at(unit_at);
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(secs).map(Duration::from_secs)
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+// endregion: -- TtlSettings
+
+// region: -- TtlJob
+/// What one rule's runs have done so far.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleProgress {
+    pub table: String,
+    pub runs: u64,
+    pub deleted_total: u64,
+    /// Whether a run is working through the rule now; `batches` and
+    /// `deleted` then count the run so far.
+    pub running: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub batches: usize,
+    pub deleted: usize,
+    /// The run stopped at `max_batches` with expired records left.
+    pub backlog: bool,
+    pub last_error: Option<String>,
+}
+
+/// Deletes expired records in bounded batches, every `interval_secs` and on
+/// `POST /admin/ttl/run`. Runs are skipped while the service is read-only.
+/// Rules are read at the start of each run, so a reload applies from the
+/// next one.
+#[derive(Debug)]
+pub struct TtlJob {
+    settings: RwLock<TtlSettings>,
+    progress: Mutex<BTreeMap<String, RuleProgress>>,
+    run: tokio::sync::Mutex<()>,
+}
+
+impl TtlJob {
+    pub fn new(settings: &TtlSettings) -> Self {
+        Self {
+            settings: RwLock::new(settings.clone()),
+            progress: Mutex::new(BTreeMap::new()),
+            run: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn configure(&self, settings: &TtlSettings) {
+        *self.settings.write().unwrap() = settings.clone();
+    }
+
+    pub fn settings(&self) -> TtlSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Progress for every configured rule, including ones not run yet.
+    pub fn progress(&self) -> Vec<RuleProgress> {
+        let progress = self.progress.lock().unwrap();
+        self.settings()
+            .rules
+            .iter()
+            .map(|rule| {
+                progress.get(&rule.table).cloned().unwrap_or(RuleProgress {
+                    table: rule.table.clone(),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Runs every rule once, one after the other. A run already going is
+    /// waited for rather than overlapped.
+    pub async fn run(&self, db: &Surreal<Client>) -> Vec<RuleProgress> {
+        let _running = self.run.lock().await;
+        let settings = self.settings();
+        if MAINTENANCE.is_read_only() {
+            tracing::debug!("Skipping the TTL run while read-only");
+            return self.progress();
+        }
+        for rule in &settings.rules {
+            self.run_rule(db, rule, &settings).await;
+        }
+        self.progress()
+    }
+
+    async fn run_rule(&self, db: &Surreal<Client>, rule: &TtlRule, settings: &TtlSettings) {
+        let Some(age) = parse_age(&rule.older_than) else {
+            return;
+        };
+        let cutoff = Utc::now() - chrono::Duration::from_std(age).unwrap_or_default();
+        self.update(rule, |progress| {
+            progress.running = true;
+            progress.last_run_at = Some(Utc::now());
+            progress.batches = 0;
+            progress.deleted = 0;
+            progress.backlog = false;
+            progress.last_error = None;
+        });
+
+        let mut error = None;
+        for batch in 1..=settings.max_batches {
+            match delete_expired(db, rule, cutoff, settings.batch_size).await {
+                Ok(deleted) => {
+                    self.update(rule, |progress| {
+                        progress.batches = batch;
+                        progress.deleted += deleted;
+                        progress.deleted_total += deleted as u64;
+                        progress.backlog =
+                            batch == settings.max_batches && deleted == settings.batch_size;
+                    });
+                    if deleted < settings.batch_size {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        let progress = self.update(rule, |progress| {
+            progress.running = false;
+            progress.runs += 1;
+            progress.last_error = error.clone();
+        });
+        match error {
+            Some(error) => {
+                tracing::warn!(table = %rule.table, %error, "Failed to delete expired records")
+            }
+            None if progress.deleted > 0 => tracing::info!(
+                table = %rule.table,
+                deleted = progress.deleted,
+                batches = progress.batches,
+                backlog = progress.backlog,
+                "Deleted expired records"
+            ),
+            None => {}
+        }
+    }
+
+    fn update(&self, rule: &TtlRule, change: impl FnOnce(&mut RuleProgress)) -> RuleProgress {
+        let mut progress = self.progress.lock().unwrap();
+        let entry = progress
+            .entry(rule.table.clone())
+            .or_insert_with(|| RuleProgress {
+                table: rule.table.clone(),
+                ..Default::default()
+            });
+        change(entry);
+        entry.clone()
+    }
+}
+
+/// Runs [`TtlJob::run`] every `ttl.interval_secs`, as set at the time, while
+/// `ttl.enabled`.
+pub fn spawn(db: Surreal<Client>) {
+    tokio::spawn(async move {
+        loop {
+            let settings = TTL.settings();
+            tokio::time::sleep(Duration::from_secs(settings.interval_secs.max(1))).await;
+            if TTL.settings().enabled {
+                TTL.run(&db).await;
+            }
+        }
+    });
+}
+// endregion: -- TtlJob
+
+// region: -- Query
+/// Deletes up to `limit` records of `rule.table` whose `rule.field` is
+/// before `cutoff`, dropping the table's cached counts if any went. Returns
+/// how many went.
+#[tracing::instrument(name = "Query: Delete Expired", skip(db))]
+pub async fn delete_expired(
+    db: &Surreal<Client>,
+    rule: &TtlRule,
+    cutoff: DateTime<Utc>,
+    limit: usize,
+) -> Result<usize, Error> {
+    // The field was checked to be an identifier when the settings loaded.
+    let sql = format!(
+        "LET $expired = (SELECT VALUE id FROM type::table($table) \
+         WHERE {field} < <datetime> $cutoff LIMIT $limit); \
+         DELETE $expired RETURN id;",
+        field = rule.field
+    );
+    let cutoff = cutoff.to_rfc3339();
+    let bindings = vec![
+        Binding::new("table", &rule.table),
+        Binding::new("cutoff", &cutoff),
+        Binding::new("limit", &limit),
+    ];
+    let deleted: Vec<Thing> = traced_with_bindings(&sql, bindings, async {
+        db.query(&sql)
+            .bind(correlation())
+            .bind(("table", &rule.table))
+            .bind(("cutoff", &cutoff))
+            .bind(("limit", limit))
+            .await?
+            .take((1, "id"))
+    })
+    .await?;
+    if !deleted.is_empty() {
+        COUNTS.invalidate(&rule.table);
+    }
+    Ok(deleted.len())
+}
+// endregion: -- Query
@@ -0,0 +1,51 @@
+use crate::error::Error;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+/// Server versions the queries in this crate are written against.
+pub const SUPPORTED_VERSIONS: &str = ">=1.0.0-beta.9, <2.0.0";
+
+// region: -- VersionSettings
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct VersionSettings {
+    /// Refuse to start on an unsupported server instead of only warning.
+    pub enforce: bool,
+}
+
+impl Default for VersionSettings {
+    fn default() -> Self {
+        Self { enforce: true }
+    }
+}
+// endregion: -- VersionSettings
+
+// region: -- check
+pub fn is_supported(version: &Version) -> bool {
+    VersionReq::parse(SUPPORTED_VERSIONS)
+        .expect("SUPPORTED_VERSIONS is a valid requirement")
+        .matches(version)
+}
+
+#[tracing::instrument(name = "Checking SurrealDB version", skip(client))]
+pub async fn check_version(
+    client: &Surreal<Client>,
+    settings: &VersionSettings,
+) -> Result<Version, Error> {
+    let version = client.version().await?;
+    if is_supported(&version) {
+        tracing::info!(%version, "Connected to SurrealDB");
+        return Ok(version);
+    }
+
+    if settings.enforce {
+        return Err(Error::UnsupportedVersion(version.to_string()));
+    }
+    tracing::warn!(
+        %version,
+        supported = SUPPORTED_VERSIONS,
+        "!!! UNSUPPORTED SURREALDB VERSION: queries may fail or misbehave !!!"
+    );
+    Ok(version)
+}
+// endregion: -- check
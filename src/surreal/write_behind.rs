@@ -0,0 +1,303 @@
+use crate::error::Error;
+use crate::surreal::db::Transaction;
+use crate::surreal::history::{keep_previous, MISSING};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use tokio::sync::{oneshot, Notify};
+
+// region: -- WriteBehindSettings
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct WriteBehindSettings {
+    /// Off, every write runs on its own as it arrives.
+    pub enabled: bool,
+    /// Writes in one transaction at most; a full buffer is flushed at once.
+    pub flush_size: usize,
+    /// How long the first buffered write waits for others to join it.
+    pub flush_interval_ms: u64,
+    /// Reads of a record with a write still in the buffer see that write
+    /// instead of the database.
+    pub read_your_writes: bool,
+}
+
+impl Default for WriteBehindSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            flush_size: 64,
+            flush_interval_ms: 10,
+            read_your_writes: true,
+        }
+    }
+}
+
+impl WriteBehindSettings {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.flush_size == 0 {
+            problems.push("`write_behind.flush_size` must be at least 1".into());
+        }
+        if !(1..=1000).contains(&self.flush_interval_ms) {
+            problems.push("`write_behind.flush_interval_ms` must be between 1 and 1000".into());
+        }
+        problems
+    }
+}
+// endregion: -- WriteBehindSettings
+
+// region: -- WriteBehind
+/// The writes the buffer takes.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteKind {
+    Create,
+    /// `UPDATE … CONTENT` with its `RETURN` clause. Like
+    /// [`update_with_history`], it keeps the version it replaces and fails
+    /// on a missing record, the read for both made in the batch itself.
+    ///
+    /// [`update_with_history`]: crate::surreal::history::update_with_history
+    Update {
+        returning: &'static str,
+        actor: Option<String>,
+    },
+}
+
+impl WriteKind {
+    /// The statements of write `i` of a batch, its record and data bound as
+    /// `$record_{i}` and `$data_{i}`. The write itself comes last.
+    fn statements(&self, i: usize, record: &Thing) -> Vec<String> {
+        match self {
+            Self::Create => vec![format!("CREATE $record_{i} CONTENT $data_{i} RETURN NONE")],
+            Self::Update { returning, .. } => {
+                let mut statements = keep_previous(&record.tb, &format!("_{i}"));
+                statements.push(format!("UPDATE $record_{i} CONTENT $data_{i} {returning}"));
+                statements
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PendingWrite {
+    record: Thing,
+    kind: WriteKind,
+    data: Value,
+    reply: oneshot::Sender<Result<Option<Value>, Error>>,
+}
+
+/// Coalesces single-record creates and updates arriving close together into
+/// one transaction: the first write waits up to `flush_interval_ms` for
+/// others, or less once `flush_size` have gathered. Each caller still waits
+/// for its own write to commit, so nothing is acknowledged that could be
+/// lost; bursts just cost one round trip instead of one each.
+///
+/// When the transaction fails, its writes are run again one at a time, so
+/// only the write at fault gets the error.
+#[derive(Clone, Debug)]
+pub struct WriteBehind {
+    inner: Arc<Buffer>,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    settings: WriteBehindSettings,
+    queue: Mutex<Vec<PendingWrite>>,
+    /// The latest data written to each record with writes outstanding, and
+    /// how many there are.
+    buffered: Mutex<HashMap<String, (usize, Value)>>,
+    arrived: Notify,
+    full: Notify,
+}
+
+/// Takes a write's record out of the buffer once its caller is done with it,
+/// even if the caller stopped waiting.
+struct Settle<'a> {
+    buffer: &'a Buffer,
+    key: String,
+}
+
+impl Drop for Settle<'_> {
+    fn drop(&mut self) {
+        let mut buffered = self.buffer.buffered.lock().unwrap();
+        if let Some((outstanding, _)) = buffered.get_mut(&self.key) {
+            *outstanding -= 1;
+            if *outstanding == 0 {
+                buffered.remove(&self.key);
+            }
+        }
+    }
+}
+
+impl WriteBehind {
+    /// A buffer nothing flushes yet; see [`WriteBehind::spawn`].
+    pub fn new(settings: &WriteBehindSettings) -> Self {
+        Self {
+            inner: Arc::new(Buffer {
+                settings: settings.clone(),
+                queue: Mutex::new(Vec::new()),
+                buffered: Mutex::new(HashMap::new()),
+                arrived: Notify::new(),
+                full: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.settings.enabled
+    }
+
+    /// Writes waiting for the next flush.
+    pub fn queued(&self) -> usize {
+        self.inner.queue.lock().unwrap().len()
+    }
+
+    /// What the buffer holds for `record`, with `read_your_writes` on.
+    pub fn pending(&self, record: &Thing) -> Option<Value> {
+        if !self.inner.settings.read_your_writes {
+            return None;
+        }
+        let buffered = self.inner.buffered.lock().unwrap();
+        buffered
+            .get(&record.to_string())
+            .map(|(_, data)| data.clone())
+    }
+
+    /// Buffers the write and waits until the transaction holding it is done.
+    /// Updates give back what their `RETURN` clause asks for; creates give
+    /// nothing.
+    pub async fn write(
+        &self,
+        record: Thing,
+        kind: WriteKind,
+        data: Value,
+    ) -> Result<Option<Value>, Error> {
+        let buffer = &*self.inner;
+        let key = record.to_string();
+        {
+            let mut buffered = buffer.buffered.lock().unwrap();
+            let entry = buffered.entry(key.clone()).or_insert((0, Value::Null));
+            entry.0 += 1;
+            entry.1 = data.clone();
+        }
+        let _settle = Settle { buffer, key };
+
+        let (reply, done) = oneshot::channel();
+        let queued = {
+            let mut queue = buffer.queue.lock().unwrap();
+            queue.push(PendingWrite {
+                record,
+                kind,
+                data,
+                reply,
+            });
+            queue.len()
+        };
+        if queued == 1 {
+            buffer.arrived.notify_one();
+        }
+        if queued >= buffer.settings.flush_size {
+            buffer.full.notify_one();
+        }
+        done.await.unwrap_or(Err(Error::Db))
+    }
+
+    /// Flushes the buffer on `db` until the process exits.
+    pub fn spawn(&self, db: Surreal<Client>) {
+        let buffer = self.inner.clone();
+        tokio::spawn(async move {
+            let latency = Duration::from_millis(buffer.settings.flush_interval_ms);
+            loop {
+                let queued = buffer.queue.lock().unwrap().len();
+                if queued == 0 {
+                    buffer.arrived.notified().await;
+                }
+                if queued < buffer.settings.flush_size {
+                    let _ = tokio::time::timeout(latency, buffer.full.notified()).await;
+                }
+                let batch = {
+                    let mut queue = buffer.queue.lock().unwrap();
+                    let size = queue.len().min(buffer.settings.flush_size);
+                    queue.drain(..size).collect::<Vec<_>>()
+                };
+                if !batch.is_empty() {
+                    flush(&db, batch).await;
+                }
+            }
+        });
+    }
+}
+
+/// `BEGIN`, the statements of each write with its record and data bound as
+/// `$record_{i}` and `$data_{i}`, and `COMMIT`.
+pub fn batch_statement(writes: &[(&Thing, &WriteKind)]) -> String {
+    let mut sql = String::from("BEGIN TRANSACTION;\n");
+    for (i, (record, kind)) in writes.iter().enumerate() {
+        for statement in kind.statements(i, record) {
+            sql.push_str(&statement);
+            sql.push_str(";\n");
+        }
+    }
+    sql.push_str("COMMIT TRANSACTION;");
+    sql
+}
+
+#[tracing::instrument(name = "Query: Write Behind", skip_all, fields(writes = batch.len()))]
+async fn flush(db: &Surreal<Client>, batch: Vec<PendingWrite>) {
+    if batch.len() > 1 {
+        match run(db, &batch).await {
+            Ok(results) => {
+                for (write, result) in batch.into_iter().zip(results) {
+                    let _ = write.reply.send(Ok(result));
+                }
+                return;
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Write-behind batch failed; running its writes one by one")
+            }
+        }
+    }
+    for write in batch {
+        let result = run(db, std::slice::from_ref(&write))
+            .await
+            .map(|mut results| results.remove(0));
+        let _ = write.reply.send(result);
+    }
+}
+
+/// Sends the writes as one [`Transaction`] and gives back each one's result.
+/// An update of a missing record fails it with [`Error::NotFound`].
+async fn run(db: &Surreal<Client>, writes: &[PendingWrite]) -> Result<Vec<Option<Value>>, Error> {
+    let mut transaction = Transaction::new(db);
+    let mut written = Vec::with_capacity(writes.len());
+    for (i, write) in writes.iter().enumerate() {
+        transaction
+            .bind(format!("record_{i}"), write.record.clone())
+            .bind(format!("data_{i}"), write.data.clone());
+        if let WriteKind::Update { actor, .. } = &write.kind {
+            transaction.bind(format!("actor_{i}"), actor.clone());
+        }
+        let mut index = 0;
+        for statement in write.kind.statements(i, &write.record) {
+            index = transaction.query(statement);
+        }
+        written.push(index);
+    }
+
+    let mut response = match transaction.commit().await {
+        Ok(response) => response,
+        Err(Error::Aborted(reason)) if reason == MISSING => {
+            let records: Vec<String> = writes.iter().map(|w| w.record.to_string()).collect();
+            return Err(Error::NotFound(records.join(", ")));
+        }
+        Err(error) => return Err(error),
+    };
+    written
+        .into_iter()
+        .map(|index| Ok(response.take(index)?))
+        .collect()
+}
+// endregion: -- WriteBehind
@@ -1,8 +1,17 @@
+use crate::api::auth::Principal;
+use crate::api::REQUEST_ID;
+use axum::http::Request;
+use once_cell::sync::OnceCell;
 use tracing::subscriber::set_global_default;
-use tracing::Subscriber;
+use tracing::{field, Span, Subscriber};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
-use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, EnvFilter, Registry};
+use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, reload, EnvFilter, Registry};
+
+/// Lets the filter be swapped at runtime, see [`set_log_level`].
+static LOG_FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+/// The filter the subscriber started with, see [`reset_log_level`].
+static STARTUP_FILTER: OnceCell<String> = OnceCell::new();
 
 // region: -- Tracing: Initialize
 pub fn get_subscriber<Sink>(
@@ -15,6 +24,11 @@ where
 {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
+    // Only the first subscriber is installed globally, so only its filter is
+    // worth reloading.
+    let _ = STARTUP_FILTER.set(env_filter.to_string());
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let _ = LOG_FILTER.set(handle);
     let formatting_layer = BunyanFormattingLayer::new(name, sink);
 
     Registry::default()
@@ -27,4 +41,50 @@ pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
     LogTracer::init().expect("Failed to set logger.");
     set_global_default(subscriber).expect("Failed to set subscriber.");
 }
+
+/// Replaces the log filter, e.g. `"debug"` or `"info,surreal_simple=trace"`.
+pub fn set_log_level(filter: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(filter).map_err(|e| e.to_string())?;
+    match LOG_FILTER.get() {
+        Some(handle) => handle.reload(filter).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Goes back to the filter the subscriber started with, `RUST_LOG` or the
+/// default, once `log_level` is unset.
+pub fn reset_log_level() -> Result<(), String> {
+    match STARTUP_FILTER.get() {
+        Some(filter) => set_log_level(filter),
+        None => Ok(()),
+    }
+}
 // endregion: --- Tracing: Initialize
+
+// region: -- Tracing: Request span
+/// The span every request runs in. `user_id` and `tenant` stay empty until
+/// [`record_principal`] fills them in after the token was validated; the
+/// token itself is never recorded.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        uri = %request.uri(),
+        user_id = field::Empty,
+        tenant = field::Empty,
+    )
+}
+
+pub fn record_principal(span: &Span, principal: &Principal) {
+    span.record("user_id", principal.user_id.as_str());
+    if let Some(tenant) = &principal.tenant {
+        span.record("tenant", tenant.as_str());
+    }
+}
+// endregion: -- Tracing: Request span
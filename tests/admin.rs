@@ -0,0 +1,45 @@
+use surreal_simple::api::MAINTENANCE;
+
+mod support;
+use support::app::{admin_bearer, spawn_app};
+use support::http::ResponseExt;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_routes_need_the_admin_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let url = format!("{}/admin/status", app.address);
+
+    // Act
+    let anonymous = minreq::get(&url).send().unwrap();
+    let wrong = minreq::get(&url)
+        .with_header("Authorization", "Bearer not-the-token")
+        .send()
+        .unwrap();
+    let admin = minreq::get(&url)
+        .with_header("Authorization", admin_bearer())
+        .send()
+        .unwrap();
+
+    // Assert
+    anonymous.problem(401);
+    wrong.problem(401);
+    admin.assert_status(200);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_writes_are_refused_without_the_token() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = minreq::put(format!("{}/admin/read-only", app.address))
+        .with_json(&serde_json::json!({ "read_only": true }))
+        .unwrap()
+        .send()
+        .unwrap();
+
+    // Assert
+    response.problem(401);
+    assert!(!MAINTENANCE.is_read_only());
+}
@@ -0,0 +1,57 @@
+use once_cell::sync::Lazy;
+use surreal_simple::surreal::db::Database;
+use surreal_simple::telemetry::{get_subscriber, init_subscriber};
+
+mod support;
+use support::app::spawn_app;
+use support::container::database_settings;
+use support::http::ResponseExt;
+
+// region: -- conditional tracing for tests
+static TRACING: Lazy<()> = Lazy::new(|| {
+    let default_filter_level = "info".to_string();
+    let subscriber_name = "test".to_string();
+    if std::env::var("TEST_LOG").is_ok() {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        init_subscriber(subscriber);
+    } else {
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        init_subscriber(subscriber);
+    }
+});
+// endregion: -- conditional tracing for tests
+
+#[tokio::test]
+async fn ping_answers_on_a_live_connection() {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let db = Database::new(&database_settings().await).await.unwrap();
+
+    // Act
+    let pong = db.ping().await;
+
+    // Assert
+    assert!(pong.is_ok(), "{pong:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn built_app_serves_requests() {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let health = minreq::get(format!("{}/health_check", app.address))
+        .send()
+        .unwrap();
+    let ready = minreq::get(format!("{}/health/ready", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    health.assert_status(200);
+    let readiness: serde_json::Value = ready.assert_status(200).data();
+    assert_eq!(readiness["supported"], true);
+}
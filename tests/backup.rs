@@ -0,0 +1,228 @@
+use chrono::{DateTime, TimeZone, Utc};
+use surreal_simple::surreal::admin::Scope;
+use surreal_simple::surreal::backup::cron::Schedule;
+use surreal_simple::surreal::backup::s3::{hex, parse_list_page, signing_key, ListPage};
+use surreal_simple::surreal::backup::store::{BackupStore, DirectoryStore};
+use surreal_simple::surreal::backup::{
+    prune_plan, snapshot_name, snapshot_time, BackupSettings, RetentionSettings,
+};
+use uuid::Uuid;
+
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+fn scope() -> Scope {
+    Scope {
+        namespace: "test".into(),
+        database: "test".into(),
+    }
+}
+
+fn next(expression: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    expression.parse::<Schedule>().unwrap().next_after(after)
+}
+
+#[test]
+fn schedules_fire_at_the_next_matching_minute() {
+    // 2023-05-10 was a Wednesday.
+    let now = at(2023, 5, 10, 14, 30);
+
+    assert_eq!(next("0 3 * * *", now), Some(at(2023, 5, 11, 3, 0)));
+    assert_eq!(next("*/15 * * * *", now), Some(at(2023, 5, 10, 14, 45)));
+    assert_eq!(next("30 14 * * *", now), Some(at(2023, 5, 11, 14, 30)));
+    assert_eq!(next("0 9-17/4 * * 1-5", now), Some(at(2023, 5, 10, 17, 0)));
+    assert_eq!(next("0 0 * * 7", now), Some(at(2023, 5, 14, 0, 0)));
+    assert_eq!(next("0 0 1 1,7 *", now), Some(at(2023, 7, 1, 0, 0)));
+    assert_eq!(next("0 0 31 12 *", now), Some(at(2023, 12, 31, 0, 0)));
+}
+
+#[test]
+fn restricted_days_of_month_and_week_match_either() {
+    // The 13th, or any Friday.
+    let schedule: Schedule = "0 0 13 * 5".parse().unwrap();
+
+    assert_eq!(
+        schedule.next_after(at(2023, 5, 10, 0, 0)),
+        Some(at(2023, 5, 12, 0, 0))
+    );
+    assert_eq!(
+        schedule.next_after(at(2023, 5, 12, 0, 0)),
+        Some(at(2023, 5, 13, 0, 0))
+    );
+}
+
+#[test]
+fn impossible_schedules_never_fire() {
+    assert_eq!(next("0 0 30 2 *", at(2023, 1, 1, 0, 0)), None);
+}
+
+#[test]
+fn malformed_schedules_are_rejected() {
+    for expression in [
+        "0 3 * *",
+        "60 * * * *",
+        "* 24 * * *",
+        "* * 0 * *",
+        "* * * 13 *",
+        "* * * * 8",
+        "*/0 * * * *",
+        "10-5 * * * *",
+        "a * * * *",
+    ] {
+        assert!(
+            expression.parse::<Schedule>().is_err(),
+            "`{expression}` parsed"
+        );
+    }
+}
+
+#[test]
+fn snapshot_names_carry_their_scope_and_time() {
+    // Arrange
+    let taken_at = at(2023, 5, 10, 3, 0);
+
+    // Act
+    let name = snapshot_name(&scope(), taken_at);
+
+    // Assert
+    assert_eq!(name, "test-test-20230510T030000Z.surql");
+    assert_eq!(snapshot_time(&scope(), &name), Some(taken_at));
+    let other = Scope {
+        namespace: "test".into(),
+        database: "other".into(),
+    };
+    assert_eq!(snapshot_time(&other, &name), None);
+}
+
+#[test]
+fn pruning_keeps_the_newest_snapshots() {
+    // Arrange
+    let names: Vec<String> = (1..=5)
+        .map(|day| snapshot_name(&scope(), at(2023, 5, day, 3, 0)))
+        .chain(["notes.txt".to_string()])
+        .collect();
+    let retention = RetentionSettings {
+        keep_last: 3,
+        max_age_days: None,
+    };
+
+    // Act
+    let expired = prune_plan(&scope(), &names, at(2023, 5, 5, 4, 0), &retention);
+
+    // Assert
+    assert_eq!(expired, [names[0].clone(), names[1].clone()]);
+}
+
+#[test]
+fn pruning_by_age_never_removes_the_newest() {
+    // Arrange
+    let names: Vec<String> = [1, 2, 3]
+        .iter()
+        .map(|day| snapshot_name(&scope(), at(2023, 5, *day, 3, 0)))
+        .collect();
+    let retention = RetentionSettings {
+        keep_last: 10,
+        max_age_days: Some(7),
+    };
+
+    // Act
+    let recent = prune_plan(&scope(), &names, at(2023, 5, 9, 0, 0), &retention);
+    let stale = prune_plan(&scope(), &names, at(2023, 6, 1, 0, 0), &retention);
+
+    // Assert
+    assert_eq!(recent, [names[0].clone()]);
+    assert_eq!(stale, [names[0].clone(), names[1].clone()]);
+}
+
+#[test]
+fn enabled_backups_are_validated() {
+    // Arrange
+    let disabled = BackupSettings {
+        schedule: "nonsense".into(),
+        ..BackupSettings::default()
+    };
+    let enabled = BackupSettings {
+        enabled: true,
+        schedule: "nonsense".into(),
+        retention: RetentionSettings {
+            keep_last: 0,
+            max_age_days: None,
+        },
+        ..BackupSettings::default()
+    };
+
+    // Act
+    let problems = enabled.problems();
+
+    // Assert
+    assert!(disabled.problems().is_empty());
+    assert_eq!(problems.len(), 2, "{problems:?}");
+}
+
+#[tokio::test]
+async fn directory_stores_keep_snapshots_as_files() {
+    // Arrange
+    let dir = std::env::temp_dir().join(format!("backups-{}", Uuid::new_v4()));
+    let store = DirectoryStore::new(&dir);
+    let empty = store.list().await.unwrap();
+
+    // Act
+    store.put("a.surql", b"-- a".to_vec()).await.unwrap();
+    store.put("b.surql", b"-- b".to_vec()).await.unwrap();
+    store.delete("a.surql").await.unwrap();
+    store.delete("a.surql").await.unwrap();
+    let escape = store.put("../a.surql", Vec::new()).await;
+
+    // Assert
+    assert!(empty.is_empty());
+    assert_eq!(store.list().await.unwrap(), ["b.surql"]);
+    assert_eq!(std::fs::read(dir.join("b.surql")).unwrap(), b"-- b");
+    assert!(escape.is_err());
+
+    // Teardown
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn signing_keys_match_the_aws_example() {
+    // From the AWS Signature Version 4 documentation.
+    let key = signing_key(
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "20120215",
+        "us-east-1",
+        "iam",
+    );
+
+    assert_eq!(
+        hex(&key),
+        "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+    );
+}
+
+#[test]
+fn list_pages_give_their_keys_and_continuation() {
+    // Arrange
+    let truncated = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult><Name>backups</Name><IsTruncated>true</IsTruncated>
+<Contents><Key>db/a.surql</Key><Size>4</Size></Contents>
+<Contents><Key>db/b&amp;c.surql</Key><Size>4</Size></Contents>
+<NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=</NextContinuationToken>
+</ListBucketResult>"#;
+    let last = "<ListBucketResult><IsTruncated>false</IsTruncated></ListBucketResult>";
+
+    // Act
+    let first = parse_list_page(truncated);
+    let second = parse_list_page(last);
+
+    // Assert
+    assert_eq!(
+        first,
+        ListPage {
+            keys: vec!["db/a.surql".into(), "db/b&c.surql".into()],
+            next: Some("1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=".into()),
+        }
+    );
+    assert_eq!(second, ListPage::default());
+}
@@ -0,0 +1,194 @@
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use std::time::{Duration, Instant};
+use surrealdb::error::{Api, Db};
+
+use surreal_simple::error::Error;
+use surreal_simple::surreal::breaker::{
+    counts_as_failure, is_circuit_open, BreakerSettings, CircuitBreaker, CircuitState,
+};
+
+fn settings() -> BreakerSettings {
+    BreakerSettings {
+        enabled: true,
+        window: 4,
+        min_calls: 4,
+        error_rate_percent: 50,
+        slow_ms: 100,
+        slow_rate_percent: 75,
+        open_secs: 10,
+        probes: 2,
+    }
+}
+
+fn run(breaker: &CircuitBreaker, elapsed_ms: u64, failed: bool) {
+    breaker
+        .admit(Instant::now())
+        .unwrap()
+        .record(Duration::from_millis(elapsed_ms), failed);
+}
+
+fn tripped() -> CircuitBreaker {
+    let breaker = CircuitBreaker::new(&settings());
+    for failed in [false, false, true, true] {
+        run(&breaker, 1, failed);
+    }
+    breaker
+}
+
+#[test]
+fn breaker_stays_closed_below_the_error_rate() {
+    // Arrange
+    let breaker = CircuitBreaker::new(&settings());
+
+    // Act
+    for failed in [false, false, false, true] {
+        run(&breaker, 1, failed);
+    }
+
+    // Assert
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Closed);
+    assert!(breaker.admit(Instant::now()).is_ok());
+}
+
+#[test]
+fn breaker_needs_min_calls_before_opening() {
+    // Arrange
+    let breaker = CircuitBreaker::new(&settings());
+
+    // Act
+    for _ in 0..3 {
+        run(&breaker, 1, true);
+    }
+
+    // Assert
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Closed);
+}
+
+#[test]
+fn breaker_opens_on_errors_and_fails_fast() {
+    // Arrange
+    let breaker = tripped();
+
+    // Act
+    let rejected = breaker.admit(Instant::now()).unwrap_err();
+
+    // Assert
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Open);
+    assert!(rejected.retry_after <= Duration::from_secs(10));
+    assert!(is_circuit_open(&rejected.into_error()));
+    assert_eq!(breaker.metrics().trips, 1);
+    assert_eq!(breaker.metrics().rejected, 1);
+}
+
+#[test]
+fn breaker_opens_on_slow_queries() {
+    // Arrange
+    let breaker = CircuitBreaker::new(&settings());
+
+    // Act
+    for elapsed_ms in [1, 150, 200, 300] {
+        run(&breaker, elapsed_ms, false);
+    }
+
+    // Assert
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Open);
+}
+
+#[test]
+fn half_open_breaker_closes_after_successful_probes() {
+    // Arrange
+    let breaker = tripped();
+    let later = Instant::now() + Duration::from_secs(11);
+
+    // Act
+    let first = breaker.admit(later).unwrap();
+    let second = breaker.admit(later).unwrap();
+    let third = breaker.admit(later);
+    first.record(Duration::from_millis(1), false);
+    second.record(Duration::from_millis(1), false);
+
+    // Assert
+    assert!(third.is_err());
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Closed);
+}
+
+#[test]
+fn failed_probe_opens_the_breaker_again() {
+    // Arrange
+    let breaker = tripped();
+    let later = Instant::now() + Duration::from_secs(11);
+
+    // Act
+    breaker
+        .admit(later)
+        .unwrap()
+        .record(Duration::from_millis(1), true);
+
+    // Assert
+    assert_eq!(breaker.state(Instant::now()), CircuitState::Open);
+    assert_eq!(breaker.metrics().trips, 2);
+}
+
+#[test]
+fn abandoned_probes_free_their_slot() {
+    // Arrange
+    let breaker = tripped();
+    let later = Instant::now() + Duration::from_secs(11);
+    let first = breaker.admit(later).unwrap();
+    let second = breaker.admit(later).unwrap();
+
+    // Act
+    drop(first);
+    let replacement = breaker.admit(later);
+
+    // Assert
+    assert!(replacement.is_ok());
+    drop(second);
+}
+
+#[test]
+fn disabled_breaker_lets_everything_through() {
+    // Arrange
+    let breaker = tripped();
+
+    // Act
+    breaker.configure(&BreakerSettings {
+        enabled: false,
+        ..settings()
+    });
+
+    // Assert
+    assert!(breaker.admit(Instant::now()).is_ok());
+}
+
+#[test]
+fn only_database_trouble_counts_as_failure() {
+    // Arrange
+    let transient = surrealdb::Error::Api(Api::Ws("connection reset".into()));
+    let conflict = surrealdb::Error::Db(Db::RecordExists {
+        thing: "person:1".into(),
+    });
+
+    // Act & Assert
+    assert!(counts_as_failure(&transient));
+    assert!(!counts_as_failure(&conflict));
+    assert!(!counts_as_failure(&surrealdb::Error::Db(Db::InvalidAuth)));
+}
+
+#[test]
+fn open_circuit_is_a_503_with_retry_after() {
+    // Arrange
+    let error: Error = tripped()
+        .admit(Instant::now())
+        .unwrap_err()
+        .into_error()
+        .into();
+
+    // Act
+    let response = error.into_response();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+}
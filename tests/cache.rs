@@ -0,0 +1,66 @@
+use serde_json::json;
+use std::time::{Duration, Instant};
+use surreal_simple::surreal::cache::{
+    Cache, CacheBackend, CacheSettings, MemoryCache, RedisSettings,
+};
+
+fn redis(url: &str) -> CacheSettings {
+    CacheSettings {
+        backend: CacheBackend::Redis(RedisSettings {
+            url: url.into(),
+            password: None,
+            key_prefix: "test:".into(),
+            channel: "test:invalidate".into(),
+        }),
+    }
+}
+
+#[test]
+fn entries_are_reused_until_they_expire() {
+    // Arrange
+    let cache = MemoryCache::new();
+    let now = Instant::now();
+    cache.put_at("person", "a", json!(3), Duration::from_secs(5), now);
+
+    // Act
+    let fresh = cache.get_at("person", "a", now + Duration::from_secs(4));
+    let other_key = cache.get_at("person", "b", now);
+    let stale = cache.get_at("person", "a", now + Duration::from_secs(5));
+
+    // Assert
+    assert_eq!(fresh, Some(json!(3)));
+    assert_eq!(other_key, None);
+    assert_eq!(stale, None);
+}
+
+#[test]
+fn invalidation_and_clearing_drop_entries() {
+    // Arrange
+    let cache = MemoryCache::new();
+    let now = Instant::now();
+    let ttl = Duration::from_secs(5);
+    cache.put_at("person", "a", json!(3), ttl, now);
+    cache.put_at("registry", "a", json!(7), ttl, now);
+
+    // Act
+    cache.invalidate("person");
+    let after_invalidate = cache.len();
+    cache.clear();
+
+    // Assert
+    assert_eq!(after_invalidate, 1);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn memory_is_the_default_backend() {
+    assert_eq!(CacheSettings::default().backend, CacheBackend::Memory);
+    assert!(CacheSettings::default().problems().is_empty());
+}
+
+#[test]
+fn redis_urls_are_checked() {
+    assert!(redis("redis://127.0.0.1:6379/0").problems().is_empty());
+    assert!(redis("rediss://cache.internal:6380").problems().is_empty());
+    assert_eq!(redis("http://127.0.0.1:6379").problems().len(), 1);
+}
@@ -0,0 +1,116 @@
+use serde::Deserialize;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::edge::{delete_node, EdgeAllowList, EdgeSettings, OnDelete};
+
+#[derive(Deserialize, Debug)]
+struct Person {
+    #[allow(dead_code)]
+    name: String,
+}
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+use uuid::Uuid;
+
+#[test]
+fn edges_touching_a_table_come_from_the_allow_list() {
+    // Arrange
+    let edges = EdgeAllowList::new(&EdgeSettings::default());
+
+    // Act & Assert
+    assert_eq!(edges.touching("person"), ["licenses"]);
+    assert_eq!(edges.touching("registry"), ["licenses"]);
+    assert!(edges.touching("flag").is_empty());
+    assert_eq!(edges.on_delete(), OnDelete::Cascade);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deleting_a_person_deletes_their_licenses() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Cascaded")
+        .with_license(777)
+        .insert(&app.db)
+        .await;
+    let id = doc.person.id.to_string();
+
+    // Act
+    let deleted = app.client().delete_person(&id).await;
+
+    // Assert
+    assert!(deleted.is_ok(), "{deleted:?}");
+    let left: Vec<serde_json::Value> = app
+        .db
+        .query("SELECT * FROM licenses WHERE out = $person")
+        .bind(("person", &doc.person))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(left.is_empty(), "{left:?}");
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn bulk_deletes_delete_the_licenses_too() {
+    // Arrange
+    let app = spawn_app().await;
+    let name = format!("BulkCascaded-{}", Uuid::new_v4());
+    let doc = PersonFixture::new(&name)
+        .with_license(779)
+        .insert(&app.db)
+        .await;
+
+    // Act
+    let response = minreq::delete(format!("{}/people?name_starts_with={name}", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    let report: serde_json::Value = response.assert_status(200).data();
+    assert_eq!(report["deleted"], 1);
+    let left: Vec<serde_json::Value> = app
+        .db
+        .query("SELECT * FROM licenses WHERE out = $person")
+        .bind(("person", &doc.person))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(left.is_empty(), "{left:?}");
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn restrict_refuses_to_delete_related_people() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Restricted")
+        .with_license(778)
+        .insert(&app.db)
+        .await;
+    let edges = EdgeAllowList::new(&EdgeSettings {
+        on_delete: OnDelete::Restrict,
+        ..EdgeSettings::default()
+    });
+
+    // Act
+    let result = delete_node::<Person>(&app.db, &edges, &doc.person).await;
+
+    // Assert
+    assert!(
+        matches!(result, Err(Error::StillRelated { relations: 1, .. })),
+        "{result:?}"
+    );
+    let still_there: Option<Person> = app.db.select(&doc.person).await.unwrap();
+    assert!(still_there.is_some());
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
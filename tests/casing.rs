@@ -0,0 +1,97 @@
+use axum::body::Body;
+use axum::http::{header, Request};
+use axum::middleware;
+use axum::routing::get;
+use axum::{Json, Router};
+use futures_util::stream;
+use serde_json::{json, Value};
+use surreal_simple::api::{camel_case, casing, Case, Streamed, X_CASE};
+use surreal_simple::error::Error;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/person",
+            get(|| async {
+                Json(json!({"date_of_birth": "1815-12-10", "meta": {"request_id": "r"}}))
+            }),
+        )
+        .route(
+            "/people",
+            get(|| async {
+                Streamed::new(stream::iter([Ok::<_, Error>(vec![
+                    json!({"date_of_birth": null}),
+                ])]))
+            }),
+        )
+        .layer(middleware::from_fn(casing))
+}
+
+async fn get_json(case: Option<&str>, uri: &str) -> Value {
+    let mut request = Request::builder().uri(uri);
+    if let Some(case) = case {
+        request = request.header(X_CASE, case);
+    }
+    let response = app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::VARY], "x-case");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[test]
+fn snake_keys_become_camel() {
+    assert_eq!(camel_case("date_of_birth"), "dateOfBirth");
+    assert_eq!(camel_case("name"), "name");
+    assert_eq!(camel_case("_id"), "_id");
+    assert_eq!(camel_case("updated_at_2"), "updatedAt2");
+}
+
+#[test]
+fn nested_keys_are_renamed_but_values_are_not() {
+    // Arrange
+    let mut value = json!({"licenses": [{"issued_at": "x", "registry": {"reg_no": "a_b"}}]});
+
+    // Act
+    Case::Camel.apply(&mut value);
+
+    // Assert
+    assert_eq!(
+        value,
+        json!({"licenses": [{"issuedAt": "x", "registry": {"regNo": "a_b"}}]})
+    );
+}
+
+#[tokio::test]
+async fn responses_stay_snake_case_by_default() {
+    // Act
+    let body = get_json(None, "/person").await;
+
+    // Assert
+    assert_eq!(body["date_of_birth"], "1815-12-10");
+    assert_eq!(body["meta"]["request_id"], "r");
+}
+
+#[tokio::test]
+async fn x_case_camel_renames_response_keys() {
+    // Act
+    let body = get_json(Some("camel"), "/person").await;
+
+    // Assert
+    assert_eq!(body["dateOfBirth"], "1815-12-10");
+    assert_eq!(body["meta"]["requestId"], "r");
+    assert!(body.get("date_of_birth").is_none());
+}
+
+#[tokio::test]
+async fn streamed_lists_are_renamed_row_by_row() {
+    // Act
+    let body = get_json(Some("camel"), "/people").await;
+
+    // Assert
+    assert_eq!(body["data"], json!([{"dateOfBirth": null}]));
+    assert_eq!(body["meta"], json!({"requestId": null}));
+}
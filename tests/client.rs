@@ -0,0 +1,186 @@
+use axum::extract::{Path, RawQuery};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use surreal_simple::api::PeopleQuery;
+use surreal_simple::client::{ClientError, NewPerson, SurrealThingClient};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+/// Serves `routes` on a free port, for checking what the client sends and
+/// how it reads what comes back without a database.
+fn serve(routes: Router) -> SurrealThingClient {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}/", listener.local_addr().unwrap());
+    let server = axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(routes.into_make_service());
+    tokio::spawn(server);
+    SurrealThingClient::new(address)
+}
+
+fn person(id: &str, name: &str) -> Value {
+    json!({ "id": format!("person:{id}"), "name": name, "tags": ["a"] })
+}
+
+#[tokio::test]
+async fn ids_are_escaped_and_keys_asked_for_in_snake_case() {
+    // Arrange
+    let client = serve(Router::new().route(
+        "/person/:id",
+        get(|Path(id): Path<String>, headers: HeaderMap| async move {
+            Json(json!({
+                "data": person(&id, headers["x-case"].to_str().unwrap()),
+                "meta": { "request_id": null },
+                "errors": [],
+            }))
+        }),
+    ));
+
+    // Act
+    let read = client.get_person("a b/c").await.unwrap();
+
+    // Assert
+    assert_eq!(read.id, "person:a b/c");
+    assert_eq!(read.name, "snake");
+    assert_eq!(read.tags, ["a"]);
+    assert_eq!(read.age, None);
+}
+
+#[tokio::test]
+async fn searches_send_the_query_and_read_the_pagination() {
+    // Arrange
+    let client = serve(Router::new().route(
+        "/people",
+        get(|RawQuery(query): RawQuery| async move {
+            Json(json!({
+                "data": [person("1", &query.unwrap_or_default())],
+                "meta": {
+                    "request_id": "r",
+                    "pagination": { "start": 20, "limit": 10, "count": 1, "total": 21 },
+                },
+                "errors": [],
+            }))
+        }),
+    ));
+
+    // Act
+    let page = client
+        .search_people(&PeopleQuery {
+            name_starts_with: Some("Jo".into()),
+            start: Some(20),
+            limit: Some(10),
+            ..PeopleQuery::default()
+        })
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(page.items[0].name, "name_starts_with=Jo&start=20&limit=10");
+    let pagination = page.pagination.unwrap();
+    assert_eq!(
+        (pagination.start, pagination.count, pagination.total),
+        (20, 1, Some(21))
+    );
+}
+
+#[tokio::test]
+async fn problems_come_back_as_api_errors() {
+    // Arrange
+    let client = serve(
+        Router::new()
+            .route(
+                "/person/:id",
+                get(|| async {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({
+                            "data": null,
+                            "meta": { "request_id": null },
+                            "errors": [{
+                                "type": "about:blank",
+                                "title": "Not Found",
+                                "status": 404,
+                                "detail": "`person:nobody` not found",
+                            }],
+                        })),
+                    )
+                }),
+            )
+            .route(
+                "/people",
+                get(|| async { (StatusCode::BAD_GATEWAY, "upstream down") }),
+            ),
+    );
+
+    // Act
+    let missing = client.get_person("nobody").await.unwrap_err();
+    let garbled = client
+        .search_people(&PeopleQuery::default())
+        .await
+        .unwrap_err();
+
+    // Assert
+    assert!(missing.is_not_found(), "{missing}");
+    assert_eq!(
+        missing.to_string(),
+        "404 Not Found: `person:nobody` not found"
+    );
+    assert!(
+        matches!(&garbled, ClientError::Unexpected(message) if message.contains("upstream down")),
+        "{garbled}"
+    );
+    assert_eq!(garbled.status(), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn people_go_through_their_whole_lifecycle() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    let id = Uuid::new_v4().to_string();
+    let tag = Uuid::new_v4().simple().to_string();
+    let jane = NewPerson {
+        tags: vec![tag.clone()],
+        ..NewPerson::new("Jane")
+    };
+
+    // Act
+    let created = client.create_person_with_id(&id, &jane).await.unwrap();
+    let read = client.get_person(&id).await.unwrap();
+    let updated = client
+        .update_person(&id, &NewPerson::new("Janet"))
+        .await
+        .unwrap();
+    let found = client
+        .search_people(&PeopleQuery {
+            name_starts_with: Some("Janet".into()),
+            limit: Some(100),
+            ..PeopleQuery::default()
+        })
+        .await
+        .unwrap();
+    let deleted = client.delete_person(&id).await.unwrap();
+    let gone = client.get_person(&id).await.unwrap_err();
+    let deleted_again = client.delete_person(&id).await.unwrap_err();
+    let updated_again = client
+        .update_person(&id, &NewPerson::new("Jan"))
+        .await
+        .unwrap_err();
+
+    // Assert
+    assert_eq!(created.id, format!("person:{id}"));
+    assert_eq!(created.tags, [tag]);
+    assert_eq!(read, created);
+    assert_eq!(updated.name, "Janet");
+    assert!(updated.tags.is_empty());
+    assert!(found.items.iter().any(|person| person.id == created.id));
+    assert_eq!(deleted.name, "Janet");
+    assert!(gone.is_not_found(), "{gone}");
+    assert!(deleted_again.is_not_found(), "{deleted_again}");
+    assert!(updated_again.is_not_found(), "{updated_again}");
+}
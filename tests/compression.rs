@@ -0,0 +1,84 @@
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use surreal_simple::api::compression::{compression, request_decompression, CompressionSettings};
+use surreal_simple::api::ApiJson;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/small", axum::routing::get(|| async { "tiny" }))
+        .route("/large", axum::routing::get(|| async { "x".repeat(4096) }))
+        .layer(compression(&CompressionSettings::default()))
+}
+
+#[tokio::test]
+async fn large_responses_are_compressed_when_accepted() {
+    // Act
+    let response = app()
+        .oneshot(
+            Request::get("/large")
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+}
+
+#[tokio::test]
+async fn small_responses_are_sent_as_is() {
+    // Act
+    let response = app()
+        .oneshot(
+            Request::get("/small")
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn gzipped_request_bodies_are_decompressed() {
+    // Arrange
+    let app = Router::new()
+        .route(
+            "/batch",
+            axum::routing::post(
+                |ApiJson(names): ApiJson<Vec<String>>| async move { names.join(",") },
+            ),
+        )
+        .layer(request_decompression());
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(br#"["Luke","John"]"#).unwrap();
+    let body = encoder.finish().unwrap();
+
+    // Act
+    let response = app
+        .oneshot(
+            Request::post("/batch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_ENCODING, "gzip")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"Luke,John");
+}
@@ -0,0 +1,57 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use serde_json::json;
+use surreal_simple::api::PersonView;
+use surreal_simple::client::NewPerson;
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+#[test]
+fn read_dtos_carry_computed_fields() {
+    // Arrange
+    let with_age = json!({ "name": "Ada", "date_of_birth": "1990-05-01", "age": 33 });
+    let without = json!({ "name": "Bob" });
+
+    // Act
+    let with_age: PersonView = serde_json::from_value(with_age).unwrap();
+    let without: PersonView = serde_json::from_value(without).unwrap();
+
+    // Assert
+    assert_eq!(
+        serde_json::to_value(&with_age).unwrap(),
+        json!({ "name": "Ada", "date_of_birth": "1990-05-01", "age": 33 })
+    );
+    assert_eq!(
+        serde_json::to_value(&without).unwrap(),
+        json!({ "name": "Bob" })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn age_is_computed_from_date_of_birth_on_read() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    let id = Uuid::new_v4().to_string();
+    let born = NaiveDate::from_ymd_opt(1990, 5, 1).unwrap();
+    let today = Utc::now().date_naive();
+    let expected = today.year()
+        - born.year()
+        - i32::from((today.month(), today.day()) < (born.month(), born.day()));
+    let ada = NewPerson {
+        date_of_birth: Some(born),
+        ..NewPerson::new("Ada")
+    };
+    client.create_person_with_id(&id, &ada).await.unwrap();
+
+    // Act
+    let person = client.get_person(&id).await.unwrap();
+
+    // Assert
+    assert_eq!(person.age, Some(expected as u32));
+    assert_eq!(person.date_of_birth, Some(born));
+
+    // Teardown
+    client.delete_person(&id).await.unwrap();
+}
@@ -0,0 +1,89 @@
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use chrono::{TimeZone, Utc};
+use surreal_simple::api::{http_date, modified_since, parse_http_date, Conditional};
+
+fn if_modified_since(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::IF_MODIFIED_SINCE,
+        HeaderValue::from_str(value).unwrap(),
+    );
+    headers
+}
+
+#[test]
+fn http_dates_round_trip() {
+    // Arrange
+    let at = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+
+    // Act
+    let formatted = http_date(at);
+
+    // Assert
+    assert_eq!(formatted, "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(parse_http_date(&formatted), Some(at));
+    assert_eq!(parse_http_date("yesterday"), None);
+}
+
+#[test]
+fn unchanged_within_the_same_second_is_not_modified() {
+    // Arrange
+    let at =
+        Utc.with_ymd_and_hms(2023, 5, 1, 12, 0, 0).unwrap() + chrono::Duration::milliseconds(750);
+    let headers = if_modified_since("Mon, 01 May 2023 12:00:00 GMT");
+
+    // Act
+    let response = Conditional::new(&headers, Some(at), "body").into_response();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(
+        response.headers()[header::LAST_MODIFIED],
+        "Mon, 01 May 2023 12:00:00 GMT"
+    );
+}
+
+#[test]
+fn changed_since_is_sent_with_last_modified() {
+    // Arrange
+    let at = Utc.with_ymd_and_hms(2023, 5, 1, 12, 0, 1).unwrap();
+    let headers = if_modified_since("Mon, 01 May 2023 12:00:00 GMT");
+
+    // Act
+    let response = Conditional::new(&headers, Some(at), "body").into_response();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::LAST_MODIFIED],
+        "Mon, 01 May 2023 12:00:01 GMT"
+    );
+}
+
+#[test]
+fn missing_or_invalid_header_counts_as_modified() {
+    // Arrange
+    let at = Utc::now();
+
+    // Act
+    let without = modified_since(&HeaderMap::new(), at);
+    let invalid = modified_since(&if_modified_since("not a date"), at);
+
+    // Assert
+    assert!(without);
+    assert!(invalid);
+}
+
+#[test]
+fn records_without_updated_at_are_always_sent() {
+    // Arrange
+    let headers = if_modified_since("Mon, 01 May 2023 12:00:00 GMT");
+
+    // Act
+    let response = Conditional::new(&headers, None, "body").into_response();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::LAST_MODIFIED).is_none());
+}
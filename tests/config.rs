@@ -0,0 +1,196 @@
+use surreal_simple::api::shed::LIMITS;
+use surreal_simple::config::{ConfigReloader, Settings};
+use surreal_simple::error::Error;
+use surreal_simple::surreal::db::{AuthMode, MissingScope};
+
+#[test]
+fn default_settings_are_valid() {
+    assert!(Settings::default().validate().is_ok());
+}
+
+#[test]
+fn every_problem_is_reported() {
+    // Arrange
+    let mut settings = Settings::default();
+    settings.database.password = "".into();
+    settings.database.ssl_mode = true;
+    settings.database.port = 80;
+    settings.admin.token = Some(" ".into());
+
+    // Act
+    let error = settings.validate().unwrap_err();
+
+    // Assert
+    assert_eq!(error.0.len(), 3, "{error}");
+    let message = error.to_string();
+    assert!(message.contains("`database.password` must not be empty"));
+    assert!(message.contains("`database.ssl_mode` is on but `database.port` is 80"));
+    assert!(message.contains("`admin.token`"));
+}
+
+#[test]
+fn printed_settings_hide_secrets() {
+    // Arrange
+    let mut settings = Settings::default();
+    settings.database.password = "correct horse".into();
+    settings.admin.token = Some("hunter2".into());
+
+    // Act
+    let printed = serde_json::to_string(&settings).unwrap();
+    let debugged = format!("{:?}", settings.database);
+
+    // Assert
+    assert!(!printed.contains("correct horse"));
+    assert!(!printed.contains("hunter2"));
+    assert!(printed.contains(&settings.database.username));
+    assert!(!debugged.contains("correct horse"));
+    assert_eq!(settings.database.password.expose(), "correct horse");
+}
+
+#[test]
+fn invalid_log_levels_are_reported() {
+    // Arrange
+    let settings = Settings {
+        log_level: Some("info,=[".into()),
+        ..Settings::default()
+    };
+
+    // Act
+    let error = settings.validate().unwrap_err();
+
+    // Assert
+    assert!(error.to_string().contains("`log_level`"), "{error}");
+}
+
+#[test]
+fn reload_applies_hot_settings_and_reports_the_rest() {
+    // Arrange
+    let reloader = ConfigReloader::new(Settings::default());
+    let mut new = Settings::default();
+    new.slow_query.threshold_ms = 5;
+    new.database.port = 8001;
+    new.database.password = "rotated".into();
+
+    // Act
+    let first = reloader.apply(new.clone());
+    let second = reloader.apply(new);
+
+    // Assert
+    assert_eq!(first.applied, ["slow_query"]);
+    assert_eq!(first.restart_required, ["database"]);
+    assert!(second.applied.is_empty());
+    assert_eq!(second.restart_required, ["database"]);
+}
+
+#[test]
+fn limits_and_an_unset_log_level_apply_on_reload() {
+    // Arrange
+    let reloader = ConfigReloader::new(Settings {
+        log_level: Some("debug".into()),
+        ..Settings::default()
+    });
+    let mut new = Settings::default();
+    new.limits.batch = 2;
+
+    // Act
+    let report = reloader.apply(new);
+
+    // Assert
+    assert_eq!(report.applied, ["log_level", "limits"]);
+    assert!(report.restart_required.is_empty());
+    assert_eq!(LIMITS.settings().batch, 2);
+}
+
+#[test]
+fn secret_only_changes_still_need_a_restart() {
+    // Arrange
+    let reloader = ConfigReloader::new(Settings::default());
+    let mut new = Settings::default();
+    new.admin.token = Some("new-token".into());
+
+    // Act
+    let report = reloader.apply(new);
+
+    // Assert
+    assert_eq!(report.restart_required, ["admin"]);
+}
+
+#[test]
+fn a_database_switch_is_kept_in_the_settings() {
+    // Arrange
+    let reloader = ConfigReloader::new(Settings::default());
+
+    // Act
+    reloader.use_database("test", "test_restore_20230510T040000");
+
+    // Assert
+    let settings = reloader.settings();
+    assert_eq!(settings.database.namespace, "test");
+    assert_eq!(settings.database.database, "test_restore_20230510T040000");
+}
+
+#[test]
+fn scope_auth_needs_a_scope() {
+    // Arrange
+    let mut settings = Settings::default();
+    settings.database.auth = AuthMode::Scope;
+
+    // Act
+    let missing = settings.validate().unwrap_err();
+    settings.database.scope = Some("app".into());
+    let complete = settings.validate();
+
+    // Assert
+    assert!(missing.to_string().contains("`database.scope` is not set"));
+    assert!(complete.is_ok());
+}
+
+#[test]
+fn scope_without_scope_auth_is_reported() {
+    // Arrange
+    let mut settings = Settings::default();
+    settings.database.auth = AuthMode::Database;
+    settings.database.scope = Some("app".into());
+
+    // Act
+    let problems = settings.validate().unwrap_err();
+
+    // Assert
+    assert!(problems
+        .to_string()
+        .contains("`database.scope` is only used with `auth: scope`"));
+}
+
+#[test]
+fn auth_modes_read_from_configuration() {
+    // Act
+    let modes: Vec<AuthMode> =
+        serde_json::from_str(r#"["root", "namespace", "database", "scope"]"#).unwrap();
+
+    // Assert
+    assert_eq!(
+        modes,
+        [
+            AuthMode::Root,
+            AuthMode::Namespace,
+            AuthMode::Database,
+            AuthMode::Scope
+        ]
+    );
+}
+
+#[test]
+fn missing_scopes_are_created_or_named() {
+    // Act
+    let created = MissingScope::Create.resolve("NAMESPACE", "my-app");
+    let failed = MissingScope::Fail.resolve("DATABASE", "test");
+
+    // Assert
+    assert_eq!(created.unwrap(), "DEFINE NAMESPACE `my-app`;");
+    let failed = failed.unwrap_err();
+    assert!(matches!(failed, Error::ScopeNotFound(_)));
+    assert!(failed
+        .to_string()
+        .contains("database `test` does not exist"));
+    assert_eq!(Settings::default().database.on_missing, MissingScope::Fail);
+}
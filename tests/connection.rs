@@ -0,0 +1,139 @@
+use surreal_simple::api::render_connection_metrics;
+use surreal_simple::surreal::connection::{is_connection_error, ConnectionMonitor, ErrorKind};
+use surrealdb::error::{Api, Db};
+
+fn error(message: &str) -> surrealdb::Error {
+    surrealdb::Error::Api(Api::InternalError(message.into()))
+}
+
+#[test]
+fn connection_errors_are_told_apart() {
+    // Assert
+    assert!(is_connection_error(&error("Connection reset by peer")));
+    assert_eq!(
+        ErrorKind::of(&error("connection closed")),
+        ErrorKind::Connection
+    );
+    assert_eq!(
+        ErrorKind::of(&surrealdb::Error::Db(Db::InvalidAuth)),
+        ErrorKind::Auth
+    );
+    assert_eq!(
+        ErrorKind::of(&surrealdb::Error::Db(Db::QueryCancelled)),
+        ErrorKind::Cancelled
+    );
+    assert_eq!(
+        ErrorKind::of(&error("unexpected response")),
+        ErrorKind::Other
+    );
+}
+
+#[test]
+fn database_errors_are_told_apart() {
+    // Arrange
+    let cases = [
+        (
+            error("Parse error on line 1 at character 0 when parsing 'SELEC'"),
+            ErrorKind::Parse,
+        ),
+        (
+            surrealdb::Error::Db(Db::QueryPermissions),
+            ErrorKind::Permission,
+        ),
+        (
+            error("Database record `person:1` already exists"),
+            ErrorKind::RecordExists,
+        ),
+        (
+            error("Database index `name` already contains 'John', with record `person:1`"),
+            ErrorKind::IndexViolation,
+        ),
+        (
+            surrealdb::Error::Api(Api::ConnectionUninitialised),
+            ErrorKind::Connection,
+        ),
+    ];
+
+    for (error, expected) in cases {
+        // Act
+        let kind = ErrorKind::of(&error);
+
+        // Assert
+        assert_eq!(kind, expected, "{error}");
+    }
+}
+
+#[test]
+fn a_connection_error_marks_it_down_until_the_server_answers() {
+    // Arrange
+    let monitor = ConnectionMonitor::new();
+    monitor.set_connected(true);
+
+    // Act
+    monitor.begin().finish(Some(&error("connection reset")));
+    let down = monitor.metrics();
+    monitor.begin().finish(Some(&error("connection refused")));
+    monitor.begin().finish(Some(&error("parse error")));
+    let up = monitor.metrics();
+
+    // Assert
+    assert!(!down.connected);
+    assert_eq!(down.disconnects, 1);
+    assert!(up.connected);
+    assert_eq!(up.reconnects, 1);
+    assert_eq!(up.reconnect_attempts, 2);
+    assert_eq!(up.queries, 3);
+    assert_eq!(up.errors, [2, 0, 0, 1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn cancelled_queries_leave_the_state_alone() {
+    // Arrange
+    let monitor = ConnectionMonitor::new();
+    monitor.set_connected(true);
+    monitor.begin().finish(Some(&error("connection reset")));
+
+    // Act
+    monitor
+        .begin()
+        .finish(Some(&surrealdb::Error::Db(Db::QueryCancelled)));
+
+    // Assert
+    assert!(!monitor.metrics().connected);
+}
+
+#[test]
+fn in_flight_counts_queries_until_dropped() {
+    // Arrange
+    let monitor = ConnectionMonitor::new();
+
+    // Act
+    let first = monitor.begin();
+    let second = monitor.begin();
+    let during = monitor.metrics().in_flight;
+    first.finish(None);
+    drop(second);
+
+    // Assert
+    assert_eq!(during, 2);
+    assert_eq!(monitor.metrics().in_flight, 0);
+}
+
+#[test]
+fn metrics_render_in_prometheus_format() {
+    // Arrange
+    let monitor = ConnectionMonitor::new();
+    monitor.set_connected(true);
+    monitor.begin().finish(Some(&error("connection reset")));
+
+    // Act
+    let text = render_connection_metrics(&monitor.metrics());
+
+    // Assert
+    assert!(text.contains("# TYPE surrealdb_connected gauge\nsurrealdb_connected 0\n"));
+    assert!(text.contains("surrealdb_disconnects_total 1\n"));
+    assert!(text.contains("surrealdb_query_errors_total{kind=\"connection\"} 1\n"));
+    assert!(text.contains("surrealdb_query_errors_total{kind=\"other\"} 0\n"));
+    assert!(text.contains("surrealdb_query_errors_total{kind=\"index_violation\"} 0\n"));
+    assert!(text.contains("surrealdb_queries_in_flight 0\n"));
+}
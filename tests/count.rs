@@ -0,0 +1,96 @@
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use surreal_simple::api::hooks::{CountInvalidation, Mutation, MutationHooks};
+use surreal_simple::surreal::cache::{Cache, MemoryCache};
+use surreal_simple::surreal::count::{
+    count_statement, CountCache, CountKey, CountSettings, COUNTS,
+};
+
+fn key(table: &str, prefix: &str) -> CountKey {
+    let bindings = BTreeMap::from([("prefix".to_string(), json!(prefix))]);
+    CountKey::new(table, " WHERE string::startsWith(name, $prefix)", &bindings)
+}
+
+#[test]
+fn counts_are_grouped_in_the_database() {
+    assert_eq!(
+        count_statement("person", " WHERE name >= $prefix"),
+        "SELECT count() FROM person WHERE name >= $prefix GROUP ALL"
+    );
+    assert_eq!(
+        count_statement("person", ""),
+        "SELECT count() FROM person GROUP ALL"
+    );
+}
+
+#[tokio::test]
+async fn counts_are_kept_per_filter_and_bindings() {
+    // Arrange
+    let cache = CountCache::new(&CountSettings { cache_secs: 5 });
+    cache.put(key("person", "A"), 3).await;
+
+    // Act
+    let same = cache.get(&key("person", "A")).await;
+    let other_bindings = cache.get(&key("person", "B")).await;
+
+    // Assert
+    assert_eq!(same, Some(3));
+    assert_eq!(other_bindings, None);
+}
+
+#[tokio::test]
+async fn invalidation_drops_only_the_written_table() {
+    // Arrange
+    let cache = CountCache::new(&CountSettings::default());
+    cache.put(key("person", "A"), 3).await;
+    cache.put(key("registry", "A"), 7).await;
+
+    // Act
+    cache.invalidate("person");
+
+    // Assert
+    assert_eq!(cache.get(&key("person", "A")).await, None);
+    assert_eq!(cache.get(&key("registry", "A")).await, Some(7));
+}
+
+#[tokio::test]
+async fn a_zero_cache_never_remembers() {
+    // Arrange
+    let cache = CountCache::new(&CountSettings { cache_secs: 0 });
+
+    // Act
+    cache.put(key("person", "A"), 3).await;
+
+    // Assert
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn counts_use_the_backend_they_are_given() {
+    // Arrange
+    let cache = CountCache::new(&CountSettings::default());
+    let backend = Arc::new(MemoryCache::new());
+    cache.use_backend(backend.clone());
+
+    // Act
+    cache.put(key("person", "A"), 3).await;
+
+    // Assert
+    assert_eq!(backend.len(), 1);
+}
+
+#[tokio::test]
+async fn writes_through_the_hooks_invalidate_counts() {
+    // Arrange
+    let hooks = MutationHooks::new().with(CountInvalidation);
+    let table = "count_hook_test";
+    COUNTS.put(key(table, "A"), 1).await;
+    let mutation = Mutation { table, id: "a" };
+
+    // Act
+    hooks.after_create(mutation, &json!({ "name": "A" })).await;
+
+    // Assert
+    assert_eq!(COUNTS.get(&key(table, "A")).await, None);
+}
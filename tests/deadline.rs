@@ -0,0 +1,124 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::get;
+use axum::{Extension, Router};
+use chrono::{Duration as ChronoDuration, Utc};
+use std::time::Duration;
+use surreal_simple::api::{
+    current_deadline, deadline, parse_grpc_timeout, parse_request_deadline, Deadline,
+};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "done"
+            }),
+        )
+        .route(
+            "/budget",
+            get(|Extension(deadline): Extension<Deadline>| async move {
+                let seen = current_deadline().is_some();
+                format!("{} {seen}", deadline.remaining() <= Duration::from_secs(2))
+            }),
+        )
+        .layer(middleware::from_fn(deadline))
+}
+
+fn request(uri: &str, header: (&str, &str)) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .header(header.0, header.1)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[test]
+fn grpc_timeouts_parse_every_unit() {
+    for (value, expected) in [
+        ("2H", Duration::from_secs(7200)),
+        ("3M", Duration::from_secs(180)),
+        ("4S", Duration::from_secs(4)),
+        ("250m", Duration::from_millis(250)),
+        ("7u", Duration::from_micros(7)),
+        ("9n", Duration::from_nanos(9)),
+    ] {
+        assert_eq!(parse_grpc_timeout(value), Some(expected), "{value}");
+    }
+    for value in ["", "m", "10", "123456789S", "-1S", "1.5S", "5é"] {
+        assert_eq!(parse_grpc_timeout(value), None, "{value}");
+    }
+}
+
+#[test]
+fn request_deadlines_take_milliseconds_or_an_instant() {
+    // Arrange
+    let now = Utc::now();
+    let later = (now + ChronoDuration::seconds(3)).to_rfc3339();
+    let earlier = (now - ChronoDuration::seconds(3)).to_rfc3339();
+
+    // Act & Assert
+    assert_eq!(
+        parse_request_deadline("1500", now),
+        Some(Duration::from_millis(1500))
+    );
+    assert_eq!(
+        parse_request_deadline(&later, now),
+        Some(Duration::from_secs(3))
+    );
+    assert_eq!(parse_request_deadline(&earlier, now), Some(Duration::ZERO));
+    assert_eq!(parse_request_deadline("soon", now), None);
+}
+
+#[tokio::test]
+async fn slow_requests_get_504_with_the_budget() {
+    // Act
+    let response = app()
+        .oneshot(request("/slow", ("grpc-timeout", "50m")))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let detail = body["errors"][0]["detail"].as_str().unwrap();
+    assert!(detail.contains("deadline of 50 ms"), "{detail}");
+}
+
+#[tokio::test]
+async fn handlers_see_the_tighter_deadline() {
+    // Act
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/budget")
+                .header("x-request-deadline", "60000")
+                .header("grpc-timeout", "2S")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"true true");
+}
+
+#[tokio::test]
+async fn requests_without_a_deadline_are_untouched() {
+    // Act
+    let response = app()
+        .oneshot(request("/budget", ("x-other", "1")))
+        .await
+        .unwrap();
+
+    // Assert: the extension is missing, so the extractor rejects the request.
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,117 @@
+use surreal_simple::client::NewPerson;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::duplicates::DuplicatesQuery;
+use uuid::Uuid;
+
+mod support;
+use support::app::{admin_bearer, spawn_app};
+use support::http::ResponseExt;
+
+fn query(table: &str, key: &str) -> DuplicatesQuery {
+    DuplicatesQuery {
+        table: table.into(),
+        key: key.into(),
+        ..DuplicatesQuery::default()
+    }
+}
+
+#[test]
+fn records_are_grouped_on_the_key() {
+    // Act
+    let sql = query("person", "name").statement().unwrap();
+
+    // Assert
+    assert!(sql.contains("fn::normalize_name(name) AS value"));
+    assert!(sql.contains("GROUP BY value"));
+    assert!(sql.contains("WHERE count > 1"));
+    assert!(sql.contains("LIMIT 20 START 0"));
+}
+
+#[test]
+fn other_keys_are_grouped_as_they_are() {
+    // Act
+    let sql = query("registry", "registration").statement().unwrap();
+
+    // Assert
+    assert!(sql.contains("SELECT registration AS value"));
+}
+
+#[test]
+fn only_known_tables_and_fields_are_grouped_on() {
+    for (table, key) in [
+        ("requests", "route"),
+        ("person", "nickname"),
+        ("person", "id"),
+        ("person", "name; DELETE person"),
+    ] {
+        // Act
+        let result = query(table, key).statement();
+
+        // Assert
+        assert!(
+            matches!(result, Err(Error::InvalidQuery(_))),
+            "{table}.{key}: {result:?}"
+        );
+    }
+}
+
+#[test]
+fn pages_are_clamped() {
+    // Arrange
+    let query = DuplicatesQuery {
+        limit: Some(1_000),
+        start: Some(40),
+        ..query("person", "name")
+    };
+
+    // Act
+    let sql = query.statement().unwrap();
+
+    // Assert
+    assert!(sql.contains("LIMIT 100 START 40"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_duplicates_lists_clusters_with_their_ids() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    let twin = Uuid::new_v4();
+    let mut ids = Vec::new();
+    let names = [
+        format!("Twin {twin}"),
+        format!(" twin {twin}"),
+        format!("Twin {twin}"),
+    ];
+    for name in names {
+        let id = Uuid::new_v4().to_string();
+        client
+            .create_person_with_id(&id, &NewPerson::new(name))
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+
+    // Act
+    let response = minreq::get(format!(
+        "{}/admin/duplicates?table=person&key=name&limit=100",
+        app.address
+    ))
+    .with_header("Authorization", admin_bearer())
+    .send()
+    .unwrap();
+
+    // Assert
+    let clusters: Vec<serde_json::Value> = response.assert_status(200).data();
+    let cluster = clusters
+        .iter()
+        .find(|cluster| cluster["value"] == format!("twin {twin}"))
+        .unwrap();
+    assert_eq!(cluster["count"], 3);
+    assert_eq!(cluster["ids"].as_array().unwrap().len(), 3);
+
+    // Teardown
+    for id in ids {
+        let _ = client.delete_person(&id).await;
+    }
+}
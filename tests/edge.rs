@@ -0,0 +1,40 @@
+use surreal_simple::api::{LicenseProps, Licenses, LicensesQuery};
+use surreal_simple::surreal::edge::Edge;
+
+#[test]
+fn licenses_relate_registries_to_people() {
+    assert_eq!(
+        Licenses::statement(),
+        "RELATE $from->licenses->$to CONTENT $props RETURN id"
+    );
+}
+
+#[test]
+fn unset_license_dates_are_left_off_the_edge() {
+    // Arrange
+    let props = LicenseProps::default();
+
+    // Act
+    let content = serde_json::to_value(props).unwrap();
+
+    // Assert
+    assert_eq!(content, serde_json::json!({ "status": "active" }));
+}
+
+#[test]
+fn active_filter_checks_status_and_expiry() {
+    // Arrange
+    let active = LicensesQuery { active: Some(true) };
+    let inactive = LicensesQuery {
+        active: Some(false),
+    };
+
+    // Act
+    let (active, inactive) = (active.filter(), inactive.filter());
+
+    // Assert
+    assert!(active.contains("status = 'active'"));
+    assert!(active.contains("expires_at > time::now()"));
+    assert!(inactive.contains("status != 'active'"));
+    assert_eq!(LicensesQuery::default().filter(), "");
+}
@@ -1,7 +1,15 @@
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use surreal_simple::api::PeopleQuery;
+use surreal_simple::client::{NewPerson, SurrealThingClient};
 use surreal_simple::telemetry::{get_subscriber, init_subscriber};
 
+mod support;
+use support::http::{Envelope, ResponseExt};
+
+/// `admin.token` in `configuration/local.yaml`.
+const ADMIN_BEARER: &str = "Bearer local-admin-token";
+
 // region: -- conditional tracing for tests
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
@@ -49,6 +57,7 @@ async fn crud_endpoints_work() -> color_eyre::Result<()> {
 
     // Arrange
     let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let client = SurrealThingClient::new(&conn_string);
 
     // Act
 
@@ -56,45 +65,61 @@ async fn crud_endpoints_work() -> color_eyre::Result<()> {
     let route = "/health_check";
     let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
     response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    response.assert_status(200);
 
     // CREATE: POST -> .route("/person/:id", post(person::create))
-    let route = "/person/1";
+    let created = client
+        .create_person_with_id("1", &NewPerson::new("John"))
+        .await?;
+    assert_eq!(created.name, "John");
+
+    // READ: GET -> .route("/person/:id", get(person::read))
+    assert_eq!(client.get_person("1").await?.name, "John");
+
+    // UPDATE: PUT -> .route("/person/:id", put(person::update))
+    let updated = client.update_person("1", &NewPerson::new("Mark")).await?;
+    assert_eq!(updated.name, "Mark");
+
+    // UPDATE with ?return=: the record before the update, then only the diff
+    let route = "/person/1?return=before";
     let data: Person = Person {
-        name: "John".into(),
+        name: "Matt".into(),
     };
-    let response = minreq::post(format!("{conn_string}{route}"))
+    let response = minreq::put(format!("{conn_string}{route}"))
         .with_json(&data)?
         .send()?;
-    response.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
-
-    // READ: GET -> .route("/person/:id", get(person::read))
-    let route = "/person/1";
-    let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
-    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    response.sexy_print("PUT", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(response.assert_status(200).data::<Person>().name, "Mark");
 
-    // UPDATE: PUT -> .route("/person/:id", put(person::update))
-    let route = "/person/1";
+    let route = "/person/1?return=diff";
     let data: Person = Person {
-        name: "Mark".into(),
+        name: "Mary".into(),
     };
     let response = minreq::put(format!("{conn_string}{route}"))
         .with_json(&data)?
         .send()?;
     response.sexy_print("PUT", format!("{conn_string}{route}").as_str())?;
+    let diff: serde_json::Value = response.assert_status(200).data();
+    assert!(diff.to_string().contains("Mary"), "{diff}");
 
     // DELETE: DELETE -> .route("/person/:id", delete(person::delete))
-    let route = "/person/1";
-    let response = minreq::delete(format!("{conn_string}{route}"))
-        .send()
-        .unwrap();
-    response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(client.delete_person("1").await?.name, "Mary");
+
+    // MISSING: reading, replacing or deleting it again is a 404, not `200 null`
+    let data = NewPerson::new("Mark");
+    assert!(client.get_person("1").await.unwrap_err().is_not_found());
+    assert!(client
+        .update_person("1", &data)
+        .await
+        .unwrap_err()
+        .is_not_found());
+    assert!(client.delete_person("1").await.unwrap_err().is_not_found());
 
     // LIST: GET -> .route("/people", get(person::list))
-    let route = "/people";
-    let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
-    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    let people = client.search_people(&PeopleQuery::default()).await?;
 
     // Assert
+    assert!(people.items.iter().all(|p| p.name != "Mark"));
 
     Ok(())
 }
@@ -105,6 +130,7 @@ async fn crud_query_endpoints_work() -> color_eyre::Result<()> {
 
     // Arrange
     let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let client = SurrealThingClient::new(&conn_string);
 
     // Act
 
@@ -112,6 +138,7 @@ async fn crud_query_endpoints_work() -> color_eyre::Result<()> {
     let route = "/health_check";
     let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
     response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    response.assert_status(200);
 
     // CREATE: POST -> .route("/person/:id", post(person::create))
     let route = "/person/qry/1";
@@ -122,11 +149,13 @@ async fn crud_query_endpoints_work() -> color_eyre::Result<()> {
         .with_json(&data)?
         .send()?;
     response.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(response.assert_status(201).data::<Person>().name, "John");
 
     // READ: GET -> .route("/person/:id", get(person::read))
     let route = "/person/qry/1";
     let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
     response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(response.assert_status(200).data::<Person>().name, "John");
 
     // UPDATE: PUT -> .route("/person/:id", put(person::update))
     let route = "/person/qry/1";
@@ -137,11 +166,14 @@ async fn crud_query_endpoints_work() -> color_eyre::Result<()> {
         .with_json(&data)?
         .send()?;
     response.sexy_print("PUT", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(response.assert_status(200).data::<Person>().name, "Mark");
 
     // LIST: GET -> .route("/people", get(person::list))
     let route = "/person/qry/people";
     let response = minreq::get(format!("{conn_string}{route}")).send().unwrap();
     response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    let people: Vec<Person> = response.assert_status(200).data();
+    assert!(people.iter().any(|p| p.name == "Mark"));
 
     // DELETE: DELETE -> .route("/person/:id", delete(person::delete))
     let route = "/person/qry/1";
@@ -149,28 +181,468 @@ async fn crud_query_endpoints_work() -> color_eyre::Result<()> {
         .send()
         .unwrap();
     response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    response.assert_status(200);
+
+    // MISSING: reading, replacing or deleting it again is a 404, not `200 null`
+    let route = "/person/qry/1";
+    let data: Person = Person {
+        name: "Mark".into(),
+    };
+    minreq::get(format!("{conn_string}{route}"))
+        .send()?
+        .problem(404);
+    minreq::put(format!("{conn_string}{route}"))
+        .with_json(&data)?
+        .send()?
+        .problem(404);
+    minreq::delete(format!("{conn_string}{route}"))
+        .send()?
+        .problem(404);
 
     // BATCH: POST -> .route("/person/qry/batch", post(person::batch))
-    let route = "/person/qry/batch_up";
-    let data: Vec<Person> = vec![
-        Person {
-            name: "Luke".into(),
-        },
-        Person {
-            name: "John".into(),
-        },
-    ];
+    let people = client
+        .batch_create(&[NewPerson::new("Luke"), NewPerson::new("John")])
+        .await?;
+    assert!(people.iter().any(|p| p.name == "Luke"));
+
+    // BULK DELETE: DELETE -> .route("/people", delete(person::delete_people))
+    let route = "/people";
+    let response = minreq::delete(format!("{conn_string}{route}")).send()?;
+    response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    response.problem(400);
+
+    let route = "/people?name_starts_with=Luke&limit=1";
+    let response = minreq::delete(format!("{conn_string}{route}")).send()?;
+    response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    let report: serde_json::Value = response.assert_status(200).data();
+    assert_eq!(report["deleted"], 1);
+
+    let route = "/people?confirm=all";
+    let response = minreq::delete(format!("{conn_string}{route}")).send()?;
+    response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    response.assert_status(200);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Scope {
+    namespace: String,
+    database: String,
+}
+
+#[tokio::test]
+async fn admin_scope_endpoints_work() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+
+    // Act
+
+    // NAMESPACES: GET -> .route("/admin/namespaces", get(admin::namespaces))
+    let route = "/admin/namespaces";
+    let response = minreq::get(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .send()?;
+    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    assert_eq!(response.status_code, 200);
+
+    // SCOPE: GET -> .route("/admin/scope", get(admin::scope))
+    let route = "/admin/scope";
+    let response = minreq::get(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .send()?;
+    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+    let scope: Scope = response.json::<Envelope<Scope>>()?.data.unwrap();
+
+    // SCOPE: PUT -> .route("/admin/scope", put(admin::use_scope))
+    let data = Scope {
+        namespace: scope.namespace.clone(),
+        database: "does-not-exist".into(),
+    };
+    let response = minreq::put(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .with_json(&data)?
+        .send()?;
+    response.sexy_print("PUT", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    assert_eq!(response.status_code, 404);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Registry {
+    registration: u64,
+}
+
+#[tokio::test]
+async fn duplicate_registration_conflicts() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let data = Registry {
+        registration: 424242,
+    };
+
+    // Act
+
+    // CREATE: POST -> .route("/registry/:id", post(registry::create_registry))
+    let route = "/registry/first";
     let response = minreq::post(format!("{conn_string}{route}"))
         .with_json(&data)?
         .send()?;
     response.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
 
-    // DELETE: DELETE -> .route("/person/qry/batch_down", delete(person::delete))
-    let route = "/person/qry/batch_down";
-    let response = minreq::delete(format!("{conn_string}{route}"))
-        .send()
-        .unwrap();
-    response.sexy_print("DELETE", format!("{conn_string}{route}").as_str())?;
+    let route = "/registry/second";
+    let response = minreq::post(format!("{conn_string}{route}"))
+        .with_json(&data)?
+        .send()?;
+    response.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    let problem = response.problem(409);
+    assert_eq!(problem["field"], "registration");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn people_can_be_searched_by_name_prefix() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let client = SurrealThingClient::new(&conn_string);
+    let names = [
+        "Prefixed Ada",
+        "Prefixed Bea",
+        "Prefixed Cy",
+        "Unrelated Dee",
+    ];
+    for (i, name) in names.iter().enumerate() {
+        client
+            .create_person_with_id(&format!("prefix{i}"), &NewPerson::new(*name))
+            .await?;
+    }
+
+    // Act
+    let route = "/people?name_starts_with=Prefixed&limit=2";
+    let first = minreq::get(format!("{conn_string}{route}")).send()?;
+    first.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    let route = "/people?name_starts_with=Prefixed&limit=2&start=2";
+    let second = minreq::get(format!("{conn_string}{route}")).send()?;
+    second.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    assert_eq!(
+        first.headers.get("x-total-count").map(String::as_str),
+        Some("3")
+    );
+    assert!(first.headers["link"].contains("rel=\"next\""));
+    let first: Envelope<Vec<Person>> = first.json()?;
+    let second: Envelope<Vec<Person>> = second.json()?;
+    assert_eq!(first.meta["pagination"]["count"], 2);
+    let names: Vec<String> = (first.data.into_iter().flatten())
+        .chain(second.data.into_iter().flatten())
+        .map(|p| p.name)
+        .collect();
+    assert_eq!(names, ["Prefixed Ada", "Prefixed Bea", "Prefixed Cy"]);
+
+    // Teardown
+    for i in 0..4 {
+        client.delete_person(&format!("prefix{i}")).await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn debug_db_header_reports_query_stats() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/people";
+
+    // Act
+    let anonymous = minreq::get(format!("{conn_string}{route}"))
+        .with_header("X-Debug-DB", "true")
+        .send()?;
+    anonymous.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    let admin = minreq::get(format!("{conn_string}{route}"))
+        .with_header("X-Debug-DB", "true")
+        .with_header("Authorization", ADMIN_BEARER)
+        .send()?;
+    admin.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    assert_eq!(anonymous.status_code, 401);
+    assert_eq!(admin.status_code, 200);
+    assert_eq!(
+        admin.headers.get("x-db-query-count").map(String::as_str),
+        Some("1")
+    );
+    assert!(admin.headers.contains_key("x-db-time-ms"));
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Flag {
+    name: String,
+    enabled: bool,
+}
+
+#[tokio::test]
+async fn admin_can_toggle_feature_flags() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/admin/flags/test_flag";
+
+    // Act
+    let response = minreq::put(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .with_json(&serde_json::json!({ "enabled": true }))?
+        .send()?;
+    response.sexy_print("PUT", format!("{conn_string}{route}").as_str())?;
+
+    let route = "/admin/flags";
+    let listed = minreq::get(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .send()?;
+    listed.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    let expected = Flag {
+        name: "test_flag".into(),
+        enabled: true,
+    };
+    assert_eq!(
+        response.json::<Envelope<Flag>>()?.data,
+        Some(expected.clone())
+    );
+    let listed: Envelope<Vec<Flag>> = listed.json()?;
+    assert!(listed.data.unwrap().contains(&expected));
+
+    // Teardown
+    let route = "/admin/flags/test_flag";
+    minreq::put(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .with_json(&serde_json::json!({ "enabled": false }))?
+        .send()?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_returns_location_and_conflicts_on_existing_id() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/person/located";
+    let data = Person {
+        name: "Located Lou".into(),
+    };
+
+    // Act
+    let created = minreq::post(format!("{conn_string}{route}"))
+        .with_json(&data)?
+        .send()?;
+    created.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+
+    let duplicate = minreq::post(format!("{conn_string}{route}"))
+        .with_json(&Person {
+            name: "Another Lou".into(),
+        })?
+        .send()?;
+    duplicate.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    assert_eq!(created.status_code, 201);
+    assert_eq!(
+        created.headers.get("location").map(String::as_str),
+        Some(route)
+    );
+    assert_eq!(
+        created.headers.get("content-location").map(String::as_str),
+        Some(route)
+    );
+    assert_eq!(duplicate.status_code, 409);
+
+    // Teardown
+    SurrealThingClient::new(&conn_string)
+        .delete_person("located")
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn responses_share_the_envelope_and_echo_the_request_id() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/admin/scope";
+
+    // Act
+    let response = minreq::get(format!("{conn_string}{route}"))
+        .with_header("Authorization", ADMIN_BEARER)
+        .with_header("X-Request-Id", "envelope-test")
+        .send()?;
+    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    assert_eq!(
+        response.headers.get("x-request-id").map(String::as_str),
+        Some("envelope-test")
+    );
+    let envelope: Envelope<Scope> = response.json()?;
+    assert!(envelope.data.is_some());
+    assert!(envelope.errors.is_empty());
+    assert_eq!(envelope.meta["request_id"], "envelope-test");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_people_are_not_found() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+
+    for route in ["/person/does-not-exist", "/person/qry/does-not-exist"] {
+        // Act
+        let response = minreq::get(format!("{conn_string}{route}")).send()?;
+        response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+        // Assert
+        let problem = response.problem(404);
+        assert_eq!(problem["detail"], "`person:does-not-exist` not found");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_bodies_are_unprocessable() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let bodies = ["{ not json", r#"{ "nickname": "Johnny" }"#];
+
+    for route in ["/person/invalid", "/person/qry/invalid"] {
+        for body in bodies {
+            // Act
+            let response = minreq::post(format!("{conn_string}{route}"))
+                .with_header("Content-Type", "application/json")
+                .with_body(body)
+                .send()?;
+            response.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+
+            // Assert
+            response.problem(422);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn duplicate_query_create_conflicts() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/person/qry/duplicate";
+    let first = Person {
+        name: "Dupe One".into(),
+    };
+    let second = Person {
+        name: "Dupe Two".into(),
+    };
+
+    // Act
+    let created = minreq::post(format!("{conn_string}{route}"))
+        .with_json(&first)?
+        .send()?;
+    let duplicate = minreq::post(format!("{conn_string}{route}"))
+        .with_json(&second)?
+        .send()?;
+    duplicate.sexy_print("POST", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    created.assert_status(201);
+    let problem = duplicate.problem(409);
+    assert_eq!(problem["detail"], "`person:duplicate` already exists");
+
+    // Teardown
+    SurrealThingClient::new(&conn_string)
+        .delete_person("duplicate")
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn readiness_reports_the_server_version() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let route = "/health/ready";
+
+    // Act
+    let response = minreq::get(format!("{conn_string}{route}")).send()?;
+    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    let readiness: serde_json::Value = response.assert_status(200).data();
+    assert!(readiness["surrealdb_version"].is_string());
+    assert!(readiness["supported"].is_boolean());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn people_stats_are_aggregated() -> color_eyre::Result<()> {
+    Lazy::force(&TRACING);
+
+    // Arrange
+    let conn_string = format!("http://{}:{}", "127.0.0.1", "8080");
+    let client = SurrealThingClient::new(&conn_string);
+    for (id, name) in [("stats1", "Quincy Stats"), ("stats2", "Quentin Stats")] {
+        client
+            .create_person_with_id(id, &NewPerson::new(name))
+            .await?;
+    }
+
+    // Act
+    let route = "/people/stats";
+    let response = minreq::get(format!("{conn_string}{route}")).send()?;
+    response.sexy_print("GET", format!("{conn_string}{route}").as_str())?;
+
+    // Assert
+    let stats: serde_json::Value = response.assert_status(200).data();
+    assert!(stats["total"].as_u64().unwrap() >= 2);
+    assert!(stats["by_initial"]["Q"].as_u64().unwrap() >= 2);
+    assert!(stats["last_updated"].is_string());
+
+    // Teardown
+    for id in ["stats1", "stats2"] {
+        client.delete_person(id).await?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,89 @@
+use axum::http::StatusCode;
+use surrealdb::error::{Api, Db};
+
+use surreal_simple::error::Error;
+use surreal_simple::surreal::schema::indexes::index_violation;
+
+#[test]
+fn index_violations_name_the_declared_field() {
+    // Arrange
+    let error = surrealdb::Error::Api(Api::Query(
+        "Database index `registration` already contains 12345, with record `registry:abc`".into(),
+    ));
+
+    // Act
+    let violation = index_violation(&error);
+
+    // Assert
+    assert_eq!(violation, Some(("registration".into(), "12345".into())));
+}
+
+#[test]
+fn index_violations_map_to_conflict() {
+    let error = surrealdb::Error::Api(Api::Query(
+        "Database index `name` already contains 'John', with record `person:1`".into(),
+    ));
+
+    let error = Error::from(error);
+
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+    assert!(matches!(error, Error::Conflict { field, .. } if field == "name"));
+}
+
+#[test]
+fn other_errors_are_not_conflicts() {
+    let error = surrealdb::Error::Api(Api::Query("There was a problem with the database".into()));
+    assert_eq!(index_violation(&error), None);
+    assert_eq!(
+        Error::from(error).status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[test]
+fn existing_records_map_to_conflict() {
+    let error = surrealdb::Error::Api(Api::Query(
+        "Database record `person:1` already exists".into(),
+    ));
+
+    let error = Error::from(error);
+
+    assert_eq!(error.status(), StatusCode::CONFLICT);
+    assert!(matches!(error, Error::AlreadyExists(thing) if thing == "person:1"));
+}
+
+#[test]
+fn parse_errors_are_bad_requests() {
+    let error = surrealdb::Error::Api(Api::Query(
+        "Parse error on line 1 at character 0 when parsing 'SELEC * FROM person'".into(),
+    ));
+
+    let error = Error::from(error);
+
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    assert!(matches!(error, Error::QuerySyntax(message) if message.contains("SELEC")));
+}
+
+#[test]
+fn permission_errors_are_forbidden() {
+    let remote = surrealdb::Error::Api(Api::Query(
+        "You don't have permission to perform this query type".into(),
+    ));
+    let local = surrealdb::Error::Db(Db::QueryPermissions);
+
+    assert!(matches!(Error::from(remote), Error::PermissionDenied));
+    assert_eq!(Error::from(local).status(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn transport_failures_are_unavailable() {
+    let dropped =
+        surrealdb::Error::Api(Api::Ws("Connection reset without closing handshake".into()));
+    let unopened = surrealdb::Error::Api(Api::ConnectionUninitialised);
+
+    assert!(matches!(Error::from(dropped), Error::DbUnavailable));
+    assert_eq!(
+        Error::from(unopened).status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+}
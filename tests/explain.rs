@@ -0,0 +1,47 @@
+use surreal_simple::error::Error;
+use surreal_simple::surreal::explain::explain_statement;
+
+#[test]
+fn selects_get_explain_appended() {
+    // Act
+    let plain = explain_statement("SELECT * FROM person WHERE name = $name;", false).unwrap();
+    let full = explain_statement("  select * from person ", true).unwrap();
+
+    // Assert
+    assert_eq!(plain, "SELECT * FROM person WHERE name = $name EXPLAIN");
+    assert_eq!(full, "select * from person EXPLAIN FULL");
+}
+
+#[test]
+fn only_single_selects_are_explained() {
+    for sql in [
+        "DELETE person",
+        "SELECT * FROM person; DELETE person",
+        "SELECT * FROM person EXPLAIN",
+        "sel",
+        "",
+    ] {
+        // Act
+        let result = explain_statement(sql, false);
+
+        // Assert
+        assert!(matches!(result, Err(Error::InvalidQuery(_))), "{sql}");
+    }
+}
+
+#[test]
+fn queries_that_might_write_are_not_explained() {
+    for sql in [
+        "SELECT * FROM (DELETE person RETURN BEFORE)",
+        "SELECT * FROM person WHERE id IN (UPDATE person SET age = 3)",
+        "SELECT fn::mutating() FROM person",
+        "SELECT http::post('https://example.com', {}) FROM person",
+    ] {
+        // Act
+        let result = explain_statement(sql, true);
+
+        // Assert
+        assert!(matches!(result, Err(Error::InvalidQuery(_))), "{sql}");
+    }
+    assert!(explain_statement("SELECT * FROM (SELECT * FROM person)", true).is_ok());
+}
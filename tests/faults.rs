@@ -0,0 +1,143 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::get;
+use axum::Router;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use surreal_simple::api::{inject_faults, Fault, FaultRule, FaultSettings, FAULTS};
+use surreal_simple::config::Environment;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::breaker::{BreakerSettings, CircuitState, BREAKER};
+use surreal_simple::surreal::instrument::traced;
+use tower::ServiceExt;
+
+/// Answers with what a query made for the request came back with.
+async fn query() -> String {
+    match traced("SELECT * FROM person", async { Ok(Vec::<()>::new()) }).await {
+        Ok(_) => "ran".into(),
+        Err(error) => Error::from(error).status().as_u16().to_string(),
+    }
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/slow", get(|| async { "slow" }))
+        .route("/broken", get(|| async { "broken" }))
+        .route("/cut", get(|| async { "cut" }))
+        .route("/flaky", get(query))
+        .route("/fine", get(query))
+        .route("/admin/broken", get(|| async { "admin" }))
+        .layer(middleware::from_fn(inject_faults))
+}
+
+fn rule(route: &str, fault: Fault, percent: u8) -> FaultRule {
+    FaultRule {
+        route: route.into(),
+        fault,
+        percent,
+        delay_ms: 0,
+    }
+}
+
+async fn get_text(app: &Router, uri: &str) -> (StatusCode, String) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+#[test]
+fn fault_settings_are_checked() {
+    // Arrange
+    let settings: FaultSettings = serde_json::from_value(json!({
+        "enabled": true,
+        "rules": [
+            { "route": "person", "fault": "error", "percent": 50 },
+            { "route": "/", "fault": "delay", "percent": 101 },
+        ],
+    }))
+    .unwrap();
+
+    // Act
+    let local = settings.problems(&Environment::Local);
+    let production = settings.problems(&Environment::Production);
+
+    // Assert
+    assert_eq!(local.len(), 3, "{local:?}");
+    assert_eq!(production.len(), 4, "{production:?}");
+    assert!(production[0].contains("production"));
+    assert!(FaultSettings::default()
+        .problems(&Environment::Production)
+        .is_empty());
+    assert!(serde_json::from_value::<Fault>(json!("explode")).is_err());
+}
+
+// One test, since the faults are process-wide.
+#[tokio::test]
+async fn faults_are_injected_into_the_routes_they_name() {
+    // Arrange
+    let app = app();
+    BREAKER.configure(&BreakerSettings {
+        window: 4,
+        min_calls: 4,
+        ..BreakerSettings::default()
+    });
+    FAULTS.configure(&FaultSettings {
+        enabled: true,
+        rules: vec![
+            FaultRule {
+                delay_ms: 200,
+                ..rule("/slow", Fault::Delay, 100)
+            },
+            rule("/broken", Fault::Error, 100),
+            rule("/admin/", Fault::Error, 100),
+            rule("/cut", Fault::Drop, 100),
+            rule("/flaky", Fault::Query, 100),
+            rule("/fine", Fault::Error, 0),
+        ],
+    });
+
+    // Act
+    let start = Instant::now();
+    let slow = get_text(&app, "/slow").await;
+    let slow_elapsed = start.elapsed();
+    let broken = get_text(&app, "/broken").await;
+    let admin = get_text(&app, "/admin/broken").await;
+    let fine = get_text(&app, "/fine").await;
+    let cut = app
+        .clone()
+        .oneshot(Request::get("/cut").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let cut = hyper::body::to_bytes(cut.into_body()).await;
+    let mut flaky = Vec::new();
+    for _ in 0..5 {
+        flaky.push(get_text(&app, "/flaky").await);
+    }
+
+    // Assert
+    assert_eq!(slow, (StatusCode::OK, "slow".into()));
+    assert!(slow_elapsed >= Duration::from_millis(200));
+    assert_eq!(broken.0, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(
+        broken.1.contains("fault injected on `/broken`"),
+        "{}",
+        broken.1
+    );
+    assert_eq!(admin, (StatusCode::OK, "admin".into()));
+    assert_eq!(fine, (StatusCode::OK, "ran".into()));
+    assert!(cut.is_err());
+    assert!(flaky.iter().all(|answer| answer.1 == "503"), "{flaky:?}");
+    assert_eq!(BREAKER.state(Instant::now()), CircuitState::Open);
+    assert_eq!(FAULTS.status().injected, 8);
+
+    // Act: nothing is injected once they are switched off.
+    FAULTS.configure(&FaultSettings::default());
+    BREAKER.configure(&BreakerSettings::default());
+    let broken = get_text(&app, "/broken").await;
+
+    // Assert
+    assert_eq!(broken, (StatusCode::OK, "broken".into()));
+}
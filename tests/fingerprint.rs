@@ -0,0 +1,33 @@
+use surreal_simple::surreal::instrument::fingerprint;
+
+#[test]
+fn literals_are_stripped() {
+    let sql = "CREATE person:1 CONTENT { name: 'John', age: 42 }";
+    assert_eq!(
+        fingerprint(sql),
+        "CREATE person:? CONTENT { name: ?, age: ? }"
+    );
+}
+
+#[test]
+fn queries_differing_by_literals_share_a_fingerprint() {
+    let a = fingerprint("SELECT * FROM person WHERE id = 'person:abc'");
+    let b = fingerprint("SELECT  *  FROM person\n  WHERE id = \"person:xyz\"");
+    assert_eq!(a, b);
+}
+
+#[test]
+fn params_and_escaped_ids_are_handled() {
+    assert_eq!(
+        fingerprint("RELATE $license->licenses->person:⟨d9a1-4f⟩"),
+        "RELATE $license->licenses->person:?"
+    );
+    assert_eq!(
+        fingerprint("SELECT * FROM registry WHERE registration = 12345"),
+        "SELECT * FROM registry WHERE registration = ?"
+    );
+    assert_eq!(
+        fingerprint("SELECT * FROM `odd table`"),
+        "SELECT * FROM `odd table`"
+    );
+}
@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use surreal_simple::surreal::schema::functions::{
+    Function, FunctionArgs, FUNCTIONS, NORMALIZE_NAME,
+};
+
+#[test]
+fn calls_bind_each_argument_by_position() {
+    // Arrange
+    let add: Function<(i64, i64), i64> = Function::new("add");
+
+    // Act
+    let values = (1, "two").into_values().unwrap();
+
+    // Assert
+    assert_eq!(add.statement(2), "RETURN fn::add($arg0, $arg1);");
+    assert_eq!(values, [serde_json::json!(1), serde_json::json!("two")]);
+    assert_eq!(
+        NORMALIZE_NAME.statement(1),
+        "RETURN fn::normalize_name($arg0);"
+    );
+}
+
+#[test]
+fn arguments_that_do_not_serialize_are_errors() {
+    // Arrange
+    let pairs = BTreeMap::from([((1, 2), "not a string key")]);
+
+    // Act
+    let values = (pairs,).into_values();
+
+    // Assert
+    assert!(values.is_err());
+}
+
+#[test]
+fn declared_functions_define_what_they_are_named() {
+    for function in FUNCTIONS {
+        assert!(
+            function
+                .statement
+                .starts_with(&format!("DEFINE FUNCTION fn::{}(", function.name)),
+            "{}",
+            function.name
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use serde_json::json;
+use std::collections::BTreeSet;
+use surreal_simple::surreal::graph::{Direction, EdgeRow, Graph, GraphEdge, MAX_GRAPH_NODES};
+use surrealdb::sql::Thing;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+
+fn person(id: &str) -> Thing {
+    Thing::from(("person", id))
+}
+
+fn license(id: &str, registry: &str, person: &str) -> EdgeRow {
+    EdgeRow {
+        id: format!("licenses:{id}"),
+        source: format!("registry:{registry}"),
+        target: format!("person:{person}"),
+        record: json!({
+            "id": format!("licenses:{id}"),
+            "in": format!("registry:{registry}"),
+            "out": format!("person:{person}"),
+            "status": "active",
+        }),
+    }
+}
+
+fn frontier(records: &[&str]) -> BTreeSet<String> {
+    records.iter().map(|record| record.to_string()).collect()
+}
+
+#[test]
+fn edges_are_found_once_with_their_direction_and_properties() {
+    // Arrange
+    let mut graph = Graph::new(&person("john"), 1);
+    graph.add_node(
+        &person("john"),
+        0,
+        json!({ "id": "person:john", "name": "John" }),
+    );
+    let from = frontier(&["person:john"]);
+    let rows = vec![license("a", "r1", "john"), license("a", "r1", "john")];
+    let mut found = Vec::new();
+
+    // Act
+    graph.discover(&from, &rows, &mut found);
+    graph.add_node(&Thing::from(("registry", "r1")), 1, json!({}));
+    graph.add_edges("licenses", &from, rows);
+
+    // Assert
+    assert_eq!(found, ["registry:r1"]);
+    assert_eq!(graph.nodes[0].data, json!({ "name": "John" }));
+    assert_eq!(
+        graph.edges,
+        [GraphEdge {
+            id: "licenses:a".into(),
+            table: "licenses".into(),
+            source: "registry:r1".into(),
+            target: "person:john".into(),
+            direction: Direction::Incoming,
+            data: json!({ "status": "active" }),
+        }]
+    );
+}
+
+#[test]
+fn edges_to_records_left_out_are_dropped() {
+    // Arrange
+    let mut graph = Graph::new(&person("john"), 1);
+    graph.add_node(&person("john"), 0, json!({}));
+
+    // Act
+    graph.add_edges(
+        "licenses",
+        &frontier(&["person:john"]),
+        vec![license("a", "gone", "john")],
+    );
+
+    // Assert
+    assert!(graph.edges.is_empty());
+}
+
+#[test]
+fn discovery_stops_at_the_node_limit() {
+    // Arrange
+    let mut graph = Graph::new(&person("john"), 1);
+    graph.add_node(&person("john"), 0, json!({}));
+    let rows: Vec<EdgeRow> = (0..MAX_GRAPH_NODES + 10)
+        .map(|i| license(&i.to_string(), &i.to_string(), "john"))
+        .collect();
+    let mut found = Vec::new();
+
+    // Act
+    graph.discover(&frontier(&["person:john"]), &rows, &mut found);
+
+    // Assert
+    assert_eq!(found.len(), MAX_GRAPH_NODES - 1);
+    assert!(graph.truncated);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_walks_the_allowed_edges_from_the_root() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Graphed")
+        .with_license(101)
+        .with_license(202)
+        .insert(&app.db)
+        .await;
+
+    // Act
+    let response = minreq::get(format!(
+        "{}/graph/export?root={}&depth=1",
+        app.address, doc.person
+    ))
+    .send()
+    .unwrap();
+
+    // Assert
+    let graph: serde_json::Value = response.assert_status(200).data();
+    assert_eq!(graph["root"], doc.person.to_string());
+    let nodes = graph["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[0]["table"], "person");
+    assert_eq!(nodes[0]["depth"], 0);
+    let edges = graph["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 2);
+    assert!(edges
+        .iter()
+        .all(|edge| edge["target"] == doc.person.to_string() && edge["direction"] == "incoming"));
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn export_refuses_bad_roots_and_depths() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for query in [
+        "root=secrets:x",
+        "root=person",
+        "root=person:x&depth=0",
+        "root=person:x&depth=9",
+    ] {
+        // Act
+        let response = minreq::get(format!("{}/graph/export?{query}", app.address))
+            .send()
+            .unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 400, "{query}");
+    }
+}
@@ -0,0 +1,160 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use surreal_simple::client::NewPerson;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::history::{history, history_table, set_with_history, Version};
+use surreal_simple::surreal::schema::indexes::INDEXES;
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+
+#[test]
+fn versions_are_kept_in_a_table_per_table_with_unique_numbers() {
+    // Act
+    let table = history_table("person");
+    let index = INDEXES.iter().find(|index| index.table == table);
+
+    // Assert
+    assert_eq!(table, "person_history");
+    assert_eq!(
+        index.unwrap().define_statement(),
+        "DEFINE INDEX person_history_version ON TABLE person_history FIELDS record, version UNIQUE;"
+    );
+}
+
+#[test]
+fn reverting_a_deleted_record_is_gone() {
+    // Act
+    let error = Error::Deleted("person:john".into());
+
+    // Assert
+    assert_eq!(error.status(), StatusCode::GONE);
+    assert_eq!(error.to_string(), "`person:john` has been deleted");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn updates_keep_the_previous_version() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    let id = Uuid::new_v4().to_string();
+    let names: Vec<String> = (1..=3).map(|n| format!("history-{id}-{n}")).collect();
+    client
+        .create_person_with_id(&id, &NewPerson::new(&names[0]))
+        .await
+        .unwrap();
+
+    // Act
+    for name in &names[1..] {
+        client
+            .update_person(&id, &NewPerson::new(name))
+            .await
+            .unwrap();
+    }
+    let history: Vec<Version> = minreq::get(format!("{}/person/{id}/history", app.address))
+        .send()
+        .unwrap()
+        .data();
+    let first: Version = minreq::get(format!("{}/person/{id}/versions/1", app.address))
+        .send()
+        .unwrap()
+        .data();
+    let missing = minreq::get(format!("{}/person/{id}/versions/3", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    let versions: Vec<u64> = history.iter().map(|version| version.version).collect();
+    assert_eq!(versions, [1, 2]);
+    assert_eq!(history[1].data["name"], names[1]);
+    assert_eq!(first.data["name"], names[0]);
+    assert_eq!(first.actor, None);
+    missing.problem(404);
+
+    // Teardown
+    client.delete_person(&id).await.unwrap();
+    app.db
+        .query("DELETE person_history WHERE record = type::thing('person', $id)")
+        .bind(("id", id.clone()))
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_failed_update_keeps_no_version() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("History rollback").insert(&app.db).await;
+
+    // Act
+    let result = set_with_history(
+        &app.db,
+        &doc.person,
+        "id = $data",
+        &json!("person:someone_else"),
+        None,
+    )
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+    let versions = history(&app.db, &doc.person).await.unwrap();
+    assert!(versions.is_empty(), "{versions:?}");
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reverts_are_new_versions_and_need_the_record() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    let id = Uuid::new_v4().to_string();
+    let names: Vec<String> = (1..=2).map(|n| format!("revert-{id}-{n}")).collect();
+    client
+        .create_person_with_id(&id, &NewPerson::new(&names[0]))
+        .await
+        .unwrap();
+    client
+        .update_person(&id, &NewPerson::new(&names[1]))
+        .await
+        .unwrap();
+
+    // Act
+    let reverted: serde_json::Value = minreq::post(format!("{}/person/{id}/revert/1", app.address))
+        .send()
+        .unwrap()
+        .data();
+    let unknown = minreq::post(format!("{}/person/{id}/revert/9", app.address))
+        .send()
+        .unwrap();
+    let history: Vec<Version> = minreq::get(format!("{}/person/{id}/history", app.address))
+        .send()
+        .unwrap()
+        .data();
+    client.delete_person(&id).await.unwrap();
+    let deleted = minreq::post(format!("{}/person/{id}/revert/1", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    assert_eq!(reverted["name"], names[0]);
+    unknown.problem(404);
+    let kept: Vec<&serde_json::Value> = history
+        .iter()
+        .map(|version| &version.data["name"])
+        .collect();
+    assert_eq!(kept, [&json!(names[0]), &json!(names[1])]);
+    deleted.problem(410);
+
+    // Teardown
+    app.db
+        .query("DELETE person_history WHERE record = type::thing('person', $id)")
+        .bind(("id", id.clone()))
+        .await
+        .unwrap();
+}
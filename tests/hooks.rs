@@ -0,0 +1,138 @@
+use futures_core::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use surreal_simple::api::hooks::{Mutation, MutationHook, MutationHooks};
+use surreal_simple::error::Error;
+
+/// Records every call as `"<hook>:<stage>:<record>"` and optionally fails one
+/// stage.
+struct Recorder {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+    fail_on: Option<&'static str>,
+}
+
+impl Recorder {
+    fn record(&self, stage: &'static str, mutation: Mutation<'_>) -> Result<(), Error> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("{}:{stage}:{mutation}", self.name));
+        match self.fail_on {
+            Some(failing) if failing == stage => Err(Error::InvalidBody(stage.into())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MutationHook for Recorder {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn before_create<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _data: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move { self.record("before_create", mutation) })
+    }
+
+    fn after_create<'a>(
+        &'a self,
+        mutation: Mutation<'a>,
+        _record: &'a Value,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move { self.record("after_create", mutation) })
+    }
+}
+
+fn hooks(fail_on: Option<&'static str>) -> (MutationHooks, Arc<Mutex<Vec<String>>>) {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let hooks = MutationHooks::new()
+        .with(Recorder {
+            name: "first",
+            calls: calls.clone(),
+            fail_on,
+        })
+        .with(Recorder {
+            name: "second",
+            calls: calls.clone(),
+            fail_on: None,
+        });
+    (hooks, calls)
+}
+
+const JOHN: Mutation<'static> = Mutation {
+    table: "person",
+    id: "john",
+};
+
+#[tokio::test]
+async fn hooks_run_in_registration_order() {
+    // Arrange
+    let (hooks, calls) = hooks(None);
+
+    // Act
+    hooks
+        .before_create(JOHN, &json!({ "name": "John" }))
+        .await
+        .unwrap();
+    hooks.after_create(JOHN, &json!({ "name": "John" })).await;
+
+    // Assert
+    assert_eq!(hooks.names(), ["first", "second"]);
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "first:before_create:person:john",
+            "second:before_create:person:john",
+            "first:after_create:person:john",
+            "second:after_create:person:john",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_failing_before_hook_aborts_the_rest() {
+    // Arrange
+    let (hooks, calls) = hooks(Some("before_create"));
+
+    // Act
+    let result = hooks.before_create(JOHN, &json!({ "name": "John" })).await;
+
+    // Assert
+    assert!(matches!(result, Err(Error::InvalidBody(_))));
+    assert_eq!(*calls.lock().unwrap(), ["first:before_create:person:john"]);
+}
+
+#[tokio::test]
+async fn a_failing_after_hook_does_not_stop_the_others() {
+    // Arrange
+    let (hooks, calls) = hooks(Some("after_create"));
+
+    // Act
+    hooks.after_create(JOHN, &json!({ "name": "John" })).await;
+
+    // Assert
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            "first:after_create:person:john",
+            "second:after_create:person:john",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unimplemented_stages_default_to_doing_nothing() {
+    // Arrange
+    let (hooks, calls) = hooks(None);
+
+    // Act
+    let result = hooks.before_delete(JOHN).await;
+
+    // Assert
+    assert!(result.is_ok());
+    assert!(calls.lock().unwrap().is_empty());
+}
@@ -0,0 +1,155 @@
+use serde_json::json;
+use std::collections::BTreeMap;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::ids::{
+    differing_field, ulid_from_id, ulid_timestamp, ulid_to_id, IdGenerator, IdSettings, IdStrategy,
+};
+use surrealdb::sql::Id;
+use ulid::Ulid;
+use uuid::Uuid;
+
+fn generator(strategy: IdStrategy) -> IdGenerator {
+    IdGenerator::new(&IdSettings {
+        tables: BTreeMap::from([("thing".to_string(), strategy)]),
+        snowflake_node: 3,
+        natural_keys: BTreeMap::from([("thing".to_string(), vec!["code".to_string()])]),
+    })
+}
+
+#[test]
+fn client_ids_are_validated_and_generated_when_missing() {
+    // Arrange
+    let ids = generator(IdStrategy::Client);
+
+    // Act
+    let supplied = ids.resolve("thing", Some("john_doe-1"));
+    let hostile = ids.resolve("thing", Some("1;DELETE person"));
+    let generated = ids.resolve("thing", None).unwrap();
+
+    // Assert
+    assert_eq!(supplied.unwrap(), "john_doe-1");
+    assert!(matches!(hostile, Err(Error::InvalidId(_))));
+    assert!(Uuid::parse_str(&generated).is_ok());
+}
+
+#[test]
+fn unlisted_tables_use_client_ids() {
+    assert_eq!(
+        generator(IdStrategy::Uuid).strategy("other"),
+        IdStrategy::Client
+    );
+}
+
+#[test]
+fn uuid_tables_only_accept_uuids() {
+    // Arrange
+    let ids = generator(IdStrategy::Uuid);
+    let uuid = Uuid::new_v4().to_string();
+
+    // Act
+    let valid = ids.resolve("thing", Some(&uuid));
+    let invalid = ids.resolve("thing", Some("1"));
+
+    // Assert
+    assert_eq!(valid.unwrap(), uuid);
+    assert!(matches!(invalid, Err(Error::InvalidId(_))));
+}
+
+#[test]
+fn snowflakes_are_unique_and_increasing() {
+    // Arrange
+    let ids = generator(IdStrategy::Snowflake);
+
+    // Act
+    let generated: Vec<u64> = (0..10_000)
+        .map(|_| ids.resolve("thing", None).unwrap().parse().unwrap())
+        .collect();
+
+    // Assert
+    assert!(generated.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!((generated[0] >> 12) & 0x3ff, 3, "node bits");
+    assert!(ids.resolve("thing", Some("abc")).is_err());
+}
+
+#[test]
+fn ulids_sort_in_creation_order() {
+    // Arrange
+    let ids = generator(IdStrategy::Ulid);
+
+    // Act
+    let generated: Vec<String> = (0..1_000)
+        .map(|_| ids.resolve("thing", None).unwrap())
+        .collect();
+
+    // Assert
+    assert!(generated.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(ids.resolve("thing", Some(&generated[0])).is_ok());
+    assert!(ids.resolve("thing", Some("not-a-ulid")).is_err());
+}
+
+#[test]
+fn ulids_round_trip_through_record_ids() {
+    // Arrange
+    let before = chrono::Utc::now().timestamp_millis();
+    let ulid = Ulid::new();
+
+    // Act
+    let id = ulid_to_id(ulid);
+    let back = ulid_from_id(&id);
+    let created = ulid_timestamp(ulid).timestamp_millis();
+
+    // Assert
+    assert_eq!(back, Some(ulid));
+    assert_eq!(ulid_from_id(&Id::Number(7)), None);
+    assert!((before..=before + 1_000).contains(&created));
+}
+
+#[test]
+fn natural_ids_come_from_the_key_fields() {
+    // Arrange
+    let ids = generator(IdStrategy::Natural);
+    let content = json!({"code": "ab-1", "name": "Thing"});
+
+    // Act
+    let derived = ids.resolve_record("thing", None, &content);
+    let matching = ids.resolve_record("thing", Some("ab-1"), &content);
+    let other = ids.resolve_record("thing", Some("ab-2"), &content);
+    let missing = ids.resolve_record("thing", None, &json!({"name": "Thing"}));
+    let numeric = ids.resolve_record("thing", None, &json!({"code": 7}));
+
+    // Assert
+    assert_eq!(derived.unwrap(), "ab-1");
+    assert_eq!(matching.unwrap(), "ab-1");
+    assert!(matches!(other, Err(Error::InvalidId(_))));
+    assert!(matches!(missing, Err(Error::InvalidBody(_))));
+    assert_eq!(numeric.unwrap(), "7");
+}
+
+#[test]
+fn natural_ids_need_the_record() {
+    // Act
+    let resolved = generator(IdStrategy::Natural).resolve("thing", None);
+
+    // Assert
+    assert!(matches!(resolved, Err(Error::InvalidId(_))));
+}
+
+#[test]
+fn only_fields_sent_are_compared_with_the_existing_record() {
+    // Arrange
+    let existing = json!({"id": "thing:7", "code": 7, "name": "Thing", "age": 3});
+
+    // Act & Assert
+    assert_eq!(
+        differing_field(&json!({"code": 7, "name": "Thing"}), &existing),
+        None
+    );
+    assert_eq!(
+        differing_field(&json!({"code": 7, "name": "Other"}), &existing),
+        Some("name".to_string())
+    );
+    assert_eq!(
+        differing_field(&json!({"code": 7, "colour": "red"}), &existing),
+        Some("colour".to_string())
+    );
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use surreal_simple::api::{ColumnMapping, ImportReport};
+use surreal_simple::error::Error;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+
+fn mapping(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(header, field)| (header.to_string(), field.to_string()))
+        .collect()
+}
+
+#[test]
+fn headers_named_like_fields_map_without_a_mapping() {
+    // Act
+    let columns = ColumnMapping::resolve(&["email", "name", "id"], &BTreeMap::new()).unwrap();
+
+    // Assert
+    assert_eq!(
+        columns,
+        ColumnMapping {
+            id: Some(2),
+            name: 1
+        }
+    );
+}
+
+#[test]
+fn mapping_renames_columns() {
+    // Act
+    let columns = ColumnMapping::resolve(
+        &["Person Id", "Full Name"],
+        &mapping(&[("Full Name", "name"), ("Person Id", "id")]),
+    )
+    .unwrap();
+
+    // Assert
+    assert_eq!(
+        columns,
+        ColumnMapping {
+            id: Some(0),
+            name: 1
+        }
+    );
+}
+
+#[test]
+fn bad_mappings_are_rejected() {
+    for (headers, pairs) in [
+        // unknown field
+        (vec!["Full Name"], vec![("Full Name", "nickname")]),
+        // header not in the upload
+        (vec!["name"], vec![("Full Name", "name")]),
+        // two columns for one field
+        (vec!["name", "Full Name"], vec![("Full Name", "name")]),
+        // nothing for `name`
+        (vec!["Full Name"], vec![]),
+    ] {
+        // Act
+        let result = ColumnMapping::resolve(&headers, &mapping(&pairs));
+
+        // Assert
+        assert!(
+            matches!(result, Err(Error::InvalidQuery(_))),
+            "{headers:?} {pairs:?}: {result:?}"
+        );
+    }
+}
+
+#[test]
+fn rows_without_a_name_are_invalid() {
+    // Arrange
+    let columns = ColumnMapping { id: None, name: 0 };
+    let row = csv::StringRecord::from(vec![""]);
+
+    // Act
+    let person = columns.person(&row);
+
+    // Assert
+    assert!(person.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn import_reports_rejected_rows() {
+    // Arrange
+    let app = spawn_app().await;
+    let fresh = format!("import-{}", uuid::Uuid::new_v4().simple());
+    let csv = format!("Person Id,Full Name\n{fresh},Ada\nnot valid!,Bad Id\n,\n");
+
+    // Act
+    let response = minreq::post(format!(
+        "{}/people/import/csv?mapping=%7B%22Person%20Id%22%3A%22id%22%2C%22Full%20Name%22%3A%22name%22%7D",
+        app.address
+    ))
+    .with_header("Content-Type", "text/csv")
+    .with_body(csv)
+    .send()
+    .unwrap();
+
+    // Assert
+    let report: ImportReport = response.assert_status(200).data();
+    assert_eq!(report.imported, 1);
+    let lines: Vec<u64> = report.rejected.iter().map(|row| row.line).collect();
+    assert_eq!(lines, vec![3, 4]);
+
+    // Teardown
+    let _ = app
+        .db
+        .query("DELETE type::thing('person', $id)")
+        .bind(("id", fresh))
+        .await;
+}
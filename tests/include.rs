@@ -0,0 +1,123 @@
+use serde_json::json;
+use std::collections::BTreeMap;
+use surreal_simple::api::Includes;
+use surreal_simple::surreal::edge::{EdgeAllowList, EdgeEndpoints, EdgeSettings};
+
+fn edges() -> EdgeAllowList {
+    let mut settings = EdgeSettings::default();
+    settings.allowed.insert(
+        "audits".into(),
+        EdgeEndpoints {
+            from: "registry".into(),
+            to: "registry".into(),
+        },
+    );
+    EdgeAllowList::new(&settings)
+}
+
+#[test]
+fn nothing_asked_for_includes_nothing() {
+    // Act
+    let includes = Includes::parse(&edges(), "person", None).unwrap();
+
+    // Assert
+    assert_eq!(includes, None);
+}
+
+#[test]
+fn edge_and_node_are_selected_as_nested_subqueries() {
+    // Act
+    let includes = Includes::parse(&edges(), "person", Some("licenses.registry"))
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        includes.select(),
+        "(SELECT *, (SELECT * FROM registry WHERE id = $parent.in) AS registry \
+         FROM licenses WHERE out = $parent.id) AS licenses"
+    );
+}
+
+#[test]
+fn paths_sharing_a_prefix_are_merged() {
+    // Act
+    let merged = Includes::parse(&edges(), "person", Some("licenses,licenses.registry"))
+        .unwrap()
+        .unwrap();
+    let single = Includes::parse(&edges(), "person", Some("licenses.registry"))
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    assert_eq!(merged, single);
+}
+
+#[test]
+fn unknown_edges_and_wrong_tables_are_refused() {
+    // Act
+    let unknown = Includes::parse(&edges(), "person", Some("friends")).unwrap_err();
+    let not_touching = Includes::parse(&edges(), "person", Some("audits")).unwrap_err();
+    let wrong_table = Includes::parse(&edges(), "person", Some("licenses.person")).unwrap_err();
+
+    // Assert
+    assert!(unknown.to_string().contains("friends"), "{unknown}");
+    assert!(
+        not_touching.to_string().contains("audits"),
+        "{not_touching}"
+    );
+    assert!(
+        wrong_table.to_string().contains("registry"),
+        "{wrong_table}"
+    );
+}
+
+#[test]
+fn paths_deeper_than_the_limit_are_refused() {
+    // Act
+    let within = Includes::parse(
+        &edges(),
+        "person",
+        Some("licenses.registry.audits.registry"),
+    );
+    let deeper = Includes::parse(
+        &edges(),
+        "person",
+        Some("licenses.registry.audits.registry.audits"),
+    );
+
+    // Assert
+    assert!(within.is_ok());
+    assert!(deeper.unwrap_err().to_string().contains("deeper"));
+}
+
+#[test]
+fn take_unwraps_expanded_nodes() {
+    // Arrange
+    let includes = Includes::parse(&edges(), "person", Some("licenses.registry"))
+        .unwrap()
+        .unwrap();
+    let mut row = json!({
+        "name": "Ada",
+        "licenses": [
+            {"id": "licenses:one", "registry": [{"id": "registry:dmv", "registration": 7}]},
+            {"id": "licenses:two", "registry": []},
+        ],
+    });
+
+    // Act
+    let taken = includes.take(&mut row);
+
+    // Assert
+    assert_eq!(row, json!({"name": "Ada"}));
+    assert_eq!(
+        taken.into_iter().collect::<BTreeMap<_, _>>(),
+        BTreeMap::from([(
+            "licenses".to_string(),
+            json!([
+                {"id": "licenses:one", "registry": {"id": "registry:dmv", "registration": 7}},
+                {"id": "licenses:two", "registry": null},
+            ])
+        )])
+    );
+}
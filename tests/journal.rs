@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use surreal_simple::surreal::journal::{BatchJournal, PendingBatch};
+use surreal_simple::surreal::query_manager::{TransactionMonitor, TransactionSettings};
+use uuid::Uuid;
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("batch-journal-{}", Uuid::new_v4()))
+}
+
+fn batch(chunks: &[&[&str]]) -> PendingBatch {
+    PendingBatch::new(
+        chunks
+            .iter()
+            .map(|chunk| chunk.iter().map(|sql| sql.to_string()).collect())
+            .collect(),
+    )
+}
+
+#[test]
+fn batches_are_recorded_until_removed() {
+    // Arrange
+    let dir = scratch_dir();
+    let journal = BatchJournal::new(&dir);
+    let first = batch(&[&["CREATE person:a"]]);
+    let mut second = batch(&[&["CREATE person:b"], &["CREATE person:c"]]);
+
+    // Act
+    journal.record(&first).unwrap();
+    journal.record(&second).unwrap();
+    second.committed = 1;
+    journal.record(&second).unwrap();
+    let pending = journal.pending().unwrap();
+    let removed = journal.remove(&first.id).unwrap();
+    let removed_again = journal.remove(&first.id).unwrap();
+
+    // Assert
+    assert_eq!(pending, [first.clone(), second.clone()]);
+    assert_eq!(
+        journal.get(&second.id).unwrap().unwrap().remaining().len(),
+        1
+    );
+    assert!(removed);
+    assert!(!removed_again);
+    assert_eq!(journal.pending().unwrap(), [second]);
+
+    // Teardown
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_directories_and_unreadable_entries_are_not_errors() {
+    // Arrange
+    let dir = scratch_dir();
+    let journal = BatchJournal::new(&dir);
+    let empty = journal.pending().unwrap();
+    let kept = batch(&[&["CREATE person:a"]]);
+    journal.record(&kept).unwrap();
+    std::fs::write(dir.join("01H0000000000000000000000.json"), b"{ torn").unwrap();
+
+    // Act
+    let pending = journal.pending().unwrap();
+
+    // Assert
+    assert!(empty.is_empty());
+    assert_eq!(pending, [kept]);
+
+    // Teardown
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn ids_cannot_reach_outside_the_journal() {
+    // Arrange
+    let journal = BatchJournal::new(scratch_dir());
+
+    // Act
+    let result = journal.get("../../etc/passwd");
+
+    // Assert
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn the_journal_follows_the_settings() {
+    // Arrange
+    let dir = scratch_dir();
+    let monitor = TransactionMonitor::new(&TransactionSettings::default());
+
+    // Act
+    let before = monitor.journal();
+    monitor.configure(&TransactionSettings {
+        journal_dir: Some(dir.clone()),
+        ..TransactionSettings::default()
+    });
+
+    // Assert
+    assert_eq!(before, None);
+    assert_eq!(monitor.journal(), Some(BatchJournal::new(&dir)));
+    assert_eq!(monitor.settings().journal_dir, Some(dir));
+}
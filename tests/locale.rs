@@ -0,0 +1,116 @@
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use axum::routing::get;
+use axum::{middleware, Router};
+use serde_json::Value;
+use surreal_simple::api::{locale, Catalogs, MessageCatalog};
+use surreal_simple::error::Error;
+use tower::ServiceExt;
+
+struct German;
+
+impl MessageCatalog for German {
+    fn language(&self) -> &'static str {
+        "de"
+    }
+
+    fn title(&self, status: StatusCode) -> Option<String> {
+        (status == StatusCode::NOT_FOUND).then(|| "Nicht gefunden".into())
+    }
+
+    fn detail(&self, error: &Error) -> Option<String> {
+        match error {
+            Error::NotFound(record) => Some(format!("`{record}` wurde nicht gefunden")),
+            _ => None,
+        }
+    }
+}
+
+fn catalogs() -> Catalogs {
+    Catalogs::new().with(German)
+}
+
+async fn get_error(app: Router, accept_language: Option<&str>) -> (HeaderMap, Value) {
+    let mut request = Request::builder().uri("/missing");
+    if let Some(accept_language) = accept_language {
+        request = request.header(header::ACCEPT_LANGUAGE, accept_language);
+    }
+    let response = app
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap();
+    (parts.headers, serde_json::from_slice(&body).unwrap())
+}
+
+fn app(error: fn() -> Error) -> Router {
+    Router::new()
+        .route("/missing", get(move || async move { error() }))
+        .layer(middleware::from_fn_with_state(catalogs(), locale))
+}
+
+#[test]
+fn the_most_preferred_language_with_a_catalog_wins() {
+    // Arrange
+    let catalogs = catalogs();
+
+    // Act & Assert
+    assert_eq!(catalogs.negotiate(None).language(), "en");
+    assert_eq!(catalogs.negotiate(Some("de")).language(), "de");
+    assert_eq!(catalogs.negotiate(Some("de-AT")).language(), "de");
+    assert_eq!(catalogs.negotiate(Some("fr, de;q=0.5")).language(), "de");
+    assert_eq!(
+        catalogs.negotiate(Some("de;q=0.4, en;q=0.9")).language(),
+        "en"
+    );
+    assert_eq!(catalogs.negotiate(Some("de;q=0, *")).language(), "en");
+    assert_eq!(catalogs.negotiate(Some("fr")).language(), "en");
+}
+
+#[test]
+fn registering_a_language_again_replaces_it() {
+    // Act
+    let catalogs = catalogs().with(German);
+
+    // Assert
+    assert_eq!(catalogs.languages(), ["en", "de"]);
+}
+
+#[tokio::test]
+async fn errors_are_rendered_in_the_negotiated_language() {
+    // Act
+    let (headers, body) = get_error(app(|| Error::NotFound("person:1".into())), Some("de")).await;
+
+    // Assert
+    assert_eq!(headers[header::CONTENT_LANGUAGE], "de");
+    assert_eq!(body["errors"][0]["title"], "Nicht gefunden");
+    assert_eq!(
+        body["errors"][0]["detail"],
+        "`person:1` wurde nicht gefunden"
+    );
+}
+
+#[tokio::test]
+async fn untranslated_messages_fall_back_to_english() {
+    // Act
+    let (headers, body) = get_error(app(|| Error::Unauthorized), Some("de")).await;
+
+    // Assert
+    assert_eq!(headers[header::CONTENT_LANGUAGE], "de");
+    assert_eq!(body["errors"][0]["title"], "Unauthorized");
+    assert_eq!(
+        body["errors"][0]["detail"],
+        "missing or invalid admin token"
+    );
+}
+
+#[tokio::test]
+async fn english_is_the_default() {
+    // Act
+    let (headers, body) = get_error(app(|| Error::NotFound("person:1".into())), None).await;
+
+    // Assert
+    assert_eq!(headers[header::CONTENT_LANGUAGE], "en");
+    assert_eq!(body["errors"][0]["detail"], "`person:1` not found");
+}
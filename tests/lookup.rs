@@ -0,0 +1,87 @@
+use serde_json::json;
+use surreal_simple::api::{PeopleLookup, PersonView, WithId, MAX_LOOKUP_IDS};
+use surreal_simple::error::Error;
+use surrealdb::sql::Thing;
+
+mod support;
+use support::app::spawn_app;
+use support::PersonFixture;
+
+fn ids(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn row(id: &str, name: &str) -> WithId<PersonView> {
+    serde_json::from_value(json!({ "id": format!("person:{id}"), "name": name })).unwrap()
+}
+
+#[test]
+fn bare_and_whole_ids_name_the_same_people_once() {
+    // Act
+    let records = PeopleLookup::records(&ids(&["john", "person:jane", "person:john"])).unwrap();
+
+    // Assert
+    assert_eq!(
+        records,
+        [
+            ("john".to_string(), Thing::from(("person", "john"))),
+            ("person:jane".to_string(), Thing::from(("person", "jane"))),
+        ]
+    );
+}
+
+#[test]
+fn lookups_are_bounded_and_limited_to_people() {
+    // Arrange
+    let too_many: Vec<String> = (0..=MAX_LOOKUP_IDS).map(|i| i.to_string()).collect();
+
+    // Act
+    let too_many = PeopleLookup::records(&too_many);
+    let registry = PeopleLookup::records(&ids(&["registry:dmv"]));
+    let empty = PeopleLookup::records(&ids(&[""]));
+
+    // Assert
+    assert!(matches!(too_many, Err(Error::InvalidBody(_))));
+    assert!(matches!(registry, Err(Error::InvalidId(_))));
+    assert!(matches!(empty, Err(Error::InvalidId(_))));
+}
+
+#[test]
+fn found_people_keep_the_order_asked_for_and_the_rest_are_missing() {
+    // Arrange
+    let records = PeopleLookup::records(&ids(&["jane", "nobody", "person:john"])).unwrap();
+    let rows = vec![row("john", "John"), row("jane", "Jane")];
+
+    // Act
+    let lookup = PeopleLookup::new(records, rows);
+
+    // Assert
+    let found: Vec<String> = lookup.found.iter().map(|row| row.id.to_string()).collect();
+    assert_eq!(found, ["person:jane", "person:john"]);
+    assert_eq!(lookup.missing, ["nobody"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lookup_reads_many_people_in_one_request() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Looked Up").insert(&app.db).await;
+
+    let person = doc.person.to_string();
+
+    // Act
+    let lookup = app
+        .client()
+        .lookup_people(&[&person, "nobody"])
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(lookup.found.len(), 1);
+    assert_eq!(lookup.found[0].id, person);
+    assert_eq!(lookup.found[0].name, "Looked Up");
+    assert_eq!(lookup.missing, ["nobody"]);
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
@@ -0,0 +1,188 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::Router;
+use surreal_simple::api::{
+    is_write, read_only, writes, Maintenance, MaintenanceSettings, ReadOnlyToggle, MAINTENANCE,
+};
+use surreal_simple::error::Error;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/person/:id",
+            get(|| async { "read" }).post(|| async { "written" }),
+        )
+        .route("/people/lookup", post(|| async { "looked up" }))
+        .route("/admin/read-only", post(|| async { "switched" }))
+        .route(
+            "/admin/snapshot",
+            get(|| async { "exported" }).post(|| async { "restored" }),
+        )
+        .route(
+            "/sneaky",
+            get(|| async {
+                match MAINTENANCE.reject_write("UPDATE person:1 SET age = 3") {
+                    Some(rejected) => Error::from(rejected).status().as_u16().to_string(),
+                    None => "allowed".into(),
+                }
+            }),
+        )
+        .layer(middleware::from_fn(read_only))
+}
+
+fn request(method: Method, uri: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn text(app: &Router, method: Method, uri: &str) -> (StatusCode, String) {
+    let response = app.clone().oneshot(request(method, uri)).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+#[test]
+fn only_statements_that_write_are_writes() {
+    for sql in [
+        "CREATE person:? CONTENT $data",
+        "update person SET age = 3",
+        "BEGIN; DELETE person:1; COMMIT",
+        "RELATE person:1->knows->person:2",
+        "INSERT INTO person $rows",
+        "DEFINE INDEX name ON person FIELDS name",
+    ] {
+        assert!(writes(sql), "{sql}");
+    }
+    for sql in [
+        "SELECT * FROM person:?",
+        "SELECT * FROM person WHERE name = 'create'",
+        "SELECT updated_at, deleted FROM person",
+        "INFO FOR DB",
+    ] {
+        assert!(!writes(sql), "{sql}");
+    }
+}
+
+#[test]
+fn writes_are_mutating_methods_outside_the_switch_and_health() {
+    assert!(is_write(&Method::POST, "/person/1"));
+    assert!(is_write(&Method::PUT, "/person/1"));
+    assert!(is_write(&Method::PATCH, "/person/1"));
+    assert!(is_write(&Method::DELETE, "/people"));
+    assert!(!is_write(&Method::GET, "/person/1"));
+    assert!(!is_write(&Method::POST, "/people/lookup"));
+    assert!(is_write(&Method::POST, "/admin/snapshot"));
+    assert!(is_write(&Method::DELETE, "/admin/slow-queries"));
+    assert!(!is_write(&Method::GET, "/admin/snapshot"));
+    assert!(!is_write(&Method::POST, "/admin/explain"));
+    assert!(!is_write(&Method::PUT, "/admin/read-only"));
+    assert!(!is_write(&Method::POST, "/health/self-test"));
+}
+
+#[test]
+fn toggling_keeps_the_message_and_records_since_when() {
+    // Arrange
+    let maintenance = Maintenance::new(&MaintenanceSettings::default());
+
+    // Act
+    let on = maintenance
+        .set(ReadOnlyToggle {
+            read_only: true,
+            message: Some("migrating to v2".into()),
+        })
+        .unwrap();
+    let still_on = maintenance
+        .set(ReadOnlyToggle {
+            read_only: true,
+            message: None,
+        })
+        .unwrap();
+    let off = maintenance
+        .set(ReadOnlyToggle {
+            read_only: false,
+            message: None,
+        })
+        .unwrap();
+
+    // Assert
+    assert!(on.settings.read_only);
+    assert!(on.since.is_some());
+    assert_eq!(still_on.since, on.since);
+    assert_eq!(still_on.settings.message, "migrating to v2");
+    assert!(!off.settings.read_only);
+    assert_eq!(off.since, None);
+    assert!(matches!(
+        maintenance.set(ReadOnlyToggle {
+            read_only: true,
+            message: Some(" ".into()),
+        }),
+        Err(Error::InvalidBody(_))
+    ));
+    assert!(!maintenance.is_read_only());
+}
+
+#[test]
+fn settings_need_a_message_and_a_retry_after() {
+    let settings = MaintenanceSettings {
+        read_only: true,
+        message: "".into(),
+        retry_after_secs: 0,
+    };
+
+    assert_eq!(settings.problems().len(), 2);
+    assert!(MaintenanceSettings::default().problems().is_empty());
+}
+
+// One test, since read-only mode is process-wide.
+#[tokio::test]
+async fn read_only_mode_turns_writes_away_and_lets_reads_through() {
+    // Arrange
+    let app = app();
+    MAINTENANCE.configure(&MaintenanceSettings {
+        read_only: true,
+        message: "back in five".into(),
+        retry_after_secs: 30,
+    });
+
+    // Act
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, "/person/1"))
+        .await
+        .unwrap();
+    let read = text(&app, Method::GET, "/person/1").await;
+    let lookup = text(&app, Method::POST, "/people/lookup").await;
+    let admin = text(&app, Method::POST, "/admin/read-only").await;
+    let restore = text(&app, Method::POST, "/admin/snapshot").await;
+    let export = text(&app, Method::GET, "/admin/snapshot").await;
+    let sneaky = text(&app, Method::GET, "/sneaky").await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["retry-after"], "30");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["errors"][0]["detail"], "back in five");
+    assert_eq!(read, (StatusCode::OK, "read".into()));
+    assert_eq!(lookup, (StatusCode::OK, "looked up".into()));
+    assert_eq!(admin, (StatusCode::OK, "switched".into()));
+    assert_eq!(restore.0, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(export, (StatusCode::OK, "exported".into()));
+    assert_eq!(sneaky, (StatusCode::OK, "503".into()));
+
+    // Act: writes are back once it is switched off.
+    MAINTENANCE.configure(&MaintenanceSettings::default());
+    let written = text(&app, Method::POST, "/person/1").await;
+    let sneaky = text(&app, Method::GET, "/sneaky").await;
+
+    // Assert
+    assert_eq!(written, (StatusCode::OK, "written".into()));
+    assert_eq!(sneaky, (StatusCode::OK, "allowed".into()));
+}
@@ -0,0 +1,40 @@
+use axum::http::StatusCode;
+use surreal_simple::api::Registry;
+use surreal_simple::surreal::flags::Flag;
+use surreal_simple::surreal::model::SurrealModel;
+use surrealdb::sql::Thing;
+
+#[test]
+fn records_are_named_after_the_models_table() {
+    // Act
+    let registry = Registry::record("dmv");
+    let flag = Flag::record("beta");
+
+    // Assert
+    assert_eq!(registry, Thing::from(("registry", "dmv")));
+    assert_eq!(flag.to_string(), "flags:beta");
+}
+
+#[test]
+fn missing_records_are_not_found_under_their_full_id() {
+    // Act
+    let error = Registry::not_found("dmv");
+
+    // Assert
+    assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    assert!(error.to_string().contains("registry:dmv"), "{error}");
+}
+
+#[test]
+fn schema_hints_come_from_the_table_declarations() {
+    // Act
+    let definition = Registry::definition().unwrap();
+    let fields = Registry::fields();
+
+    // Assert
+    assert_eq!(definition.table, "registry");
+    assert_eq!(definition.fields, [("registration", "int")]);
+    assert_eq!(fields[0], "id");
+    assert!(fields.contains(&"registration"));
+    assert!(Flag::definition().is_none());
+}
@@ -0,0 +1,123 @@
+use axum::http::{header, Uri};
+use axum::response::IntoResponse;
+use futures_util::stream;
+use surreal_simple::api::{ApiResponse, Pagination, Streamed, TOTAL_COUNT};
+use surreal_simple::error::Error;
+
+mod support;
+use support::app::spawn_app;
+
+fn page(start: u32, count: usize, total: Option<usize>) -> Pagination {
+    Pagination {
+        start,
+        limit: 2,
+        count,
+        total,
+    }
+}
+
+#[test]
+fn first_page_links_to_next_and_last() {
+    // Arrange
+    let uri: Uri = "/people?name_starts_with=Pre&limit=2".parse().unwrap();
+
+    // Act
+    let links = page(0, 2, Some(5)).links(&uri).unwrap();
+
+    // Assert
+    assert_eq!(
+        links,
+        "</people?name_starts_with=Pre&start=2&limit=2>; rel=\"next\", \
+         </people?name_starts_with=Pre&start=4&limit=2>; rel=\"last\""
+    );
+}
+
+#[test]
+fn middle_page_links_every_way() {
+    // Arrange
+    let uri: Uri = "/people?start=2&limit=2&has_license=true".parse().unwrap();
+
+    // Act
+    let links = page(2, 2, Some(5)).links(&uri).unwrap();
+
+    // Assert
+    assert!(links.contains("</people?has_license=true&start=0&limit=2>; rel=\"first\""));
+    assert!(links.contains("</people?has_license=true&start=0&limit=2>; rel=\"prev\""));
+    assert!(links.contains("</people?has_license=true&start=4&limit=2>; rel=\"next\""));
+    assert!(links.contains("</people?has_license=true&start=4&limit=2>; rel=\"last\""));
+}
+
+#[test]
+fn last_page_has_no_next() {
+    // Arrange
+    let uri: Uri = "/people?start=4&limit=2".parse().unwrap();
+
+    // Act
+    let links = page(4, 1, Some(5)).links(&uri).unwrap();
+
+    // Assert
+    assert!(!links.contains("rel=\"next\""));
+    assert!(!links.contains("rel=\"last\""));
+}
+
+#[test]
+fn single_page_has_no_links() {
+    // Arrange
+    let uri: Uri = "/people?limit=2".parse().unwrap();
+
+    // Act & Assert
+    assert_eq!(page(0, 1, Some(1)).links(&uri), None);
+    assert_eq!(page(0, 0, Some(0)).links(&uri), None);
+}
+
+#[test]
+fn pagination_is_sent_as_headers() {
+    // Arrange
+    let uri: Uri = "/people?limit=2".parse().unwrap();
+
+    // Act
+    let response = ApiResponse::ok(vec![1, 2])
+        .with_pagination(page(0, 2, Some(3)), &uri)
+        .into_response();
+
+    // Assert
+    assert_eq!(response.headers()[TOTAL_COUNT], "3");
+    assert_eq!(
+        response.headers()[header::LINK],
+        "</people?start=2&limit=2>; rel=\"next\", </people?start=2&limit=2>; rel=\"last\""
+    );
+}
+
+#[test]
+fn streamed_lists_send_their_total() {
+    // Arrange
+    let pages = stream::iter([Ok::<_, Error>(vec![1, 2]), Ok(vec![3])]);
+
+    // Act
+    let response = Streamed::new(pages).with_total(3).into_response();
+
+    // Assert
+    assert_eq!(response.headers()[TOTAL_COUNT], "3");
+    assert!(!response.headers().contains_key(header::LINK));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn every_list_of_people_sends_the_total() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let totals: Vec<bool> = ["/people", "/people?limit=1", "/person/qry/people?limit=1"]
+        .iter()
+        .map(|route| {
+            minreq::get(format!("{}{route}", app.address))
+                .send()
+                .unwrap()
+                .headers
+                .contains_key("x-total-count")
+        })
+        .collect();
+
+    // Assert
+    assert_eq!(totals, [true, true, true]);
+}
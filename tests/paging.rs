@@ -0,0 +1,104 @@
+use axum::response::IntoResponse;
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use surreal_simple::api::Streamed;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::db::Database;
+use surreal_simple::surreal::paging::stream_table;
+
+mod support;
+use support::container::database_settings;
+use support::http::Envelope;
+use support::PersonFixture;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct Person {
+    name: String,
+}
+
+async fn body(response: axum::response::Response) -> String {
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn streamed_pages_form_one_envelope() {
+    // Arrange
+    let pages = stream::iter(vec![
+        Ok::<_, Error>(vec![serde_json::json!({ "name": "a" })]),
+        Ok(vec![]),
+        Ok(vec![
+            serde_json::json!({ "name": "b" }),
+            serde_json::json!({ "name": "c" }),
+        ]),
+    ]);
+
+    // Act
+    let body = body(Streamed::new(pages).into_response()).await;
+
+    // Assert
+    let envelope: Envelope<Vec<Person>> = serde_json::from_str(&body).unwrap();
+    let names: Vec<String> = envelope.data.unwrap().into_iter().map(|p| p.name).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert!(envelope.errors.is_empty());
+}
+
+#[tokio::test]
+async fn empty_streams_are_empty_arrays() {
+    // Arrange
+    let pages = stream::iter(Vec::<Result<Vec<Person>, Error>>::new());
+
+    // Act
+    let body = body(Streamed::new(pages).into_response()).await;
+
+    // Assert
+    let envelope: Envelope<Vec<Person>> = serde_json::from_str(&body).unwrap();
+    assert_eq!(envelope.data, Some(vec![]));
+}
+
+#[tokio::test]
+async fn errors_cut_the_body_short() {
+    // Arrange
+    let pages = stream::iter(vec![
+        Ok(vec![serde_json::json!({ "name": "a" })]),
+        Err(Error::Db),
+    ]);
+
+    // Act
+    let bytes = hyper::body::to_bytes(Streamed::new(pages).into_response().into_body()).await;
+
+    // Assert
+    assert!(bytes.is_err());
+}
+
+#[tokio::test]
+async fn stream_table_pages_through_every_record() {
+    // Arrange
+    let db = Database::new(&database_settings().await).await.unwrap();
+    let mut docs = Vec::new();
+    for i in 0..5 {
+        docs.push(
+            PersonFixture::new(format!("Paged {i}"))
+                .insert(&db.client)
+                .await,
+        );
+    }
+
+    // Act
+    let pages: Vec<Vec<Person>> = stream_table::<Person>(db.client.clone(), "person", 2)
+        .map(|page| page.unwrap())
+        .collect()
+        .await;
+
+    // Assert
+    assert!(pages.iter().all(|page| page.len() <= 2));
+    let names: Vec<String> = pages.into_iter().flatten().map(|p| p.name).collect();
+    for i in 0..5 {
+        assert!(names.contains(&format!("Paged {i}")), "{names:?}");
+    }
+
+    // Teardown
+    for doc in docs {
+        doc.teardown(&db.client).await;
+    }
+}
@@ -0,0 +1,145 @@
+use serde_json::json;
+use surreal_simple::api::{clean_tags, DeletePeopleQuery, PeopleQuery};
+use surreal_simple::error::Error;
+
+#[test]
+fn no_filters_means_no_where_clause() {
+    // Arrange
+    let query = PeopleQuery {
+        limit: Some(10),
+        ..Default::default()
+    };
+
+    // Act
+    let (clause, bindings) = query.filter();
+
+    // Assert
+    assert!(!query.is_empty());
+    assert_eq!(clause, "");
+    assert!(bindings.is_empty());
+}
+
+#[test]
+fn relation_filters_traverse_licenses_back_to_the_registry() {
+    // Arrange
+    let query = PeopleQuery {
+        has_license: Some(true),
+        license_number: Some(12345),
+        ..Default::default()
+    };
+
+    // Act
+    let (clause, bindings) = query.filter();
+
+    // Assert
+    assert_eq!(
+        clause,
+        " WHERE count(<-licenses) > 0 \
+         AND <-licenses<-registry.registration CONTAINS $license_number"
+    );
+    assert_eq!(bindings["license_number"], 12345);
+}
+
+#[test]
+fn has_license_false_matches_people_without_licenses() {
+    // Arrange
+    let query = PeopleQuery {
+        name_starts_with: Some("Jo".into()),
+        has_license: Some(false),
+        ..Default::default()
+    };
+
+    // Act
+    let (clause, bindings) = query.filter();
+
+    // Assert
+    assert_eq!(
+        clause,
+        " WHERE name >= $prefix AND string::startsWith(name, $prefix) \
+         AND count(<-licenses) = 0"
+    );
+    assert_eq!(bindings["prefix"], "Jo");
+}
+
+#[test]
+fn bulk_deletes_need_a_filter_or_confirmation() {
+    // Arrange
+    let unfiltered = DeletePeopleQuery::default();
+    let misconfirmed = DeletePeopleQuery {
+        confirm: Some("yes".into()),
+        ..Default::default()
+    };
+    let confirmed = DeletePeopleQuery {
+        confirm: Some("all".into()),
+        ..Default::default()
+    };
+
+    // Act
+    let unfiltered = unfiltered.selection();
+    let misconfirmed = misconfirmed.selection();
+    let (sql, bindings) = confirmed.selection().unwrap();
+
+    // Assert
+    assert!(matches!(unfiltered, Err(Error::InvalidQuery(_))));
+    assert!(matches!(misconfirmed, Err(Error::InvalidQuery(_))));
+    assert_eq!(sql, "SELECT VALUE id FROM person");
+    assert!(bindings.is_empty());
+}
+
+#[test]
+fn limited_bulk_deletes_pick_records_by_name() {
+    // Arrange
+    let query = DeletePeopleQuery {
+        has_license: Some(false),
+        limit: Some(10),
+        ..Default::default()
+    };
+
+    // Act
+    let (sql, _) = query.selection().unwrap();
+
+    // Assert
+    assert_eq!(
+        sql,
+        "SELECT VALUE id FROM person WHERE count(<-licenses) = 0 ORDER BY name LIMIT 10"
+    );
+}
+
+#[test]
+fn tag_filters_need_every_tag() {
+    // Arrange
+    let query = PeopleQuery {
+        tag: Some("vip, staff".into()),
+        ..Default::default()
+    };
+
+    // Act
+    let (clause, bindings) = query.filter();
+
+    // Assert
+    assert!(!query.is_empty());
+    assert_eq!(clause, " WHERE tags CONTAINSALL $tags");
+    assert_eq!(bindings["tags"], json!(["vip", "staff"]));
+}
+
+#[test]
+fn tags_are_trimmed_and_deduplicated() {
+    // Act
+    let tags = clean_tags(vec![" vip".into(), "staff".into(), "vip ".into()]).unwrap();
+
+    // Assert
+    assert_eq!(tags, ["vip", "staff"]);
+}
+
+#[test]
+fn empty_long_and_comma_tags_are_rejected() {
+    for tag in ["  ", "a,b", &"x".repeat(65)] {
+        assert!(
+            matches!(
+                clean_tags(vec![tag.to_string()]),
+                Err(Error::InvalidBody(_))
+            ),
+            "`{tag}` was accepted"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::{json, Value};
+use surreal_simple::surreal::db::{AuthMode, Database, DatabaseSettings};
+use surreal_simple::surreal::schema::permissions::{Permissions, Rule, OWNER};
+use surreal_simple::surreal::schema::tables::{define_statements, TableMode};
+use surrealdb::{engine::remote::ws::Client, Surreal};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+use support::container::database_settings;
+
+const SCOPE: &str = "person_owner";
+
+async fn query(db: &Surreal<Client>, sql: &str, record: &str) -> Vec<Value> {
+    db.query(sql)
+        .bind(("record", record))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap_or_default()
+}
+
+/// Creates a person who can sign in to [`SCOPE`] with their name and
+/// `secret`.
+async fn person(db: &Surreal<Client>) -> (String, String) {
+    let name = format!("owner{}", Uuid::new_v4().simple());
+    let id = format!("person:{name}");
+    db.query(
+        "CREATE type::thing($record) SET name = $name, date_of_birth = '1990-05-01', \
+         secret = crypto::argon2::generate('secret')",
+    )
+    .bind(("record", &id))
+    .bind(("name", &name))
+    .await
+    .unwrap()
+    .check()
+    .unwrap();
+    (id, name)
+}
+
+#[test]
+fn permissions_are_rendered_into_the_define_statements() {
+    // Arrange
+    let permissions = Permissions {
+        create: Rule::None,
+        update: OWNER,
+        ..Permissions::FULL
+    };
+
+    // Act
+    let table = permissions.table_clause();
+    let field = permissions.field_clause();
+
+    // Assert
+    assert_eq!(
+        table,
+        "PERMISSIONS FOR select FULL FOR create NONE FOR update WHERE id = $auth.id FOR delete FULL"
+    );
+    assert_eq!(
+        field,
+        "PERMISSIONS FOR select FULL FOR create NONE FOR update WHERE id = $auth.id"
+    );
+    assert_eq!(
+        define_statements("person", TableMode::Schemaless),
+        [
+            "DEFINE TABLE person SCHEMALESS PERMISSIONS FOR select FULL FOR create FULL \
+             FOR update WHERE id = $auth.id FOR delete WHERE id = $auth.id;",
+            "DEFINE FIELD date_of_birth ON TABLE person PERMISSIONS FOR select WHERE id = $auth.id \
+             FOR create FULL FOR update FULL;",
+        ]
+    );
+    assert_eq!(
+        define_statements("person", TableMode::Schemafull)[2],
+        "DEFINE FIELD date_of_birth ON TABLE person TYPE option<string> PERMISSIONS \
+         FOR select WHERE id = $auth.id FOR create FULL FOR update FULL;"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn scope_users_only_change_their_own_person() {
+    // Arrange
+    let app = spawn_app().await;
+    app.db
+        .query(format!(
+            "DEFINE SCOPE {SCOPE} SESSION 1h SIGNIN (SELECT * FROM person \
+             WHERE name = $user AND crypto::argon2::compare(secret, $pass))"
+        ))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    let (own, name) = person(&app.db).await;
+    let (other, _) = person(&app.db).await;
+    let registry = format!("registry:{}", Uuid::new_v4().simple());
+    app.db
+        .query(
+            "LET $from = type::thing($registry); LET $to = type::thing($other); \
+             CREATE $from; RELATE $from->licenses->$to",
+        )
+        .bind(("registry", &registry))
+        .bind(("other", &other))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    let owner = Database::new(&DatabaseSettings {
+        auth: AuthMode::Scope,
+        scope: Some(SCOPE.into()),
+        username: name,
+        password: "secret".into(),
+        ..database_settings().await
+    })
+    .await
+    .expect("Failed to sign in as a scope user")
+    .client;
+
+    // Act
+    let updated_own = query(
+        &owner,
+        "UPDATE type::thing($record) SET tags = ['mine']",
+        &own,
+    )
+    .await;
+    let updated_other = query(
+        &owner,
+        "UPDATE type::thing($record) SET tags = ['mine']",
+        &other,
+    )
+    .await;
+    let deleted_other = query(&owner, "DELETE type::thing($record) RETURN BEFORE", &other).await;
+    let read_own = query(&owner, "SELECT * FROM type::thing($record)", &own).await;
+    let read_other = query(&owner, "SELECT * FROM type::thing($record)", &other).await;
+    let created = owner
+        .query("CREATE person SET name = 'Newcomer'")
+        .await
+        .ok()
+        .and_then(|mut response| response.take::<Vec<Value>>(0).ok())
+        .unwrap_or_default();
+    let deleted_license = query(
+        &owner,
+        "DELETE licenses WHERE out = type::thing($record) RETURN BEFORE",
+        &other,
+    )
+    .await;
+    let licenses = query(
+        &owner,
+        "SELECT * FROM licenses WHERE out = type::thing($record)",
+        &other,
+    )
+    .await;
+
+    // Assert
+    assert_eq!(updated_own[0]["tags"], json!(["mine"]));
+    assert!(updated_other.is_empty(), "{updated_other:?}");
+    assert!(deleted_other.is_empty(), "{deleted_other:?}");
+    assert_eq!(read_own[0]["date_of_birth"], "1990-05-01");
+    assert_eq!(read_other[0]["id"], other);
+    assert!(
+        read_other[0].get("date_of_birth").is_none(),
+        "{read_other:?}"
+    );
+    assert_eq!(created[0]["name"], "Newcomer");
+    assert!(deleted_license.is_empty(), "{deleted_license:?}");
+    assert_eq!(licenses.len(), 1, "{licenses:?}");
+    let other_now = query(&app.db, "SELECT * FROM type::thing($record)", &other).await;
+    assert_eq!(other_now.len(), 1);
+    assert!(other_now[0].get("tags").is_none());
+
+    // Teardown
+    let newcomer = created[0]["id"].as_str().unwrap().to_string();
+    for record in [&own, &other, &newcomer, &registry] {
+        query(&app.db, "DELETE type::thing($record)", record).await;
+    }
+    query(
+        &app.db,
+        "DELETE licenses WHERE out = type::thing($record)",
+        &other,
+    )
+    .await;
+    app.db.query(format!("REMOVE SCOPE {SCOPE}")).await.unwrap();
+}
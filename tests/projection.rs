@@ -0,0 +1,51 @@
+use serde_json::json;
+use surreal_simple::api::Projection;
+
+#[test]
+fn leaving_fields_out_selects_everything() {
+    // Act
+    let projection = Projection::parse("person", None).unwrap();
+
+    // Assert
+    assert_eq!(projection, None);
+}
+
+#[test]
+fn known_fields_are_selected_once_in_order() {
+    // Act
+    let projection = Projection::parse("person", Some("name, id,name"))
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    assert_eq!(projection.fields(), ["name", "id"]);
+    assert_eq!(projection.select(&[]), "name, id");
+    assert_eq!(
+        projection.select(&["id", "updated_at"]),
+        "name, id, updated_at"
+    );
+}
+
+#[test]
+fn unknown_fields_are_refused() {
+    // Act
+    let error = Projection::parse("person", Some("name,password")).unwrap_err();
+
+    // Assert
+    let message = error.to_string();
+    assert!(message.contains("password"), "{message}");
+    assert!(message.contains("name"), "{message}");
+}
+
+#[test]
+fn trim_drops_fields_not_asked_for() {
+    // Arrange
+    let projection = Projection::parse("person", Some("name")).unwrap().unwrap();
+    let mut row = json!({"id": "person:one", "name": "Ada", "updated_at": "2023-05-01T12:00:00Z"});
+
+    // Act
+    projection.trim(&mut row);
+
+    // Assert
+    assert_eq!(row, json!({"name": "Ada"}));
+}
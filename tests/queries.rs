@@ -1,13 +1,26 @@
+use futures_core::future::BoxFuture;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serial_test::serial;
 use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
 
 use surreal_simple::{
-    surreal::db::{Database, DatabaseSettings, Transaction},
+    api::LicenseStatus,
+    error::Error,
+    from_response,
+    surreal::db::{Database, Transaction},
+    surreal::from_response::FromResponse,
+    surreal::licenses::expire_licenses,
+    surreal::saga::{Saga, Step},
+    surreal::schema::functions::{sync_functions, NORMALIZE_NAME},
+    surreal::schema::indexes::{detect_drift, sync_indexes},
     telemetry::{get_subscriber, init_subscriber},
 };
 use uuid::Uuid;
+
+mod support;
+use support::container::database_settings;
+use support::PersonFixture;
 // region: -- conditional tracing for tests
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
@@ -29,11 +42,9 @@ pub struct TestApp {
 async fn setup() -> TestApp {
     Lazy::force(&TRACING);
 
-    let db = Database::new(&DatabaseSettings::default()).await.unwrap();
+    let db = Database::new(&database_settings().await).await.unwrap();
 
-    TestApp {
-        db: db.client,
-    }
+    TestApp { db: db.client }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -105,8 +116,7 @@ async fn create_people() {
 async fn create_transaction() {
     // Arrange
     let app = setup().await;
-    let transaction = Transaction::begin(&app.db).await.unwrap();
-    let conn = transaction.conn;
+    let mut transaction = Transaction::new(&app.db);
     let sql_0 = format!(
         "CREATE {} CONTENT {{ name: 'foo' }}",
         Thing::from(("person".into(), Uuid::new_v4().to_string()))
@@ -121,10 +131,10 @@ async fn create_transaction() {
     );
 
     // Act
-    conn.query(&sql_0).await.unwrap();
-    conn.query(&sql_1).await.unwrap();
-    conn.query(&sql_2).await.unwrap();
-    transaction.commit().await;
+    transaction.query(sql_0);
+    transaction.query(sql_1);
+    transaction.query(sql_2);
+    transaction.commit().await.unwrap();
 
     // Assert
     let sql = "SELECT * FROM person ORDER BY name ASC";
@@ -150,108 +160,232 @@ struct LicenseModel {
 #[tokio::test]
 #[serial]
 async fn create_license() {
-    // region: Arrange
+    // Arrange
     let app = setup().await;
-    let transaction = Transaction::begin(&app.db).await.unwrap();
-    let conn = transaction.conn;
-
-    // Create Doc McStuffins
-    let doc_id = Thing::from(("person".to_string(), Uuid::new_v4().to_string()));
-    let sql = format!("CREATE {} CONTENT {{ name: '{}' }}", doc_id, "McStuffins");
-    conn.query(&sql).await.unwrap();
-
-    // Create a license for Doc McStuffins
     let license_number_0: usize = 12345;
-    let lic_id_0 = Thing::from(("registry".to_string(), Uuid::new_v4().to_string()));
-    let sql = format!(
-        "CREATE {} CONTENT {{ registration: {} }}",
-        lic_id_0, license_number_0
-    );
-    conn.query(&sql).await.unwrap();
-
-    // Create another license for Doc McStuffins
     let license_number_1: usize = 678910;
-    let lic_id_1 = Thing::from(("registry".to_string(), Uuid::new_v4().to_string()));
-    let sql = format!(
-        "CREATE {} CONTENT {{ registration: {} }}",
-        lic_id_1, license_number_1
-    );
-    conn.query(&sql).await.unwrap();
-
-    transaction.commit().await;
 
-    // endregion
+    // Act
+    let doc = PersonFixture::new("McStuffins")
+        .with_license(license_number_0 as u64)
+        .with_license(license_number_1 as u64)
+        .insert(&app.db)
+        .await;
 
-    // region: Act
-    let sql = "
-            RELATE $license->licenses->$person SET id = licenses:uuid();
-        ";
-    let _ = app
+    // region: Assert
+    let sql = "\
+        SELECT name, ->licenses->person.name AS name FROM ( SELECT id FROM registry WHERE registration = $registration_0 );\
+        SELECT name, ->licenses->person.name AS name FROM ( SELECT id FROM registry WHERE registration = $registration_1 );\
+        SELECT registrations, <-licenses<-registry.registration AS registrations FROM (SELECT id FROM person WHERE name=$name);";
+    let res = app
         .db
         .query(sql)
-        .bind(("license", &lic_id_0))
-        .bind(("person", &doc_id))
+        .bind(("registration_0", license_number_0))
+        .bind(("registration_1", license_number_1))
+        .bind(("name", "McStuffins"))
         .await
         .unwrap();
 
-    let sql = "
-        RELATE $license->licenses->$person SET id = licenses:uuid();
-    ";
-    let _ = app
-        .db
-        .query(sql)
-        .bind(("license", &lic_id_1))
-        .bind(("person", &doc_id))
-        .await
-        .unwrap();
+    from_response! {
+        struct Licensed {
+            first: Option<Vec<String>> = (0, "name"),
+            second: Option<Vec<String>> = (1, "name"),
+            registrations: Option<Vec<usize>> = (2, "registrations"),
+        }
+    }
+    let licensed = Licensed::from_response(res).unwrap();
+
+    assert_eq!(licensed.first.unwrap(), vec!["McStuffins"]);
+    assert_eq!(licensed.second.unwrap(), vec!["McStuffins"]);
+    for registration in licensed.registrations.unwrap() {
+        assert!(registration == license_number_0 || registration == license_number_1);
+    }
     // endregion
 
-    // region: Assert
-    let sql = "SELECT name, ->licenses->person.name AS name FROM ( SELECT id FROM registry WHERE registration = $registration );";
-    let mut res = app
-        .db
-        .query(sql)
-        .bind(("registration", license_number_0))
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn declared_indexes_exist_after_sync() {
+    // Arrange
+    let app = setup().await;
+
+    // Act
+    sync_indexes(&app.db).await.unwrap();
+    let drift = detect_drift(&app.db).await.unwrap();
+
+    // Assert
+    assert!(drift.missing.is_empty(), "missing: {:?}", drift.missing);
+}
+
+#[tokio::test]
+#[serial]
+async fn declared_functions_can_be_called_after_sync() {
+    // Arrange
+    let app = setup().await;
+    sync_functions(&app.db).await.unwrap();
+
+    // Act
+    let again = sync_functions(&app.db).await.unwrap();
+    let name = NORMALIZE_NAME
+        .call(&app.db, ("  Jane Doe ".to_string(),))
         .await
         .unwrap();
 
-    let name: Option<Vec<String>> = res.take((0, "name")).unwrap();
-    assert_eq!(name.unwrap(), vec!["McStuffins"]);
+    // Assert
+    assert!(again.applied.is_empty(), "applied: {:?}", again.applied);
+    assert_eq!(name, "jane doe");
+}
 
-    let sql = "SELECT name, ->licenses->person.name AS name FROM ( SELECT id FROM registry WHERE registration = $registration );";
-    let mut res = app
-        .db
-        .query(sql)
-        .bind(("registration", license_number_1))
+#[tokio::test]
+#[serial]
+async fn past_due_licenses_are_expired() {
+    // Arrange
+    let app = setup().await;
+    let doc = PersonFixture::new("Expiry")
+        .with_license(rand::random::<u32>().into())
+        .insert(&app.db)
+        .await;
+    let license = doc.licenses[0].clone();
+    app.db
+        .query("UPDATE $license SET expires_at = time::now() - 1d")
+        .bind(("license", &license))
         .await
+        .unwrap()
+        .check()
         .unwrap();
 
-    let name: Option<Vec<String>> = res.take((0, "name")).unwrap();
-    assert_eq!(name.unwrap(), vec!["McStuffins"]);
+    // Act
+    let expired = expire_licenses(&app.db).await.unwrap();
 
-    // SELECT registration, <-licenses<-registry.registration AS registration FROM (SELECT id FROM person WHERE name='McStuffins')
-    let sql = "SELECT registrations, <-licenses<-registry.registration AS registrations FROM (SELECT id FROM person WHERE name=$name);";
-    let mut res = app
+    // Assert
+    let status: Option<LicenseStatus> = app
         .db
-        .query(sql)
-        .bind(("name", "McStuffins"))
+        .query("SELECT VALUE status FROM $license")
+        .bind(("license", &license))
         .await
+        .unwrap()
+        .take(0)
         .unwrap();
+    assert!(expired >= 1);
+    assert_eq!(status, Some(LicenseStatus::Expired));
 
-    let registrations: Option<Vec<usize>> = res.take((0, "registrations")).unwrap();
-    for registration in registrations.unwrap() {
-        assert!(registration == license_number_0 || registration == license_number_1);
+    // Teardown
+    doc.teardown(&app.db).await;
+}
+
+// region: -- saga
+#[derive(Default)]
+struct Issuance {
+    outbox: Vec<&'static str>,
+}
+
+struct CreateLicense;
+
+impl Step<Issuance> for CreateLicense {
+    fn name(&self) -> &'static str {
+        "create license"
     }
 
+    fn action<'a>(
+        &'a self,
+        tx: &'a mut Transaction,
+        _ctx: &'a mut Issuance,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            tx.query("CREATE licenses:saga SET registrations = [424242]");
+            Ok(())
+        })
+    }
+}
+
+struct Notify;
+
+impl Step<Issuance> for Notify {
+    fn name(&self) -> &'static str {
+        "notify"
+    }
+
+    fn action<'a>(
+        &'a self,
+        _tx: &'a mut Transaction,
+        ctx: &'a mut Issuance,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            ctx.outbox.push("issued");
+            Ok(())
+        })
+    }
+
+    fn compensate<'a>(&'a self, ctx: &'a mut Issuance) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            ctx.outbox.push("revoked");
+            Ok(())
+        })
+    }
+}
+
+struct Fail;
+
+impl Step<Issuance> for Fail {
+    fn name(&self) -> &'static str {
+        "fail"
+    }
+
+    fn action<'a>(
+        &'a self,
+        _tx: &'a mut Transaction,
+        _ctx: &'a mut Issuance,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async { Err(Error::Db) })
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn failed_saga_compensates_completed_steps() {
+    // Arrange
+    let app = setup().await;
+    let saga = Saga::new("license issuance")
+        .step(CreateLicense)
+        .step(Notify)
+        .step(Fail);
+    let mut issuance = Issuance::default();
+
+    // Act
+    let result = saga.run(&app.db, &mut issuance).await;
+
+    // Assert
+    assert!(result.is_err());
+    assert_eq!(issuance.outbox, ["issued", "revoked"]);
+    let mut res = app.db.query("SELECT * FROM licenses:saga").await.unwrap();
+    let license: Option<Thing> = res.take((0, "id")).unwrap();
+    assert!(license.is_none());
+
     // Teardown
-    let transaction = Transaction::begin(&app.db).await.unwrap();
-    let conn = transaction.conn;
-    let sql = "DELETE person WHERE name = 'McStuffins'";
-    conn.query(sql).await.unwrap();
-    let sql = "DELETE registry WHERE registration = 12345 OR registration = 678910";
-    conn.query(sql).await.unwrap();
-    let sql = "DELETE licenses";
-    conn.query(sql).await.unwrap();
-    transaction.commit().await;
-    // endregion
+    let _ = app.db.query("DELETE licenses:saga").await;
+}
+
+#[tokio::test]
+#[serial]
+async fn successful_saga_keeps_its_side_effects() {
+    // Arrange
+    let app = setup().await;
+    let saga = Saga::new("license issuance")
+        .step(CreateLicense)
+        .step(Notify);
+    let mut issuance = Issuance::default();
+
+    // Act
+    let result = saga.run(&app.db, &mut issuance).await;
+
+    // Assert
+    assert!(result.is_ok());
+    assert_eq!(issuance.outbox, ["issued"]);
+
+    // Teardown
+    let _ = app.db.query("DELETE licenses:saga").await;
 }
+// endregion: -- saga
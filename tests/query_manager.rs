@@ -0,0 +1,147 @@
+use surreal_simple::error::Error;
+use surreal_simple::surreal::query_manager::{
+    QueryManager, StatementKind, StatementPolicy, TransactionMonitor, TransactionSettings,
+    TransactionSize,
+};
+
+fn settings(max_statements: usize, max_bytes: usize) -> TransactionSettings {
+    TransactionSettings {
+        max_statements,
+        max_bytes,
+        split: true,
+        ..TransactionSettings::default()
+    }
+}
+
+fn manager(statements: &[&str]) -> QueryManager {
+    let mut manager = QueryManager::new();
+    for statement in statements {
+        manager.add_query(*statement);
+    }
+    manager
+}
+
+#[test]
+fn statements_are_trimmed_and_empty_ones_dropped() {
+    // Arrange
+    let manager = manager(&["CREATE a; ", "  ", ";", "CREATE bb"]);
+
+    // Act
+    let size = manager.size();
+
+    // Assert
+    assert_eq!(
+        size,
+        TransactionSize {
+            statements: 2,
+            bytes: "CREATE a".len() + "CREATE bb".len(),
+        }
+    );
+}
+
+#[test]
+fn batches_are_split_by_statement_count() {
+    // Arrange
+    let manager = manager(&["a", "b", "c", "d", "e"]);
+
+    // Act
+    let chunks = manager.partition(&settings(2, 1024));
+
+    // Assert
+    assert_eq!(chunks, [&["a", "b"][..], &["c", "d"], &["e"]]);
+}
+
+#[test]
+fn batches_are_split_by_bytes_and_big_statements_stand_alone() {
+    // Arrange
+    let manager = manager(&["aa", "bb", "cccccc", "d"]);
+
+    // Act
+    let chunks = manager.partition(&settings(100, 4));
+
+    // Assert
+    assert_eq!(chunks, [&["aa", "bb"][..], &["cccccc"], &["d"]]);
+}
+
+#[test]
+fn oversized_transactions_are_counted() {
+    // Arrange
+    let monitor = TransactionMonitor::new(&settings(2, 1024));
+
+    // Act
+    let small = monitor.observe(TransactionSize {
+        statements: 2,
+        bytes: 10,
+    });
+    let large = monitor.observe(TransactionSize {
+        statements: 3,
+        bytes: 30,
+    });
+
+    // Assert
+    assert!(!small);
+    assert!(large);
+    let metrics = monitor.metrics();
+    assert_eq!(metrics.transactions, 2);
+    assert_eq!(metrics.statements, 5);
+    assert_eq!(metrics.bytes, 40);
+    assert_eq!(metrics.oversized, 1);
+    assert_eq!(
+        metrics.largest,
+        TransactionSize {
+            statements: 3,
+            bytes: 30,
+        }
+    );
+}
+
+#[test]
+fn request_path_batches_refuse_schema_changes() {
+    // Arrange
+    let policy = StatementPolicy::request_path();
+
+    // Act
+    let create = policy.check("CREATE person:a CONTENT { name: 'a' }");
+    let smuggled = policy.check("CREATE person:a CONTENT { name: 'a' }; REMOVE TABLE person");
+    let info = policy.check("INFO FOR DB");
+
+    // Assert
+    assert!(create.is_ok());
+    assert!(matches!(
+        smuggled,
+        Err(Error::StatementNotAllowed("REMOVE"))
+    ));
+    assert!(matches!(info, Err(Error::StatementNotAllowed("INFO"))));
+}
+
+#[test]
+fn policies_allow_only_what_they_list() {
+    // Arrange
+    let policy = StatementPolicy::allow([StatementKind::Select]);
+
+    // Assert
+    assert!(policy.allows(StatementKind::Select));
+    assert!(!policy.allows(StatementKind::Define));
+    assert!(!StatementPolicy::request_path().allows(StatementKind::Define));
+    assert_eq!(StatementKind::Remove.keyword(), "REMOVE");
+}
+
+#[test]
+fn managers_check_every_statement_against_their_policy() {
+    // Arrange
+    let unchecked = manager(&["SELECT * FROM person", "DEFINE TABLE person"]);
+    let checked = unchecked
+        .clone()
+        .with_policy(StatementPolicy::request_path());
+
+    // Act
+    let error = checked.check().unwrap_err();
+
+    // Assert
+    assert!(unchecked.check().is_ok());
+    assert_eq!(
+        error.to_string(),
+        "`DEFINE` statements are not allowed here"
+    );
+    assert_eq!(error.status(), axum::http::StatusCode::BAD_REQUEST);
+}
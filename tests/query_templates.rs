@@ -0,0 +1,148 @@
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use surreal_simple::surreal::queries::{read_dir, QueryDiagnostic, QueryTemplate};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+
+#[test]
+fn shipped_templates_parse() {
+    // Act
+    let templates = read_dir(Path::new("queries")).unwrap();
+
+    // Assert
+    let template = &templates["person_by_license"];
+    assert_eq!(template.params, BTreeSet::from(["registry".to_string()]));
+}
+
+fn parse(sql: &str) -> Result<QueryTemplate, Vec<QueryDiagnostic>> {
+    QueryTemplate::parse(Path::new("queries/example.surql"), sql)
+}
+
+#[test]
+fn declared_params_match_the_placeholders() {
+    // Act
+    let template = parse(
+        "-- @param $name the person's name\n\
+         -- @param $window how far back to look\n\
+         LET $since = time::now() - $window;\n\
+         SELECT * FROM person WHERE name = $name AND note != '$quoted' \
+         AND updated_at > $since AND id != $auth.id;",
+    )
+    .unwrap();
+
+    // Assert
+    assert_eq!(template.name, "example");
+    assert_eq!(
+        template.params,
+        BTreeSet::from(["name".to_string(), "window".to_string()])
+    );
+}
+
+#[test]
+fn undeclared_and_unused_params_are_reported_with_their_lines() {
+    // Act
+    let diagnostics = parse(
+        "-- @param $name\n\
+         -- @param $unused\n\
+         SELECT * FROM person\n\
+         WHERE name = $name AND age > $age;",
+    )
+    .unwrap_err();
+
+    // Assert
+    let reported: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        reported,
+        [
+            "queries/example.surql:2: `$unused` is declared but never used",
+            "queries/example.surql:4: `$age` is used but not declared with `@param`",
+        ]
+    );
+}
+
+#[test]
+fn templates_only_hold_statements_a_request_may_run() {
+    // Act
+    let diagnostics = parse("SELECT * FROM person;\nREMOVE TABLE person;").unwrap_err();
+
+    // Assert
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 2);
+    assert_eq!(
+        diagnostics[0].message,
+        "`REMOVE` statements are not allowed"
+    );
+}
+
+#[test]
+fn bindings_must_match_the_params() {
+    // Arrange
+    let template = parse("-- @param $name\nSELECT * FROM person WHERE name = $name;").unwrap();
+    let bindings = |names: &[&str]| -> BTreeMap<String, serde_json::Value> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), json!("Ada")))
+            .collect()
+    };
+
+    // Act / Assert
+    assert!(template.check(&bindings(&["name"])).is_ok());
+    assert!(template.check(&bindings(&[])).is_err());
+    assert!(template.check(&bindings(&["name", "extra"])).is_err());
+}
+
+#[test]
+fn every_broken_file_is_reported() {
+    // Arrange
+    let dir = std::env::temp_dir().join(format!("queries-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("fine.surql"), "SELECT * FROM person;").unwrap();
+    std::fs::write(
+        dir.join("broken.surql"),
+        "SELECT * FROM person WHERE name = 'open;",
+    )
+    .unwrap();
+    std::fs::write(dir.join("undeclared.surql"), "SELECT * FROM $table;").unwrap();
+
+    // Act
+    let diagnostics = read_dir(&dir).unwrap_err();
+
+    // Assert
+    let files: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.file.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(files, ["broken.surql", "undeclared.surql"]);
+
+    // Teardown
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn registry_people_runs_the_template() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Templated")
+        .with_license(5151)
+        .insert(&app.db)
+        .await;
+    let registry = doc.registries[0].id.to_string();
+
+    // Act
+    let response = minreq::get(format!("{}/registry/{registry}/people", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    let people: Vec<serde_json::Value> = response.assert_status(200).data();
+    assert_eq!(people.len(), 1);
+    assert_eq!(people[0]["name"], "Templated");
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
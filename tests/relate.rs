@@ -0,0 +1,160 @@
+use serde_json::json;
+use surreal_simple::error::{Error, Problem};
+use surreal_simple::surreal::edge::{parse_record, EdgeAllowList, EdgeEndpoints, EdgeSettings};
+use surreal_simple::surreal::schema::edges::define_statements;
+use surrealdb::sql::Thing;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+
+#[test]
+fn record_ids_parse_with_and_without_brackets() {
+    // Act
+    let plain = parse_record("registry:xyz").unwrap();
+    let bracketed = parse_record("person:⟨a-b⟩").unwrap();
+
+    // Assert
+    assert_eq!(plain, Thing::from(("registry", "xyz")));
+    assert_eq!(bracketed, Thing::from(("person", "a-b")));
+}
+
+#[test]
+fn malformed_record_ids_are_rejected() {
+    for input in [
+        "person",
+        ":abc",
+        "person:",
+        "per son:abc",
+        "person;DELETE:x",
+    ] {
+        // Act
+        let result = parse_record(input);
+
+        // Assert
+        assert!(matches!(result, Err(Error::InvalidId(_))), "{input}");
+    }
+}
+
+#[test]
+fn only_allowed_edges_between_their_tables_pass() {
+    // Arrange
+    let edges = EdgeAllowList::new(&EdgeSettings::default());
+    let registry = Thing::from(("registry", "r"));
+    let person = Thing::from(("person", "p"));
+
+    // Act & Assert
+    assert!(edges.check(&registry, "licenses", &person).is_ok());
+    assert!(matches!(
+        edges.check(&person, "licenses", &registry),
+        Err(Error::InvalidBody(_))
+    ));
+    assert!(matches!(
+        edges.check(&registry, "owns", &person),
+        Err(Error::InvalidBody(_))
+    ));
+}
+
+#[test]
+fn missing_endpoints_are_422s_naming_the_side() {
+    // Arrange
+    let error = Error::MissingEndpoint {
+        side: "to",
+        record: "person:gone".into(),
+    };
+
+    // Act
+    let problem = Problem::from(&error);
+
+    // Assert
+    assert_eq!(problem.status, 422);
+    assert_eq!(problem.field.as_deref(), Some("to"));
+    assert!(problem.detail.contains("person:gone"));
+}
+
+#[test]
+fn schema_asserts_edge_endpoint_tables() {
+    // Arrange
+    let endpoints = EdgeEndpoints {
+        from: "registry".into(),
+        to: "person".into(),
+    };
+
+    // Act
+    let [r#in, out] = define_statements("licenses", &endpoints);
+
+    // Assert
+    assert_eq!(
+        r#in,
+        "DEFINE FIELD in ON TABLE licenses TYPE record(registry) \
+         ASSERT $value != NONE AND $value.id != NONE;"
+    );
+    assert!(out.starts_with("DEFINE FIELD out ON TABLE licenses TYPE record(person)"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relate_creates_an_edge_between_existing_records() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Related")
+        .with_license(4242)
+        .insert(&app.db)
+        .await;
+    let body = json!({
+        "from": doc.registries[0].to_string(),
+        "edge": "licenses",
+        "to": doc.person.to_string(),
+        "data": { "status": "expired" },
+    });
+
+    // Act
+    let response = minreq::post(format!("{}/relate", app.address))
+        .with_json(&body)
+        .unwrap()
+        .send()
+        .unwrap();
+
+    // Assert
+    let relation: serde_json::Value = response.assert_status(201).data();
+    assert_eq!(relation["edge"], "licenses");
+    assert!(relation["id"].as_str().unwrap().starts_with("licenses:"));
+
+    // Teardown
+    let _ = app
+        .db
+        .query("DELETE licenses WHERE out = $person")
+        .bind(("person", &doc.person))
+        .await;
+    doc.teardown(&app.db).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relate_refuses_missing_records() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Unrelated").insert(&app.db).await;
+    let body = json!({
+        "from": "registry:does_not_exist",
+        "edge": "licenses",
+        "to": doc.person.to_string(),
+    });
+
+    // Act
+    let response = minreq::post(format!("{}/relate", app.address))
+        .with_json(&body)
+        .unwrap()
+        .send()
+        .unwrap();
+
+    // Assert
+    let problem = response.problem(422);
+    assert_eq!(problem["field"], "from");
+    assert!(problem["detail"]
+        .as_str()
+        .unwrap()
+        .contains("registry:does_not_exist"));
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
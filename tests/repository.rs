@@ -0,0 +1,117 @@
+use serde_json::{json, Value};
+use surreal_simple::api::ROUTE_TABLE;
+use surreal_simple::app::routes;
+use surreal_simple::config::Settings;
+use surreal_simple::surreal::repository::{Repository, RepositorySettings, Strategy};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app_with;
+
+fn methods(path: &str) -> Vec<String> {
+    ROUTE_TABLE
+        .entries()
+        .into_iter()
+        .find(|route| route.path == path)
+        .map(|route| route.methods)
+        .unwrap_or_default()
+}
+
+/// One request and what came back, with the parts that differ from run to
+/// run (the id, the request id, `updated_at`) replaced or left out.
+fn exchange(method: &str, response: minreq::Response, id: &str) -> Value {
+    let location = response
+        .headers
+        .get("location")
+        .map(|location| location.replace(id, "{id}"));
+    let mut body: Value = response.json().unwrap_or(Value::Null);
+    if let Value::Object(fields) = &mut body {
+        fields.remove("meta");
+    }
+    let body = body.to_string().replace(id, "{id}");
+    json!({
+        "method": method,
+        "status": response.status_code,
+        "location": location,
+        "body": serde_json::from_str::<Value>(&body).unwrap(),
+    })
+}
+
+/// Creates, reads, replaces and deletes a person under `prefix`, then does
+/// it all again on the person that is now gone.
+fn lifecycle(address: &str, prefix: &str) -> Vec<Value> {
+    let id = format!("contract{}", Uuid::new_v4().simple());
+    let url = format!("{address}{prefix}/{id}");
+    let jane = json!({ "name": "Jane", "tags": ["contract"] });
+    let janet = json!({ "name": "Janet" });
+    let send =
+        |method: &str, request: minreq::Request| exchange(method, request.send().unwrap(), &id);
+
+    vec![
+        send("POST", minreq::post(&url).with_json(&jane).unwrap()),
+        send("POST", minreq::post(&url).with_json(&jane).unwrap()),
+        send("GET", minreq::get(&url)),
+        send("PUT", minreq::put(&url).with_json(&janet).unwrap()),
+        send("GET", minreq::get(&url)),
+        send("DELETE", minreq::delete(&url)),
+        send("GET", minreq::get(&url)),
+        send("PUT", minreq::put(&url).with_json(&janet).unwrap()),
+        send("DELETE", minreq::delete(&url)),
+    ]
+}
+
+#[test]
+fn strategies_are_picked_in_the_settings() {
+    // Arrange
+    let query: RepositorySettings = serde_json::from_value(json!({ "strategy": "query" })).unwrap();
+
+    // Act
+    let repository = Repository::new(&query);
+
+    // Assert
+    assert_eq!(repository.strategy(), Strategy::Query);
+    assert_eq!(
+        Repository::new(&RepositorySettings::default()).strategy(),
+        Strategy::Sdk
+    );
+    assert!(serde_json::from_value::<RepositorySettings>(json!({ "strategy": "orm" })).is_err());
+}
+
+#[test]
+fn both_route_sets_answer_the_same_methods() {
+    // Arrange
+    let _ = routes(&Settings::default());
+
+    // Act
+    let person = methods("/person/:id");
+    let person_qry = methods("/person/qry/:id");
+
+    // Assert
+    assert!(!person.is_empty());
+    assert_eq!(person, person_qry);
+    assert_eq!(methods("/person/qry/people"), ["GET", "HEAD", "OPTIONS"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn both_strategies_answer_alike_on_both_route_sets() {
+    // Arrange
+    let sdk = spawn_app_with(|settings| settings.repository.strategy = Strategy::Sdk).await;
+    let query = spawn_app_with(|settings| settings.repository.strategy = Strategy::Query).await;
+
+    // Act
+    let runs = [
+        lifecycle(&sdk.address, "/person"),
+        lifecycle(&sdk.address, "/person/qry"),
+        lifecycle(&query.address, "/person"),
+        lifecycle(&query.address, "/person/qry"),
+    ];
+
+    // Assert
+    let statuses: Vec<&Value> = runs[0].iter().map(|exchange| &exchange["status"]).collect();
+    assert_eq!(statuses, [201, 409, 200, 200, 200, 200, 404, 404, 404]);
+    assert_eq!(runs[0][0]["location"], "/person/{id}");
+    assert_eq!(runs[0][2]["body"]["data"]["id"], "person:{id}");
+    for run in &runs[1..] {
+        assert_eq!(run, &runs[0]);
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{TimeZone, Utc};
+use serial_test::serial;
+use std::time::{Duration, Instant};
+use surreal_simple::surreal::request_log::{
+    query_requests, RecentErrors, RequestLog, RequestLogSettings, RequestsQuery,
+    RECENT_ERRORS_WINDOW, REQUEST_LOG,
+};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+fn log(sample_percent: u8) -> RequestLog {
+    RequestLog::new(&RequestLogSettings {
+        sample_percent,
+        ..RequestLogSettings::default()
+    })
+}
+
+#[test]
+fn sampling_keeps_all_or_nothing_at_the_extremes() {
+    // Arrange
+    let all = log(100);
+    let none = log(0);
+
+    // Act
+    let kept = (0..50).filter(|_| all.keeps("/person/:id", 200)).count();
+    let dropped = (0..50).filter(|_| none.keeps("/person/:id", 200)).count();
+
+    // Assert
+    assert_eq!(kept, 50);
+    assert_eq!(dropped, 0);
+}
+
+#[test]
+fn server_errors_are_kept_whatever_the_sample() {
+    // Arrange
+    let log = log(0);
+
+    // Act
+    let kept = log.keeps("/person/:id", 503);
+
+    // Assert
+    assert!(kept);
+}
+
+#[test]
+fn excluded_routes_and_a_disabled_log_keep_nothing() {
+    // Arrange
+    let log = log(100);
+    let disabled = RequestLog::new(&RequestLogSettings {
+        enabled: false,
+        ..RequestLogSettings::default()
+    });
+
+    // Act
+    let metrics = log.keeps("/metrics", 500);
+    let off = disabled.keeps("/person/:id", 500);
+
+    // Assert
+    assert!(!metrics);
+    assert!(!off);
+}
+
+#[test]
+fn recent_errors_count_failures_within_the_window() {
+    // Arrange
+    let log = log(0);
+    let start = Instant::now();
+    for status in [200, 404, 422, 503] {
+        log.record_status(start, status);
+    }
+    log.record_status(start + Duration::from_secs(60), 500);
+
+    // Act
+    let recent = log.recent_errors(start + Duration::from_secs(1));
+    let later = log.recent_errors(start + RECENT_ERRORS_WINDOW + Duration::from_secs(1));
+
+    // Assert
+    assert_eq!(
+        recent,
+        RecentErrors {
+            window_secs: 300,
+            client_errors: 2,
+            server_errors: 2,
+        }
+    );
+    assert_eq!((later.client_errors, later.server_errors), (0, 1));
+}
+
+#[test]
+fn filters_become_bound_conditions() {
+    // Arrange
+    let query = RequestsQuery {
+        status: Some(500),
+        since: Some(Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap()),
+        limit: Some(5000),
+        ..RequestsQuery::default()
+    };
+
+    // Act
+    let (sql, bindings) = query.statement();
+
+    // Assert
+    assert_eq!(
+        sql,
+        "SELECT * FROM requests WHERE status = $status AND at >= <datetime> $since \
+         ORDER BY at DESC LIMIT 1000;"
+    );
+    assert_eq!(bindings["status"], 500);
+    assert_eq!(bindings["since"], "2023-05-01T00:00:00+00:00");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn requests_are_kept_and_found_by_request_id() {
+    // Arrange
+    let app = spawn_app().await;
+    REQUEST_LOG.configure(&RequestLogSettings {
+        sample_percent: 100,
+        ..RequestLogSettings::default()
+    });
+    let request_id = Uuid::new_v4().to_string();
+
+    // Act
+    let response = minreq::get(format!("{}/person/{}", app.address, Uuid::new_v4()))
+        .with_header("x-request-id", &request_id)
+        .send()
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let records = query_requests(
+        &app.db,
+        &RequestsQuery {
+            request_id: Some(request_id.clone()),
+            ..RequestsQuery::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status_code, 404);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].route, "/person/:id");
+    assert_eq!(records[0].method, "GET");
+    assert_eq!(records[0].status, 404);
+    assert!(records[0].at.is_some());
+
+    // Teardown
+    REQUEST_LOG.configure(&RequestLogSettings::default());
+}
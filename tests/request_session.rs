@@ -0,0 +1,172 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::get;
+use axum::Router;
+use serde_json::json;
+use surreal_simple::api::request_session;
+use surreal_simple::client::NewPerson;
+use surreal_simple::surreal::db::DatabaseSettings;
+use surreal_simple::surreal::request_session::{RequestSessionSettings, RequestSessions};
+use surrealdb::engine::remote::ws::Ws;
+use surrealdb::opt::auth::Scope;
+use surrealdb::Surreal;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app_with;
+use support::container::database_settings;
+
+const SCOPE: &str = "request_session";
+
+fn sessions(enabled: bool) -> RequestSessions {
+    RequestSessions::new(
+        &RequestSessionSettings {
+            enabled,
+            ..RequestSessionSettings::default()
+        },
+        &DatabaseSettings::default(),
+    )
+}
+
+#[test]
+fn request_session_settings_are_checked() {
+    // Arrange
+    let settings = RequestSessionSettings {
+        enabled: true,
+        routes: vec!["person".into(), "/admin/scope".into(), "/people".into()],
+        pool_size: 0,
+    };
+
+    // Act
+    let problems = settings.problems();
+
+    // Assert
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert!(RequestSessionSettings::default().problems().is_empty());
+}
+
+#[test]
+fn only_the_configured_route_groups_run_with_the_callers_token() {
+    // Arrange
+    let sessions = sessions(true);
+
+    // Assert
+    assert!(sessions.applies_to("/person/1"));
+    assert!(sessions.applies_to("/person/qry/1"));
+    assert!(sessions.applies_to("/people"));
+    assert!(!sessions.applies_to("/registry/1"));
+    assert!(!sessions.applies_to("/health"));
+    assert!(!self::sessions(false).applies_to("/person/1"));
+}
+
+#[tokio::test]
+async fn requests_without_a_token_are_refused_in_a_route_group() {
+    // Arrange
+    let app = Router::new()
+        .route("/person/:id", get(|| async { "read" }))
+        .route("/registry/:id", get(|| async { "read" }))
+        .layer(middleware::from_fn_with_state(
+            sessions(true),
+            request_session,
+        ));
+
+    // Act
+    let person = app
+        .clone()
+        .oneshot(Request::get("/person/1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let registry = app
+        .oneshot(Request::get("/registry/1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(person.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(registry.status(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_run_as_the_person_whose_token_they_carry() {
+    // Arrange
+    let app = spawn_app_with(|settings| settings.request_sessions.enabled = true).await;
+    let database = database_settings().await;
+    app.db
+        .query(format!(
+            "DEFINE SCOPE {SCOPE} SESSION 1h SIGNIN (SELECT * FROM person \
+             WHERE name = $user AND crypto::argon2::compare(secret, $pass))"
+        ))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    let mut people = Vec::new();
+    for _ in 0..2 {
+        let name = format!("caller{}", Uuid::new_v4().simple());
+        app.db
+            .query(
+                "CREATE type::thing('person', $name) SET name = $name, \
+                 date_of_birth = '1990-05-01', secret = crypto::argon2::generate('secret')",
+            )
+            .bind(("name", &name))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        people.push(name);
+    }
+    let (own, other) = (&people[0], &people[1]);
+    let client = Surreal::new::<Ws>(format!("{}:{}", database.host, database.port))
+        .await
+        .unwrap();
+    let token = client
+        .signin(Scope {
+            namespace: &database.namespace,
+            database: &database.database,
+            scope: SCOPE,
+            params: json!({ "user": own, "pass": "secret" }),
+        })
+        .await
+        .unwrap();
+    let caller = app.client().with_bearer(token.as_insecure_token());
+    let mine = |name: &str| NewPerson {
+        tags: vec!["mine".into()],
+        ..NewPerson::new(name)
+    };
+
+    // Act
+    let update_own = caller.update_person(own, &mine(own)).await;
+    let update_other = caller.update_person(other, &mine(other)).await;
+    let delete_other = caller.delete_person(other).await;
+    let read_other = caller.get_person(other).await.unwrap();
+    let anonymous = app.client().get_person(own).await.unwrap_err();
+
+    // Assert
+    assert!(update_own.is_ok(), "{update_own:?}");
+    assert!(update_other.is_err(), "{update_other:?}");
+    assert!(delete_other.is_err(), "{delete_other:?}");
+    assert_eq!(read_other.name, other.as_str());
+    assert_eq!(read_other.date_of_birth, None);
+    assert_eq!(anonymous.status(), Some(401));
+    let stored: Option<serde_json::Value> = app
+        .db
+        .query("SELECT * FROM type::thing('person', $name)")
+        .bind(("name", other))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(stored.unwrap().get("tags").is_none());
+
+    // Teardown
+    for name in &people {
+        app.db
+            .query("DELETE type::thing('person', $name)")
+            .bind(("name", name))
+            .await
+            .unwrap();
+    }
+    app.db.query(format!("REMOVE SCOPE {SCOPE}")).await.unwrap();
+}
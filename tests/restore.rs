@@ -0,0 +1,168 @@
+use chrono::{Duration, TimeZone, Utc};
+use std::collections::BTreeMap;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::admin::Scope;
+use surreal_simple::surreal::backup::store::{BackupStore, DirectoryStore};
+use surreal_simple::surreal::restore::{
+    check_tables, staging_scope, PendingRestores, RestorePlan, TableCheck, CONFIRM_WITHIN_MINUTES,
+};
+use surreal_simple::surreal::snapshot::expected_counts;
+use uuid::Uuid;
+
+fn scope(database: &str) -> Scope {
+    Scope {
+        namespace: "test".into(),
+        database: database.into(),
+    }
+}
+
+fn plan(passed: bool) -> RestorePlan {
+    RestorePlan {
+        snapshot: "test-test-20230510T030000Z.surql".into(),
+        staging: scope("test_restore_20230510T040000"),
+        statements: 3,
+        tables: Vec::new(),
+        passed,
+        token: None,
+        expires_at: None,
+    }
+}
+
+#[test]
+fn snapshots_note_their_counts_per_table() {
+    // Arrange
+    let surql = "-- snapshot of test/test taken 2023-05-10T03:00:00+00:00\n\
+                 BEGIN TRANSACTION;\n\
+                 INSERT INTO person { id: person:a, name: '-- count 9 person' };\n\
+                 -- count 1 person\n\
+                 -- count 0 my table\n\
+                 COMMIT TRANSACTION;\n";
+
+    // Act
+    let counts = expected_counts(surql);
+
+    // Assert
+    assert_eq!(
+        counts,
+        BTreeMap::from([("person".to_string(), 1), ("my table".to_string(), 0)])
+    );
+}
+
+#[test]
+fn restored_tables_are_checked_against_the_snapshot() {
+    // Arrange
+    let expected = BTreeMap::from([
+        ("licenses".to_string(), 2),
+        ("person".to_string(), 3),
+        ("registry".to_string(), 1),
+    ]);
+    let restored = BTreeMap::from([
+        ("person".to_string(), 3),
+        ("licenses".to_string(), 1),
+        ("extra".to_string(), 4),
+    ]);
+
+    // Act
+    let checks = check_tables(&expected, &restored);
+
+    // Assert
+    let check = |table: &str, expected: Option<usize>, restored: usize, ok: bool| TableCheck {
+        table: table.into(),
+        expected,
+        restored,
+        ok,
+    };
+    assert_eq!(
+        checks,
+        [
+            check("extra", None, 4, true),
+            check("licenses", Some(2), 1, false),
+            check("person", Some(3), 3, true),
+            check("registry", Some(1), 0, false),
+        ]
+    );
+}
+
+#[test]
+fn staging_databases_are_named_after_the_live_one() {
+    let at = Utc.with_ymd_and_hms(2023, 5, 10, 3, 0, 0).unwrap();
+
+    assert_eq!(
+        staging_scope(&scope("test"), at),
+        scope("test_restore_20230510T030000")
+    );
+}
+
+#[test]
+fn only_passing_plans_get_a_token() {
+    // Arrange
+    let restores = PendingRestores::default();
+    let now = Utc::now();
+
+    // Act
+    let failed = restores.offer(plan(false), now);
+    let passed = restores.offer(plan(true), now);
+
+    // Assert
+    assert_eq!(failed.token, None);
+    assert!(passed.token.is_some());
+    assert_eq!(
+        passed.expires_at,
+        Some(now + Duration::minutes(CONFIRM_WITHIN_MINUTES))
+    );
+}
+
+#[test]
+fn a_token_confirms_one_switch() {
+    // Arrange
+    let restores = PendingRestores::default();
+    let now = Utc::now();
+    let token = restores.offer(plan(true), now).token.unwrap();
+
+    // Act
+    let wrong = restores.confirm("guess", now);
+    let right = restores.confirm(&token, now);
+    let again = restores.confirm(&token, now);
+
+    // Assert
+    assert!(matches!(wrong, Err(Error::InvalidConfirmation)));
+    assert_eq!(right.unwrap(), scope("test_restore_20230510T040000"));
+    assert!(matches!(again, Err(Error::InvalidConfirmation)));
+}
+
+#[test]
+fn tokens_expire() {
+    // Arrange
+    let restores = PendingRestores::default();
+    let now = Utc::now();
+    let token = restores.offer(plan(true), now).token.unwrap();
+
+    // Act
+    let late = restores.confirm(&token, now + Duration::minutes(CONFIRM_WITHIN_MINUTES));
+
+    // Assert
+    assert!(matches!(late, Err(Error::InvalidConfirmation)));
+    assert_eq!(
+        Error::InvalidConfirmation.status(),
+        axum::http::StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn missing_backups_are_not_found() {
+    // Arrange
+    let dir = std::env::temp_dir().join(format!("backups-{}", Uuid::new_v4()));
+    let store = DirectoryStore::new(&dir);
+    store.put("a.surql", b"-- a".to_vec()).await.unwrap();
+
+    // Act
+    let found = store.get("a.surql").await.unwrap();
+    let missing = store.get("b.surql").await;
+
+    // Assert
+    assert_eq!(found, "-- a");
+    assert!(matches!(missing, Err(Error::NotFound(_))));
+
+    // Teardown
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use surrealdb::error::Api;
+
+use surreal_simple::surreal::retry::{is_retryable, retry, RetryPolicy};
+
+fn policy() -> RetryPolicy {
+    RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(5), 10)
+}
+
+#[tokio::test]
+async fn transient_errors_are_retried() {
+    // Arrange
+    let policy = policy();
+    let calls = AtomicU32::new(0);
+
+    // Act
+    let result = retry(&policy, || async {
+        match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(surrealdb::Error::Api(Api::Ws("connection reset".into()))),
+            n => Ok(n),
+        }
+    })
+    .await;
+
+    // Assert
+    assert_eq!(result.unwrap(), 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn permanent_errors_are_not_retried() {
+    // Arrange
+    let policy = policy();
+    let calls = AtomicU32::new(0);
+    let error = surrealdb::Error::Api(Api::Query("Parse error on line 1".into()));
+    assert!(!is_retryable(&error));
+
+    // Act
+    let result: surrealdb::Result<()> = retry(&policy, || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(surrealdb::Error::Api(Api::Query(
+            "Parse error on line 1".into(),
+        )))
+    })
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn retries_stop_when_budget_is_spent() {
+    // Arrange
+    let policy = RetryPolicy::new(5, Duration::from_millis(1), Duration::from_millis(1), 1);
+    let calls = AtomicU32::new(0);
+
+    // Act
+    let result: surrealdb::Result<()> = retry(&policy, || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(surrealdb::Error::Api(Api::Ws("connection closed".into())))
+    })
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(policy.remaining_budget(), 0);
+}
+
+#[test]
+fn backoff_is_capped() {
+    let policy = policy();
+    for attempt in 0..32 {
+        assert!(policy.backoff(attempt) <= Duration::from_millis(5));
+    }
+}
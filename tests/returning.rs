@@ -0,0 +1,34 @@
+use axum::extract::Query;
+use axum::http::Uri;
+use surreal_simple::api::{ReturnMode, ReturnQuery};
+
+fn parse(uri: &str) -> Option<ReturnMode> {
+    let uri: Uri = uri.parse().unwrap();
+    Query::<ReturnQuery>::try_from_uri(&uri)
+        .ok()
+        .map(|query| query.0.mode)
+}
+
+#[test]
+fn updates_return_the_new_record_by_default() {
+    assert_eq!(parse("/person/1"), Some(ReturnMode::After));
+    assert_eq!(ReturnMode::default().clause(), "RETURN AFTER");
+}
+
+#[test]
+fn return_modes_map_onto_surrealql() {
+    for (query, clause) in [
+        ("diff", "RETURN DIFF"),
+        ("before", "RETURN BEFORE"),
+        ("after", "RETURN AFTER"),
+        ("none", "RETURN NONE"),
+    ] {
+        let mode = parse(&format!("/person/1?return={query}")).unwrap();
+        assert_eq!(mode.clause(), clause, "{query}");
+    }
+}
+
+#[test]
+fn unknown_return_modes_are_rejected() {
+    assert_eq!(parse("/person/1?return=everything"), None);
+}
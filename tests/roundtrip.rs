@@ -0,0 +1,143 @@
+use once_cell::sync::Lazy;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use surreal_simple::api::{PersonView, Registry, MAX_REGISTRATION};
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+mod support;
+use support::app::{spawn_app, TestApp};
+use support::http::ResponseExt;
+use support::strategies::{person, registry, text};
+
+/// Every case goes over HTTP to one app, kept on its own runtime so blocking
+/// requests can be made from the proptest body.
+static APP: Lazy<(Runtime, TestApp)> = Lazy::new(|| {
+    let runtime = Runtime::new().unwrap();
+    let app = runtime.block_on(spawn_app());
+    (runtime, app)
+});
+
+fn app() -> &'static TestApp {
+    &APP.1
+}
+
+/// Cases that each need the database.
+fn database_cases() -> ProptestConfig {
+    ProptestConfig {
+        cases: 32,
+        ..ProptestConfig::default()
+    }
+}
+
+/// `record` without the fields the database adds.
+fn written(mut record: Value) -> Value {
+    for field in ["id", "age", "updated_at"] {
+        record.as_object_mut().unwrap().remove(field);
+    }
+    record
+}
+
+proptest! {
+    #[test]
+    fn people_survive_serde(person in person()) {
+        let view: PersonView = serde_json::from_value(person.clone()).unwrap();
+
+        prop_assert_eq!(serde_json::to_value(&view).unwrap(), person);
+    }
+
+    #[test]
+    fn registrations_are_limited_to_what_the_database_stores(registration: u64) {
+        let registry: Registry =
+            serde_json::from_value(json!({ "registration": registration })).unwrap();
+
+        prop_assert_eq!(
+            registry.validated().is_ok(),
+            registration <= MAX_REGISTRATION
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(database_cases())]
+
+    #[test]
+    fn people_survive_create_and_read(person in person()) {
+        // Arrange
+        let id = Uuid::new_v4();
+        let url = format!("{}/person/{id}", app().address);
+
+        // Act
+        minreq::post(&url)
+            .with_json(&person)
+            .unwrap()
+            .send()
+            .unwrap()
+            .assert_status(201);
+        let read: Value = minreq::get(&url).send().unwrap().data();
+
+        // Teardown
+        minreq::delete(&url).send().unwrap();
+
+        // Assert
+        prop_assert_eq!(written(read), person);
+    }
+
+    #[test]
+    fn registries_survive_create_and_read(registry in registry()) {
+        // Arrange
+        let id = Uuid::new_v4();
+        let url = format!("{}/registry/{id}", app().address);
+
+        // Act
+        minreq::post(&url)
+            .with_json(&registry)
+            .unwrap()
+            .send()
+            .unwrap()
+            .assert_status(201);
+        let read: Value = minreq::get(&url).send().unwrap().data();
+
+        // Teardown
+        let _: Option<Value> = APP
+            .0
+            .block_on(async { app().db.delete(("registry", id.to_string())).await })
+            .unwrap();
+
+        // Assert
+        prop_assert_eq!(written(read), registry);
+    }
+
+    #[test]
+    fn query_built_people_survive_create_update_and_read(
+        name in text(128),
+        renamed in text(128),
+    ) {
+        // Arrange
+        let id = Uuid::new_v4();
+        let url = format!("{}/person/qry/{id}", app().address);
+
+        // Act
+        minreq::post(&url)
+            .with_json(&json!({ "name": name }))
+            .unwrap()
+            .send()
+            .unwrap()
+            .assert_status(201);
+        let created: Value = minreq::get(&url).send().unwrap().data();
+        minreq::put(&url)
+            .with_json(&json!({ "name": renamed }))
+            .unwrap()
+            .send()
+            .unwrap()
+            .assert_status(200);
+        let updated: Value = minreq::get(&url).send().unwrap().data();
+
+        // Teardown
+        minreq::delete(&url).send().unwrap();
+
+        // Assert
+        prop_assert_eq!(written(created), json!({ "name": name }));
+        prop_assert_eq!(written(updated), json!({ "name": renamed }));
+    }
+}
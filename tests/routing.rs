@@ -0,0 +1,112 @@
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use surreal_simple::api::{ResourceRoutes, RouteEntry, ROUTE_TABLE};
+use tower::ServiceExt;
+
+fn routes() -> ResourceRoutes<()> {
+    ResourceRoutes::new()
+        .get("/thing/:id", || async { "thing" })
+        .put("/thing/:id", || async { StatusCode::OK })
+        .delete("/thing/:id", || async { StatusCode::OK })
+        .post("/things", || async { StatusCode::CREATED })
+}
+
+async fn send(app: Router, method: Method, uri: &str) -> axum::response::Response {
+    app.oneshot(
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+#[test]
+fn allow_lists_each_paths_methods() {
+    // Act
+    let routes = routes();
+
+    // Assert
+    assert_eq!(
+        routes.allow("/thing/:id").as_deref(),
+        Some("DELETE, GET, HEAD, OPTIONS, PUT")
+    );
+    assert_eq!(routes.allow("/things").as_deref(), Some("OPTIONS, POST"));
+    assert_eq!(routes.allow("/nothing"), None);
+}
+
+#[tokio::test]
+async fn options_advertises_allowed_methods() {
+    // Arrange
+    let app = routes().into_router();
+
+    // Act
+    let response = send(app, Method::OPTIONS, "/thing/1").await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers()[header::ALLOW],
+        "DELETE, GET, HEAD, OPTIONS, PUT"
+    );
+}
+
+#[tokio::test]
+async fn head_sends_get_headers_without_body() {
+    // Arrange
+    let app = routes().into_router();
+
+    // Act
+    let response = send(app, Method::HEAD, "/thing/1").await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(header::CONTENT_TYPE));
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(body.is_empty());
+}
+
+#[tokio::test]
+async fn head_is_not_added_to_paths_without_get() {
+    // Arrange
+    let app = routes().into_router();
+
+    // Act
+    let response = send(app, Method::HEAD, "/things").await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[test]
+fn built_routes_are_noted_in_the_route_table() {
+    // Arrange
+    let routes = ResourceRoutes::<()>::new()
+        .get("/table/:id", || async { "table" })
+        .post("/table/:id", || async { StatusCode::CREATED });
+
+    // Act
+    let _ = routes.into_router();
+    let _ = ResourceRoutes::<()>::new()
+        .delete("/table/:id", || async { StatusCode::OK })
+        .into_router();
+
+    // Assert
+    let entry = ROUTE_TABLE
+        .entries()
+        .into_iter()
+        .find(|entry| entry.path == "/table/:id")
+        .unwrap();
+    assert_eq!(
+        entry,
+        RouteEntry {
+            path: "/table/:id".into(),
+            methods: ["DELETE", "GET", "HEAD", "OPTIONS", "POST"]
+                .map(String::from)
+                .to_vec(),
+        }
+    );
+}
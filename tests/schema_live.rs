@@ -0,0 +1,137 @@
+use serde_json::json;
+use std::collections::BTreeMap;
+use surreal_simple::surreal::schema::live::{diff, LiveTable, TableInfo};
+use surreal_simple::surreal::schema::tables::TableMode;
+
+mod support;
+use support::app::{admin_bearer, spawn_app};
+use support::http::ResponseExt;
+
+fn person(definition: &str, fields: serde_json::Value) -> LiveTable {
+    let info: TableInfo = serde_json::from_value(json!({
+        "fd": fields,
+        "ix": {
+            "name": "DEFINE INDEX name ON person FIELDS name",
+            "person_updated_at": "DEFINE INDEX person_updated_at ON person FIELDS updated_at UNIQUE",
+            "person_tags": "DEFINE INDEX person_tags ON person FIELDS tags",
+            "person_name_search": "DEFINE INDEX person_name_search ON person FIELDS name SEARCH ANALYZER name_search BM25",
+        },
+        "ev": {
+            "audit": "DEFINE EVENT audit ON person WHEN $event = 'UPDATE' THEN (CREATE log SET at = time::now())",
+        },
+    }))
+    .unwrap();
+    LiveTable::new("person", definition, info)
+}
+
+#[test]
+fn definitions_are_taken_apart() {
+    // Act
+    let table = person(
+        "DEFINE TABLE person SCHEMAFULL",
+        json!({
+            "name": "DEFINE FIELD name ON person TYPE string",
+            "updated_at": "DEFINE FIELD updated_at ON person VALUE time::now()",
+        }),
+    );
+
+    // Assert
+    assert_eq!(table.mode, TableMode::Schemafull);
+    assert_eq!(table.fields[0].name, "name");
+    assert_eq!(table.fields[0].kind.as_deref(), Some("string"));
+    assert_eq!(table.fields[1].kind, None);
+    let name = &table.indexes[0];
+    assert_eq!(
+        (name.fields.as_slice(), name.unique, name.search),
+        (&["name".to_string()][..], false, false)
+    );
+    let search = &table.indexes[1];
+    assert_eq!(
+        (search.fields.as_slice(), search.unique, search.search),
+        (&["name".to_string()][..], false, true)
+    );
+    assert!(!table.indexes[2].unique && !table.indexes[2].search);
+    assert!(table.indexes[3].unique);
+    assert_eq!(table.events[0].when.as_deref(), Some("$event = 'UPDATE'"));
+    assert_eq!(
+        table.events[0].then.as_deref(),
+        Some("(CREATE log SET at = time::now())")
+    );
+    assert_eq!(
+        person("DEFINE TABLE person SCHEMALESS", json!({})).mode,
+        TableMode::Schemaless
+    );
+}
+
+#[test]
+fn drift_lists_what_differs_from_the_code() {
+    // Arrange
+    let tables = [person(
+        "DEFINE TABLE person SCHEMALESS",
+        json!({
+            "name": "DEFINE FIELD name ON person TYPE int",
+            "tags": "DEFINE FIELD tags ON person TYPE option<array<string>>",
+            "updated_at": "DEFINE FIELD updated_at ON person VALUE time::now()",
+            "age": "DEFINE FIELD age ON person VALUE <future> { 1 }",
+        }),
+    )];
+    let configured = BTreeMap::from([
+        ("person".to_string(), TableMode::Schemafull),
+        ("registry".to_string(), TableMode::Schemaless),
+    ]);
+
+    // Act
+    let drift = diff(&tables, &configured);
+
+    // Assert
+    assert_eq!(drift.missing_tables, ["registry"]);
+    assert_eq!(drift.mismatched_tables, ["person"]);
+    assert_eq!(
+        drift.missing_fields,
+        ["person.date_of_birth", "requests.at"]
+    );
+    assert_eq!(drift.mismatched_fields, ["person.name"]);
+    assert_eq!(
+        drift.indexes.missing,
+        [
+            "person_history.person_history_version",
+            "registry.registration"
+        ]
+    );
+    assert!(drift.indexes.unknown.is_empty());
+    assert_eq!(drift.indexes.mismatched, ["person.person_updated_at"]);
+    assert!(!drift.is_clean());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_schema_describes_the_live_schema() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = minreq::get(format!("{}/admin/schema", app.address))
+        .with_header("Authorization", admin_bearer())
+        .send()
+        .unwrap();
+
+    // Assert
+    let schema: serde_json::Value = response.assert_status(200).data();
+    let person = schema["tables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|table| table["name"] == "person")
+        .unwrap();
+    assert!(person["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|field| field["name"] == "updated_at"));
+    assert!(person["indexes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|index| index["name"] == "name" && index["unique"] == false));
+    assert_eq!(schema["drift"]["indexes"]["missing"], json!([]));
+    assert_eq!(schema["drift"]["missing_fields"], json!([]));
+}
@@ -0,0 +1,122 @@
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use serde_json::json;
+use std::collections::BTreeMap;
+use surreal_simple::api::{Registry, SchemaJson};
+use surreal_simple::error::Error;
+use surreal_simple::surreal::schema::tables::{
+    define_statements, SchemaGuard, SchemaSettings, TableMode,
+};
+use tower::ServiceExt;
+
+fn settings(mode: TableMode) -> SchemaSettings {
+    SchemaSettings {
+        tables: BTreeMap::from([("person".to_string(), mode), ("registry".to_string(), mode)]),
+    }
+}
+
+#[test]
+fn schemafull_tables_define_their_fields() {
+    // Act
+    let statements = define_statements("registry", TableMode::Schemafull);
+
+    // Assert
+    assert_eq!(
+        statements,
+        [
+            "DEFINE TABLE registry SCHEMAFULL;",
+            "DEFINE FIELD registration ON TABLE registry TYPE int;",
+        ]
+    );
+    assert_eq!(
+        define_statements("registry", TableMode::Schemaless),
+        ["DEFINE TABLE registry SCHEMALESS;"]
+    );
+}
+
+#[test]
+fn guard_refuses_unknown_fields_on_schemafull_tables() {
+    // Arrange
+    let guard = SchemaGuard::new(&settings(TableMode::Schemafull));
+
+    // Act
+    let known = guard.check(
+        "person",
+        &json!({ "id": "a", "name": "Ann", "updated_at": null }),
+    );
+    let unknown = guard.check("person", &json!([{ "name": "Ann" }, { "nmae": "Bob" }]));
+
+    // Assert
+    assert!(known.is_ok());
+    match unknown {
+        Err(Error::UnknownField { table, field }) => {
+            assert_eq!(table, "person");
+            assert_eq!(field, "nmae");
+        }
+        other => panic!("expected an unknown field, got {other:?}"),
+    }
+}
+
+#[test]
+fn guard_lets_anything_into_schemaless_tables() {
+    // Arrange
+    let guard = SchemaGuard::new(&settings(TableMode::Schemaless));
+
+    // Act
+    let checked = guard.check("person", &json!({ "name": "Ann", "nickname": "A" }));
+
+    // Assert
+    assert!(checked.is_ok());
+    assert_eq!(guard.mode("licenses"), TableMode::Schemaless);
+}
+
+#[test]
+fn only_declared_tables_can_be_schemafull() {
+    // Arrange
+    let settings = SchemaSettings {
+        tables: BTreeMap::from([
+            ("licenses".to_string(), TableMode::Schemafull),
+            ("people; REMOVE".to_string(), TableMode::Schemaless),
+            ("person".to_string(), TableMode::Schemafull),
+        ]),
+    };
+
+    // Act
+    let problems = settings.problems();
+
+    // Assert
+    assert_eq!(problems.len(), 2);
+    assert!(problems[0].contains("schema.tables.licenses"));
+    assert!(problems[1].contains("letters, digits"));
+}
+
+#[tokio::test]
+async fn unknown_fields_are_a_422_naming_the_field() {
+    // Arrange
+    let app: Router = Router::new()
+        .route(
+            "/registry",
+            axum::routing::post(|SchemaJson(_, _): SchemaJson<Registry>| async {
+                StatusCode::CREATED
+            }),
+        )
+        .with_state(SchemaGuard::new(&settings(TableMode::Schemafull)));
+
+    // Act
+    let response = app
+        .oneshot(
+            Request::post("/registry")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"registration":1,"issuer":"dmv"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["errors"][0]["field"], "issuer");
+}
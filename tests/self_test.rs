@@ -0,0 +1,64 @@
+use surreal_simple::surreal::db::DatabaseSettings;
+use surreal_simple::surreal::self_test::{self_test, CheckStatus, SelfTestReport, CHECKS};
+
+mod support;
+use support::container::database_settings;
+
+#[tokio::test]
+async fn a_failed_check_skips_the_rest() {
+    // Arrange
+    let mut report = SelfTestReport::default();
+
+    // Act
+    let connected = report.check("connect", async { Ok(()) }).await;
+    let signed_in = report
+        .check("signin", async { Err("bad credentials".to_string()) })
+        .await;
+    let created = report.check("create", async { Ok(()) }).await;
+    let report = report.finish();
+
+    // Assert
+    assert!(connected);
+    assert!(!signed_in);
+    assert!(!created);
+    assert!(!report.passed);
+    let statuses: Vec<_> = report.checks.iter().map(|c| (c.name, c.status)).collect();
+    assert_eq!(statuses[1], ("signin", CheckStatus::Failed));
+    assert_eq!(report.checks[1].detail.as_deref(), Some("bad credentials"));
+    assert_eq!(report.checks.len(), CHECKS.len());
+    assert!(statuses[2..]
+        .iter()
+        .all(|(_, status)| *status == CheckStatus::Skipped));
+}
+
+#[tokio::test]
+async fn unreachable_database_fails_at_connect() {
+    // Arrange
+    let settings = DatabaseSettings {
+        port: 1,
+        ..DatabaseSettings::default()
+    };
+
+    // Act
+    let report = self_test(&settings).await;
+
+    // Assert
+    assert!(!report.passed);
+    assert_eq!(report.checks[0].status, CheckStatus::Failed);
+    assert!(report.checks[1..]
+        .iter()
+        .all(|c| c.status == CheckStatus::Skipped));
+}
+
+#[tokio::test]
+async fn every_check_passes_against_a_live_database() {
+    // Arrange
+    let settings = database_settings().await;
+
+    // Act
+    let report = self_test(&settings).await;
+
+    // Assert
+    assert!(report.passed, "{report:#?}");
+    assert_eq!(report.checks.len(), CHECKS.len());
+}
@@ -0,0 +1,87 @@
+use axum::Router;
+use std::io::{Read, Write};
+use surreal_simple::server::{Listener, ServerSettings};
+
+fn hello() -> Router {
+    Router::new().route("/hello", axum::routing::get(|| async { "hello" }))
+}
+
+#[tokio::test]
+async fn binds_the_configured_host_and_port() {
+    // Arrange
+    let settings = ServerSettings {
+        host: "127.0.0.1".into(),
+        port: 0,
+        unix_socket: None,
+    };
+
+    // Act
+    let listener = Listener::bind(&settings).unwrap();
+
+    // Assert
+    let addr = listener
+        .local_addr()
+        .expect("TCP listeners have an address");
+    assert!(addr.ip().is_loopback());
+    assert_ne!(addr.port(), 0);
+    assert_eq!(listener.to_string(), format!("http://{addr}"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_on_the_bound_port() {
+    // Arrange
+    let settings = ServerSettings {
+        port: 0,
+        ..ServerSettings::default()
+    };
+    let listener = Listener::bind(&settings).unwrap();
+    let address = listener.to_string();
+    tokio::spawn(listener.serve(hello()));
+
+    // Act
+    let response = minreq::get(format!("{address}/hello")).send().unwrap();
+
+    // Assert
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.as_str().unwrap(), "hello");
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn serves_on_a_unix_socket() {
+    // Arrange
+    let path = std::env::temp_dir().join(format!("surreal-simple-{}.sock", uuid::Uuid::new_v4()));
+    // A leftover file from an earlier run must not stop the bind.
+    std::fs::write(&path, b"stale").unwrap();
+    let settings = ServerSettings {
+        unix_socket: Some(path.clone()),
+        ..ServerSettings::default()
+    };
+    let listener = Listener::bind(&settings).unwrap();
+    assert_eq!(listener.local_addr(), None);
+    assert_eq!(listener.to_string(), format!("unix:{}", path.display()));
+    tokio::spawn(listener.serve(hello()));
+
+    // Act
+    let response = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || {
+            let mut stream = std::os::unix::net::UnixStream::connect(path).unwrap();
+            stream
+                .write_all(b"GET /hello HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        }
+    })
+    .await
+    .unwrap();
+
+    // Assert
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("hello"), "{response}");
+
+    // Teardown
+    let _ = std::fs::remove_file(path);
+}
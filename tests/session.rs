@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+use surrealdb::error::{Api, Db};
+
+use surreal_simple::surreal::session::{is_auth_error, Admission, ReauthBreaker, SessionSettings};
+
+fn settings() -> SessionSettings {
+    SessionSettings {
+        max_reauths: 2,
+        window_secs: 60,
+        cooldown_secs: 300,
+    }
+}
+
+#[test]
+fn expired_sessions_are_auth_errors() {
+    // Arrange
+    let errors = [
+        surrealdb::Error::Db(Db::InvalidAuth),
+        surrealdb::Error::Api(Api::Query("There was a problem with authentication".into())),
+        surrealdb::Error::Api(Api::Query("The token has expired".into())),
+    ];
+
+    // Act & Assert
+    for error in &errors {
+        assert!(is_auth_error(error), "{error}");
+    }
+    assert!(!is_auth_error(&surrealdb::Error::Db(Db::QueryPermissions)));
+}
+
+#[test]
+fn breaker_allows_reauths_up_to_the_limit() {
+    // Arrange
+    let mut breaker = ReauthBreaker::new();
+    let now = Instant::now();
+
+    // Act
+    let first = breaker.admit(now, &settings());
+    let second = breaker.admit(now + Duration::from_secs(1), &settings());
+
+    // Assert
+    assert_eq!(first, Admission::Allowed);
+    assert_eq!(second, Admission::Allowed);
+    assert!(!breaker.is_open(now + Duration::from_secs(1)));
+}
+
+#[test]
+fn breaker_trips_when_reauths_loop() {
+    // Arrange
+    let mut breaker = ReauthBreaker::new();
+    let now = Instant::now();
+    breaker.admit(now, &settings());
+    breaker.admit(now, &settings());
+
+    // Act
+    let tripped = breaker.admit(now + Duration::from_secs(2), &settings());
+    let during_cooldown = breaker.admit(now + Duration::from_secs(60), &settings());
+
+    // Assert
+    assert_eq!(tripped, Admission::Tripped);
+    assert_eq!(during_cooldown, Admission::Open);
+    assert!(breaker.is_open(now + Duration::from_secs(60)));
+}
+
+#[test]
+fn breaker_closes_after_the_cooldown() {
+    // Arrange
+    let mut breaker = ReauthBreaker::new();
+    let now = Instant::now();
+    for _ in 0..3 {
+        breaker.admit(now, &settings());
+    }
+
+    // Act
+    let after = breaker.admit(now + Duration::from_secs(301), &settings());
+
+    // Assert
+    assert_eq!(after, Admission::Allowed);
+}
+
+#[test]
+fn reauths_outside_the_window_are_forgotten() {
+    // Arrange
+    let mut breaker = ReauthBreaker::new();
+    let now = Instant::now();
+    breaker.admit(now, &settings());
+    breaker.admit(now, &settings());
+
+    // Act
+    let later = breaker.admit(now + Duration::from_secs(61), &settings());
+
+    // Assert
+    assert_eq!(later, Admission::Allowed);
+}
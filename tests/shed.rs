@@ -0,0 +1,68 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
+use surreal_simple::api::shed::{batch_shed, LimitSettings, LIMITS};
+use tokio::sync::{Notify, Semaphore};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn requests_over_the_limit_are_shed_with_retry_after() {
+    // Arrange
+    let entered = Arc::new(Notify::new());
+    let release = Arc::new(Semaphore::new(0));
+    let (entered_tx, release_rx) = (entered.clone(), release.clone());
+    LIMITS.configure(&LimitSettings {
+        batch: 1,
+        retry_after_secs: 7,
+        ..LimitSettings::default()
+    });
+    let app = Router::new()
+        .route(
+            "/slow",
+            axum::routing::get(move || async move {
+                entered_tx.notify_one();
+                let _ = release_rx.acquire().await;
+            }),
+        )
+        .layer(batch_shed())
+        // Like `main`, so handlers become services once instead of per request.
+        .with_state(());
+
+    let first = tokio::spawn(
+        app.clone()
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap()),
+    );
+    entered.notified().await;
+
+    // Act
+    let second = tokio::time::timeout(
+        Duration::from_secs(5),
+        app.clone()
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap()),
+    )
+    .await
+    .expect("second request should be shed, not queued")
+    .unwrap();
+
+    // Assert
+    assert_eq!(second.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(second.headers()["retry-after"], "7");
+
+    // Act: a raised limit applies to the next request.
+    LIMITS.configure(&LimitSettings {
+        batch: 2,
+        ..LimitSettings::default()
+    });
+    let third = tokio::spawn(
+        app.clone()
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap()),
+    );
+    entered.notified().await;
+
+    // Assert
+    release.add_permits(2);
+    assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+    assert_eq!(third.await.unwrap().unwrap().status(), StatusCode::OK);
+}
@@ -0,0 +1,97 @@
+use axum::body::Body;
+use axum::http::Request;
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use serde_json::{json, Value};
+use std::panic::Location;
+use std::time::Duration;
+use tower::ServiceExt;
+
+use surreal_simple::api::request_id;
+use surreal_simple::surreal::instrument::correlation;
+use surreal_simple::surreal::slow_log::{Binding, SlowQueryLog, SlowQuerySettings};
+
+fn log(capacity: usize) -> SlowQueryLog {
+    SlowQueryLog::new(&SlowQuerySettings {
+        threshold_ms: 10,
+        capacity,
+        explain: false,
+    })
+}
+
+#[test]
+fn fast_queries_are_not_recorded() {
+    let log = log(10);
+    let recorded = log.observe(
+        "SELECT * FROM person",
+        Duration::from_millis(3),
+        &[],
+        Location::caller(),
+    );
+
+    assert!(!recorded);
+    assert!(log.entries().is_empty());
+}
+
+#[test]
+fn slow_queries_are_kept_in_a_ring_buffer() {
+    // Arrange
+    let log = log(2);
+    let bindings = vec![Binding::new("name", &"Blaze")];
+
+    // Act
+    for i in 0..3 {
+        log.observe(
+            &format!("SELECT * FROM t{i}"),
+            Duration::from_millis(25),
+            &bindings,
+            Location::caller(),
+        );
+    }
+
+    // Assert
+    let entries = log.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].fingerprint, "SELECT * FROM t2");
+    assert_eq!(entries[1].fingerprint, "SELECT * FROM t1");
+    assert_eq!(entries[0].bindings[0].kind, "string");
+    assert!(entries[0].call_site.contains("slow_log.rs"));
+}
+
+#[tokio::test]
+async fn queries_carry_the_id_of_the_request_they_run_for() {
+    // Arrange
+    let app = Router::new()
+        .route(
+            "/slow",
+            get(|| async {
+                let log = log(10);
+                log.observe(
+                    "SELECT * FROM person",
+                    Duration::from_millis(25),
+                    &[],
+                    Location::caller(),
+                );
+                Json(json!({
+                    "correlation": correlation(),
+                    "slow": log.entries()[0].request_id,
+                }))
+            }),
+        )
+        .layer(middleware::from_fn(request_id));
+    let request = Request::builder()
+        .uri("/slow")
+        .header("x-request-id", "req-42")
+        .body(Body::empty())
+        .unwrap();
+
+    // Act
+    let response = app.oneshot(request).await.unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+
+    // Assert
+    assert_eq!(body["correlation"], json!(["correlation_id", "req-42"]));
+    assert_eq!(body["slow"], "req-42");
+    assert_eq!(correlation(), ("correlation_id", None));
+}
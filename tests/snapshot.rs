@@ -0,0 +1,104 @@
+use surreal_simple::error::Error;
+use surreal_simple::surreal::admin::{AdminDatabase, Scope};
+use surreal_simple::surreal::db::Database;
+use surreal_simple::surreal::snapshot::check_restorable;
+use uuid::Uuid;
+
+mod support;
+use support::container::database_settings;
+use support::PersonFixture;
+
+#[test]
+fn snapshots_may_only_hold_what_an_export_writes() {
+    let exported = "-- snapshot of test/test\n\
+        BEGIN TRANSACTION;\n\
+        INSERT INTO person { id: person:1, name: 'Ada' };\n\
+        -- count 1 person\n\
+        CREATE registry:1 CONTENT { registration: 1 };\n\
+        RELATE registry:1->licenses->person:1 CONTENT { id: licenses:1 };\n\
+        DEFINE TABLE person SCHEMALESS;\n\
+        DEFINE FUNCTION fn::normalize_name($name: string) { RETURN string::lowercase($name); };\n\
+        DEFINE FIELD name ON person TYPE string;\n\
+        DEFINE INDEX person_tags ON person FIELDS tags;\n\
+        DEFINE EVENT audit ON person WHEN $event = 'UPDATE' THEN (CREATE log SET at = time::now());\n\
+        COMMIT TRANSACTION;";
+    assert!(check_restorable(exported).is_ok());
+
+    for surql in [
+        "USE NS other DB other; INSERT INTO person { id: person:1 };",
+        "REMOVE NAMESPACE test;",
+        "REMOVE DATABASE test;",
+        "DEFINE NAMESPACE other;",
+        "DEFINE DATABASE other;",
+        "DEFINE TOKEN api ON NAMESPACE TYPE HS512 VALUE 'secret';",
+        "DEFINE SCOPE account SESSION 1d;",
+        "INSERT INTO person { id: person:1 }; DELETE person;",
+    ] {
+        assert!(
+            matches!(check_restorable(surql), Err(Error::StatementNotAllowed(_))),
+            "{surql}"
+        );
+    }
+    assert!(matches!(
+        check_restorable("INSERT INTO"),
+        Err(Error::QuerySyntax(_))
+    ));
+}
+
+#[tokio::test]
+async fn snapshots_restore_into_another_database() {
+    // Arrange
+    let settings = database_settings().await;
+    let db = Database::new(&settings).await.unwrap();
+    let admin = AdminDatabase::new(&settings).await.unwrap();
+    let doc = PersonFixture::new("Snapshot")
+        .with_license(4242)
+        .insert(&db.client)
+        .await;
+    let clone = Scope {
+        namespace: settings.namespace.clone(),
+        database: format!("clone_{}", Uuid::new_v4().simple()),
+    };
+
+    let original: Option<serde_json::Value> = db.client.select(&doc.person).await.unwrap();
+
+    // Act
+    let snapshot = admin.export(None).await.unwrap();
+    let report = admin.restore(clone.clone(), &snapshot.surql).await.unwrap();
+
+    // Assert
+    assert!(snapshot
+        .surql
+        .contains(&format!("INSERT INTO person {{ id: {}", doc.person)));
+    let position = |statement: &str| snapshot.surql.find(statement).unwrap();
+    assert!(position("INSERT INTO person") < position("RELATE registry:"));
+    assert!(position("RELATE registry:") < position("DEFINE FIELD"));
+    assert_eq!(report.scope, clone);
+    assert!(report.statements > snapshot.records);
+    let restored = Database::new(&settings).await.unwrap();
+    restored
+        .client
+        .use_ns(&clone.namespace)
+        .use_db(&clone.database)
+        .await
+        .unwrap();
+    let licensed: Vec<Vec<u64>> = restored
+        .client
+        .query("SELECT VALUE <-licenses<-registry.registration FROM $person")
+        .bind(("person", &doc.person))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(licensed, vec![vec![4242]]);
+    let copy: Option<serde_json::Value> = restored.client.select(&doc.person).await.unwrap();
+    assert_eq!(copy.unwrap()["updated_at"], original.unwrap()["updated_at"]);
+    assert_eq!(admin.scope().await.database, settings.database);
+
+    // Teardown
+    let _ = restored
+        .client
+        .query(format!("REMOVE DATABASE {}", clone.database))
+        .await;
+    doc.teardown(&db.client).await;
+}
@@ -0,0 +1,127 @@
+use surreal_simple::api::{Collation, PeopleQuery, Sort, MAX_SORT_KEYS};
+use surreal_simple::client::{NewPerson, SurrealThingClient};
+use surreal_simple::error::Error;
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+#[test]
+fn the_default_sorts_bytewise_and_ties_on_id() {
+    // Act
+    let sort = Sort::parse("person", None, "name:asc").unwrap();
+
+    // Assert
+    assert_eq!(sort.order_by(), " ORDER BY name ASC, id ASC");
+    assert!(sort.computed().is_empty());
+}
+
+#[test]
+fn collations_map_onto_surrealql() {
+    // Act
+    let sort = Sort::parse(
+        "person",
+        Some("name:asc:ci:numeric,date_of_birth:desc:unicode"),
+        "name:asc",
+    )
+    .unwrap();
+
+    // Assert
+    assert_eq!(
+        sort.keys()[0].collation,
+        Collation {
+            case_insensitive: true,
+            numeric: true,
+            unicode: false,
+        }
+    );
+    assert_eq!(sort.fields(), ["name", "date_of_birth"]);
+    assert_eq!(sort.computed(), ["string::lowercase(name) AS sort_0"]);
+    assert_eq!(
+        sort.order_by(),
+        " ORDER BY sort_0 NUMERIC ASC, date_of_birth COLLATE DESC, id ASC"
+    );
+}
+
+#[test]
+fn sorting_on_id_needs_no_tie_break() {
+    // Act
+    let sort = Sort::parse("person", Some("id:desc"), "name:asc").unwrap();
+
+    // Assert
+    assert_eq!(sort.order_by(), " ORDER BY id DESC");
+}
+
+#[test]
+fn unknown_fields_directions_and_collations_are_refused() {
+    for sort in [
+        "nickname:asc",
+        "name:up",
+        "name:asc:klingon",
+        "name:asc,name:desc",
+        "",
+    ] {
+        // Act
+        let parsed = Sort::parse("person", Some(sort), "name:asc");
+
+        // Assert
+        assert!(matches!(parsed, Err(Error::InvalidQuery(_))), "{sort}");
+    }
+    let too_many = vec!["name"; MAX_SORT_KEYS + 1].join(",");
+    assert!(Sort::parse("person", Some(&too_many), "name:asc").is_err());
+}
+
+async fn sorted_names(client: &SurrealThingClient, tag: &str, sort: &str) -> Vec<String> {
+    let page = client
+        .search_people(&PeopleQuery {
+            tag: Some(tag.into()),
+            sort: Some(sort.into()),
+            limit: Some(10),
+            ..PeopleQuery::default()
+        })
+        .await
+        .unwrap();
+    page.items.into_iter().map(|person| person.name).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unicode_names_sort_by_the_collation_asked_for() {
+    // Arrange
+    let app = spawn_app().await;
+    let tag = Uuid::new_v4().simple().to_string();
+    let client = app.client();
+    for name in ["Zoë", "eve", "Émile", "bob", "Carol", "item10", "item9"] {
+        let person = NewPerson {
+            tags: vec![tag.clone()],
+            ..NewPerson::new(name)
+        };
+        client.create_person(&person).await.unwrap();
+    }
+
+    // Act
+    let bytes = sorted_names(&client, &tag, "name:asc").await;
+    let ci = sorted_names(&client, &tag, "name:asc:ci").await;
+    let numeric = sorted_names(&client, &tag, "name:asc:numeric").await;
+    let unicode = sorted_names(&client, &tag, "name:asc:unicode").await;
+
+    // Assert
+    assert_eq!(
+        bytes,
+        ["Carol", "Zoë", "bob", "eve", "item10", "item9", "Émile"]
+    );
+    assert_eq!(
+        ci,
+        ["bob", "Carol", "eve", "item10", "item9", "Zoë", "Émile"]
+    );
+    assert_eq!(
+        numeric,
+        ["Carol", "Zoë", "bob", "eve", "item9", "item10", "Émile"]
+    );
+    assert_eq!(
+        unicode,
+        ["bob", "Carol", "Émile", "eve", "item10", "item9", "Zoë"]
+    );
+
+    // Teardown
+    let _ = minreq::delete(format!("{}/people?tag={tag}", app.address)).send();
+}
@@ -0,0 +1,96 @@
+use proptest::prelude::*;
+use surreal_simple::surreal::query_manager::StatementKind;
+use surreal_simple::surreal::sql::{escape_ident, escape_str};
+
+/// The kinds of statement `sql` parses into, or the parse error.
+fn kinds(sql: &str) -> Result<Vec<StatementKind>, String> {
+    let query = surrealdb::sql::parse(sql).map_err(|e| e.to_string())?;
+    Ok(query.iter().map(StatementKind::of).collect())
+}
+
+/// Strings made mostly of what could end a literal or identifier early.
+fn hostile() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        Just("'".to_string()),
+        Just("\"".to_string()),
+        Just("`".to_string()),
+        Just("\\".to_string()),
+        Just(";".to_string()),
+        Just("⟨".to_string()),
+        Just("⟩".to_string()),
+        Just("--".to_string()),
+        Just("/*".to_string()),
+        Just("\n".to_string()),
+        Just(" DELETE person ".to_string()),
+        "\\PC{1,4}",
+    ];
+    prop::collection::vec(piece, 0..12).prop_map(|pieces| pieces.concat())
+}
+
+fn any_text() -> impl Strategy<Value = String> {
+    prop_oneof![hostile(), any::<String>()]
+}
+
+#[test]
+fn strings_escape_quotes_and_backslashes() {
+    assert_eq!(escape_str("plain"), "'plain'");
+    assert_eq!(escape_str("it's"), r"'it\'s'");
+    assert_eq!(escape_str(r"C:\"), r"'C:\\'");
+    assert_eq!(escape_str(""), "''");
+}
+
+#[test]
+fn only_odd_identifiers_are_quoted() {
+    assert_eq!(escape_ident("person_history"), "person_history");
+    assert_eq!(escape_ident("my table"), "`my table`");
+    assert_eq!(escape_ident("a`b"), r"`a\`b`");
+    assert_eq!(escape_ident("2023"), "`2023`");
+    assert_eq!(escape_ident(""), "``");
+}
+
+#[test]
+fn unescaped_input_breaks_out() {
+    // The check the fuzzing below relies on does notice a breakout.
+    let name = "x'; DELETE person; SELECT '";
+
+    let sql = format!("SELECT * FROM person WHERE name = '{name}'; RETURN 1");
+
+    assert_ne!(
+        kinds(&sql),
+        Ok(vec![StatementKind::Select, StatementKind::Return])
+    );
+}
+
+proptest! {
+    #[test]
+    fn strings_stay_one_literal(value in any_text()) {
+        let sql = format!(
+            "SELECT * FROM person WHERE name = {}; RETURN {}",
+            escape_str(&value),
+            escape_str(&value)
+        );
+
+        prop_assert_eq!(
+            kinds(&sql),
+            Ok(vec![StatementKind::Select, StatementKind::Return])
+        );
+    }
+
+    #[test]
+    fn identifiers_stay_one_name(name in any_text()) {
+        let sql = format!(
+            "DEFINE TABLE {} SCHEMALESS; SELECT * FROM {}; RETURN 1",
+            escape_ident(&name),
+            escape_ident(&name)
+        );
+
+        prop_assert_eq!(
+            kinds(&sql),
+            Ok(vec![
+                StatementKind::Define,
+                StatementKind::Select,
+                StatementKind::Return
+            ])
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use surreal_simple::app::{routes, Migrations, StartupReport};
+use surreal_simple::config::Settings;
+use surreal_simple::surreal::schema::functions::FunctionSync;
+use surreal_simple::surreal::schema::indexes::IndexDrift;
+use surreal_simple::surreal::schema::SchemaReport;
+
+mod support;
+use support::app::{admin_bearer, spawn_app};
+use support::http::ResponseExt;
+
+fn methods(report: &StartupReport, path: &str) -> Vec<String> {
+    report
+        .routes
+        .iter()
+        .find(|route| route.path == path)
+        .map(|route| route.methods.clone())
+        .unwrap_or_default()
+}
+
+#[test]
+fn the_report_lists_every_route_the_app_builds() {
+    // Arrange
+    let settings = Settings::default();
+    let _ = routes(&settings);
+
+    // Act
+    let report = StartupReport::new(&settings, "1.0.0", SchemaReport::default());
+
+    // Assert
+    assert_eq!(
+        methods(&report, "/health_check"),
+        ["GET", "HEAD", "OPTIONS"]
+    );
+    assert_eq!(
+        methods(&report, "/admin/routes"),
+        ["GET", "HEAD", "OPTIONS"]
+    );
+    assert_eq!(
+        methods(&report, "/admin/snapshot"),
+        ["GET", "HEAD", "OPTIONS", "POST"]
+    );
+    assert_eq!(
+        methods(&report, "/person/:id"),
+        ["DELETE", "GET", "HEAD", "OPTIONS", "POST", "PUT"]
+    );
+    assert!(!methods(&report, "/ws").is_empty());
+    assert!(!methods(&report, "/metrics").is_empty());
+}
+
+#[test]
+fn the_report_redacts_secrets_and_names_the_engine() {
+    // Arrange
+    let mut settings = Settings::default();
+    settings.database.password = "hunter2".into();
+    settings.database.ssl_mode = true;
+
+    // Act
+    let report = StartupReport::new(&settings, "1.0.0", SchemaReport::default());
+
+    // Assert
+    assert_eq!(report.settings["database"]["password"], "[REDACTED]");
+    assert!(!report.settings.to_string().contains("hunter2"));
+    assert_eq!(report.engine.protocol, "wss");
+    assert_eq!(
+        report.engine.address,
+        format!("{}:{}", settings.database.host, settings.database.port)
+    );
+    assert_eq!(report.engine.server_version, "1.0.0");
+}
+
+#[test]
+fn migrations_are_clean_unless_the_database_is_ahead_or_different() {
+    // Arrange
+    let created = SchemaReport {
+        indexes: IndexDrift {
+            missing: vec!["person.person_email".into()],
+            ..IndexDrift::default()
+        },
+        functions: FunctionSync {
+            applied: vec!["normalize_name".into()],
+            ..FunctionSync::default()
+        },
+    };
+    let newer = SchemaReport {
+        functions: FunctionSync {
+            newer: vec!["normalize_name".into()],
+            ..FunctionSync::default()
+        },
+        ..SchemaReport::default()
+    };
+
+    // Act
+    let created = Migrations::from(created);
+    let newer = Migrations::from(newer);
+
+    // Assert
+    assert_eq!(created.indexes_created, ["person.person_email"]);
+    assert_eq!(created.functions_applied, ["normalize_name"]);
+    assert!(created.is_clean());
+    assert!(!newer.is_clean());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn admin_routes_serves_the_startup_report() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = minreq::get(format!("{}/admin/routes", app.address))
+        .with_header("Authorization", admin_bearer())
+        .send()
+        .unwrap();
+
+    // Assert
+    let report: serde_json::Value = response.assert_status(200).data();
+    assert_eq!(report["settings"]["database"]["password"], "[REDACTED]");
+    assert!(report["engine"]["server_version"].is_string());
+    assert!(report["routes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|route| route["path"] == "/admin/routes"));
+}
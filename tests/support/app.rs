@@ -0,0 +1,72 @@
+use std::net::TcpListener;
+use surreal_simple::app;
+use surreal_simple::client::SurrealThingClient;
+use surreal_simple::config::Settings;
+use surreal_simple::state::AppState;
+use surrealdb::{engine::remote::ws::Client, Surreal};
+
+use super::container::database_settings;
+
+/// What [`spawn_app`] sets `admin.token` to, for `/admin` routes.
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// The `Authorization` header value for [`ADMIN_TOKEN`].
+pub fn admin_bearer() -> String {
+    format!("Bearer {ADMIN_TOKEN}")
+}
+
+/// The API served on a free port, built by the same [`app::build`] as `main`.
+pub struct TestApp {
+    pub address: String,
+    pub db: Surreal<Client>,
+    pub state: AppState,
+}
+
+impl TestApp {
+    /// A client for the app's HTTP API.
+    pub fn client(&self) -> SurrealThingClient {
+        SurrealThingClient::new(&self.address)
+    }
+}
+
+/// Builds the app against the test database and serves it in the background.
+/// Blocking clients like `minreq` need a multi-threaded runtime:
+///
+/// ```ignore
+/// #[tokio::test(flavor = "multi_thread")]
+/// async fn it_works() {
+///     let app = spawn_app().await;
+///     minreq::get(format!("{}/health_check", app.address)).send().unwrap();
+/// }
+/// ```
+pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Like [`spawn_app`], with the default settings changed by `configure`
+/// first, e.g. to pick `repository.strategy`.
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
+    let mut configuration = Settings {
+        database: database_settings().await,
+        ..Settings::default()
+    };
+    configuration.admin.token = Some(ADMIN_TOKEN.into());
+    configure(&mut configuration);
+    let app = app::build(&configuration)
+        .await
+        .expect("Failed to build the app");
+    let state = app.state.clone();
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind a free port");
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let server = axum::Server::from_tcp(listener)
+        .expect("Failed to serve on the bound port")
+        .serve(app.into_router().into_make_service());
+    tokio::spawn(server);
+
+    TestApp {
+        address,
+        db: state.db.clone(),
+        state,
+    }
+}
@@ -0,0 +1,111 @@
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use surreal_simple::surreal::db::{Database, DatabaseSettings, MissingScope};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+const DEFAULT_IMAGE: &str = "surrealdb/surrealdb:nightly";
+const MIGRATIONS: [&str; 2] = [
+    include_str!("../../schemas/script_migration.surql"),
+    include_str!("../../schemas/new_table_migration.surql"),
+];
+
+static SURREAL: OnceCell<DatabaseSettings> = OnceCell::const_new();
+
+/// Settings for the SurrealDB the tests in this binary share.
+///
+/// The first call starts a throwaway in-memory instance in Docker on a free
+/// port and applies `schemas/`. The container is removed once the test binary
+/// exits. Set `TEST_SURREAL=external` to use the server from
+/// `scripts/init_db.sh` instead, and `TEST_SURREAL_IMAGE` to pick the image.
+pub async fn database_settings() -> DatabaseSettings {
+    SURREAL.get_or_init(start).await.clone()
+}
+
+async fn start() -> DatabaseSettings {
+    // A fresh container has no namespaces yet.
+    let mut settings = DatabaseSettings {
+        on_missing: MissingScope::Create,
+        ..DatabaseSettings::default()
+    };
+    if std::env::var("TEST_SURREAL").as_deref() == Ok("external") {
+        return settings;
+    }
+
+    let image = std::env::var("TEST_SURREAL_IMAGE").unwrap_or_else(|_| DEFAULT_IMAGE.into());
+    let name = format!("surreal-test-{}", Uuid::new_v4());
+    docker(&[
+        "run",
+        "--rm",
+        "-d",
+        "--name",
+        &name,
+        "-p",
+        "127.0.0.1::8000",
+        &image,
+        "start",
+        "--user",
+        &settings.username,
+        "--pass",
+        settings.password.expose(),
+        "memory",
+    ]);
+    reap_on_exit(&name);
+
+    let port = docker(&["port", &name, "8000/tcp"]);
+    settings.host = "127.0.0.1".into();
+    settings.port = port
+        .lines()
+        .next()
+        .and_then(|line| line.rsplit(':').next())
+        .and_then(|port| port.trim().parse().ok())
+        .unwrap_or_else(|| panic!("unexpected `docker port` output: {port}"));
+
+    let db = connect(&settings).await;
+    for migration in MIGRATIONS {
+        db.client.query(migration).await.unwrap().check().unwrap();
+    }
+
+    settings
+}
+
+/// The server takes a moment to accept connections after the container starts.
+async fn connect(settings: &DatabaseSettings) -> Database {
+    for _ in 0..50 {
+        if let Ok(db) = Database::new(settings).await {
+            return db;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    panic!("SurrealDB test container did not come up");
+}
+
+fn docker(args: &[&str]) -> String {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .expect("failed to run docker; set TEST_SURREAL=external to use a running server");
+    assert!(
+        output.status.success(),
+        "docker {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Statics are never dropped, so a detached watcher removes the container
+/// once this process is gone.
+fn reap_on_exit(name: &str) {
+    let script = format!(
+        "(while kill -0 {pid} 2>/dev/null; do sleep 1; done; docker rm -f {name} >/dev/null 2>&1) &",
+        pid = std::process::id(),
+    );
+    Command::new("sh")
+        .args(["-c", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("failed to start the container reaper");
+}
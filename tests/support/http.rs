@@ -0,0 +1,56 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Body every API response is wrapped in.
+#[derive(Deserialize, Debug)]
+pub struct Envelope<T> {
+    pub data: Option<T>,
+    pub meta: serde_json::Value,
+    pub errors: Vec<serde_json::Value>,
+}
+
+// region: -- ResponseExt
+/// Assertions over `minreq` responses from the API. They panic with the
+/// response body so failures show what the server actually said.
+pub trait ResponseExt {
+    fn assert_status(&self, expected: i32) -> &Self;
+
+    /// Asserts a successful envelope and returns its `data`.
+    fn data<T: DeserializeOwned>(&self) -> T;
+
+    /// Asserts a failed envelope with `status` and returns its first problem.
+    fn problem(&self, status: i32) -> serde_json::Value;
+}
+
+impl ResponseExt for minreq::Response {
+    fn assert_status(&self, expected: i32) -> &Self {
+        assert_eq!(
+            self.status_code,
+            expected,
+            "unexpected status, body: {:?}",
+            self.as_str()
+        );
+        self
+    }
+
+    fn data<T: DeserializeOwned>(&self) -> T {
+        let envelope: Envelope<T> = self.json().expect("body is not an API envelope");
+        assert!(envelope.errors.is_empty(), "errors: {:?}", envelope.errors);
+        envelope.data.expect("envelope has no data")
+    }
+
+    fn problem(&self, status: i32) -> serde_json::Value {
+        self.assert_status(status);
+        let envelope: Envelope<serde_json::Value> =
+            self.json().expect("body is not an API envelope");
+        assert!(envelope.data.is_none(), "data: {:?}", envelope.data);
+        let problem = envelope
+            .errors
+            .into_iter()
+            .next()
+            .expect("no errors reported");
+        assert_eq!(problem["status"], status);
+        problem
+    }
+}
+// endregion: -- ResponseExt
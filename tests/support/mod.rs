@@ -0,0 +1,124 @@
+#![allow(dead_code)]
+
+pub mod app;
+pub mod container;
+pub mod http;
+pub mod strategies;
+
+use surreal_simple::api::{LicenseProps, Licenses};
+use surreal_simple::surreal::edge::Edge;
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use uuid::Uuid;
+
+// region: -- PersonFixture
+/// Builds a person together with their registries and the `licenses` edges
+/// between them, inserted in one transaction:
+///
+/// ```ignore
+/// let doc = PersonFixture::new("McStuffins")
+///     .with_license(12345)
+///     .insert(&app.db)
+///     .await;
+/// // ...
+/// doc.teardown(&app.db).await;
+/// ```
+#[derive(Debug, Clone)]
+pub struct PersonFixture {
+    name: String,
+    registrations: Vec<u64>,
+}
+
+impl PersonFixture {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            registrations: Vec::new(),
+        }
+    }
+
+    pub fn with_license(mut self, registration: u64) -> Self {
+        self.registrations.push(registration);
+        self
+    }
+
+    pub async fn insert(self, db: &Surreal<Client>) -> Inserted {
+        let person_id = Uuid::new_v4().to_string();
+        let registry_ids: Vec<String> = self
+            .registrations
+            .iter()
+            .map(|_| Uuid::new_v4().to_string())
+            .collect();
+        let person = Thing::from(("person", person_id.as_str()));
+        let registries: Vec<Thing> = registry_ids
+            .iter()
+            .map(|id| Thing::from(("registry", id.as_str())))
+            .collect();
+
+        let count = self.registrations.len();
+        let mut sql = String::from("BEGIN TRANSACTION;\nCREATE $person CONTENT { name: $name };\n");
+        for i in 0..count {
+            sql.push_str(&format!(
+                "CREATE $registry_{i} CONTENT {{ registration: $registration_{i} }};\n"
+            ));
+        }
+        for i in 0..count {
+            sql.push_str(&format!(
+                "RELATE $registry_{i}->{}->$person CONTENT $props RETURN id;\n",
+                Licenses::TABLE
+            ));
+        }
+        sql.push_str("COMMIT TRANSACTION;");
+
+        let mut query = db
+            .query(sql)
+            .bind(("person", &person))
+            .bind(("name", &self.name))
+            .bind(("props", LicenseProps::default()));
+        for (i, registration) in self.registrations.iter().enumerate() {
+            query = query
+                .bind((format!("registry_{i}"), &registries[i]))
+                .bind((format!("registration_{i}"), registration));
+        }
+        let mut response = query.await.unwrap().check().unwrap();
+
+        // `BEGIN` and `COMMIT` have no results: the person comes first, then
+        // the registries, then the edges.
+        let licenses = (0..count)
+            .map(|i| {
+                let license: Option<Thing> = response.take((1 + count + i, "id")).unwrap();
+                license.unwrap()
+            })
+            .collect();
+
+        Inserted {
+            person,
+            registries,
+            licenses,
+        }
+    }
+}
+// endregion: -- PersonFixture
+
+// region: -- Inserted
+/// Everything a fixture created. Hand it back to [`Inserted::teardown`] at
+/// the end of the test.
+#[derive(Debug, Clone)]
+pub struct Inserted {
+    pub person: Thing,
+    pub registries: Vec<Thing>,
+    pub licenses: Vec<Thing>,
+}
+
+impl Inserted {
+    pub async fn teardown(self, db: &Surreal<Client>) {
+        let things = self
+            .licenses
+            .into_iter()
+            .chain(self.registries)
+            .chain(std::iter::once(self.person));
+        for thing in things {
+            let _ = db.query("DELETE $thing").bind(("thing", thing)).await;
+        }
+    }
+}
+// endregion: -- Inserted
@@ -0,0 +1,80 @@
+//! Proptest strategies for the API's models, as the JSON a client sends.
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use surreal_simple::api::MAX_REGISTRATION;
+
+/// Snippets that break SQL built with `format!`: quotes, escapes, statement
+/// ends, comments and SurrealQL's record id and parameter syntax.
+const AWKWARD: [&str; 10] = [
+    "'",
+    "\"",
+    "\\",
+    "`",
+    ";",
+    "--",
+    "/*",
+    "⟨person:x⟩",
+    "$data",
+    "} ",
+];
+
+/// Any printable text, weighted towards awkward snippets and text outside
+/// the Basic Multilingual Plane.
+pub fn text(max_len: usize) -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        3 => "\\PC{1,8}",
+        2 => proptest::sample::select(AWKWARD.to_vec()).prop_map(String::from),
+        1 => "[😀-🙏𝄞-𝄪]{1,3}",
+    ];
+    proptest::collection::vec(piece, 0..=max_len / 4).prop_map(move |pieces| {
+        let text: String = pieces.concat();
+        text.chars().take(max_len).collect()
+    })
+}
+
+/// Dates `age` can be computed from: year 1 up to the end of 2000.
+pub fn date_of_birth() -> impl Strategy<Value = NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+    let last = NaiveDate::from_ymd_opt(2000, 12, 31).unwrap();
+    (0..=(last - first).num_days()).prop_map(move |days| first + chrono::Duration::days(days))
+}
+
+/// A tag `clean_tags` keeps as it is: trimmed, without commas.
+pub fn tag() -> impl Strategy<Value = String> {
+    text(64).prop_filter_map("tags are trimmed and comma-free", |tag| {
+        let tag = tag.replace(',', "").trim().to_string();
+        (!tag.is_empty() && tag.len() <= 64).then_some(tag)
+    })
+}
+
+/// A `Person` body, leaving optional fields out as the API does.
+pub fn person() -> impl Strategy<Value = Value> {
+    (
+        text(128),
+        proptest::option::of(date_of_birth()),
+        proptest::collection::btree_set(tag(), 0..4),
+    )
+        .prop_map(|(name, date_of_birth, tags)| {
+            let mut person = json!({ "name": name });
+            if let Some(date) = date_of_birth {
+                person["date_of_birth"] = json!(date);
+            }
+            if !tags.is_empty() {
+                person["tags"] = json!(tags);
+            }
+            person
+        })
+}
+
+/// A `Registry` body, with registrations at and around the extremes.
+pub fn registry() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(0),
+        Just(MAX_REGISTRATION),
+        Just(u64::from(u32::MAX) + 1),
+        0..=MAX_REGISTRATION,
+    ]
+    .prop_map(|registration| json!({ "registration": registration }))
+}
@@ -0,0 +1,96 @@
+use axum::body::Body;
+use axum::http::{header, Request};
+use axum::{middleware, Router};
+use std::sync::{Arc, Mutex};
+use surreal_simple::api::auth::{identify, AdminAuth, AdminSettings};
+use surreal_simple::telemetry::request_span;
+use tower::ServiceExt;
+use tower_http::trace::TraceLayer;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+/// Every non-empty field value any span was created or recorded with.
+#[derive(Clone, Default)]
+struct Fields(Arc<Mutex<Vec<(String, String)>>>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Fields {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        attrs.record(&mut self.clone());
+    }
+
+    fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        values.record(&mut self.clone());
+    }
+}
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn contains_value(&self, needle: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(_, value)| value.contains(needle))
+    }
+}
+
+async fn call(authorization: Option<&str>) -> Fields {
+    let fields = Fields::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(fields.clone()));
+    let auth = AdminAuth::new(&AdminSettings {
+        token: Some("s3cret-token".into()),
+    });
+    let app = Router::new()
+        .route("/", axum::routing::get(|| async {}))
+        .layer(middleware::from_fn_with_state(auth, identify))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span::<Body>));
+
+    let mut request = Request::builder().uri("/");
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    app.oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    fields
+}
+
+#[tokio::test]
+async fn authenticated_requests_record_the_user_but_not_the_token() {
+    // Act
+    let fields = call(Some("Bearer s3cret-token")).await;
+
+    // Assert
+    assert_eq!(fields.get("user_id").as_deref(), Some("\"admin\""));
+    assert!(!fields.contains_value("s3cret-token"));
+}
+
+#[tokio::test]
+async fn invalid_tokens_leave_the_user_empty() {
+    // Act
+    let fields = call(Some("Bearer wrong")).await;
+
+    // Assert
+    assert_eq!(fields.get("user_id"), None);
+    assert!(fields.get("method").is_some());
+}
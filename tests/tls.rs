@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use surreal_simple::surreal::tls::{DatabaseTlsSettings, ReloadingClientCert};
+use uuid::Uuid;
+
+/// A fresh directory with a self-signed `client.pem`/`client.key` pair.
+fn certificate_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("surreal-simple-tls-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    write_certificate(&dir, "client");
+    dir
+}
+
+fn write_certificate(dir: &Path, name: &str) -> rustls::Certificate {
+    let cert = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+    std::fs::write(dir.join("client.pem"), cert.serialize_pem().unwrap()).unwrap();
+    std::fs::write(dir.join("client.key"), cert.serialize_private_key_pem()).unwrap();
+    // Each serialization signs afresh, so read back what was written.
+    let pem = std::fs::read(dir.join("client.pem")).unwrap();
+    let der = rustls_pemfile::certs(&mut pem.as_slice())
+        .unwrap()
+        .remove(0);
+    rustls::Certificate(der)
+}
+
+/// Some filesystems only keep whole seconds, so make the change visible.
+fn touch_later(path: &Path) {
+    let later = SystemTime::now() + Duration::from_secs(5);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+}
+
+#[test]
+fn settings_load_a_ca_bundle_and_client_certificate() {
+    // Arrange
+    let dir = certificate_dir();
+    let settings = DatabaseTlsSettings {
+        ca_file: Some(dir.join("client.pem")),
+        cert_file: Some(dir.join("client.pem")),
+        key_file: Some(dir.join("client.key")),
+    };
+
+    // Act
+    let config = settings.client_config();
+
+    // Assert
+    assert!(settings.problems().is_empty(), "{:?}", settings.problems());
+    let config = config.unwrap();
+    assert!(config.client_auth_cert_resolver.has_certs());
+
+    // Teardown
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn incomplete_settings_are_reported() {
+    // Arrange
+    let settings = DatabaseTlsSettings {
+        ca_file: Some("/does/not/exist.pem".into()),
+        cert_file: Some("/does/not/exist.pem".into()),
+        key_file: None,
+    };
+
+    // Act
+    let problems = settings.problems();
+
+    // Assert
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert!(settings.client_config().is_err());
+}
+
+#[test]
+fn rotated_certificates_are_picked_up() {
+    // Arrange
+    let dir = certificate_dir();
+    let resolver =
+        ReloadingClientCert::new(&dir.join("client.pem"), &dir.join("client.key")).unwrap();
+    let before = resolver.current();
+
+    // Act
+    let rotated = write_certificate(&dir, "rotated");
+    touch_later(&dir.join("client.pem"));
+    let after = resolver.current();
+
+    // Assert
+    assert_ne!(before.cert, after.cert);
+    assert_eq!(after.cert, vec![rotated]);
+
+    // Teardown
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn broken_rotations_keep_the_previous_certificate() {
+    // Arrange
+    let dir = certificate_dir();
+    let resolver =
+        ReloadingClientCert::new(&dir.join("client.pem"), &dir.join("client.key")).unwrap();
+    let before = resolver.current();
+
+    // Act
+    std::fs::write(dir.join("client.key"), "not a key").unwrap();
+    touch_later(&dir.join("client.key"));
+    let after = resolver.current();
+
+    // Assert
+    assert_eq!(before.cert, after.cert);
+
+    // Teardown
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,161 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use surreal_simple::client::NewPerson;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::db::{RequestTransaction, Transaction};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+
+#[tokio::test]
+async fn request_transaction_is_open_only_while_the_request_runs() {
+    // Act
+    let (inside, transaction) =
+        RequestTransaction::run(async { RequestTransaction::current() }).await;
+
+    // Assert
+    assert!(inside.is_some());
+    assert!(RequestTransaction::current().is_none());
+    assert!(!transaction.is_begun());
+    assert!(transaction.end(true).await.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn after_commit_tasks_run_only_once_committed() {
+    // Arrange
+    let app = spawn_app().await;
+    let ran = Arc::new(AtomicUsize::new(0));
+    let request = |ran: Arc<AtomicUsize>| {
+        let db = app.db.clone();
+        RequestTransaction::run(async move {
+            let request = RequestTransaction::current().unwrap();
+            request.begin(&db);
+            request
+                .queue(|transaction| transaction.query("RETURN true"))
+                .unwrap();
+            RequestTransaction::after_commit(async move {
+                ran.fetch_add(1, Ordering::SeqCst);
+            })
+            .await;
+        })
+    };
+
+    // Act
+    let (_, committed) = request(ran.clone()).await;
+    let before_commit = ran.load(Ordering::SeqCst);
+    committed.end(true).await.unwrap();
+    let after_commit = ran.load(Ordering::SeqCst);
+    let (_, cancelled) = request(ran.clone()).await;
+    cancelled.end(false).await.unwrap();
+    let (_, outside) = RequestTransaction::run(RequestTransaction::after_commit({
+        let ran = ran.clone();
+        async move {
+            ran.fetch_add(1, Ordering::SeqCst);
+        }
+    }))
+    .await;
+    outside.end(true).await.unwrap();
+
+    // Assert
+    assert_eq!(before_commit, 0);
+    assert_eq!(after_commit, 1);
+    assert_eq!(ran.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancelled_request_transaction_sends_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let (_, transaction) = RequestTransaction::run(async {
+        let request = RequestTransaction::current().unwrap();
+        request.begin(&app.db);
+        request
+            .queue(|transaction| {
+                transaction.query("CREATE person:tx_cancelled CONTENT { name: 'tx cancelled' }")
+            })
+            .unwrap();
+        request.cancel();
+    })
+    .await;
+    transaction.end(true).await.unwrap();
+
+    // Assert
+    let person: Option<serde_json::Value> =
+        app.db.select(("person", "tx_cancelled")).await.unwrap();
+    assert!(person.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_failed_statement_keeps_none_of_the_transaction() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut transaction = Transaction::new(&app.db);
+    transaction.query("CREATE person:tx_atomic CONTENT { name: 'tx atomic' }");
+    transaction.query("CREATE person:tx_atomic CONTENT { name: 'tx atomic again' }");
+
+    // Act
+    let result = transaction.commit().await;
+
+    // Assert
+    assert!(matches!(result, Err(Error::AlreadyExists(_))));
+    let person: Option<serde_json::Value> = app.db.select(("person", "tx_atomic")).await.unwrap();
+    assert!(person.is_none());
+
+    // Teardown
+    let _ = app.db.query("DELETE person:tx_atomic").await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_throw_aborts_the_transaction_with_its_message() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut transaction = Transaction::new(&app.db);
+    transaction.query("CREATE person:tx_thrown CONTENT { name: 'tx thrown' }");
+    transaction.query("THROW $reason");
+    transaction.bind("reason", "changed my mind");
+
+    // Act
+    let result = transaction.commit().await;
+
+    // Assert
+    assert!(matches!(result, Err(Error::Aborted(reason)) if reason == "changed my mind"));
+    let person: Option<serde_json::Value> = app.db.select(("person", "tx_thrown")).await.unwrap();
+    assert!(person.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_people_runs_in_the_request_transaction() {
+    // Arrange
+    let app = spawn_app().await;
+    let client = app.client();
+    for name in ["tx one", "tx two"] {
+        let person = NewPerson {
+            tags: vec!["tx_delete".into()],
+            ..NewPerson::new(name)
+        };
+        client
+            .create_person_with_id(&Uuid::new_v4().to_string(), &person)
+            .await
+            .unwrap();
+    }
+
+    // Act
+    let response = minreq::delete(format!("{}/people?tag=tx_delete", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    let report: serde_json::Value = response.assert_status(200).data();
+    assert_eq!(report["deleted"], 2);
+    let mut left = app
+        .db
+        .query("SELECT * FROM person WHERE tags CONTAINS 'tx_delete'")
+        .await
+        .unwrap();
+    let left: Vec<serde_json::Value> = left.take(0).unwrap();
+    assert!(left.is_empty());
+}
@@ -0,0 +1,117 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use surreal_simple::surreal::count::count;
+use surreal_simple::surreal::ttl::{delete_expired, parse_age, TtlJob, TtlRule, TtlSettings};
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+fn rule(table: &str, field: &str, older_than: &str) -> TtlRule {
+    TtlRule {
+        table: table.into(),
+        field: field.into(),
+        older_than: older_than.into(),
+    }
+}
+
+#[test]
+fn ages_are_a_number_and_a_unit() {
+    for (value, expected) in [
+        ("30s", Duration::from_secs(30)),
+        ("15m", Duration::from_secs(900)),
+        ("12h", Duration::from_secs(43_200)),
+        ("90d", Duration::from_secs(90 * 86_400)),
+        (" 2w ", Duration::from_secs(14 * 86_400)),
+    ] {
+        assert_eq!(parse_age(value), Some(expected), "{value}");
+    }
+    for value in ["", "d", "90", "1.5d", "-1d", "90y", "9é"] {
+        assert_eq!(parse_age(value), None, "{value}");
+    }
+}
+
+#[test]
+fn rules_need_identifiers_an_age_and_a_table_each() {
+    // Arrange
+    let settings = TtlSettings {
+        batch_size: 0,
+        rules: vec![
+            rule("person_history", "replaced_at", "90d"),
+            rule("person_history", "replaced_at", "30d"),
+            rule("requests; DELETE person", "at", "1d"),
+            rule("requests", "at", "0d"),
+        ],
+        ..TtlSettings::default()
+    };
+
+    // Act
+    let problems = settings.problems();
+
+    // Assert
+    assert_eq!(problems.len(), 4, "{problems:?}");
+    assert!(problems[0].contains("ttl.batch_size"));
+    assert!(problems[1].contains("more than one rule for `person_history`"));
+    assert!(problems[2].contains("ttl.rules[2]"));
+    assert!(problems[3].contains("ttl.rules[3].older_than"));
+    assert!(TtlSettings::default().problems().is_empty());
+}
+
+#[test]
+fn rules_not_run_yet_report_empty_progress() {
+    // Arrange
+    let job = TtlJob::new(&TtlSettings {
+        rules: vec![rule("requests", "at", "30d")],
+        ..TtlSettings::default()
+    });
+
+    // Act
+    let progress = job.progress();
+
+    // Assert
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].table, "requests");
+    assert_eq!(progress[0].runs, 0);
+    assert_eq!(progress[0].last_run_at, None);
+}
+
+#[tokio::test]
+async fn expired_records_go_in_bounded_batches() {
+    // Arrange
+    let app = spawn_app().await;
+    let table = format!("ttl_{}", Uuid::new_v4().simple());
+    let old = (Utc::now() - ChronoDuration::days(10)).to_rfc3339();
+    let new = Utc::now().to_rfc3339();
+    let sql = "CREATE type::table($table) SET at = <datetime> $old;".repeat(5)
+        + "CREATE type::table($table) SET at = <datetime> $new;";
+    app.db
+        .query(&sql)
+        .bind(("table", &table))
+        .bind(("old", &old))
+        .bind(("new", &new))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    let rule = rule(&table, "at", "7d");
+    let cutoff = Utc::now() - ChronoDuration::days(7);
+    let before = count(&app.db, &table, "", &BTreeMap::new()).await.unwrap();
+
+    // Act
+    let first = delete_expired(&app.db, &rule, cutoff, 3).await.unwrap();
+    let second = delete_expired(&app.db, &rule, cutoff, 3).await.unwrap();
+    let third = delete_expired(&app.db, &rule, cutoff, 3).await.unwrap();
+    let left = count(&app.db, &table, "", &BTreeMap::new()).await.unwrap();
+
+    // Assert
+    assert_eq!((first, second, third), (3, 2, 0));
+    assert_eq!((before, left), (6, 1));
+
+    // Teardown
+    app.db
+        .query("DELETE type::table($table)")
+        .bind(("table", &table))
+        .await
+        .unwrap();
+}
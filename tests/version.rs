@@ -0,0 +1,19 @@
+use semver::Version;
+use surreal_simple::surreal::version::is_supported;
+
+#[test]
+fn supported_range_covers_the_1_x_line() {
+    for version in ["1.0.0-beta.9", "1.0.0", "1.4.2"] {
+        assert!(is_supported(&Version::parse(version).unwrap()), "{version}");
+    }
+}
+
+#[test]
+fn older_and_next_major_versions_are_unsupported() {
+    for version in ["1.0.0-beta.8", "0.3.0", "2.0.0"] {
+        assert!(
+            !is_supported(&Version::parse(version).unwrap()),
+            "{version}"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use serde_json::json;
+use surreal_simple::api::{plain_ids, Registry, WithId};
+use surrealdb::sql::Thing;
+
+mod support;
+use support::app::spawn_app;
+use support::http::ResponseExt;
+use support::PersonFixture;
+
+#[test]
+fn ids_are_written_as_plain_strings_beside_the_fields() {
+    // Arrange
+    let registry: Registry = serde_json::from_value(json!({ "registration": 7 })).unwrap();
+    let with_id = WithId::new(Thing::from(("registry", "dmv")), registry);
+
+    // Act
+    let written = serde_json::to_value(&with_id).unwrap();
+
+    // Assert
+    assert_eq!(written, json!({ "id": "registry:dmv", "registration": 7 }));
+}
+
+#[test]
+fn ids_are_read_from_strings_and_objects() {
+    // Arrange
+    let plain = json!({ "id": "registry:dmv", "registration": 7 });
+    let object = json!({
+        "id": { "tb": "registry", "id": { "String": "dmv" } },
+        "registration": 7,
+    });
+
+    // Act
+    let plain: WithId<Registry> = serde_json::from_value(plain).unwrap();
+    let object: WithId<Registry> = serde_json::from_value(object).unwrap();
+
+    // Assert
+    assert_eq!(plain.id, Thing::from(("registry", "dmv")));
+    assert_eq!(object.id, plain.id);
+    assert_eq!(
+        serde_json::to_value(&object).unwrap(),
+        serde_json::to_value(&plain).unwrap()
+    );
+}
+
+#[test]
+fn malformed_ids_are_refused() {
+    // Act
+    let read = serde_json::from_value::<WithId<Registry>>(json!({
+        "id": "dmv",
+        "registration": 7,
+    }));
+
+    // Assert
+    assert!(read.is_err());
+}
+
+#[test]
+fn plain_ids_rewrites_every_record_id_and_nothing_else() {
+    // Arrange
+    let thing = |tb: &str, id: &str| json!({ "tb": tb, "id": { "String": id } });
+    let mut record = json!({
+        "id": thing("licenses", "a"),
+        "in": thing("registry", "dmv"),
+        "out": thing("person", "john"),
+        "people": [{ "id": thing("person", "jane"), "name": "Jane" }],
+        "place": { "tb": "not a record" },
+    });
+    let mut diff = json!([{ "op": "replace", "path": "/name", "value": "Jo" }]);
+    let untouched = diff.clone();
+
+    // Act
+    plain_ids(&mut record);
+    plain_ids(&mut diff);
+
+    // Assert
+    assert_eq!(
+        record,
+        json!({
+            "id": "licenses:a",
+            "in": "registry:dmv",
+            "out": "person:john",
+            "people": [{ "id": "person:jane", "name": "Jane" }],
+            "place": { "tb": "not a record" },
+        })
+    );
+    assert_eq!(diff, untouched);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn records_are_answered_with_their_ids() {
+    // Arrange
+    let app = spawn_app().await;
+    let doc = PersonFixture::new("Identified").insert(&app.db).await;
+    let id = doc.person.id.to_string();
+
+    // Act
+    let read = app.client().get_person(&id).await.unwrap();
+    let read_qry = minreq::get(format!("{}/person/qry/{id}", app.address))
+        .send()
+        .unwrap();
+
+    // Assert
+    let read_qry: serde_json::Value = read_qry.assert_status(200).data();
+    assert_eq!(read.id, doc.person.to_string());
+    assert_eq!(read_qry["id"], doc.person.to_string());
+
+    // Teardown
+    doc.teardown(&app.db).await;
+}
@@ -0,0 +1,156 @@
+use serde_json::json;
+use std::time::Duration;
+use surreal_simple::error::Error;
+use surreal_simple::surreal::history::history;
+use surreal_simple::surreal::write_behind::{
+    batch_statement, WriteBehind, WriteBehindSettings, WriteKind,
+};
+use surrealdb::sql::Thing;
+use uuid::Uuid;
+
+mod support;
+use support::app::spawn_app;
+
+fn enabled(read_your_writes: bool) -> WriteBehindSettings {
+    WriteBehindSettings {
+        enabled: true,
+        read_your_writes,
+        ..WriteBehindSettings::default()
+    }
+}
+
+/// Buffers a write in the background; with nothing flushing, it stays there
+/// until the task is aborted.
+async fn buffer(write_behind: &WriteBehind, id: &str) -> tokio::task::JoinHandle<()> {
+    let write_behind = write_behind.clone();
+    let record = Thing::from(("person", id));
+    let task = tokio::spawn(async move {
+        let _ = write_behind
+            .write(record, WriteKind::Create, json!({ "name": "John" }))
+            .await;
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    task
+}
+
+#[test]
+fn batches_bind_each_write() {
+    // Arrange
+    let record = Thing::from(("person", "john"));
+    let update = WriteKind::Update {
+        returning: "RETURN AFTER",
+        actor: None,
+    };
+
+    // Act
+    let sql = batch_statement(&[(&record, &WriteKind::Create), (&record, &update)]);
+
+    // Assert
+    assert_eq!(
+        sql,
+        "BEGIN TRANSACTION;\n\
+         CREATE $record_0 CONTENT $data_0 RETURN NONE;\n\
+         LET $previous_1 = (SELECT * OMIT id FROM $record_1)[0];\n\
+         IF $previous_1 = NONE { THROW \"no record to update\" };\n\
+         LET $version_1 = array::len((SELECT VALUE id FROM person_history \
+         WHERE record = $record_1)) + 1;\n\
+         CREATE person_history SET record = $record_1, version = $version_1, \
+         actor = $actor_1, replaced_at = time::now(), data = $previous_1;\n\
+         UPDATE $record_1 CONTENT $data_1 RETURN AFTER;\n\
+         COMMIT TRANSACTION;"
+    );
+}
+
+#[test]
+fn flush_limits_are_validated() {
+    // Arrange
+    let settings = WriteBehindSettings {
+        flush_size: 0,
+        flush_interval_ms: 0,
+        ..enabled(true)
+    };
+
+    // Act
+    let problems = settings.problems();
+
+    // Assert
+    assert_eq!(problems.len(), 2, "{problems:?}");
+    assert!(WriteBehindSettings::default().problems().is_empty());
+    assert!(!WriteBehind::new(&WriteBehindSettings::default()).is_enabled());
+}
+
+#[tokio::test]
+async fn reads_see_buffered_writes() {
+    // Arrange
+    let write_behind = WriteBehind::new(&enabled(true));
+    let record = Thing::from(("person", "john"));
+
+    // Act
+    let task = buffer(&write_behind, "john").await;
+    let pending = write_behind.pending(&record);
+    task.abort();
+    let _ = task.await;
+
+    // Assert
+    assert_eq!(pending, Some(json!({ "name": "John" })));
+    assert_eq!(write_behind.queued(), 1);
+    assert_eq!(write_behind.pending(&record), None);
+}
+
+#[tokio::test]
+async fn reads_can_bypass_the_buffer() {
+    // Arrange
+    let write_behind = WriteBehind::new(&enabled(false));
+
+    // Act
+    let task = buffer(&write_behind, "jane").await;
+    let pending = write_behind.pending(&Thing::from(("person", "jane")));
+    task.abort();
+
+    // Assert
+    assert_eq!(pending, None);
+    assert_eq!(write_behind.queued(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn buffered_updates_keep_the_previous_version() {
+    // Arrange
+    let app = spawn_app().await;
+    let write_behind = WriteBehind::new(&enabled(true));
+    write_behind.spawn(app.db.clone());
+    let record = Thing::from(("person", Uuid::new_v4().simple().to_string().as_str()));
+    let missing = Thing::from(("person", Uuid::new_v4().simple().to_string().as_str()));
+    let update = |actor: &str| WriteKind::Update {
+        returning: "RETURN AFTER",
+        actor: Some(actor.into()),
+    };
+    write_behind
+        .write(record.clone(), WriteKind::Create, json!({ "name": "v1" }))
+        .await
+        .unwrap();
+
+    // Act
+    let (second, third, absent) = tokio::join!(
+        write_behind.write(record.clone(), update("a"), json!({ "name": "v2" })),
+        write_behind.write(record.clone(), update("b"), json!({ "name": "v3" })),
+        write_behind.write(missing.clone(), update("c"), json!({ "name": "v1" })),
+    );
+    let versions = history(&app.db, &record).await.unwrap();
+
+    // Assert
+    assert_eq!(second.unwrap().unwrap()["name"], "v2");
+    assert_eq!(third.unwrap().unwrap()["name"], "v3");
+    assert!(matches!(absent, Err(Error::NotFound(_))), "{absent:?}");
+    let kept: Vec<_> = versions.iter().map(|v| v.data["name"].clone()).collect();
+    assert_eq!(kept, [json!("v1"), json!("v2")]);
+    assert_eq!(versions[1].actor.as_deref(), Some("b"));
+    let stored: Option<serde_json::Value> = app.db.select(&missing).await.unwrap();
+    assert!(stored.is_none(), "{stored:?}");
+
+    // Teardown
+    app.db
+        .query("DELETE $record; DELETE person_history WHERE record = $record")
+        .bind(("record", &record))
+        .await
+        .unwrap();
+}
@@ -0,0 +1,220 @@
+use serde_json::json;
+use std::time::Duration;
+use surreal_simple::api::hooks::{Mutation, MutationHooks};
+use surreal_simple::api::{
+    parse_topic, Action, ClientMessage, ConnectionRegistry, Fanout, ServerMessage, TableEvent,
+};
+use tokio::sync::mpsc;
+
+fn subscribe(topic: &str) -> ClientMessage {
+    ClientMessage::Subscribe {
+        topic: topic.into(),
+    }
+}
+
+fn unsubscribe(topic: &str) -> ClientMessage {
+    ClientMessage::Unsubscribe {
+        topic: topic.into(),
+    }
+}
+
+/// The next message for a connection; events reach it through its feed.
+async fn next(messages: &mut mpsc::Receiver<ServerMessage>) -> ServerMessage {
+    tokio::time::timeout(Duration::from_secs(1), messages.recv())
+        .await
+        .expect("no message within a second")
+        .unwrap()
+}
+
+fn event(table: &str, id: &str) -> TableEvent {
+    TableEvent {
+        action: Action::Update,
+        table: table.into(),
+        record: format!("{table}:{id}"),
+        data: json!({ "name": id }),
+    }
+}
+
+#[test]
+fn topics_are_tables_or_records_of_them() {
+    assert_eq!(parse_topic("person"), Ok(("person", None)));
+    assert_eq!(parse_topic("person:john"), Ok(("person", Some("john"))));
+    assert!(parse_topic("person:").is_err());
+    assert!(parse_topic("secrets").is_err());
+    // Writes to `registry` don't run the hooks, so nothing would arrive.
+    assert!(parse_topic("registry").is_err());
+}
+
+#[test]
+fn client_messages_are_parsed_from_tagged_json() {
+    // Act
+    let message: ClientMessage =
+        serde_json::from_str(r#"{"type": "subscribe", "topic": "person"}"#).unwrap();
+    let ping: ClientMessage = serde_json::from_str(r#"{"type": "ping", "id": 7}"#).unwrap();
+
+    // Assert
+    assert_eq!(message, subscribe("person"));
+    assert_eq!(ping, ClientMessage::Ping { id: Some(json!(7)) });
+}
+
+#[tokio::test]
+async fn subscriptions_are_acknowledged_and_tracked() {
+    // Arrange
+    let registry = ConnectionRegistry::new();
+    let (id, _messages) = registry.connect();
+
+    // Act
+    let subscribed = registry.handle(id, subscribe("person:john"));
+    let refused = registry.handle(id, subscribe("nothing"));
+    let pong = registry.handle(
+        id,
+        ClientMessage::Ping {
+            id: Some(json!("a")),
+        },
+    );
+
+    // Assert
+    assert_eq!(
+        subscribed,
+        ServerMessage::Subscribed {
+            topic: "person:john".into()
+        }
+    );
+    assert!(matches!(refused, ServerMessage::Error { .. }));
+    assert_eq!(
+        pong,
+        ServerMessage::Pong {
+            id: Some(json!("a"))
+        }
+    );
+    assert_eq!(registry.subscribers("person:john"), 1);
+    assert_eq!(registry.subscriptions(), 1);
+
+    // Teardown
+    registry.disconnect(id);
+    assert_eq!(registry.connections(), 0);
+}
+
+#[tokio::test]
+async fn writes_are_sent_to_table_and_record_subscribers() {
+    // Arrange
+    let registry = ConnectionRegistry::new();
+    let hooks = MutationHooks::new().with(registry.clone());
+    let (table_watcher, mut table_events) = registry.connect();
+    let (record_watcher, mut record_events) = registry.connect();
+    let (other_watcher, mut other_events) = registry.connect();
+    registry.handle(table_watcher, subscribe("person"));
+    registry.handle(record_watcher, subscribe("person:john"));
+    registry.handle(other_watcher, subscribe("person:jane"));
+    let mutation = Mutation {
+        table: "person",
+        id: "john",
+    };
+
+    // Act
+    hooks.after_update(mutation, &json!({"name": "John"})).await;
+
+    // Assert
+    let expected = |topic: &str| ServerMessage::Event {
+        topic: topic.into(),
+        action: Action::Update,
+        record: "person:john".into(),
+        data: json!({"name": "John"}),
+    };
+    assert_eq!(next(&mut table_events).await, expected("person"));
+    assert_eq!(next(&mut record_events).await, expected("person:john"));
+    assert!(other_events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn unsubscribed_connections_get_nothing() {
+    // Arrange
+    let registry = ConnectionRegistry::new();
+    let (id, mut events) = registry.connect();
+    registry.handle(id, subscribe("person"));
+    registry.handle(id, unsubscribe("person"));
+
+    // Act
+    registry.publish(
+        Action::Delete,
+        Mutation {
+            table: "person",
+            id: "john",
+        },
+        &json!(null),
+    );
+
+    // Assert
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn connections_share_one_feed_per_table() {
+    // Arrange
+    let registry = ConnectionRegistry::new();
+    let (first, mut first_events) = registry.connect();
+    let (second, _second_events) = registry.connect();
+
+    // Act
+    registry.handle(first, subscribe("person"));
+    registry.handle(first, subscribe("person:john"));
+    registry.handle(second, subscribe("person:jane"));
+    registry.publish(
+        Action::Create,
+        Mutation {
+            table: "person",
+            id: "john",
+        },
+        &json!({}),
+    );
+
+    // Assert
+    assert_eq!(registry.fanout().feeds(), 1);
+    let topics = [next(&mut first_events).await, next(&mut first_events).await];
+    assert!(topics.iter().all(|message| matches!(
+        message,
+        ServerMessage::Event { record, .. } if record == "person:john"
+    )));
+    assert!(first_events.try_recv().is_err());
+
+    // Teardown
+    registry.disconnect(first);
+    registry.disconnect(second);
+    assert_eq!(registry.fanout().feeds(), 0);
+}
+
+#[tokio::test]
+async fn a_feed_closes_when_its_last_topic_goes() {
+    // Arrange
+    let registry = ConnectionRegistry::new();
+    let (id, _events) = registry.connect();
+    registry.handle(id, subscribe("person"));
+    registry.handle(id, subscribe("person:john"));
+
+    // Act
+    registry.handle(id, unsubscribe("person"));
+    let while_watched = registry.fanout().feeds();
+    registry.handle(id, unsubscribe("person:john"));
+
+    // Assert
+    assert_eq!(while_watched, 1);
+    assert_eq!(registry.fanout().feeds(), 0);
+}
+
+#[tokio::test]
+async fn feeds_hand_each_event_to_every_listener() {
+    // Arrange
+    let fanout = Fanout::new();
+    let mut first = fanout.join("person");
+    let mut second = fanout.join("person");
+    let mut other_table = fanout.join("registry");
+
+    // Act
+    fanout.publish(event("person", "john"));
+
+    // Assert
+    assert_eq!(fanout.feeds(), 2);
+    assert_eq!(first.recv().await.unwrap(), event("person", "john"));
+    assert_eq!(second.recv().await.unwrap(), event("person", "john"));
+    assert!(other_table.try_recv().is_err());
+}